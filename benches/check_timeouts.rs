@@ -0,0 +1,67 @@
+//! Per-log cost of a fiber type with many open fibers.
+//!
+//! Each iteration processes one log that joins an existing fiber, which also
+//! checks the fiber type for timeouts. The cost should stay flat as the
+//! number of open fibers grows.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use noil::config::types::FiberTypeConfig;
+use noil::fiber::{CompiledFiberType, FiberTypeProcessor};
+use noil::source::reader::LogRecord;
+use uuid::Uuid;
+
+const FIBER_TYPE: &str = r#"
+temporal:
+  max_gap: 1h
+attributes:
+  - name: session
+    type: string
+    key: true
+sources:
+  app:
+    patterns:
+      - regex: 'session=(?P<session>\d+)'
+"#;
+
+fn log(timestamp: DateTime<Utc>, session: usize) -> LogRecord {
+    LogRecord {
+        id: Uuid::new_v4(),
+        timestamp,
+        source_id: "app".to_string(),
+        raw_text: format!("request session={}", session),
+        file_offset: 0,
+        fields: Default::default(),
+        labels: Default::default(),
+        late: false,
+    }
+}
+
+fn bench_check_timeouts(c: &mut Criterion) {
+    let config: FiberTypeConfig = serde_yaml::from_str(FIBER_TYPE).unwrap();
+    let start = Utc.with_ymd_and_hms(2025, 12, 4, 10, 0, 0).unwrap();
+
+    let mut group = c.benchmark_group("process_log_with_open_fibers");
+    group.sample_size(20);
+    for open in [1_000, 10_000, 100_000, 1_000_000] {
+        let fiber_type = CompiledFiberType::from_config("session", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(fiber_type, 1);
+        for session in 0..open {
+            processor.process_log(&log(start, session));
+        }
+        assert_eq!(processor.open_fiber_count(), open);
+
+        // Logs a millisecond apart keep every fiber well inside max_gap
+        let mut n = 0;
+        group.bench_with_input(BenchmarkId::from_parameter(open), &open, |b, &open| {
+            b.iter(|| {
+                n += 1;
+                processor.process_log(&log(start + Duration::milliseconds(n), n as usize % open))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_check_timeouts);
+criterion_main!(benches);
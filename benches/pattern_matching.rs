@@ -0,0 +1,131 @@
+//! Cost of finding the first pattern that matches each log line.
+//!
+//! "prefiltered" scans each line once with the source's `RegexSet` and only
+//! captures with the candidates; "sequential" tries every pattern's captures
+//! in turn, as matching did before the prefilter.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use noil::config::parse::load_config;
+use noil::config::types::FiberTypeConfig;
+use noil::fiber::{CompiledFiberType, SourcePatterns};
+use std::path::Path;
+
+const SYNTHETIC_LINES: usize = 1_000_000;
+const SYNTHETIC_EVENTS: usize = 32;
+
+fn first_match_prefiltered(patterns: &SourcePatterns, line: &str) -> bool {
+    patterns
+        .candidates(line)
+        .any(|pattern| pattern.regex.captures(line).is_some())
+}
+
+fn first_match_sequential(patterns: &SourcePatterns, line: &str) -> bool {
+    patterns
+        .patterns
+        .iter()
+        .any(|pattern| pattern.regex.captures(line).is_some())
+}
+
+/// Run every line through the patterns of every fiber type for its source
+fn match_all(
+    fiber_types: &[CompiledFiberType],
+    lines: &[(&str, &str)],
+    first_match: fn(&SourcePatterns, &str) -> bool,
+) -> usize {
+    let mut matched = 0;
+    for (source_id, line) in lines {
+        for fiber_type in fiber_types {
+            if let Some(patterns) = fiber_type.patterns_for_source(source_id) {
+                matched += first_match(patterns, line) as usize;
+            }
+        }
+    }
+    matched
+}
+
+fn bench_group(
+    c: &mut Criterion,
+    name: &str,
+    fiber_types: &[CompiledFiberType],
+    lines: &[(&str, &str)],
+    sample_size: usize,
+) {
+    assert_eq!(
+        match_all(fiber_types, lines, first_match_prefiltered),
+        match_all(fiber_types, lines, first_match_sequential)
+    );
+
+    let mut group = c.benchmark_group(name);
+    group.sample_size(sample_size);
+    group.throughput(Throughput::Elements(lines.len() as u64));
+    group.bench_function(BenchmarkId::from_parameter("prefiltered"), |b| {
+        b.iter(|| match_all(fiber_types, lines, first_match_prefiltered))
+    });
+    group.bench_function(BenchmarkId::from_parameter("sequential"), |b| {
+        b.iter(|| match_all(fiber_types, lines, first_match_sequential))
+    });
+    group.finish();
+}
+
+fn bench_sample_logs(c: &mut Criterion) {
+    // The sample config keeps its database under $TMPDIR
+    if std::env::var_os("TMPDIR").is_none() {
+        std::env::set_var("TMPDIR", std::env::temp_dir());
+    }
+    let config = load_config(Path::new("samples/sample-config.yml")).unwrap();
+    let fiber_types: Vec<CompiledFiberType> = config
+        .fiber_types
+        .iter()
+        .flatten()
+        .map(|(name, fiber_type)| CompiledFiberType::from_config(name, fiber_type).unwrap())
+        .collect();
+
+    let contents: Vec<(String, String)> = config
+        .sources
+        .iter()
+        .map(|(source_id, source)| {
+            (source_id.clone(), std::fs::read_to_string(&source.path).unwrap())
+        })
+        .collect();
+    let lines: Vec<(&str, &str)> = contents
+        .iter()
+        .flat_map(|(source_id, text)| text.lines().map(move |line| (source_id.as_str(), line)))
+        .collect();
+
+    bench_group(c, "sample_logs", &fiber_types, &lines, 100);
+}
+
+/// A source with many event patterns, where most lines match one of the
+/// later ones or none at all
+fn bench_synthetic(c: &mut Criterion) {
+    let mut patterns = String::new();
+    for event in 0..SYNTHETIC_EVENTS {
+        patterns.push_str(&format!(
+            "      - regex: 'event=ev{event} user=(?P<user>\\w+) took=(?P<took>\\d+)ms'\n"
+        ));
+    }
+    let yaml = format!(
+        "temporal:\n  max_gap: 1m\nattributes:\n  - name: user\n    type: string\n    key: true\n  - name: took\n    type: int\nsources:\n  app:\n    patterns:\n{patterns}"
+    );
+    let config: FiberTypeConfig = serde_yaml::from_str(&yaml).unwrap();
+    let fiber_types = vec![CompiledFiberType::from_config("events", &config).unwrap()];
+
+    // Half the event kinds have no pattern
+    let kinds: Vec<String> = (0..SYNTHETIC_EVENTS * 2)
+        .map(|event| {
+            format!(
+                "2025-12-04T10:00:00.000Z INFO worker-7 event=ev{event} user=u{} took={}ms",
+                event * 7,
+                event * 13
+            )
+        })
+        .collect();
+    let lines: Vec<(&str, &str)> = (0..SYNTHETIC_LINES)
+        .map(|i| ("app", kinds[i % kinds.len()].as_str()))
+        .collect();
+
+    bench_group(c, "synthetic_1m_lines", &fiber_types, &lines, 10);
+}
+
+criterion_group!(benches, bench_sample_logs, bench_synthetic);
+criterion_main!(benches);
@@ -2,7 +2,7 @@ pub mod file_browser;
 pub mod regex_input;
 pub mod yaml_builder;
 
-use crate::source::timestamp::TimestampExtractor;
+use crate::source::timestamp::{detect_layout, TimestampExtractor};
 use dialoguer::{Confirm, Input, Select};
 use std::io::{self, BufRead, IsTerminal};
 use std::path::PathBuf;
@@ -91,7 +91,24 @@ pub fn run_interactive(
             }
 
             // Regex pattern + timestamp format input
-            let (timestamp_pattern, timestamp_format) = if !sample_lines.is_empty() {
+            // Offer a well-known layout when the samples have one
+            let detected = match detect_layout(&sample_lines) {
+                Some(layout) => {
+                    println!();
+                    println!("Detected {} timestamps:", layout.name);
+                    println!("  Pattern: {}", layout.pattern);
+                    println!("  Format: {}", layout.format);
+                    Confirm::new()
+                        .with_prompt("Use the detected timestamp pattern?")
+                        .default(true)
+                        .interact()?
+                        .then_some(layout)
+                }
+                None => None,
+            };
+            let (timestamp_pattern, timestamp_format) = if let Some(layout) = detected {
+                (layout.pattern.to_string(), layout.format.to_string())
+            } else if !sample_lines.is_empty() {
                 println!();
                 println!("Now enter a regex pattern to extract the timestamp.");
                 println!("The pattern must contain a (?P<ts>...) named group.");
@@ -109,15 +126,21 @@ pub fn run_interactive(
                     .with_prompt("Timestamp regex pattern (must contain (?P<ts>...) group)")
                     .interact_text()?;
 
-                let format_options =
-                    &["iso8601", "epoch", "epoch_ms", "Custom strptime format"];
+                let format_options = &[
+                    "iso8601",
+                    "epoch",
+                    "epoch_ms",
+                    "epoch_us",
+                    "epoch_ns",
+                    "Custom strptime format",
+                ];
                 let format_idx = Select::new()
                     .with_prompt("Timestamp format")
                     .items(format_options)
                     .default(0)
                     .interact()?;
 
-                let format = if format_idx == 3 {
+                let format = if format_idx == format_options.len() - 1 {
                     let fmt: String = Input::new()
                         .with_prompt(
                             "Enter strptime format string (e.g., '%Y-%m-%d %H:%M:%S')",
@@ -137,7 +160,7 @@ pub fn run_interactive(
             // Validate by trying to extract from first line
             if !sample_lines.is_empty() {
                 match TimestampExtractor::new(&timestamp_pattern, &timestamp_format) {
-                    Ok(extractor) => match extractor.extract(&sample_lines[0]) {
+                    Ok(mut extractor) => match extractor.extract(&sample_lines[0]) {
                         Ok(Some(ts)) => {
                             println!("  Parsed timestamp: {}", ts);
                         }
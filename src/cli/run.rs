@@ -4,21 +4,27 @@ use crate::collector::batch_buffer::{BatchBuffer, BufferStats};
 use crate::collector::epoch_batcher::EpochBatcher;
 use crate::config::parse::{load_config, load_config_with_yaml};
 use crate::config::reconcile::{reconcile_config_on_startup, ReconcileResult};
+use crate::config::types::SourceType;
 #[allow(deprecated)]
 use crate::config::version::compute_config_version;
 use crate::fiber::FiberProcessor;
 use crate::parent::collector_client::CollectorClient;
 use crate::parent::collector_stream::CollectorStream;
-use crate::pipeline::{create_channel, run_processor, run_writer, FiberUpdate};
+use crate::pipeline::{create_channel, run_processor, run_writer, FiberUpdate, SourceControl};
 use crate::reprocessing::ReprocessState;
-use crate::sequencer::merge::{run_sequencer, SequencerRunConfig};
+use crate::sequencer::merge::{
+    run_sequencer_with_control, SequencerRunConfig, SharedSequencerStatus,
+};
+use crate::source::discovery::{run_discovery, SourceDiscovery};
+use crate::source::ingest::IngestHub;
 use crate::source::reader::{LogRecord, SourceReader};
 use crate::storage::checkpoint::{
     Checkpoint, CheckpointManager, CollectorSequencerCheckpoint, ParentCheckpoint,
-    SequencerCheckpoint, SharedFiberProcessorState, SharedSourceState, SourceCheckpoint,
+    SequencerCheckpoint, SharedFiberProcessorState, SharedSourceStates, SourceCheckpoint,
 };
 use crate::storage::duckdb::DuckDbStorage;
 use crate::storage::traits::Storage;
+use crate::web::api::AppState;
 use crate::web::run_server;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
@@ -274,6 +280,7 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
                         );
                     }
                 }
+                fiber_processor.restore_links();
             }
         }
 
@@ -313,87 +320,102 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
 
     // === Phase 5: Local sources (if has_local_sources) ===
     let mut source_readers = Vec::new();
-    let mut shared_source_states: HashMap<String, SharedSourceState> = HashMap::new();
+    let shared_source_states: SharedSourceStates = Arc::new(RwLock::new(HashMap::new()));
+    let sequencer_status = SharedSequencerStatus::default();
     let mut sequencer_handle = None;
+    // Set when following local sources, so the API can add and remove them
+    let mut source_control = None;
+    let mut discovery_tasks: Vec<JoinHandle<()>> = Vec::new();
+    // Buffers fed by the web server's /ingest routes, one per http source
+    let mut ingest_hub = IngestHub::new(config.pipeline.backpressure.strategy, buffer_size);
 
     if has_local {
+        let source_checkpoints = checkpoint.as_ref().map(|cp| &cp.sources);
+        let mut discoveries = Vec::new();
+
         for (source_id, source_config) in &config.sources {
             info!(source_id = %source_id, path = %source_config.path.display(), "Creating source reader");
 
-            let reader = if let Some(ref cp) = checkpoint {
-                if let Some(source_checkpoint) = cp.sources.get(source_id) {
-                    if let Ok(metadata) = std::fs::metadata(&source_config.path) {
-                        #[cfg(unix)]
-                        let current_inode = {
-                            use std::os::unix::fs::MetadataExt;
-                            metadata.ino()
-                        };
-                        #[cfg(not(unix))]
-                        let current_inode = {
-                            use std::hash::{Hash, Hasher};
-                            let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                            metadata.len().hash(&mut hasher);
-                            if let Ok(modified) = metadata.modified() {
-                                modified.hash(&mut hasher);
-                            }
-                            hasher.finish()
-                        };
-
-                        if current_inode == source_checkpoint.inode {
-                            info!(
-                                source_id = %source_id,
-                                offset = source_checkpoint.offset,
-                                "Restoring source reader from checkpoint"
-                            );
-                            SourceReader::new_with_offset(
-                                source_id.clone(),
-                                source_config,
-                                config.pipeline.errors.on_parse_error,
-                                source_checkpoint.offset,
-                            )?
-                        } else {
-                            warn!(
-                                source_id = %source_id,
-                                "File inode changed (rotation detected), starting from beginning"
-                            );
-                            SourceReader::new(
-                                source_id.clone(),
-                                source_config,
-                                config.pipeline.errors.on_parse_error,
-                            )?
-                        }
-                    } else {
-                        warn!(source_id = %source_id, "Cannot read file metadata, starting from beginning");
-                        SourceReader::new(
-                            source_id.clone(),
-                            source_config,
-                            config.pipeline.errors.on_parse_error,
-                        )?
-                    }
-                } else {
-                    SourceReader::new(
-                        source_id.clone(),
-                        source_config,
-                        config.pipeline.errors.on_parse_error,
-                    )?
+            if source_config.source_type.is_discovered() {
+                let mut discovery = SourceDiscovery::new(source_id, source_config);
+                let readers = discovery
+                    .initial_readers(source_checkpoints, config.pipeline.errors.on_parse_error)?;
+                info!(source_id = %source_id, files = readers.len(), "Discovered source files");
+
+                for reader in readers {
+                    let (reader, state) = reader.with_shared_state();
+                    shared_source_states
+                        .write()
+                        .await
+                        .insert(reader.source_id().to_string(), state);
+                    source_readers.push(reader);
+                }
+
+                if source_config.read.follow {
+                    discoveries.push(discovery);
                 }
+                continue;
+            }
+
+            let reader = SourceReader::resume(
+                source_id.clone(),
+                source_config,
+                config.pipeline.errors.on_parse_error,
+                source_checkpoints.and_then(|sources| sources.get(source_id)),
+            )?;
+            let reader = if source_config.source_type == SourceType::Http {
+                reader.with_ingest(ingest_hub.register(source_id))
             } else {
-                SourceReader::new(
-                    source_id.clone(),
-                    source_config,
-                    config.pipeline.errors.on_parse_error,
-                )?
+                reader
             };
 
             let (reader, state) = reader.with_shared_state();
-            shared_source_states.insert(source_id.clone(), state);
+            shared_source_states.write().await.insert(source_id.clone(), state);
             source_readers.push(reader);
         }
 
-        if !source_readers.is_empty() {
+        if !source_readers.is_empty() || !discoveries.is_empty() {
             info!("Starting sequencer for local sources");
-            let sequencer_config = SequencerRunConfig::from(&config.sequencer);
-            sequencer_handle = Some(run_sequencer(source_readers, seq_tx.clone(), sequencer_config).await?);
+            let sequencer_config = SequencerRunConfig {
+                status: Arc::clone(&sequencer_status),
+                ..SequencerRunConfig::from(&config.sequencer)
+            };
+            let (registrar, registrations) = mpsc::channel(100);
+            let (retirer, retirements) = mpsc::channel(16);
+
+            // Glob/directory sources keep registering new files while following
+            for discovery in discoveries {
+                discovery_tasks.push(tokio::spawn(run_discovery(
+                    discovery,
+                    registrar.clone(),
+                    Arc::clone(&shared_source_states),
+                    config.pipeline.errors.on_parse_error,
+                )));
+            }
+
+            // Sources added at runtime keep the sequencer running, so only
+            // offer that when it runs until shutdown anyway
+            if config.sources.values().any(|source| source.read.follow) {
+                source_control = Some(Arc::new(SourceControl::new(
+                    registrar,
+                    retirer,
+                    Arc::clone(&shared_source_states),
+                    config.pipeline.errors.on_parse_error,
+                )));
+            } else {
+                drop(registrar);
+            }
+
+            sequencer_handle = Some(
+                run_sequencer_with_control(
+                    source_readers,
+                    registrations,
+                    retirements,
+                    seq_tx.clone(),
+                    sequencer_config,
+                )
+                .await?,
+            );
         }
     }
 
@@ -554,7 +576,7 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
         // Source states for collector status API
         let source_states: Arc<RwLock<HashMap<String, Arc<Mutex<CollectorSourceState>>>>> =
             Arc::new(RwLock::new(HashMap::new()));
-        for source_id in config.sources.keys() {
+        for source_id in shared_source_states.read().await.keys() {
             source_states.write().await.insert(
                 source_id.clone(),
                 Arc::new(Mutex::new(CollectorSourceState {
@@ -734,8 +756,7 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
     let checkpoint_handle = if config.pipeline.checkpoint.enabled {
         let checkpoint_storage = storage.clone();
         let interval = config.pipeline.checkpoint.interval_seconds;
-        let states = shared_source_states.clone();
-        let source_configs = config.sources.clone();
+        let states = Arc::clone(&shared_source_states);
         let fiber_state = shared_fiber_state.clone();
         let mut shutdown_watch = shutdown_rx.clone();
 
@@ -751,15 +772,16 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
             macro_rules! save_checkpoint {
                 () => {{
                     let mut sources = HashMap::new();
-                    for (source_id, state) in &states {
+                    for (source_id, state) in states.read().await.iter() {
                         if let Ok(guard) = state.lock() {
                             sources.insert(
                                 source_id.clone(),
                                 SourceCheckpoint {
-                                    path: source_configs[source_id].path.clone(),
+                                    path: guard.path.clone(),
                                     offset: guard.offset,
                                     inode: guard.inode,
                                     last_timestamp: guard.last_timestamp,
+                                    fingerprint: guard.fingerprint,
                                 },
                             );
                         }
@@ -855,9 +877,8 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
             .unwrap_or_else(|| "collector".to_string());
         let checkpoint_config_version = config_version;
 
-        // Build source checkpoint states from shared_source_states
-        let source_checkpoint_states: Arc<RwLock<HashMap<String, SharedSourceState>>> =
-            Arc::new(RwLock::new(shared_source_states.clone()));
+        // Source checkpoint states are shared with discovery, which adds files at runtime
+        let source_checkpoint_states = Arc::clone(&shared_source_states);
 
         let checkpoint_manager = CheckpointManager::new(storage.clone(), Duration::from_secs(10));
         tokio::spawn(async move {
@@ -875,7 +896,6 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
 
     // === Phase 11: Web server (always) ===
     info!("Starting web server on {}", config.web.listen);
-    let web_storage = storage.clone();
     let web_config = config.web.clone();
     let web_shutdown_rx = shutdown_rx.clone();
     // Use shared_processor for web server, or create a dummy one if not storing logs
@@ -885,26 +905,30 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
         // Create an empty processor for the web server when not storing logs
         Arc::new(RwLock::new(FiberProcessor::from_config(&config, config_version)?))
     };
-    let web_shared_config = Arc::clone(&shared_config);
-    let web_shared_config_yaml = Arc::clone(&shared_config_yaml);
-    let web_version = Arc::clone(&shared_version);
-    let web_reprocess_state = Arc::clone(&shared_reprocess_state);
-    let web_config_path = config_path.clone();
+    let app_state = AppState {
+        storage: storage.clone(),
+        fiber_types: Arc::new(config.fiber_types_or_empty().clone()),
+        fiber_processor: web_processor,
+        config: Arc::clone(&shared_config),
+        config_version: Arc::clone(&shared_version),
+        config_path: config_path.clone(),
+        config_yaml: Arc::clone(&shared_config_yaml),
+        reprocess_state: Arc::clone(&shared_reprocess_state),
+        source_states: Arc::clone(&shared_source_states),
+        sequencer_status: Arc::clone(&sequencer_status),
+        source_control,
+    };
     // Extract collector API state for the web server (if serving collector protocol)
     let web_collector_state = collector_state.as_ref().map(|cs| Arc::clone(&cs.api_state));
+    let web_ingest_hub = (!ingest_hub.is_empty()).then(|| Arc::new(ingest_hub));
 
     let web_handle = tokio::spawn(async move {
         run_server(
-            web_storage,
-            web_processor,
-            web_shared_config,
-            web_version,
-            web_reprocess_state,
-            web_config_path,
-            web_shared_config_yaml,
+            app_state,
             web_config,
             web_shutdown_rx,
             web_collector_state,
+            web_ingest_hub,
         )
         .await
         .map_err(|e| RunError::WebServer(e.to_string()))
@@ -950,6 +974,11 @@ async fn run_pipeline(config_path: &PathBuf) -> Result<(), RunError> {
             for handle in &collector_tasks {
                 handle.abort();
             }
+
+            // Stop file discovery for glob/directory sources
+            for handle in &discovery_tasks {
+                handle.abort();
+            }
         }
         result = async {
             if let Some(task) = sequencer_wait_task {
@@ -1178,11 +1207,18 @@ async fn run_epoch_batcher_task(
     source_states: Arc<RwLock<HashMap<String, Arc<Mutex<CollectorSourceState>>>>>,
 ) {
     while let Some(record) = seq_rx.recv().await {
-        // Update source watermark
-        if let Some(source_state) = source_states.read().await.get(&record.source_id) {
+        // Update source watermark (files discovered at runtime are added on first record)
+        {
+            let mut states = source_states.write().await;
+            let source_state = states.entry(record.source_id.clone()).or_insert_with(|| {
+                Arc::new(Mutex::new(CollectorSourceState {
+                    watermark: None,
+                    active: true,
+                }))
+            });
             if let Ok(mut state) = source_state.lock() {
                 state.watermark = Some(record.timestamp);
-            }
+            };
         }
 
         // Compute minimum watermark
@@ -1456,7 +1492,7 @@ async fn run_collector_checkpoint_task(
     mut manager: CheckpointManager,
     batcher: Arc<Mutex<EpochBatcher>>,
     buffer: Arc<Mutex<BatchBuffer>>,
-    source_checkpoint_states: Arc<RwLock<HashMap<String, SharedSourceState>>>,
+    source_checkpoint_states: SharedSourceStates,
     collector_id: String,
     config_version: u64,
 ) {
@@ -1479,10 +1515,11 @@ async fn run_collector_checkpoint_task(
                     sources.insert(
                         source_id.clone(),
                         SourceCheckpoint {
-                            path: PathBuf::new(),
+                            path: state.path.clone(),
                             offset: state.offset,
                             inode: state.inode,
                             last_timestamp: state.last_timestamp,
+                            fingerprint: state.fingerprint,
                         },
                     );
                 }
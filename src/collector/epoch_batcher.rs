@@ -155,6 +155,9 @@ mod tests {
             source_id: "test_source".to_string(),
             raw_text: text.to_string(),
             file_offset: 0,
+            fields: Default::default(),
+            labels: Default::default(),
+            late: false,
         }
     }
 
@@ -4,7 +4,8 @@ use crate::collector::batch_buffer::{BatchBuffer, BufferError, BufferStats};
 use crate::collector::epoch_batcher::EpochBatcher;
 use crate::collector::server::start_server;
 use crate::config::types::{CollectorServingConfig, Config};
-use crate::sequencer::merge::{run_sequencer, SequencerRunConfig};
+use crate::sequencer::merge::{run_sequencer_with_registrations, SequencerRunConfig};
+use crate::source::discovery::{run_discovery, SourceDiscovery};
 use crate::source::reader::{LogRecord, SourceReader};
 use crate::storage::checkpoint::{
     BatchBufferCheckpoint, CheckpointManager, CollectorCheckpoint, EpochBatcherCheckpoint,
@@ -140,51 +141,56 @@ impl CollectorRunner {
         let source_checkpoint_states: Arc<RwLock<HashMap<String, crate::storage::checkpoint::SharedSourceState>>> =
             Arc::new(RwLock::new(HashMap::new()));
 
+        let mut discoveries = Vec::new();
         for (source_id, source_config) in &self.config.sources {
-            // Check if we have a checkpoint for this source
-            let checkpoint_offset = checkpoint_opt
-                .as_ref()
-                .and_then(|cp| cp.sources.get(source_id))
-                .map(|src_cp| src_cp.offset);
-
-            let reader = if let Some(offset) = checkpoint_offset {
-                if offset > 0 {
-                    info!(
-                        source_id = %source_id,
-                        path = %source_config.path.display(),
-                        offset = offset,
-                        "Creating source reader from checkpoint offset"
-                    );
-                    SourceReader::new_with_offset(
-                        source_id.clone(),
-                        source_config,
-                        self.config.pipeline.errors.on_parse_error,
-                        offset,
-                    )?
-                } else {
-                    info!(
-                        source_id = %source_id,
-                        path = %source_config.path.display(),
-                        "Creating source reader (checkpoint offset is 0)"
-                    );
-                    SourceReader::new(
-                        source_id.clone(),
-                        source_config,
-                        self.config.pipeline.errors.on_parse_error,
-                    )?
-                }
-            } else {
+            // Glob/directory sources expand to one reader per discovered file
+            if source_config.source_type.is_discovered() {
+                let mut discovery = SourceDiscovery::new(source_id, source_config);
+                let discovered = discovery.initial_readers(
+                    checkpoint_opt.as_ref().map(|cp| &cp.sources),
+                    self.config.pipeline.errors.on_parse_error,
+                )?;
                 info!(
                     source_id = %source_id,
                     path = %source_config.path.display(),
-                    "Creating source reader (no checkpoint)"
+                    files = discovered.len(),
+                    "Creating source readers for discovered files"
                 );
-                SourceReader::new(
-                    source_id.clone(),
-                    source_config,
-                    self.config.pipeline.errors.on_parse_error,
-                )?
-            };
+
+                for reader in discovered {
+                    let (reader, shared_state) = reader.with_shared_state();
+                    let file_source_id = reader.source_id().to_string();
+                    source_checkpoint_states
+                        .write()
+                        .await
+                        .insert(file_source_id.clone(), shared_state);
+                    source_states.write().await.insert(
+                        file_source_id,
+                        Arc::new(Mutex::new(SourceState {
+                            watermark: None,
+                            active: true,
+                        })),
+                    );
+                    readers.push(reader);
+                }
+
+                if source_config.read.follow {
+                    discoveries.push(discovery);
+                }
+                continue;
+            }
+
+            info!(
+                source_id = %source_id,
+                path = %source_config.path.display(),
+                "Creating source reader"
+            );
+            let reader = SourceReader::resume(
+                source_id.clone(),
+                source_config,
+                self.config.pipeline.errors.on_parse_error,
+                checkpoint_opt.as_ref().and_then(|cp| cp.sources.get(source_id)),
+            )?;
 
             // Wrap reader with shared state for checkpoint tracking
             let (reader, shared_state) = reader.with_shared_state();
@@ -209,7 +215,7 @@ impl CollectorRunner {
             readers.push(reader);
         }
 
-        if readers.is_empty() {
+        if readers.is_empty() && discoveries.is_empty() {
             return Err(CollectorError::Config(
                 "No sources configured".to_string(),
             ));
@@ -226,7 +232,19 @@ impl CollectorRunner {
         // Start sequencer
         info!("Starting sequencer");
         let sequencer_config = SequencerRunConfig::from(&self.config.sequencer);
-        let mut sequencer_handle = run_sequencer(readers, seq_tx, sequencer_config).await?;
+        let (registrar, registrations) = mpsc::channel(100);
+        for discovery in discoveries {
+            tokio::spawn(run_discovery(
+                discovery,
+                registrar.clone(),
+                Arc::clone(&source_checkpoint_states),
+                self.config.pipeline.errors.on_parse_error,
+            ));
+        }
+        drop(registrar);
+        let mut sequencer_handle =
+            run_sequencer_with_registrations(readers, registrations, seq_tx, sequencer_config)
+                .await?;
 
         // Start epoch batcher task
         info!("Starting epoch batcher task");
@@ -401,11 +419,18 @@ async fn run_epoch_batcher(
 ) {
 
     while let Some(record) = seq_rx.recv().await {
-        // Update source watermark
-        if let Some(source_state) = source_states.read().await.get(&record.source_id) {
+        // Update source watermark (files discovered at runtime are added on first record)
+        {
+            let mut states = source_states.write().await;
+            let source_state = states.entry(record.source_id.clone()).or_insert_with(|| {
+                Arc::new(Mutex::new(SourceState {
+                    watermark: None,
+                    active: true,
+                }))
+            });
             if let Ok(mut state) = source_state.lock() {
                 state.watermark = Some(record.timestamp);
-            }
+            };
         }
 
         // Compute minimum watermark across all sources
@@ -536,10 +561,11 @@ async fn run_checkpoint_task(
                     sources.insert(
                         source_id.clone(),
                         SourceCheckpoint {
-                            path: std::path::PathBuf::new(), // Path not needed for restore
+                            path: state.path.clone(),
                             offset: state.offset,
                             inode: state.inode,
                             last_timestamp: state.last_timestamp,
+                            fingerprint: state.fingerprint,
                         },
                     );
                     debug!(
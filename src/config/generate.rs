@@ -26,6 +26,11 @@ pub fn generate_starter_config() -> String {
 # Define local log files to ingest. Each source needs a unique ID and timestamp
 # config. Omit this section entirely if this instance only pulls from remote
 # collectors.
+#
+# While any source follows, sources can be added (POST /api/sources with
+# 'name' and 'yaml_content') and removed (DELETE /api/sources/<name>) without
+# a restart. Each change is recorded as a new config version; activating a
+# version also starts and stops the sources it adds, removes or changes.
 
 sources:
   nginx_access:
@@ -51,6 +56,13 @@ sources:
     read:
       start: beginning
       follow: true
+    # Labels are stored with every log of the source. They can be filtered
+    # on in the API (/api/logs?labels=host:web1) and matched by fiber
+    # patterns under 'labels:' like record fields. Logs pulled from a remote
+    # collector also get a 'collector' label with its id.
+    labels:
+      host: web1
+      env: prod
 
   program1:
     type: file
@@ -82,6 +94,204 @@ sources:
       start: beginning
       follow: true
 
+  # Set 'include_rotated: true' on a file source to first read its rotated
+  # generations (app.log.2.gz, app.log.1, ...) oldest first, then follow the
+  # live file. Checkpoints track the position across rotations.
+  #
+  # archived_app:
+  #   type: file
+  #   path: /var/log/app.log
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  #     format: iso8601
+  #   read:
+  #     start: beginning
+  #     follow: true
+  #     include_rotated: true
+
+  # Strptime timestamps without an offset are read as UTC unless the source
+  # sets an IANA 'timezone'. Formats without a year (classic syslog) take
+  # the current year, or 'assume_year' for the first line, and roll over at
+  # New Year. 'clock_offset' corrects a host whose clock is known to be off.
+  #
+  # legacy_box:
+  #   type: file
+  #   path: /var/log/messages
+  #   timestamp:
+  #     pattern: '^(?P<ts>\w{3} [ \d]\d \d{2}:\d{2}:\d{2})'
+  #     format: '%b %e %H:%M:%S'
+  #     timezone: America/New_York
+  #     clock_offset: -2s           # This host's clock runs two seconds fast
+  #   read:
+  #     start: beginning
+  #     follow: true
+
+  # 'drop_if' discards noisy records before they are stored, and 'redact'
+  # rewrites sensitive values in the stored text and fields. A rule either
+  # replaces matches or keeps a keyed hash so the value can still be used as
+  # a fiber key. The hash key is read from the environment variable named by
+  # 'hash_key_env' or the file named by 'hash_key_file', so it isn't stored
+  # with the config. With a 'value' capture group only that part of the match
+  # is rewritten. Counts are served at /api/sources/stats.
+  #
+  # checkout:
+  #   type: file
+  #   path: /var/log/checkout.log
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  #     format: iso8601
+  #   read:
+  #     start: beginning
+  #     follow: true
+  #   drop_if:
+  #     - 'GET /(health|ready)z? '
+  #   redact:
+  #     - pattern: '[\w.+-]+@[\w-]+(\.[\w-]+)+'
+  #       replace: '<email>'
+  #     - pattern: '\b(?:\d[ -]?){13,16}\b'
+  #       replace: '<card>'
+  #     - pattern: 'token=(?P<value>[\w-]+)'
+  #       hash_key_env: NOIL_REDACT_KEY
+
+  # By default a line with a timestamp starts a record and indented lines
+  # continue it. 'read.multiline' sets the boundary explicitly with a
+  # 'start' or a 'continue' regex; the record's timestamp is then the first
+  # one found in its lines. 'max_lines' and 'max_bytes' split runaway
+  # records, and 'flush_after' emits the last record of a followed file
+  # without waiting for the next one to start.
+  #
+  # java_app:
+  #   type: file
+  #   path: /var/log/java-app.log
+  #   timestamp:
+  #     pattern: '(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2},\d{3})'
+  #     format: '%Y-%m-%d %H:%M:%S,%3f'
+  #   read:
+  #     start: beginning
+  #     follow: true
+  #     multiline:
+  #       continue: '^(\s+at |\s+\.\.\.|Caused by:)'
+  #       max_lines: 500
+  #       flush_after: 2s
+
+  # Sources that mix line formats list further 'fallbacks', tried in order
+  # on lines the main pattern doesn't match. 'format: auto' recognizes
+  # RFC 3339, ISO 8601, CLF, syslog and epoch timestamps; without a pattern
+  # it finds them anywhere in the line. Per-pattern match counts are served
+  # at /api/sources/stats.
+  #
+  # mixed_app:
+  #   type: file
+  #   path: /var/log/mixed.log
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  #     format: iso8601
+  #     fallbacks:
+  #       - pattern: '\[(?P<ts>\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4})\]'
+  #         format: '%d/%b/%Y:%H:%M:%S %z'
+  #       - pattern: ''
+  #         format: auto
+  #   read:
+  #     start: beginning
+  #     follow: true
+
+  # Glob sources read every matching file as its own source, with an ID of
+  # the form '<source>/<relative path>' (e.g. 'worker_logs/worker-1.log').
+  # New files are picked up while following. Use 'type: directory' with a
+  # directory path to read every file in that directory.
+  #
+  # Gzip (.gz) and zstd (.zst) files are decompressed transparently, so a
+  # pattern like '/var/log/workers/*.log*' also backfills archived logs.
+  #
+  # worker_logs:
+  #   type: glob
+  #   path: /var/log/workers/*.log
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  #     format: iso8601
+  #   read:
+  #     start: beginning
+  #     follow: true
+  #     discover_interval: 5s       # How often to scan for new files
+
+  # Sources that write one JSON object per line can set 'format: json' and
+  # point at the timestamp field instead of matching it with a regex. Lines
+  # are stored unchanged; fiber patterns can extract attributes from them with
+  # 'json:' (see FIBER TYPES below).
+  #
+  # api_json:
+  #   type: file
+  #   path: /var/log/api/api.json
+  #   format: json
+  #   timestamp:
+  #     pointer: /time                # JSON pointer, e.g. /@timestamp or /meta/ts
+  #     format: iso8601               # Numbers work with 'epoch' and 'epoch_ms'
+  #   read:
+  #     start: beginning
+  #     follow: true
+
+  # Container runtime logs: 'docker' (json-file driver) or 'cri' (Kubernetes).
+  # Partial lines are joined, the runtime's timestamp is used unless a
+  # 'timestamp.pattern' is given, and 'stream' plus the namespace, pod and
+  # container from /var/log/pods paths become fields for 'fields:' patterns.
+  #
+  # pods:
+  #   type: glob
+  #   path: /var/log/pods/*/*/*.log
+  #   format: cri
+  #   read:
+  #     start: end
+  #     follow: true
+
+  # Stdin and command sources read lines from a pipe instead of a file, e.g.
+  # 'kubectl logs -f deploy/api | noil' or a command that noil runs itself.
+  # They take no 'path' or 'read' section and always start from scratch.
+  #
+  # piped:
+  #   type: stdin
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  #     format: iso8601
+  #
+  # journal:
+  #   type: command
+  #   command: [journalctl, -o, short-iso, -f]
+  #   restart:
+  #     policy: on_failure            # never | on_failure | always
+  #     backoff: 1s                   # Doubles after each quick exit
+  #     max_backoff: 1m
+  #   timestamp:
+  #     pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}[+-]\d{4})'
+  #     format: '%Y-%m-%dT%H:%M:%S%z'
+
+  # Syslog sources receive RFC 3164 / RFC 5424 messages over the network. The
+  # header timestamp becomes the record timestamp, and the header's hostname,
+  # app_name, procid, msgid, facility and severity become record fields that
+  # fiber patterns can match with 'fields:' (see FIBER TYPES below).
+  #
+  # network_devices:
+  #   type: syslog
+  #   syslog:
+  #     listen: 0.0.0.0:5514
+  #     protocol: udp                 # udp | tcp (newline or octet-counted)
+  #     lateness: 5s                  # How far messages may trail arrival time
+
+  # Http sources receive records pushed to the web server by log shippers such
+  # as Fluent Bit or Vector, at web.listen:
+  #   POST /ingest/<source>                     newline-delimited JSON
+  #   POST /ingest/<source>/loki/api/v1/push    Loki push API (JSON)
+  #   POST /ingest/<source>/_bulk               Elasticsearch bulk API
+  # '/<source>' may be omitted when only one http source is configured. JSON
+  # objects take their timestamp from '@timestamp', 'timestamp', 'time' or
+  # 'ts', their line from 'message', 'msg', 'log' or 'line', and the other
+  # keys become record fields. Full buffers are answered with 429 (drop
+  # strategies) or 503 (block strategy, after waiting 10s).
+  #
+  # shipped:
+  #   type: http
+  #   http:
+  #     lateness: 5s                  # How far records may trail arrival time
+
 # =============================================================================
 # REMOTE COLLECTORS (optional)
 # =============================================================================
@@ -179,6 +389,13 @@ fiber_types:
       - name: dst_port
         type: int
 
+      # 'mode' sets how values from successive logs (and merged fibers)
+      # combine: last (default), first, set (distinct values, stored as a
+      # list), count, sum, min or max. Keys must use first or last.
+      # - name: bytes
+      #   type: int
+      #   mode: sum
+
       # Derived attributes: computed via interpolation from other attributes
       # Only defined when all referenced attributes have values
       - name: connection
@@ -210,6 +427,93 @@ fiber_types:
 
           - regex: 'thread-(?P<program2_thread>\d+)'
 
+      # Patterns can also match record fields, such as those parsed from
+      # syslog headers. Every listed field must be present and match, and
+      # named groups are captured as attributes. 'regex' defaults to matching
+      # any line.
+      #
+      # network_devices:
+      #   patterns:
+      #     - fields:
+      #         app_name: '^dhcpd$'
+      #       regex: 'DHCPACK on (?P<ip>\S+) to (?P<mac>[0-9a-f:]+)'
+
+      # JSON log lines can fill attributes by JSON pointer instead of regex
+      # captures. Every pointer must resolve for the pattern to match.
+      #
+      # api_json:
+      #   patterns:
+      #     - json:
+      #         ip: /client/ip
+      #         mac: /client/mac
+
+      # 'when:' guards only let a pattern apply when every condition holds;
+      # otherwise the log falls through to the next pattern. A guard reads
+      # the attribute extracted by the pattern, or else the value already on
+      # the fiber the log would join. Conditions: eq, ne, gt, gte, lt, lte,
+      # in (a list; CIDR blocks for ip attributes) and matches (a regex).
+      #
+      # web_access:
+      #   patterns:
+      #     - regex: '^(?P<ip>\S+) .* (?P<status>\d{3}) '
+      #       close: true
+      #       when:
+      #         - attribute: status
+      #           gte: 500
+      #         - attribute: ip
+      #           in: [10.0.0.0/8]
+      #
+      # 'mark: error' counts the logs a pattern matches toward the fiber's
+      # error_count, which fiber queries can filter and sort on.
+      #
+      #     - regex: 'upstream timed out'
+      #       mark: error
+
+  # A fiber type can nest inside another with 'parent': each fiber is linked
+  # to the open fiber of the parent type with the same values for the 'on'
+  # attributes (see /api/fibers/:id/children and /api/fibers/:id/parents).
+  #
+  # db_query:
+  #   parent:
+  #     fiber_type: request_trace
+  #     on: [program2_thread]
+  #   temporal:
+  #     max_gap: 5s
+  #   attributes:
+  #     - name: query_id
+  #       type: string
+  #       key: true
+  #     - name: program2_thread
+  #       type: string
+  #   sources:
+  #     program2:
+  #       patterns:
+  #         - regex: 'thread-(?P<program2_thread>\d+) query (?P<query_id>\w+)'
+
+  # 'sequence' lists the steps a fiber's logs are expected to reach, in
+  # order; patterns name the step they reach with 'step'. 'within' limits the
+  # time since the previous step. A closed fiber gets a sequence_violation
+  # attribute, and fibers that skip, reorder or never reach a step also get a
+  # sequence_violation_reason. Fibers closed by a flush (on shutdown or
+  # config activation) only get them if they already broke the sequence.
+  #
+  # order:
+  #   sequence:
+  #     - step: received
+  #     - step: charged
+  #       within: 30s
+  #     - step: shipped
+  #   sources:
+  #     program1:
+  #       patterns:
+  #         - regex: 'order (?P<order_id>\d+) received'
+  #           step: received
+  #         - regex: 'order (?P<order_id>\d+) charged'
+  #           step: charged
+  #         - regex: 'order (?P<order_id>\d+) shipped'
+  #           step: shipped
+  #           close: true
+
   # Example: single-threaded log where consecutive lines group until gap
   simple_log:
     description: "Groups consecutive log lines from a single-threaded service"
@@ -245,6 +549,9 @@ pipeline:
     # Checkpoints are stored in the DuckDB database (no separate file)
     enabled: true
     interval_seconds: 30
+  # Fiber types are processed in parallel, spread across this many threads,
+  # when a batch of logs is large enough to be worth it (default: 1)
+  # fiber_workers: 4
 
 # =============================================================================
 # SEQUENCER SETTINGS
@@ -253,6 +560,18 @@ pipeline:
 sequencer:
   batch_epoch_duration: 10s
   watermark_safety_margin: 1s
+  # Logs are emitted in order only up to the slowest source's watermark, so a
+  # followed file that stops writing holds back all the others. After
+  # 'idle_timeout' without new records, a source's watermark follows the wall
+  # clock instead until it writes again. Sources can override it with
+  # 'read.idle_timeout'. The current bottleneck is served at
+  # /api/sequencer/status. (default: off)
+  # idle_timeout: 30s
+  # A record older than records already emitted is late: 'emit_anyway'
+  # processes it out of order (tagged late), 'drop' discards it and
+  # 'side_table' stores it in the late_logs table (/api/logs/late) without
+  # processing it. Late counts per source are in /api/sequencer/status.
+  late_records: emit_anyway
 
 # =============================================================================
 # STORAGE SETTINGS
@@ -1,9 +1,13 @@
 use super::types::*;
 use crate::config::{expand_env_vars, expand_tilde};
+use crate::fiber::guard::CompiledGuard;
+use crate::fiber::sequence::{VIOLATION_ATTRIBUTE, VIOLATION_REASON_ATTRIBUTE};
+use crate::source::timestamp::{parse_timezone, TimestampFormat};
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -160,9 +164,15 @@ pub fn create_auto_source_fiber_config(source_name: &str) -> FiberTypeConfig {
         FiberSourceConfig {
             patterns: vec![PatternConfig {
                 regex: ".+".to_string(),
+                fields: Default::default(),
+                labels: Default::default(),
+                json: Default::default(),
                 release_matching_peer_keys: vec![],
                 release_self_keys: vec![],
                 close: false,
+                when: vec![],
+                mark: None,
+                step: None,
             }],
         },
     );
@@ -181,9 +191,12 @@ pub fn create_auto_source_fiber_config(source_name: &str) -> FiberTypeConfig {
             attr_type: AttributeType::String,
             key: true,
             derived: Some(source_name.to_string()),
+            mode: Default::default(),
         }],
         sources: source_patterns,
         is_source_fiber: true,
+        parent: None,
+        sequence: vec![],
     }
 }
 
@@ -214,6 +227,34 @@ fn validate_config(config: &Config) -> Result<(), ConfigError> {
     // Validate capability-based requirements
     validate_config_capabilities(config, &mut errors);
 
+    if config.sequencer.idle_timeout == Some(Duration::ZERO) {
+        errors.push("sequencer.idle_timeout must be greater than 0".to_string());
+    }
+
+    if config.pipeline.fiber_workers == Some(0) {
+        errors.push("pipeline.fiber_workers must be greater than 0".to_string());
+    }
+
+    // Validate each source
+    for (source_name, source) in &config.sources {
+        validate_source(source_name, source, &mut errors);
+    }
+
+    // Stdin can only be consumed once
+    let mut stdin_sources: Vec<&str> = config
+        .sources
+        .iter()
+        .filter(|(_, source)| source.source_type == SourceType::Stdin)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if stdin_sources.len() > 1 {
+        stdin_sources.sort_unstable();
+        errors.push(format!(
+            "only one stdin source is allowed, found: {}",
+            stdin_sources.join(", ")
+        ));
+    }
+
     // Validate each fiber type
     for (fiber_type_name, fiber_type) in config.fiber_types_or_empty() {
         validate_fiber_type(fiber_type_name, fiber_type, config, &mut errors);
@@ -226,6 +267,23 @@ fn validate_config(config: &Config) -> Result<(), ConfigError> {
     }
 }
 
+/// Parse and validate a single source definition, as added at runtime.
+/// Environment variables and `~` are expanded as when loading a config file.
+pub fn parse_source_config(name: &str, yaml: &str) -> Result<SourceConfig, ConfigError> {
+    let yaml = expand_env_vars(yaml);
+    check_unexpanded_vars(&yaml)?;
+    let mut source: SourceConfig = serde_yaml::from_str(&yaml)?;
+    source.path = expand_tilde(&source.path);
+
+    let mut errors = Vec::new();
+    validate_source(name, &source, &mut errors);
+    if errors.is_empty() {
+        Ok(source)
+    } else {
+        Err(ConfigError::ValidationList(errors))
+    }
+}
+
 fn validate_config_capabilities(config: &Config, errors: &mut Vec<String>) {
     // Must have at least one input (local sources or remote collectors)
     if !config.has_local_sources() && !config.has_remote_sources() {
@@ -273,6 +331,295 @@ fn validate_config_capabilities(config: &Config, errors: &mut Vec<String>) {
     }
 }
 
+fn validate_source(source_name: &str, source: &SourceConfig, errors: &mut Vec<String>) {
+    let prefix = format!("source '{}'", source_name);
+
+    if source.source_type.is_discovered() {
+        // Discovered files get IDs like "<source>/<file>", so the source name itself
+        // must not contain a separator
+        if source_name.contains('/') {
+            errors.push(format!(
+                "{}: {} source names cannot contain '/'",
+                prefix,
+                if source.source_type == SourceType::Glob { "glob" } else { "directory" }
+            ));
+        }
+
+        if source.source_type == SourceType::Glob {
+            if let Err(e) = glob::Pattern::new(&source.path.to_string_lossy()) {
+                errors.push(format!(
+                    "{}: invalid glob pattern '{}': {}",
+                    prefix,
+                    source.path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    if source.source_type.is_stream() {
+        if !source.path.as_os_str().is_empty() {
+            errors.push(format!(
+                "{}: path is not supported for stdin, command, syslog, or http sources",
+                prefix
+            ));
+        }
+    } else if source.path.as_os_str().is_empty() {
+        errors.push(format!("{}: path is required", prefix));
+    }
+
+    if source.source_type == SourceType::Command {
+        if source.command.first().is_none_or(|program| program.is_empty()) {
+            errors.push(format!(
+                "{}: command sources require a non-empty command",
+                prefix
+            ));
+        }
+    } else if !source.command.is_empty() || source.restart.is_some() {
+        errors.push(format!(
+            "{}: command and restart are only supported for command sources",
+            prefix
+        ));
+    }
+
+    if source.source_type == SourceType::Syslog {
+        match &source.syslog {
+            Some(syslog) if syslog.listen.is_empty() => {
+                errors.push(format!("{}: syslog.listen cannot be empty", prefix));
+            }
+            Some(_) => {}
+            None => errors.push(format!(
+                "{}: syslog sources require a 'syslog' section with a listen address",
+                prefix
+            )),
+        }
+    } else {
+        if source.syslog.is_some() {
+            errors.push(format!(
+                "{}: syslog is only supported for syslog sources",
+                prefix
+            ));
+        }
+    }
+
+    if source.source_type == SourceType::Http {
+        // The source name is a path segment of its /ingest/<source> endpoints
+        if source_name.contains('/') {
+            errors.push(format!("{}: http source names cannot contain '/'", prefix));
+        }
+    } else if source.http.is_some() {
+        errors.push(format!("{}: http is only supported for http sources", prefix));
+    }
+
+    match source.format {
+        SourceFormat::Text => {
+            if !source.source_type.is_listener() && !source.timestamp.finds_timestamps() {
+                errors.push(format!("{}: timestamp is required", prefix));
+            }
+            if source.timestamp.pointer.is_some() {
+                errors.push(format!(
+                    "{}: timestamp.pointer is only supported for json sources",
+                    prefix
+                ));
+            }
+        }
+        SourceFormat::Json => {
+            if source.source_type.is_listener() {
+                errors.push(format!(
+                    "{}: format json is not supported for syslog or http sources",
+                    prefix
+                ));
+            } else {
+                match &source.timestamp.pointer {
+                    Some(pointer) if is_json_pointer(pointer) => {}
+                    Some(pointer) => errors.push(format!(
+                        "{}: timestamp.pointer '{}' must start with '/'",
+                        prefix, pointer
+                    )),
+                    None => errors.push(format!(
+                        "{}: json sources require timestamp.pointer (e.g. '/time')",
+                        prefix
+                    )),
+                }
+            }
+            if !source.timestamp.pattern.is_empty() {
+                errors.push(format!(
+                    "{}: timestamp.pattern is not used by json sources; use timestamp.pointer",
+                    prefix
+                ));
+            }
+        }
+        SourceFormat::Docker | SourceFormat::Cri => {
+            if source.source_type.is_listener() {
+                errors.push(format!(
+                    "{}: container log formats are not supported for syslog or http sources",
+                    prefix
+                ));
+            }
+            if source.timestamp.pointer.is_some() {
+                errors.push(format!(
+                    "{}: timestamp.pointer is only supported for json sources",
+                    prefix
+                ));
+            }
+        }
+    }
+
+    // Sources that find timestamps in their lines need to know how to parse them
+    let parses_timestamps = !source.source_type.is_listener()
+        && (source.format == SourceFormat::Json || source.timestamp.finds_timestamps());
+    if parses_timestamps && source.timestamp.format.is_empty() {
+        errors.push(format!("{}: timestamp.format is required", prefix));
+    }
+
+    // Fallbacks are further patterns, so they need a pattern-based source
+    if !source.timestamp.fallbacks.is_empty()
+        && (!parses_timestamps || source.format == SourceFormat::Json)
+    {
+        errors.push(format!(
+            "{}: timestamp.fallbacks are only supported for text and container sources with a timestamp pattern",
+            prefix
+        ));
+    }
+    for (i, fallback) in source.timestamp.fallbacks.iter().enumerate() {
+        let context = format!("{}: timestamp.fallbacks[{}]", prefix, i);
+        if fallback.format.is_empty() {
+            errors.push(format!("{}: format is required", context));
+        }
+        if !(fallback.pattern.is_empty() && fallback.format == "auto") {
+            validate_timestamp_pattern(&context, &fallback.pattern, errors);
+        }
+    }
+
+    // Zones and years only apply to strptime formats (which `auto` detection
+    // may pick); the others say everything about the instant they refer to
+    let formats: Vec<TimestampFormat> = if parses_timestamps {
+        std::iter::once(&source.timestamp.format)
+            .chain(source.timestamp.fallbacks.iter().map(|fallback| &fallback.format))
+            .map(|format| TimestampFormat::parse(format))
+            .filter(|format| matches!(format, TimestampFormat::Strptime(_) | TimestampFormat::Auto))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if let Some(timezone) = &source.timestamp.timezone {
+        if formats.is_empty() {
+            errors.push(format!(
+                "{}: timestamp.timezone only applies to strptime timestamp formats",
+                prefix
+            ));
+        } else if let Err(e) = parse_timezone(timezone) {
+            errors.push(format!("{}: timestamp.timezone: {}", prefix, e));
+        }
+    }
+    if source.timestamp.assume_year.is_some() && !formats.iter().any(TimestampFormat::lacks_year) {
+        errors.push(format!(
+            "{}: timestamp.assume_year only applies to strptime formats without a year",
+            prefix
+        ));
+    }
+
+    if let Some(multiline) = &source.read.multiline {
+        validate_multiline(&prefix, source, multiline, errors);
+    }
+
+    // Label names are used in JSON paths and API filters
+    for name in source.labels.keys() {
+        let mut chars = name.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            errors.push(format!(
+                "{}: invalid label name '{}': use letters, digits and underscores",
+                prefix, name
+            ));
+        }
+    }
+
+    for (i, pattern) in source.drop_if.iter().enumerate() {
+        if let Err(e) = Regex::new(pattern) {
+            errors.push(format!("{}: invalid drop_if[{}] regex: {}", prefix, i, e));
+        }
+    }
+    for (i, rule) in source.redact.iter().enumerate() {
+        if let Err(e) = Regex::new(&rule.pattern) {
+            errors.push(format!("{}: invalid redact[{}] regex: {}", prefix, i, e));
+        }
+        match (&rule.replace, &rule.hash_key_env, &rule.hash_key_file) {
+            (Some(_), None, None) | (None, None, Some(_)) => {}
+            (None, Some(var), None) if !var.is_empty() => {}
+            (None, Some(_), None) => errors.push(format!(
+                "{}: redact[{}]: hash_key_env cannot be empty",
+                prefix, i
+            )),
+            _ => errors.push(format!(
+                "{}: redact[{}]: exactly one of replace, hash_key_env or hash_key_file is required",
+                prefix, i
+            )),
+        }
+    }
+
+    // Discovered sources already read rotated files as sources of their own
+    if source.read.include_rotated && source.source_type != SourceType::File {
+        errors.push(format!(
+            "{}: read.include_rotated is only supported for file sources",
+            prefix
+        ));
+    }
+
+    if source.read.idle_timeout == Some(Duration::ZERO) {
+        errors.push(format!("{}: read.idle_timeout must be greater than 0", prefix));
+    }
+}
+
+fn validate_multiline(
+    prefix: &str,
+    source: &SourceConfig,
+    multiline: &MultilineConfig,
+    errors: &mut Vec<String>,
+) {
+    if source.source_type.is_listener() {
+        errors.push(format!(
+            "{}: read.multiline is not supported for syslog or http sources",
+            prefix
+        ));
+    }
+    if source.format == SourceFormat::Json {
+        errors.push(format!(
+            "{}: read.multiline is not supported for json sources",
+            prefix
+        ));
+    }
+    if multiline.start.is_some() && multiline.continue_pattern.is_some() {
+        errors.push(format!(
+            "{}: read.multiline.start and read.multiline.continue are mutually exclusive",
+            prefix
+        ));
+    }
+    for (key, pattern) in [
+        ("start", &multiline.start),
+        ("continue", &multiline.continue_pattern),
+    ] {
+        if let Some(Err(e)) = pattern.as_deref().map(Regex::new) {
+            errors.push(format!(
+                "{}: invalid read.multiline.{} regex: {}",
+                prefix, key, e
+            ));
+        }
+    }
+    for (key, limit) in [
+        ("max_lines", multiline.max_lines),
+        ("max_bytes", multiline.max_bytes),
+    ] {
+        if limit == Some(0) {
+            errors.push(format!(
+                "{}: read.multiline.{} must be greater than 0",
+                prefix, key
+            ));
+        }
+    }
+}
+
 fn validate_fiber_type(
     fiber_type_name: &str,
     fiber_type: &FiberTypeConfig,
@@ -297,7 +644,18 @@ fn validate_fiber_type(
     // Validate timestamp patterns for referenced sources (skip when remote sources configured)
     if !config.has_remote_sources() {
         for (source_name, source_config) in &config.sources {
-            if fiber_type.sources.contains_key(source_name) {
+            // Syslog and http records carry their own timestamps, json
+            // sources locate theirs by pointer, container sources only use a
+            // pattern when one is given, and `auto` needs no pattern
+            if fiber_type.sources.contains_key(source_name)
+                && !source_config.source_type.is_listener()
+                && (source_config.format == SourceFormat::Text
+                    || source_config.format.is_container())
+                && !(source_config.timestamp.pattern.is_empty()
+                    && source_config.timestamp.format == "auto")
+                && !(source_config.format.is_container()
+                    && source_config.timestamp.pattern.is_empty())
+            {
                 validate_timestamp_pattern(
                     &format!("source '{}'", source_name),
                     &source_config.timestamp.pattern,
@@ -319,6 +677,22 @@ fn validate_fiber_type(
             ));
         }
         attr_map.insert(attr.name.clone(), attr);
+
+        // A key matches on a single value, and only numbers can be summed
+        if attr.key && !matches!(attr.mode, AttributeMode::First | AttributeMode::Last) {
+            errors.push(format!(
+                "{}: key attribute '{}' must use mode 'first' or 'last'",
+                prefix, attr.name
+            ));
+        }
+        if attr.mode == AttributeMode::Sum
+            && !matches!(attr.attr_type, AttributeType::Int | AttributeType::Float)
+        {
+            errors.push(format!(
+                "{}: attribute '{}' uses mode 'sum' but is not an int or float",
+                prefix, attr.name
+            ));
+        }
     }
 
     // Validate patterns
@@ -335,6 +709,110 @@ fn validate_fiber_type(
 
     // Validate derived attributes for circular dependencies
     validate_derived_attributes(&prefix, &fiber_type.attributes, errors);
+
+    if let Some(parent) = &fiber_type.parent {
+        validate_parent(&prefix, fiber_type_name, fiber_type, parent, config, errors);
+    }
+
+    validate_sequence(&prefix, fiber_type, errors);
+}
+
+fn validate_sequence(context: &str, fiber_type: &FiberTypeConfig, errors: &mut Vec<String>) {
+    let mut steps = HashSet::new();
+    for (i, step) in fiber_type.sequence.iter().enumerate() {
+        if !steps.insert(step.step.as_str()) {
+            errors.push(format!("{}: duplicate sequence step '{}'", context, step.step));
+        }
+        if i == 0 && step.within.is_some() {
+            errors.push(format!(
+                "{}: first sequence step '{}' has no previous step for 'within'",
+                context, step.step
+            ));
+        }
+    }
+
+    let marked: HashSet<&str> = fiber_type
+        .sources
+        .values()
+        .flat_map(|source| &source.patterns)
+        .filter_map(|pattern| pattern.step.as_deref())
+        .collect();
+    for step in &marked {
+        if !steps.contains(step) {
+            errors.push(format!(
+                "{}: pattern step '{}' is not in the sequence",
+                context, step
+            ));
+        }
+    }
+    for step in &fiber_type.sequence {
+        if !marked.contains(step.step.as_str()) {
+            errors.push(format!(
+                "{}: sequence step '{}' is not the step of any pattern",
+                context, step.step
+            ));
+        }
+    }
+
+    if !fiber_type.sequence.is_empty() {
+        for name in [VIOLATION_ATTRIBUTE, VIOLATION_REASON_ATTRIBUTE] {
+            if fiber_type.attributes.iter().any(|a| a.name == name) {
+                errors.push(format!(
+                    "{}: attribute '{}' is set by the sequence check",
+                    context, name
+                ));
+            }
+        }
+    }
+}
+
+fn validate_parent(
+    context: &str,
+    fiber_type_name: &str,
+    fiber_type: &FiberTypeConfig,
+    parent: &ParentConfig,
+    config: &Config,
+    errors: &mut Vec<String>,
+) {
+    let fiber_types = config.fiber_types_or_empty();
+    let Some(parent_type) = fiber_types.get(&parent.fiber_type) else {
+        errors.push(format!(
+            "{}: parent references non-existent fiber type '{}'",
+            context, parent.fiber_type
+        ));
+        return;
+    };
+
+    if parent.on.is_empty() {
+        errors.push(format!("{}: parent needs at least one attribute in 'on'", context));
+    }
+    for attr in &parent.on {
+        for (type_name, config) in [(fiber_type_name, fiber_type), (parent.fiber_type.as_str(), parent_type)] {
+            if !config.attributes.iter().any(|a| &a.name == attr) {
+                errors.push(format!(
+                    "{}: parent attribute '{}' is not defined on fiber type '{}'",
+                    context, attr, type_name
+                ));
+            }
+        }
+    }
+
+    // Following parents from here must not come back around
+    let mut seen = HashSet::from([fiber_type_name]);
+    let mut current = parent;
+    loop {
+        if !seen.insert(current.fiber_type.as_str()) {
+            errors.push(format!(
+                "{}: parent fiber types form a cycle through '{}'",
+                context, current.fiber_type
+            ));
+            break;
+        }
+        match fiber_types.get(&current.fiber_type).and_then(|t| t.parent.as_ref()) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
 }
 
 fn validate_timestamp_pattern(context: &str, pattern: &str, errors: &mut Vec<String>) {
@@ -357,6 +835,11 @@ fn validate_timestamp_pattern(context: &str, pattern: &str, errors: &mut Vec<Str
     }
 }
 
+/// An empty pointer refers to the whole document; anything else starts with '/'
+fn is_json_pointer(pointer: &str) -> bool {
+    pointer.is_empty() || pointer.starts_with('/')
+}
+
 fn validate_pattern(
     context: &str,
     pattern: &PatternConfig,
@@ -376,12 +859,51 @@ fn validate_pattern(
     };
 
     // Extract capture group names from regex
-    let capture_names: HashSet<String> = re
+    let mut capture_names: HashSet<String> = re
         .capture_names()
         .flatten()
         .map(|s| s.to_string())
         .collect();
 
+    // Field regexes can capture attributes too
+    for (field, field_regex) in &pattern.fields {
+        match Regex::new(field_regex) {
+            Ok(re) => capture_names.extend(re.capture_names().flatten().map(|s| s.to_string())),
+            Err(e) => errors.push(format!(
+                "{}: invalid regex '{}' for field '{}': {}",
+                context, field_regex, field, e
+            )),
+        }
+    }
+
+    // ...and so can label regexes
+    for (label, label_regex) in &pattern.labels {
+        match Regex::new(label_regex) {
+            Ok(re) => capture_names.extend(re.capture_names().flatten().map(|s| s.to_string())),
+            Err(e) => errors.push(format!(
+                "{}: invalid regex '{}' for label '{}': {}",
+                context, label_regex, label, e
+            )),
+        }
+    }
+
+    // JSON pointers extract attributes by name
+    for (attr_name, pointer) in &pattern.json {
+        if !is_json_pointer(pointer) {
+            errors.push(format!(
+                "{}: invalid JSON pointer '{}' for attribute '{}': must start with '/'",
+                context, pointer, attr_name
+            ));
+        }
+        if !attr_map.contains_key(attr_name) {
+            errors.push(format!(
+                "{}: json extracts '{}' which is not a defined attribute",
+                context, attr_name
+            ));
+        }
+        capture_names.insert(attr_name.clone());
+    }
+
     // Validate release_matching_peer_keys
     for key_name in &pattern.release_matching_peer_keys {
         // Must be a capture group in the pattern
@@ -432,6 +954,14 @@ fn validate_pattern(
             }
         }
     }
+
+    // Guards must name a defined attribute and compare it by its type
+    for guard in &pattern.when {
+        let attr_type = attr_map.get(&guard.attribute).map(|attr| attr.attr_type);
+        if let Err(e) = CompiledGuard::new(guard, attr_type) {
+            errors.push(format!("{}: when: {}", context, e));
+        }
+    }
 }
 
 fn validate_derived_attributes(
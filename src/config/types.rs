@@ -1,6 +1,6 @@
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -174,19 +174,274 @@ pub struct CollectorEndpoint {
 pub struct SourceConfig {
     #[serde(rename = "type")]
     pub source_type: SourceType,
+    /// File, glob pattern, or directory to read. Unused by stdin, command,
+    /// syslog, and http sources.
+    #[serde(default, skip_serializing_if = "path_is_empty")]
     pub path: PathBuf,
+    /// Program and arguments to run for command sources (e.g. `["journalctl", "-f"]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    /// What to do when a command source's process exits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RestartConfig>,
+    /// Listener settings for syslog sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syslog: Option<SyslogConfig>,
+    /// Optional settings for http sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpSourceConfig>,
+    /// Required for every source type except syslog and http, whose records
+    /// carry their own timestamps
+    #[serde(default)]
     pub timestamp: TimestampConfig,
+    /// How each line is parsed. Json sources take their timestamp from
+    /// `timestamp.pointer` instead of a regex.
+    #[serde(default, skip_serializing_if = "SourceFormat::is_text")]
+    pub format: SourceFormat,
+    #[serde(default)]
     pub read: ReadConfig,
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    /// Labels (e.g. `host`, `env`, `service`) stored with every log of the
+    /// source, filterable in the API and matchable by fiber patterns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Regexes of noisy lines (e.g. health checks) to drop before they are
+    /// stored. Multiline records are matched as a whole.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drop_if: Vec<String>,
+    /// Rewrites of sensitive values (emails, tokens, card numbers) in each
+    /// record's text and fields, applied in order before it is stored
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<RedactRule>,
+}
+
+/// Redaction of every match of `pattern`. If the pattern has a `value`
+/// capture group, only that part of the match is rewritten.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactRule {
+    pub pattern: String,
+    /// Replacement text, which may refer to capture groups (`$name`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace: Option<String>,
+    /// Replace matches with a keyed hash (HMAC-SHA256) instead, so that a
+    /// redacted value can still serve as a fiber key. The key is read from
+    /// this environment variable, keeping it out of the stored config. Use
+    /// the same key on every source whose values should correlate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_key_env: Option<String>,
+    /// Like `hash_key_env`, with the key read from this file. A trailing
+    /// newline is ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_key_file: Option<PathBuf>,
+}
+
+fn path_is_empty(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SourceType {
     File,
+    /// `path` is a glob pattern (e.g. `/var/log/app/*.log`); every matching
+    /// file is read as its own derived source.
+    Glob,
+    /// `path` is a directory; every regular file directly inside it is read
+    /// as its own derived source.
+    Directory,
+    /// Lines piped into noil's standard input.
+    Stdin,
+    /// Standard output of the process given by `command`.
+    Command,
+    /// RFC 3164 / RFC 5424 messages received on the `syslog.listen` address.
+    Syslog,
+    /// Records pushed to the web server's `/ingest` endpoints by log shippers.
+    Http,
+}
+
+impl SourceType {
+    /// Returns true if this source expands to multiple discovered files
+    pub fn is_discovered(&self) -> bool {
+        matches!(self, SourceType::Glob | SourceType::Directory)
+    }
+
+    /// Returns true if this source reads a pipe or socket rather than a file.
+    /// Stream sources can't seek, so they have no checkpointed position.
+    pub fn is_stream(&self) -> bool {
+        matches!(
+            self,
+            SourceType::Stdin | SourceType::Command | SourceType::Syslog | SourceType::Http
+        )
+    }
+
+    /// Returns true if records are received over the network. They carry their
+    /// own timestamps, and the source's watermark follows the current time.
+    pub fn is_listener(&self) -> bool {
+        matches!(self, SourceType::Syslog | SourceType::Http)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceFormat {
+    /// Plain text lines; the timestamp is found with `timestamp.pattern`
+    #[default]
+    Text,
+    /// One JSON object per line. The line is stored unchanged, and fiber
+    /// patterns can extract attributes from it by JSON pointer.
+    Json,
+    /// Docker json-file driver output. The record text is the container's
+    /// message and the timestamp is the runtime's, unless `timestamp.pattern`
+    /// is set to find one in the message.
+    Docker,
+    /// Kubernetes CRI log files (`<time> <stream> <P|F> <message>`), handled
+    /// like `docker`
+    Cri,
+}
+
+impl SourceFormat {
+    pub fn is_text(&self) -> bool {
+        *self == SourceFormat::Text
+    }
+
+    /// Container runtime log formats, whose lines wrap the container's output
+    pub fn is_container(&self) -> bool {
+        matches!(self, SourceFormat::Docker | SourceFormat::Cri)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogConfig {
+    /// Address to bind, e.g. `0.0.0.0:5514`
+    pub listen: String,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    /// How late a message may arrive relative to its header timestamp. The
+    /// source's watermark trails the current time by this much.
+    #[serde(default = "default_listener_lateness", with = "humantime_serde")]
+    pub lateness: Duration,
+}
+
+fn default_listener_lateness() -> Duration {
+    Duration::from_secs(5)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpSourceConfig {
+    /// How late a pushed record may arrive relative to its timestamp. The
+    /// source's watermark trails the current time by this much.
+    #[serde(default = "default_listener_lateness", with = "humantime_serde")]
+    pub lateness: Duration,
+}
+
+impl Default for HttpSourceConfig {
+    fn default() -> Self {
+        Self {
+            lateness: default_listener_lateness(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    #[default]
+    Udp,
+    /// Newline-delimited or octet-counted (RFC 6587) framing
+    Tcp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartConfig {
+    #[serde(default)]
+    pub policy: RestartPolicy,
+    /// Delay before the first restart; doubles after each consecutive restart
+    #[serde(default = "default_restart_backoff", with = "humantime_serde")]
+    pub backoff: Duration,
+    /// Upper bound for the restart delay. A process that ran at least this
+    /// long resets the delay to `backoff`.
+    #[serde(default = "default_restart_max_backoff", with = "humantime_serde")]
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::default(),
+            backoff: default_restart_backoff(),
+            max_backoff: default_restart_max_backoff(),
+        }
+    }
+}
+
+fn default_restart_backoff() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_restart_max_backoff() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Stop the source when the process exits
+    Never,
+    /// Restart only when the process exits with a failure status
+    #[default]
+    OnFailure,
+    /// Always restart the process when it exits
+    Always,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TimestampConfig {
+    /// Regex with a `ts` capture group, for text sources
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pattern: String,
+    /// JSON pointer to the timestamp (e.g. `/time` or `/@timestamp`), for
+    /// json sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// One of: strptime format string, `iso8601`, `epoch`, `epoch_ms`,
+    /// `epoch_us`, `epoch_ns`, `auto`. With `auto` and no pattern, common
+    /// layouts (RFC 3339, ISO 8601, CLF, syslog, epoch) are found anywhere in
+    /// the line.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub format: String,
+    /// Further patterns tried in order on lines where `pattern` finds no
+    /// timestamp, for sources that mix line formats. Text sources only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<TimestampPattern>,
+    /// IANA time zone (e.g. `Europe/Berlin`) of strptime timestamps without
+    /// an offset. Defaults to UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Correction added to every timestamp from a host whose clock is known
+    /// to be off, e.g. `-2s` for a clock running two seconds fast
+    #[serde(
+        default,
+        with = "clock_offset_format",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub clock_offset: Option<chrono::Duration>,
+    /// Year of the first timestamp when the strptime format has none (e.g.
+    /// `%b %d %H:%M:%S`). Later timestamps roll over into the next year at
+    /// New Year. Without it, the year is inferred from the current date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assume_year: Option<i32>,
+}
+
+impl TimestampConfig {
+    /// Whether timestamps are found in the line text, by a pattern or by
+    /// `auto` detection
+    pub fn finds_timestamps(&self) -> bool {
+        !self.pattern.is_empty() || self.format == "auto"
+    }
+}
+
+/// A fallback timestamp pattern with its own format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimestampPattern {
     pub pattern: String,
     pub format: String,
 }
@@ -195,6 +450,81 @@ pub struct TimestampConfig {
 pub struct ReadConfig {
     pub start: ReadStart,
     pub follow: bool,
+    /// How often glob/directory sources rescan for new files while following.
+    /// Defaults to 5s. Ignored for plain file sources.
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub discover_interval: Option<Duration>,
+    /// Read older rotated generations of the file (`app.log.2.gz`, `app.log.1`)
+    /// oldest first before following the live file. Only valid for file sources.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub include_rotated: bool,
+    /// How lines are grouped into records. Without it, a line with a
+    /// timestamp starts a record and indented lines without one continue it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiline: Option<MultilineConfig>,
+    /// Overrides `sequencer.idle_timeout` for this source
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_timeout: Option<Duration>,
+}
+
+/// Rules for grouping lines into multiline records
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MultilineConfig {
+    /// Lines matching this regex start a record; all others continue it.
+    /// The record's timestamp is the first one found in any of its lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// Lines matching this regex continue the previous record; all others
+    /// start one. Mutually exclusive with `start`.
+    #[serde(
+        default,
+        rename = "continue",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub continue_pattern: Option<String>,
+    /// Lines after which a record is split; the remaining lines form a new
+    /// record with the same timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
+    /// Size in bytes after which a record is split, like `max_lines`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+    /// Emit the last record of a followed input once no line has been added
+    /// to it for this long. Without it, the record waits for the next one
+    /// to start.
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub flush_after: Option<Duration>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Default for ReadConfig {
+    /// Read from the beginning without following (stdin and command
+    /// sources always read until their stream closes)
+    fn default() -> Self {
+        Self {
+            start: ReadStart::Beginning,
+            follow: false,
+            discover_interval: None,
+            include_rotated: false,
+            multiline: None,
+            idle_timeout: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -214,6 +544,35 @@ pub struct FiberTypeConfig {
     pub sources: HashMap<String, FiberSourceConfig>,
     #[serde(default)]
     pub is_source_fiber: bool,
+    /// Fiber type these fibers nest inside
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<ParentConfig>,
+    /// Order the fiber's logs are expected to reach the patterns' steps in.
+    /// Fibers that break it are flagged when they close.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<SequenceStepConfig>,
+}
+
+/// A step of a fiber type's expected sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceStepConfig {
+    /// Name given to the step by patterns' `step`
+    pub step: String,
+    /// Time allowed since the previous step
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub within: Option<Duration>,
+}
+
+/// Links each fiber to the open fiber of another type that has the same
+/// values for the `on` attributes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentConfig {
+    pub fiber_type: String,
+    pub on: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -244,6 +603,28 @@ pub struct AttributeConfig {
     #[serde(default)]
     pub key: bool,
     pub derived: Option<String>,
+    #[serde(default)]
+    pub mode: AttributeMode,
+}
+
+/// How an attribute combines the values seen across a fiber's logs, and
+/// across fibers when they merge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeMode {
+    /// Keep the first value seen
+    First,
+    /// Replace the value with each new one
+    #[default]
+    Last,
+    /// Collect the distinct values into a list
+    Set,
+    /// Count the values seen
+    Count,
+    /// Add up numeric values
+    Sum,
+    Min,
+    Max,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -263,13 +644,152 @@ pub struct FiberSourceConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatternConfig {
+    /// Regex matched against the raw log text. Defaults to matching any line.
+    #[serde(default)]
     pub regex: String,
+    /// Regexes matched against record fields (e.g. syslog `app_name`). Every
+    /// listed field must be present and match; named groups are captured like
+    /// those in `regex`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Regexes matched against the log's source labels (e.g. `host`), like
+    /// `fields`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Attributes read from a JSON log line, as attribute name to JSON pointer
+    /// (e.g. `request_id: /req/id`). Every pointer must resolve for the
+    /// pattern to match.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub json: BTreeMap<String, String>,
     #[serde(default)]
     pub release_matching_peer_keys: Vec<String>,
     #[serde(default)]
     pub release_self_keys: Vec<String>,
     #[serde(default)]
     pub close: bool,
+    /// Conditions on attribute values that must all hold for the pattern to
+    /// apply. A log whose guards fail falls through to the next pattern.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub when: Vec<GuardConfig>,
+    /// Counts the log in one of the fiber's metrics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mark: Option<PatternMark>,
+    /// Step of the fiber type's `sequence` that a matching log reaches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternMark {
+    /// Counted in the fiber's `error_count`
+    Error,
+}
+
+/// A condition on one attribute, e.g. `{ attribute: status, gte: 500 }`.
+///
+/// The attribute's value is the one the pattern extracted (or derived from
+/// it); when the pattern doesn't provide it, the value already set on the
+/// fiber the log would join is used. A guard on an attribute with no value
+/// fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawGuardConfig")]
+pub struct GuardConfig {
+    pub attribute: String,
+    #[serde(flatten)]
+    pub condition: GuardCondition,
+}
+
+/// A guard as written, so that a guard with several conditions (such as
+/// `gte` and `lt` for a range) is an error rather than silently keeping one
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawGuardConfig {
+    attribute: String,
+    eq: Option<GuardValue>,
+    ne: Option<GuardValue>,
+    gt: Option<f64>,
+    gte: Option<f64>,
+    lt: Option<f64>,
+    lte: Option<f64>,
+    #[serde(rename = "in")]
+    any_of: Option<Vec<GuardValue>>,
+    matches: Option<String>,
+}
+
+impl TryFrom<RawGuardConfig> for GuardConfig {
+    type Error = String;
+
+    fn try_from(raw: RawGuardConfig) -> Result<Self, Self::Error> {
+        let conditions: Vec<(&str, GuardCondition)> = [
+            raw.eq.map(|v| ("eq", GuardCondition::Eq(v))),
+            raw.ne.map(|v| ("ne", GuardCondition::Ne(v))),
+            raw.gt.map(|v| ("gt", GuardCondition::Gt(v))),
+            raw.gte.map(|v| ("gte", GuardCondition::Gte(v))),
+            raw.lt.map(|v| ("lt", GuardCondition::Lt(v))),
+            raw.lte.map(|v| ("lte", GuardCondition::Lte(v))),
+            raw.any_of.map(|v| ("in", GuardCondition::In(v))),
+            raw.matches.map(|v| ("matches", GuardCondition::Matches(v))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        match <[_; 1]>::try_from(conditions) {
+            Ok([(_, condition)]) => Ok(GuardConfig {
+                attribute: raw.attribute,
+                condition,
+            }),
+            Err(conditions) if conditions.is_empty() => Err(format!(
+                "guard on '{}' needs one of eq, ne, gt, gte, lt, lte, in or matches",
+                raw.attribute
+            )),
+            Err(conditions) => Err(format!(
+                "guard on '{}' has several conditions ({}); write a separate guard for each",
+                raw.attribute,
+                conditions
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardCondition {
+    Eq(GuardValue),
+    Ne(GuardValue),
+    Gt(f64),
+    Gte(f64),
+    Lt(f64),
+    Lte(f64),
+    /// Any of the listed values; for `ip` attributes entries may also be
+    /// CIDR blocks such as `10.0.0.0/8`
+    In(Vec<GuardValue>),
+    /// Regex matched against the attribute's value
+    Matches(String),
+}
+
+/// A value compared against an attribute, parsed as the attribute's type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GuardValue {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl std::fmt::Display for GuardValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GuardValue::Int(i) => write!(f, "{}", i),
+            GuardValue::Float(x) => write!(f, "{}", x),
+            GuardValue::Text(s) => f.write_str(s),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -280,6 +800,10 @@ pub struct PipelineConfig {
     pub errors: ErrorConfig,
     #[serde(default)]
     pub checkpoint: CheckpointConfig,
+    /// Threads fiber types are spread across for large batches of logs
+    /// (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiber_workers: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -326,6 +850,31 @@ pub struct SequencerConfig {
     pub batch_epoch_duration: Option<Duration>,
     #[serde(default, with = "duration_format")]
     pub watermark_safety_margin: Option<Duration>,
+    /// How long a followed source may go without new records before its
+    /// watermark follows the wall clock, so it stops holding back the other
+    /// sources. Unset, a quiet source holds them back until it writes again.
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_timeout: Option<Duration>,
+    /// What to do with a record older than records already emitted
+    #[serde(default)]
+    pub late_records: LateRecordPolicy,
+}
+
+/// Handling of records that arrive behind the emitted watermark
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateRecordPolicy {
+    /// Emit the record out of order, tagged as late
+    #[default]
+    EmitAnyway,
+    /// Discard the record
+    Drop,
+    /// Store the record in the `late_logs` table instead of processing it
+    SideTable,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -418,3 +967,42 @@ mod duration_format {
         }
     }
 }
+
+// Signed durations such as `-1.5s` or `+250ms`
+mod clock_offset_format {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(offset: &Option<chrono::Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match offset {
+            Some(offset) => {
+                let magnitude = offset.abs().to_std().map_err(serde::ser::Error::custom)?;
+                let sign = if *offset < chrono::Duration::zero() { "-" } else { "" };
+                serializer.serialize_str(&format!(
+                    "{}{}",
+                    sign,
+                    humantime::format_duration(magnitude)
+                ))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<chrono::Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let s = s.trim();
+        let (negative, magnitude) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let magnitude = humantime::parse_duration(magnitude.trim())
+            .map_err(|e| serde::de::Error::custom(format!("invalid clock offset '{}': {}", s, e)))?;
+        let magnitude = chrono::Duration::from_std(magnitude).map_err(serde::de::Error::custom)?;
+        Ok(Some(if negative { -magnitude } else { magnitude }))
+    }
+}
@@ -0,0 +1,253 @@
+use crate::config::types::{AttributeType, GuardCondition, GuardConfig, GuardValue};
+use crate::fiber::session::AttributeValue;
+use regex::Regex;
+use std::net::IpAddr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GuardError {
+    #[error("'{attribute}' is not a defined attribute")]
+    UndefinedAttribute { attribute: String },
+
+    #[error("numeric comparison on '{attribute}', which is a {attr_type:?} attribute")]
+    NotNumeric {
+        attribute: String,
+        attr_type: AttributeType,
+    },
+
+    #[error("'{value}' is not a valid {attr_type:?} value for '{attribute}'")]
+    InvalidValue {
+        attribute: String,
+        value: String,
+        attr_type: AttributeType,
+    },
+
+    #[error("invalid regex '{pattern}' for '{attribute}': {source}")]
+    Regex {
+        attribute: String,
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+/// A `when:` guard with its values parsed as the attribute's type
+#[derive(Debug)]
+pub struct CompiledGuard {
+    pub attribute: String,
+    attr_type: AttributeType,
+    test: GuardTest,
+}
+
+#[derive(Debug)]
+enum GuardTest {
+    Eq(AttributeValue),
+    Ne(AttributeValue),
+    Gt(f64),
+    Gte(f64),
+    Lt(f64),
+    Lte(f64),
+    In(Vec<Member>),
+    Matches(Regex),
+}
+
+#[derive(Debug)]
+enum Member {
+    Value(AttributeValue),
+    Network(IpNetwork),
+}
+
+impl CompiledGuard {
+    /// Compile a guard on an attribute of type `attr_type` (`None` when the
+    /// attribute isn't defined)
+    pub fn new(config: &GuardConfig, attr_type: Option<AttributeType>) -> Result<Self, GuardError> {
+        let attribute = &config.attribute;
+        let attr_type = attr_type.ok_or_else(|| GuardError::UndefinedAttribute {
+            attribute: attribute.clone(),
+        })?;
+
+        let value = |value: &GuardValue| {
+            let text = value.to_string();
+            AttributeValue::from_str(&text, attr_type).ok_or(GuardError::InvalidValue {
+                attribute: attribute.clone(),
+                value: text,
+                attr_type,
+            })
+        };
+        let bound = |bound: f64| {
+            if matches!(attr_type, AttributeType::Int | AttributeType::Float) {
+                Ok(bound)
+            } else {
+                Err(GuardError::NotNumeric {
+                    attribute: attribute.clone(),
+                    attr_type,
+                })
+            }
+        };
+
+        let test = match &config.condition {
+            GuardCondition::Eq(v) => GuardTest::Eq(value(v)?),
+            GuardCondition::Ne(v) => GuardTest::Ne(value(v)?),
+            GuardCondition::Gt(b) => GuardTest::Gt(bound(*b)?),
+            GuardCondition::Gte(b) => GuardTest::Gte(bound(*b)?),
+            GuardCondition::Lt(b) => GuardTest::Lt(bound(*b)?),
+            GuardCondition::Lte(b) => GuardTest::Lte(bound(*b)?),
+            GuardCondition::In(values) => GuardTest::In(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        GuardValue::Text(text)
+                            if matches!(attr_type, AttributeType::Ip) && text.contains('/') =>
+                        {
+                            IpNetwork::parse(text).map(Member::Network).ok_or_else(|| {
+                                GuardError::InvalidValue {
+                                    attribute: attribute.clone(),
+                                    value: text.clone(),
+                                    attr_type,
+                                }
+                            })
+                        }
+                        _ => value(v).map(Member::Value),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            GuardCondition::Matches(pattern) => {
+                GuardTest::Matches(Regex::new(pattern).map_err(|e| GuardError::Regex {
+                    attribute: attribute.clone(),
+                    pattern: pattern.clone(),
+                    source: e,
+                })?)
+            }
+        };
+
+        Ok(Self {
+            attribute: attribute.clone(),
+            attr_type,
+            test,
+        })
+    }
+
+    /// Whether the guard holds for a value as extracted from a log
+    pub fn holds_for_text(&self, text: &str) -> bool {
+        AttributeValue::from_str(text, self.attr_type).is_some_and(|value| self.holds(&value))
+    }
+
+    /// Whether the guard holds for a typed attribute value; a list holds
+    /// when any of its values does
+    pub fn holds(&self, value: &AttributeValue) -> bool {
+        if let AttributeValue::List(values) = value {
+            return values.iter().any(|value| self.holds(value));
+        }
+        let number = || match value {
+            AttributeValue::Int(i) => Some(*i as f64),
+            AttributeValue::Float(x) => Some(*x),
+            AttributeValue::String(_) | AttributeValue::List(_) => None,
+        };
+        match &self.test {
+            GuardTest::Eq(expected) => value == expected,
+            GuardTest::Ne(expected) => value != expected,
+            GuardTest::Gt(bound) => number().is_some_and(|n| n > *bound),
+            GuardTest::Gte(bound) => number().is_some_and(|n| n >= *bound),
+            GuardTest::Lt(bound) => number().is_some_and(|n| n < *bound),
+            GuardTest::Lte(bound) => number().is_some_and(|n| n <= *bound),
+            GuardTest::In(members) => members.iter().any(|member| match member {
+                Member::Value(expected) => value == expected,
+                Member::Network(network) => match value {
+                    AttributeValue::String(s) => s.parse().is_ok_and(|ip| network.contains(ip)),
+                    _ => false,
+                },
+            }),
+            GuardTest::Matches(regex) => regex.is_match(&value.as_key_string()),
+        }
+    }
+}
+
+/// An IPv4 or IPv6 CIDR block
+#[derive(Debug)]
+struct IpNetwork {
+    addr: IpAddr,
+    prefix: u32,
+}
+
+impl IpNetwork {
+    fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = s.split_once('/')?;
+        let addr: IpAddr = addr.parse().ok()?;
+        let prefix: u32 = prefix.parse().ok()?;
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        (prefix <= bits).then_some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.addr, ip) {
+            (IpAddr::V4(n), IpAddr::V4(ip)) => (u32::from(n) as u128, u32::from(ip) as u128, 32),
+            (IpAddr::V6(n), IpAddr::V6(ip)) => (u128::from(n), u128::from(ip), 128),
+            _ => return false,
+        };
+        let host_bits = bits - self.prefix;
+        host_bits == bits || (network >> host_bits) == (ip >> host_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(yaml: &str, attr_type: AttributeType) -> Result<CompiledGuard, GuardError> {
+        let config: GuardConfig = serde_yaml::from_str(yaml).unwrap();
+        CompiledGuard::new(&config, Some(attr_type))
+    }
+
+    #[test]
+    fn test_numeric_and_equality_guards() {
+        let status = guard("{ attribute: status, gte: 500 }", AttributeType::Int).unwrap();
+        assert!(status.holds_for_text("503"));
+        assert!(!status.holds_for_text("404"));
+        assert!(!status.holds_for_text("oops"));
+
+        let method = guard("{ attribute: method, ne: GET }", AttributeType::String).unwrap();
+        assert!(method.holds_for_text("POST"));
+        assert!(!method.holds_for_text("GET"));
+
+        // Values are normalized like the attribute's own
+        let mac = guard("{ attribute: mac, eq: 'AA-BB-CC-DD-EE-FF' }", AttributeType::Mac).unwrap();
+        assert!(mac.holds_for_text("aa:bb:cc:dd:ee:ff"));
+    }
+
+    #[test]
+    fn test_membership_and_networks() {
+        let ip = guard(
+            "{ attribute: client_ip, in: [10.0.0.0/8, 192.168.1.7] }",
+            AttributeType::Ip,
+        )
+        .unwrap();
+        assert!(ip.holds_for_text("10.20.30.40"));
+        assert!(ip.holds_for_text("192.168.1.7"));
+        assert!(!ip.holds_for_text("192.168.1.8"));
+        assert!(!ip.holds_for_text("11.0.0.1"));
+
+        let port = guard("{ attribute: port, in: [80, 443] }", AttributeType::Int).unwrap();
+        assert!(port.holds_for_text("443"));
+        assert!(!port.holds_for_text("8080"));
+    }
+
+    #[test]
+    fn test_invalid_guards() {
+        assert!(matches!(
+            guard("{ attribute: host, gt: 1 }", AttributeType::String),
+            Err(GuardError::NotNumeric { .. })
+        ));
+        assert!(matches!(
+            guard("{ attribute: status, eq: ok }", AttributeType::Int),
+            Err(GuardError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            guard("{ attribute: ip, in: [10.0.0.0/40] }", AttributeType::Ip),
+            Err(GuardError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            guard("{ attribute: host, matches: '(' }", AttributeType::String),
+            Err(GuardError::Regex { .. })
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::config::types::FiberTypeConfig;
+use crate::fiber::processor::ProcessResult;
+use crate::storage::traits::{FiberLink, FiberRecord};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Links fibers to the open fiber of their type's `parent` type that has
+/// the same values for the `parent.on` attributes.
+///
+/// Fiber types are processed independently, so links are found afterwards
+/// from the records each log produced across all types. A child is linked
+/// once, to the parent open when it first has all of the values.
+#[derive(Debug, Default)]
+pub struct FiberLinker {
+    relations: Vec<Relation>,
+}
+
+/// One fiber type's `parent:` setting and the fibers it is tracking
+#[derive(Debug)]
+struct Relation {
+    child_type: String,
+    parent_type: String,
+    on: Vec<String>,
+    /// Open parent fibers by their values for `on`
+    parents: HashMap<Vec<String>, Uuid>,
+    /// The values each open parent fiber is indexed under
+    parent_values: HashMap<Uuid, Vec<String>>,
+    /// Open child fibers that already have a parent
+    linked: HashSet<Uuid>,
+}
+
+impl FiberLinker {
+    pub fn from_config(fiber_types: &HashMap<String, FiberTypeConfig>) -> Self {
+        let mut relations: Vec<Relation> = fiber_types
+            .iter()
+            .filter_map(|(name, fiber_type)| {
+                let parent = fiber_type.parent.as_ref()?;
+                Some(Relation {
+                    child_type: name.clone(),
+                    parent_type: parent.fiber_type.clone(),
+                    on: parent.on.clone(),
+                    parents: HashMap::new(),
+                    parent_values: HashMap::new(),
+                    linked: HashSet::new(),
+                })
+            })
+            .collect();
+        relations.sort_unstable_by(|a, b| a.child_type.cmp(&b.child_type));
+        Self { relations }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.relations.is_empty()
+    }
+
+    /// Find links in the results of one log across fiber types. Each link is
+    /// added to the result holding the child fiber.
+    pub fn link(&mut self, results: &mut [ProcessResult]) {
+        for relation in &mut self.relations {
+            // Parents first, so a child can join a parent updated by the same log
+            for result in results.iter() {
+                for record in result.new_fibers.iter().chain(&result.updated_fibers) {
+                    if record.fiber_type == relation.parent_type {
+                        relation.index_parent(record);
+                    }
+                }
+            }
+
+            for result in results.iter_mut() {
+                let ProcessResult {
+                    new_fibers,
+                    updated_fibers,
+                    links,
+                    ..
+                } = result;
+                for record in new_fibers.iter().chain(updated_fibers.iter()) {
+                    if record.fiber_type == relation.child_type {
+                        links.extend(relation.link_child(record));
+                    }
+                }
+            }
+
+            for result in results.iter() {
+                for closed in &result.closed_fibers {
+                    relation.forget(closed.fiber_id);
+                }
+            }
+        }
+    }
+
+    /// Index open parent fibers, as after restoring a checkpoint. Children
+    /// aren't known to be linked, so they may be linked again; storage
+    /// keeps the first link.
+    pub fn restore(&mut self, open_fibers: &[FiberRecord]) {
+        self.clear();
+        for relation in &mut self.relations {
+            for record in open_fibers {
+                if record.fiber_type == relation.parent_type {
+                    relation.index_parent(record);
+                }
+            }
+        }
+    }
+
+    /// Forget all fibers, as when every fiber has been flushed
+    pub fn clear(&mut self) {
+        for relation in &mut self.relations {
+            relation.parents.clear();
+            relation.parent_values.clear();
+            relation.linked.clear();
+        }
+    }
+}
+
+impl Relation {
+    /// The fiber's values for `on`, if it has all of them
+    fn values(&self, record: &FiberRecord) -> Option<Vec<String>> {
+        self.on
+            .iter()
+            .map(|name| match record.attributes.get(name)? {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                serde_json::Value::Bool(b) => Some(b.to_string()),
+                // Lists of `set` attributes don't name a single parent
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn index_parent(&mut self, record: &FiberRecord) {
+        let values = self.values(record);
+        if self.parent_values.get(&record.fiber_id) == values.as_ref() {
+            return;
+        }
+        self.forget_parent(record.fiber_id);
+        if let Some(values) = values {
+            self.parents.insert(values.clone(), record.fiber_id);
+            self.parent_values.insert(record.fiber_id, values);
+        }
+    }
+
+    fn forget_parent(&mut self, fiber_id: Uuid) {
+        if let Some(values) = self.parent_values.remove(&fiber_id) {
+            // A newer parent may have taken these values over
+            if self.parents.get(&values) == Some(&fiber_id) {
+                self.parents.remove(&values);
+            }
+        }
+    }
+
+    fn link_child(&mut self, record: &FiberRecord) -> Option<FiberLink> {
+        if self.linked.contains(&record.fiber_id) {
+            return None;
+        }
+        let parent_fiber_id = *self.parents.get(&self.values(record)?)?;
+        self.linked.insert(record.fiber_id);
+        Some(FiberLink {
+            parent_fiber_id,
+            child_fiber_id: record.fiber_id,
+            linked_at: record.last_activity,
+            config_version: record.config_version,
+        })
+    }
+
+    fn forget(&mut self, fiber_id: Uuid) {
+        self.forget_parent(fiber_id);
+        self.linked.remove(&fiber_id);
+    }
+}
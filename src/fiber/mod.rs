@@ -1,7 +1,12 @@
+pub mod guard;
+pub mod links;
 pub mod processor;
 pub mod rule;
+pub mod sequence;
 pub mod session;
+pub mod timeouts;
 
+pub use links::FiberLinker;
 pub use processor::{FiberProcessor, FiberTypeProcessor, ProcessResult};
-pub use rule::{CompiledFiberType, CompiledPattern, RuleError};
+pub use rule::{CompiledFiberType, CompiledPattern, RuleError, SourcePatterns};
 pub use session::{AttributeValue, OpenFiber};
@@ -1,9 +1,13 @@
-use crate::config::types::{Config, GapMode};
-use crate::fiber::rule::{CompiledFiberType, CompiledPattern, RuleError};
+use crate::config::types::{Config, GapMode, PatternMark};
+use crate::fiber::links::FiberLinker;
+use crate::fiber::rule::{CompiledFiberType, CompiledPattern, RuleError, SourcePatterns};
+use crate::fiber::sequence::{VIOLATION_ATTRIBUTE, VIOLATION_REASON_ATTRIBUTE};
 use crate::fiber::session::{AttributeValue, OpenFiber};
+use crate::fiber::timeouts::TimeoutIndex;
 use crate::source::reader::LogRecord;
-use crate::storage::traits::{FiberMembership, FiberRecord};
+use crate::storage::traits::{CloseReason, FiberClose, FiberLink, FiberMembership, FiberRecord};
 use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use tracing::warn;
 use uuid::Uuid;
@@ -13,7 +17,11 @@ struct PatternMatchInfo {
     extracted: HashMap<String, String>,
     release_matching_peer_keys: Vec<String>,
     release_self_keys: Vec<String>,
-    close: bool,
+    /// Description of the pattern when it closes the fiber
+    close: Option<String>,
+    error: bool,
+    /// Sequence step the log reaches
+    step: Option<usize>,
 }
 
 /// Result from processing a single log record for a single fiber type
@@ -25,10 +33,12 @@ pub struct ProcessResult {
     pub new_fibers: Vec<FiberRecord>,
     /// Updated fibers (attributes changed, merged, etc.)
     pub updated_fibers: Vec<FiberRecord>,
-    /// IDs of fibers that were closed
-    pub closed_fiber_ids: Vec<Uuid>,
+    /// Fibers that were closed, with the reason
+    pub closed_fibers: Vec<FiberClose>,
     /// IDs of fibers that were merged into other fibers
     pub merged_fiber_ids: Vec<Uuid>,
+    /// Links from new or updated fibers to the fibers they nest inside
+    pub links: Vec<FiberLink>,
 }
 
 /// Processor for a single fiber type
@@ -41,7 +51,9 @@ pub struct FiberTypeProcessor {
     open_fibers: HashMap<Uuid, OpenFiber>,
     /// Key index: (key_name, value) -> fiber_id
     key_index: HashMap<(String, String), Uuid>,
-    /// Logical clock (timestamp of most recently processed log)
+    /// Open fibers by the time their gap is measured from (empty without max_gap)
+    timeouts: TimeoutIndex,
+    /// Logical clock (newest timestamp processed so far)
     logical_clock: Option<DateTime<Utc>>,
 }
 
@@ -53,6 +65,7 @@ impl FiberTypeProcessor {
             config_version,
             open_fibers: HashMap::new(),
             key_index: HashMap::new(),
+            timeouts: TimeoutIndex::default(),
             logical_clock: None,
         }
     }
@@ -63,14 +76,19 @@ impl FiberTypeProcessor {
     }
 
     /// Process a log record
+    ///
+    /// A late record (older than the logical clock) never moves the clock
+    /// backwards: it can still join or start fibers, widening their activity
+    /// range, but fibers that already timed out stay closed and timeouts are
+    /// judged against the newest timestamp seen.
     pub fn process_log(&mut self, log: &LogRecord) -> ProcessResult {
         let mut result = ProcessResult::default();
 
         // Update logical clock
-        self.logical_clock = Some(log.timestamp);
+        self.logical_clock = self.logical_clock.max(Some(log.timestamp));
 
         // Step 1: Find matching patterns for this log's source
-        let patterns = match self.fiber_type.source_patterns.get(&log.source_id) {
+        let patterns = match self.fiber_type.patterns_for_source(&log.source_id) {
             Some(p) => p,
             None => {
                 // This fiber type doesn't handle logs from this source
@@ -116,7 +134,12 @@ impl FiberTypeProcessor {
         };
 
         // Step 7: Add log to fiber, update keys and attributes
-        self.update_fiber_with_attributes(target_fiber_id, log, &all_attrs);
+        self.update_fiber_with_attributes(target_fiber_id, log, &all_attrs, match_info.error);
+        if let (Some(sequence), Some(step)) = (&self.fiber_type.sequence, match_info.step) {
+            if let Some(fiber) = self.open_fibers.get_mut(&target_fiber_id) {
+                sequence.advance(&mut fiber.sequence, step, log.timestamp);
+            }
+        }
 
         // Record new fiber AFTER attributes are set (so FiberRecord has correct attributes)
         if is_new_fiber {
@@ -134,8 +157,8 @@ impl FiberTypeProcessor {
         self.release_self_keys_by_name(&match_info.release_self_keys, target_fiber_id);
 
         // Step 9: Execute close if specified
-        if match_info.close {
-            self.close_fiber(target_fiber_id, &mut result);
+        if let Some(pattern) = match_info.close {
+            self.close_fiber(target_fiber_id, CloseReason::Pattern, Some(pattern), &mut result);
         }
 
         // Step 10: Check for timeout closures
@@ -143,7 +166,7 @@ impl FiberTypeProcessor {
 
         // Mark the target fiber as updated
         if !result.new_fibers.iter().any(|f| f.fiber_id == target_fiber_id)
-            && !result.closed_fiber_ids.contains(&target_fiber_id)
+            && !result.closed_fibers.iter().any(|c| c.fiber_id == target_fiber_id)
         {
             result.updated_fibers.push(self.fiber_to_record(target_fiber_id));
         }
@@ -156,27 +179,93 @@ impl FiberTypeProcessor {
     fn extract_attributes_with_info(
         &self,
         log: &LogRecord,
-        patterns: &[CompiledPattern],
+        patterns: &SourcePatterns,
     ) -> Option<PatternMatchInfo> {
-        for pattern in patterns {
+        // Parsed on first use by a pattern with JSON pointers
+        let mut json: Option<Option<serde_json::Value>> = None;
+
+        'patterns: for pattern in patterns.candidates(&log.raw_text) {
             if let Some(captures) = pattern.regex.captures(&log.raw_text) {
+                // Every field and label regex must match as well
+                let mut all_captures = vec![captures];
+                for (field, regex) in &pattern.field_regexes {
+                    match log.fields.get(field).and_then(|value| regex.captures(value)) {
+                        Some(field_captures) => all_captures.push(field_captures),
+                        None => continue 'patterns,
+                    }
+                }
+                for (label, regex) in &pattern.label_regexes {
+                    match log.labels.get(label).and_then(|value| regex.captures(value)) {
+                        Some(label_captures) => all_captures.push(label_captures),
+                        None => continue 'patterns,
+                    }
+                }
+
                 let mut extracted = HashMap::new();
                 for name in &pattern.capture_groups {
-                    if let Some(m) = captures.name(name) {
+                    if let Some(m) = all_captures.iter().find_map(|c| c.name(name)) {
                         extracted.insert(name.clone(), m.as_str().to_string());
                     }
                 }
+
+                // ...and every JSON pointer must resolve
+                if !pattern.json_pointers.is_empty() {
+                    let json = json.get_or_insert_with(|| serde_json::from_str(&log.raw_text).ok());
+                    for (attr, pointer) in &pattern.json_pointers {
+                        match json.as_ref().and_then(|value| json_attribute(value, pointer)) {
+                            Some(value) => {
+                                extracted.insert(attr.clone(), value);
+                            }
+                            None => continue 'patterns,
+                        }
+                    }
+                }
+                if !self.guards_hold(pattern, &extracted) {
+                    continue 'patterns;
+                }
+
                 return Some(PatternMatchInfo {
                     extracted,
                     release_matching_peer_keys: pattern.release_matching_peer_keys.clone(),
                     release_self_keys: pattern.release_self_keys.clone(),
-                    close: pattern.close,
+                    close: pattern.close.then(|| pattern.description.clone()),
+                    error: pattern.mark == Some(PatternMark::Error),
+                    step: pattern.step,
                 });
             }
         }
         None
     }
 
+    /// Check a pattern's `when` guards. Attributes the pattern doesn't provide
+    /// are read from the fibers the log would join, oldest first.
+    fn guards_hold(&self, pattern: &CompiledPattern, extracted: &HashMap<String, String>) -> bool {
+        if pattern.guards.is_empty() {
+            return true;
+        }
+
+        let mut attrs = extracted.clone();
+        self.compute_derived_attributes(&mut attrs);
+
+        // Keys released from peers before matching can't join those peers
+        let mut matching_keys = attrs.clone();
+        matching_keys.retain(|name, _| !pattern.release_matching_peer_keys.contains(name));
+        let mut fibers: Vec<&OpenFiber> = self
+            .find_matching_fibers(&matching_keys)
+            .iter()
+            .filter_map(|fiber_id| self.open_fibers.get(fiber_id))
+            .collect();
+        fibers.sort_by_key(|fiber| fiber.first_activity);
+
+        pattern.guards.iter().all(|guard| match attrs.get(&guard.attribute) {
+            Some(text) => guard.holds_for_text(text),
+            None => fibers
+                .iter()
+                .find_map(|fiber| fiber.attributes.get(&guard.attribute))
+                .is_some_and(|value| guard.holds(value)),
+        })
+    }
+
     /// Compute derived attributes based on extracted values
     fn compute_derived_attributes(&self, attrs: &mut HashMap<String, String>) {
         for derived_name in &self.fiber_type.derived_order {
@@ -221,9 +310,15 @@ impl FiberTypeProcessor {
         fiber_id: Uuid,
         log: &LogRecord,
         all_attrs: &HashMap<String, String>,
+        error: bool,
     ) {
         if let Some(fiber) = self.open_fibers.get_mut(&fiber_id) {
             fiber.add_log(log.id, log.timestamp);
+            fiber.metrics.record(&log.source_id, error);
+            if self.fiber_type.temporal.max_gap.is_some() {
+                let reference = gap_reference(self.fiber_type.temporal.gap_mode, fiber);
+                self.timeouts.schedule(fiber_id, reference);
+            }
 
             // Collect keys to update (to avoid borrow issues with key_index)
             let mut key_updates: Vec<(String, String, Option<String>)> = Vec::new();
@@ -234,7 +329,8 @@ impl FiberTypeProcessor {
                 let attr_type = self.fiber_type.get_attribute_type(name);
                 if let Some(attr_type) = attr_type {
                     if let Some(typed_value) = AttributeValue::from_str(value, attr_type) {
-                        if let Some(old_value) = fiber.set_attribute(name.clone(), typed_value) {
+                        let mode = self.fiber_type.attribute_mode(name);
+                        if let Some(old_value) = fiber.record_attribute(name.clone(), typed_value, mode) {
                             warn!(
                                 fiber_id = %fiber.fiber_id,
                                 attribute = %name,
@@ -309,7 +405,8 @@ impl FiberTypeProcessor {
 
                 // Merge into survivor
                 if let Some(survivor) = self.open_fibers.get_mut(&survivor_id) {
-                    let conflicts = survivor.merge(other_fiber);
+                    let conflicts = survivor
+                        .merge_with_modes(other_fiber, |name| self.fiber_type.attribute_mode(name));
                     for (attr_name, old_val, new_val) in conflicts {
                         warn!(
                             survivor_id = %survivor_id,
@@ -322,8 +419,13 @@ impl FiberTypeProcessor {
                     }
                 }
 
-                // Record merged fiber
+                // Record merged fiber, which is closed in storage
                 result.merged_fiber_ids.push(fiber_id);
+                result.closed_fibers.push(FiberClose {
+                    fiber_id,
+                    reason: CloseReason::Merged,
+                    pattern: None,
+                });
             }
         }
 
@@ -346,17 +448,70 @@ impl FiberTypeProcessor {
     }
 
     /// Close a fiber
-    fn close_fiber(&mut self, fiber_id: Uuid, result: &mut ProcessResult) {
+    ///
+    /// For a type with a `sequence`, the fiber is first updated with whether
+    /// and how it broke the sequence. Merged fibers don't come through here
+    /// (see `merge_fibers`); their progress carries on in the fiber they were
+    /// merged into.
+    fn close_fiber(
+        &mut self,
+        fiber_id: Uuid,
+        reason: CloseReason,
+        pattern: Option<String>,
+        result: &mut ProcessResult,
+    ) {
+        if let Some(record) = self.sequence_record(fiber_id, reason) {
+            result.updated_fibers.push(record);
+        }
         if let Some(fiber) = self.open_fibers.remove(&fiber_id) {
             // Remove all keys from index
             for (key_name, value) in fiber.keys {
                 self.key_index.remove(&(key_name, value));
             }
-            result.closed_fiber_ids.push(fiber_id);
+            result.closed_fibers.push(FiberClose {
+                fiber_id,
+                reason,
+                pattern,
+            });
         }
     }
 
+    /// The record of a closing fiber with its sequence verdict, if its type
+    /// has a sequence.
+    ///
+    /// Flushes (on shutdown or config activation) cut fibers off mid-way, so
+    /// a flushed fiber without a violation so far gets no verdict rather
+    /// than one for steps it might still have reached.
+    fn sequence_record(&self, fiber_id: Uuid, reason: CloseReason) -> Option<FiberRecord> {
+        let sequence = self.fiber_type.sequence.as_ref()?;
+        let fiber = self.open_fibers.get(&fiber_id)?;
+        let now = self.logical_clock.unwrap_or(fiber.last_activity);
+        let finished = reason != CloseReason::Flush;
+        let violation = sequence.violation(&fiber.sequence, now, finished);
+        if violation.is_none() && !finished {
+            return None;
+        }
+
+        let mut record = self.fiber_to_record(fiber_id);
+        if let serde_json::Value::Object(attributes) = &mut record.attributes {
+            attributes.insert(
+                VIOLATION_ATTRIBUTE.to_string(),
+                serde_json::Value::Bool(violation.is_some()),
+            );
+            if let Some(reason) = violation {
+                attributes.insert(
+                    VIOLATION_REASON_ATTRIBUTE.to_string(),
+                    serde_json::Value::String(reason),
+                );
+            }
+        }
+        Some(record)
+    }
+
     /// Check for fibers that should be closed due to timeout
+    ///
+    /// Only fibers whose gap has run out are visited, in the order they
+    /// timed out, so the cost doesn't grow with the number of open fibers.
     fn check_timeouts(&mut self, result: &mut ProcessResult) {
         let Some(logical_clock) = self.logical_clock else {
             return;
@@ -367,21 +522,27 @@ impl FiberTypeProcessor {
         };
 
         let max_gap = Duration::from_std(max_gap).unwrap();
-        let mut to_close = Vec::new();
-
-        for (&fiber_id, fiber) in &self.open_fibers {
-            let reference_time = match self.fiber_type.temporal.gap_mode {
-                GapMode::Session => fiber.last_activity,
-                GapMode::FromStart => fiber.first_activity,
-            };
-
-            if logical_clock - reference_time > max_gap {
-                to_close.push(fiber_id);
+        let gap_mode = self.fiber_type.temporal.gap_mode;
+
+        // A fiber times out once more than max_gap has passed since its reference time
+        let cutoff = logical_clock - max_gap;
+        while let Some((fiber_id, reference)) = self.timeouts.pop_expired(cutoff) {
+            let current = self
+                .open_fibers
+                .get(&fiber_id)
+                .map(|fiber| gap_reference(gap_mode, fiber));
+            // Otherwise the fiber has closed or been active since
+            if current == Some(reference) {
+                self.close_fiber(fiber_id, CloseReason::Timeout, None, result);
             }
         }
 
-        for fiber_id in to_close {
-            self.close_fiber(fiber_id, result);
+        if self.timeouts.needs_compaction(self.open_fibers.len()) {
+            self.timeouts.rebuild(
+                self.open_fibers
+                    .iter()
+                    .map(|(&fiber_id, fiber)| (fiber_id, gap_reference(gap_mode, fiber))),
+            );
         }
     }
 
@@ -400,6 +561,9 @@ impl FiberTypeProcessor {
             first_activity: fiber.first_activity,
             last_activity: fiber.last_activity,
             closed: false,
+            metrics: fiber.metrics.clone(),
+            close_reason: None,
+            closing_pattern: None,
         }
     }
 
@@ -413,8 +577,9 @@ impl FiberTypeProcessor {
         let mut result = ProcessResult::default();
         let fiber_ids: Vec<Uuid> = self.open_fibers.keys().copied().collect();
         for fiber_id in fiber_ids {
-            self.close_fiber(fiber_id, &mut result);
+            self.close_fiber(fiber_id, CloseReason::Flush, None, &mut result);
         }
+        self.timeouts.clear();
         result
     }
 
@@ -429,15 +594,7 @@ impl FiberTypeProcessor {
                     .attributes
                     .iter()
                     .map(|(k, v)| {
-                        let json_val = match v {
-                            AttributeValue::String(s) => serde_json::Value::String(s.clone()),
-                            AttributeValue::Int(i) => serde_json::Value::Number((*i).into()),
-                            AttributeValue::Float(f) => {
-                                serde_json::Number::from_f64(*f)
-                                    .map(serde_json::Value::Number)
-                                    .unwrap_or(serde_json::Value::Null)
-                            }
-                        };
+                        let json_val = serde_json::to_value(v).unwrap_or(serde_json::Value::Null);
                         (k.clone(), json_val)
                     })
                     .collect();
@@ -449,6 +606,8 @@ impl FiberTypeProcessor {
                     first_activity: fiber.first_activity,
                     last_activity: fiber.last_activity,
                     log_ids: fiber.log_ids.clone(),
+                    metrics: fiber.metrics.clone(),
+                    sequence: fiber.sequence.clone(),
                 }
             })
             .collect();
@@ -467,6 +626,7 @@ impl FiberTypeProcessor {
         // Clear existing state
         self.open_fibers.clear();
         self.key_index.clear();
+        self.timeouts.clear();
 
         // Restore logical clock
         self.logical_clock = Some(checkpoint.logical_clock);
@@ -478,19 +638,8 @@ impl FiberTypeProcessor {
                 .attributes
                 .iter()
                 .filter_map(|(k, v)| {
-                    let attr_val = match v {
-                        serde_json::Value::String(s) => Some(AttributeValue::String(s.clone())),
-                        serde_json::Value::Number(n) => {
-                            if let Some(i) = n.as_i64() {
-                                Some(AttributeValue::Int(i))
-                            } else if let Some(f) = n.as_f64() {
-                                Some(AttributeValue::Float(f))
-                            } else {
-                                None
-                            }
-                        }
-                        _ => None,
-                    };
+                    // Lists of `set` attributes come back as lists
+                    let attr_val = serde_json::from_value::<AttributeValue>(v.clone()).ok();
                     attr_val.map(|v| (k.clone(), v))
                 })
                 .collect();
@@ -503,6 +652,8 @@ impl FiberTypeProcessor {
                 first_activity: fiber_cp.first_activity,
                 last_activity: fiber_cp.last_activity,
                 log_ids: fiber_cp.log_ids.clone(),
+                metrics: fiber_cp.metrics.clone(),
+                sequence: fiber_cp.sequence.clone(),
             };
 
             // Rebuild key index
@@ -510,15 +661,41 @@ impl FiberTypeProcessor {
                 self.key_index
                     .insert((key_name.clone(), value.clone()), fiber.fiber_id);
             }
+            if self.fiber_type.temporal.max_gap.is_some() {
+                let reference = gap_reference(self.fiber_type.temporal.gap_mode, &fiber);
+                self.timeouts.schedule(fiber.fiber_id, reference);
+            }
 
             self.open_fibers.insert(fiber.fiber_id, fiber);
         }
     }
 }
 
+/// Fewest logs in a batch before fiber types are processed in parallel;
+/// smaller batches cost more to hand out than they save
+const MIN_PARALLEL_BATCH: usize = 32;
+
+/// Threads for `pipeline.fiber_workers`; none for a single worker
+fn worker_pool(workers: usize) -> Option<rayon::ThreadPool> {
+    if workers <= 1 {
+        return None;
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .thread_name(|i| format!("fiber-worker-{}", i))
+        .build()
+        .inspect_err(|e| warn!(error = %e, "Failed to start fiber workers; using one thread"))
+        .ok()
+}
+
 /// Multi-type fiber processor that coordinates multiple FiberTypeProcessors
 pub struct FiberProcessor {
     processors: HashMap<String, FiberTypeProcessor>,
+    /// Threads batches of logs are processed on, when `pipeline.fiber_workers`
+    /// is more than 1
+    pool: Option<rayon::ThreadPool>,
+    /// Links fibers to fibers of their `parent` type
+    linker: FiberLinker,
 }
 
 impl FiberProcessor {
@@ -532,15 +709,58 @@ impl FiberProcessor {
             processors.insert(name.clone(), processor);
         }
 
-        Ok(Self { processors })
+        Ok(Self {
+            processors,
+            pool: worker_pool(config.pipeline.fiber_workers.unwrap_or(1)),
+            linker: FiberLinker::from_config(config.fiber_types_or_empty()),
+        })
     }
 
-    /// Process a log record across all fiber types
+    /// Process a log record across all fiber types, in fiber type name order
     pub fn process_log(&mut self, log: &LogRecord) -> Vec<ProcessResult> {
-        self.processors
-            .values_mut()
-            .map(|p| p.process_log(log))
-            .collect()
+        self.process_batch(std::slice::from_ref(log))
+    }
+
+    /// Process a batch of log records across all fiber types.
+    ///
+    /// Fiber types are independent, so with `pipeline.fiber_workers` set
+    /// they are shared out among the worker threads, each fiber type running
+    /// the whole batch in order. Small batches stay on the calling thread.
+    /// Results are ordered by log, then by fiber type name, whatever the
+    /// number of workers. Links between fibers of related types are then
+    /// found log by log.
+    ///
+    /// This blocks until the batch is done; async callers should run it with
+    /// `spawn_blocking`.
+    pub fn process_batch(&mut self, logs: &[LogRecord]) -> Vec<ProcessResult> {
+        let mut processors: Vec<(&String, &mut FiberTypeProcessor)> =
+            self.processors.iter_mut().collect();
+        processors.sort_unstable_by_key(|(name, _)| *name);
+
+        let run = |(_, processor): (&String, &mut FiberTypeProcessor)| -> Vec<ProcessResult> {
+            logs.iter().map(|log| processor.process_log(log)).collect()
+        };
+        let per_type: Vec<Vec<ProcessResult>> = match &self.pool {
+            Some(pool) if logs.len() >= MIN_PARALLEL_BATCH && processors.len() > 1 => {
+                pool.install(|| processors.into_par_iter().map(run).collect())
+            }
+            _ => processors.into_iter().map(run).collect(),
+        };
+
+        // Interleave back into log order
+        let types = per_type.len();
+        let mut per_type: Vec<_> = per_type.into_iter().map(Vec::into_iter).collect();
+        let mut results = Vec::with_capacity(logs.len() * types);
+        for _ in logs {
+            results.extend(per_type.iter_mut().filter_map(Iterator::next));
+        }
+
+        if !self.linker.is_empty() {
+            for log_results in results.chunks_mut(types) {
+                self.linker.link(log_results);
+            }
+        }
+        results
     }
 
     /// Get the number of open fibers across all types
@@ -560,6 +780,7 @@ impl FiberProcessor {
 
     /// Flush all open fibers across all types
     pub fn flush(&mut self) -> Vec<ProcessResult> {
+        self.linker.clear();
         self.processors.values_mut().map(|p| p.flush()).collect()
     }
 
@@ -588,12 +809,32 @@ impl FiberProcessor {
                 );
             }
         }
+        self.restore_links();
+    }
+
+    /// Index the restored open fibers for linking. Call after restoring
+    /// fiber type processors one at a time.
+    pub fn restore_links(&mut self) {
+        if self.linker.is_empty() {
+            return;
+        }
+        let open_fibers: Vec<FiberRecord> = self
+            .processors
+            .values()
+            .flat_map(|processor| {
+                processor
+                    .open_fibers
+                    .keys()
+                    .map(|&fiber_id| processor.fiber_to_record(fiber_id))
+            })
+            .collect();
+        self.linker.restore(&open_fibers);
     }
 
     /// Check if a processor exists for the given source ID
     pub fn has_processor_for_source(&self, source_id: &str) -> bool {
         self.processors.values().any(|p| {
-            p.fiber_type.source_patterns.contains_key(source_id)
+            p.fiber_type.patterns_for_source(source_id).is_some()
         })
     }
 
@@ -631,12 +872,30 @@ impl FiberProcessor {
     }
 }
 
+/// The time a fiber's gap is measured from
+fn gap_reference(gap_mode: GapMode, fiber: &OpenFiber) -> DateTime<Utc> {
+    match gap_mode {
+        GapMode::Session => fiber.last_activity,
+        GapMode::FromStart => fiber.first_activity,
+    }
+}
+
+/// Read an attribute value from a JSON document. Strings are taken as-is and
+/// other values as JSON text; null counts as missing.
+fn json_attribute(value: &serde_json::Value, pointer: &str) -> Option<String> {
+    match value.pointer(pointer)? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::types::{
-        AttributeConfig, AttributeType, FiberSourceConfig, FiberTypeConfig, GapMode,
-        PatternConfig, TemporalConfig as ConfigTemporalConfig,
+        AttributeConfig, AttributeMode, AttributeType, FiberSourceConfig, FiberTypeConfig, GapMode,
+        ParentConfig, PatternConfig, TemporalConfig as ConfigTemporalConfig,
     };
     use std::time::Duration;
 
@@ -647,6 +906,9 @@ mod tests {
             source_id: source.to_string(),
             raw_text: text.to_string(),
             file_offset: 0,
+            fields: Default::default(),
+            labels: Default::default(),
+            late: false,
         }
     }
 
@@ -662,6 +924,7 @@ mod tests {
                 attr_type: AttributeType::String,
                 key: true,
                 derived: None,
+                mode: Default::default(),
             }],
             sources: {
                 let mut sources = HashMap::new();
@@ -670,15 +933,23 @@ mod tests {
                     FiberSourceConfig {
                         patterns: vec![PatternConfig {
                             regex: r"thread-(?P<thread_id>\d+)".to_string(),
+                            fields: Default::default(),
+                            labels: Default::default(),
+                            json: Default::default(),
                             release_matching_peer_keys: vec![],
                             release_self_keys: vec![],
                             close: false,
+                            when: vec![],
+                            mark: None,
+                            step: None,
                         }],
                     },
                 );
                 sources
             },
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         }
     }
 
@@ -733,6 +1004,26 @@ mod tests {
         assert_eq!(processor.open_fiber_count(), 2);
     }
 
+    #[test]
+    fn test_derived_source_uses_parent_patterns() {
+        let config = make_simple_fiber_type();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        // Logs from files discovered by a glob source named "program1"
+        let log1 = make_log("program1/a.log", "2025-12-04T10:00:00Z", "thread-5 doing stuff");
+        let log2 = make_log("program1/b.log", "2025-12-04T10:00:01Z", "thread-5 more stuff");
+        let result1 = processor.process_log(&log1);
+        let result2 = processor.process_log(&log2);
+
+        assert_eq!(result1.new_fibers.len(), 1);
+        assert_eq!(result2.memberships[0].fiber_id, result1.new_fibers[0].fiber_id);
+
+        // Unrelated sources still don't match
+        let log3 = make_log("program2/a.log", "2025-12-04T10:00:02Z", "thread-5 other");
+        assert!(processor.process_log(&log3).memberships.is_empty());
+    }
+
     #[test]
     fn test_timeout_closes_fiber() {
         let config = make_simple_fiber_type();
@@ -749,27 +1040,202 @@ mod tests {
         let result2 = processor.process_log(&log2);
 
         // First fiber should be closed due to timeout
-        assert_eq!(result2.closed_fiber_ids.len(), 1);
+        assert_eq!(result2.closed_fibers.len(), 1);
+        assert_eq!(result2.closed_fibers[0].reason, CloseReason::Timeout);
         // New fiber created for thread-6
         assert_eq!(result2.new_fibers.len(), 1);
         assert_eq!(processor.open_fiber_count(), 1);
     }
 
+    #[test]
+    fn test_timeout_measured_from_latest_activity() {
+        let config = make_simple_fiber_type();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-5 a"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:04Z", "thread-5 b"));
+
+        // Past the gap from the first log but not from the second
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:08Z", "thread-6 a"));
+        assert!(result.closed_fibers.is_empty());
+        assert_eq!(processor.open_fiber_count(), 2);
+
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:10Z", "thread-6 b"));
+        assert_eq!(result.closed_fibers.len(), 1);
+        assert_eq!(processor.open_fiber_count(), 1);
+    }
+
+    #[test]
+    fn test_links_child_fibers_to_parents() {
+        let parent = make_simple_fiber_type();
+        let mut child = make_simple_fiber_type();
+        child.attributes = vec![
+            AttributeConfig {
+                name: "query_id".to_string(),
+                attr_type: AttributeType::String,
+                key: true,
+                derived: None,
+                mode: Default::default(),
+            },
+            AttributeConfig {
+                name: "thread_id".to_string(),
+                attr_type: AttributeType::String,
+                key: false,
+                derived: None,
+                mode: Default::default(),
+            },
+        ];
+        child.sources.get_mut("program1").unwrap().patterns[0].regex =
+            r"thread-(?P<thread_id>\d+) query (?P<query_id>\d+)".to_string();
+        child.parent = Some(ParentConfig {
+            fiber_type: "request".to_string(),
+            on: vec!["thread_id".to_string()],
+        });
+
+        let fiber_types: HashMap<String, FiberTypeConfig> =
+            [("request".to_string(), parent), ("query".to_string(), child)].into();
+        let mut processor = FiberProcessor {
+            processors: fiber_types
+                .iter()
+                .map(|(name, config)| {
+                    let compiled = CompiledFiberType::from_config(name, config).unwrap();
+                    (name.clone(), FiberTypeProcessor::new(compiled, 1))
+                })
+                .collect(),
+            pool: None,
+            linker: FiberLinker::from_config(&fiber_types),
+        };
+        let links = |results: Vec<ProcessResult>| -> Vec<(Uuid, Uuid)> {
+            results
+                .iter()
+                .flat_map(|r| &r.links)
+                .map(|link| (link.parent_fiber_id, link.child_fiber_id))
+                .collect()
+        };
+        let fiber_of = |results: &[ProcessResult], fiber_type: &str| {
+            results
+                .iter()
+                .flat_map(|r| r.new_fibers.iter().chain(&r.updated_fibers))
+                .find(|f| f.fiber_type == fiber_type)
+                .map(|f| f.fiber_id)
+                .unwrap()
+        };
+
+        let results = processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 start"));
+        let request1 = fiber_of(&results, "request");
+        assert!(links(results).is_empty());
+
+        let results = processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "thread-1 query 7"));
+        let query7 = fiber_of(&results, "query");
+        assert_eq!(links(results), vec![(request1, query7)]);
+
+        // Linked once
+        let results = processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-1 query 7"));
+        assert!(links(results).is_empty());
+
+        // The parent can start on the same log as the child
+        let results = processor.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-2 query 8"));
+        let (request2, query8) = (fiber_of(&results, "request"), fiber_of(&results, "query"));
+        assert_eq!(links(results), vec![(request2, query8)]);
+    }
+
+    #[test]
+    fn test_process_batch_same_across_worker_counts() {
+        let logs: Vec<LogRecord> = (0..40)
+            .map(|i| {
+                let timestamp = format!("2025-12-04T10:00:{:02}Z", i);
+                make_log("program1", &timestamp, &format!("thread-{} step", i % 7))
+            })
+            .collect();
+
+        let run = |workers: usize| {
+            let processors = (0..5)
+                .map(|i| {
+                    let name = format!("type{}", i);
+                    let compiled = CompiledFiberType::from_config(&name, &make_simple_fiber_type()).unwrap();
+                    (name, FiberTypeProcessor::new(compiled, 1))
+                })
+                .collect();
+            let mut processor = FiberProcessor {
+                processors,
+                pool: worker_pool(workers),
+                linker: FiberLinker::default(),
+            };
+            let results = processor.process_batch(&logs);
+            // Fiber IDs are random; compare what each result did to which log
+            results
+                .iter()
+                .map(|result| {
+                    (
+                        result.memberships.iter().map(|m| m.log_id).collect::<Vec<_>>(),
+                        result.new_fibers.len(),
+                        result.closed_fibers.len(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = run(1);
+        assert_eq!(sequential.len(), logs.len() * 5);
+        assert_eq!(sequential[..5].iter().map(|r| r.0.clone()).collect::<Vec<_>>(), vec![vec![logs[0].id]; 5]);
+        assert_eq!(run(3), sequential);
+        assert_eq!(run(8), sequential);
+    }
+
+    #[test]
+    fn test_late_log_keeps_logical_clock() {
+        let config = make_simple_fiber_type();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:10Z", "thread-5 a"));
+
+        // A late log joins the open fiber and widens its range backwards
+        let mut late = make_log("program1", "2025-12-04T10:00:08Z", "thread-5 b");
+        late.late = true;
+        let result = processor.process_log(&late);
+        assert!(result.closed_fibers.is_empty());
+        let fiber = &result.updated_fibers[0];
+        assert_eq!(fiber.first_activity, "2025-12-04T10:00:08Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(fiber.last_activity, "2025-12-04T10:00:10Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(
+            processor.create_checkpoint().logical_clock,
+            "2025-12-04T10:00:10Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        // Timeouts are still measured from the fiber's newest log
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:16Z", "thread-6 c"));
+        assert_eq!(result.closed_fibers.iter().map(|c| c.fiber_id).collect::<Vec<_>>(), vec![fiber.fiber_id]);
+    }
+
     #[test]
     fn test_release_matching_peer_keys() {
         let mut config = make_simple_fiber_type();
         config.sources.get_mut("program1").unwrap().patterns = vec![
             PatternConfig {
                 regex: r"thread-(?P<thread_id>\d+) START".to_string(),
+                fields: Default::default(),
+                labels: Default::default(),
+                json: Default::default(),
                 release_matching_peer_keys: vec!["thread_id".to_string()],
                 release_self_keys: vec![],
                 close: false,
+                when: vec![],
+                mark: None,
+                step: None,
             },
             PatternConfig {
                 regex: r"thread-(?P<thread_id>\d+)".to_string(),
+                fields: Default::default(),
+                labels: Default::default(),
+                json: Default::default(),
                 release_matching_peer_keys: vec![],
                 release_self_keys: vec![],
                 close: false,
+                when: vec![],
+                mark: None,
+                step: None,
             },
         ];
 
@@ -797,9 +1263,15 @@ mod tests {
         // Insert END pattern first so it matches before the generic pattern
         config.sources.get_mut("program1").unwrap().patterns.insert(0, PatternConfig {
             regex: r"thread-(?P<thread_id>\d+) END".to_string(),
+            fields: Default::default(),
+            labels: Default::default(),
+            json: Default::default(),
             release_matching_peer_keys: vec![],
             release_self_keys: vec![],
             close: true,
+            when: vec![],
+            mark: None,
+            step: None,
         });
 
         let compiled = CompiledFiberType::from_config("test", &config).unwrap();
@@ -814,7 +1286,12 @@ mod tests {
         let log2 = make_log("program1", "2025-12-04T10:00:01Z", "thread-5 END");
         let result2 = processor.process_log(&log2);
 
-        assert_eq!(result2.closed_fiber_ids.len(), 1);
+        assert_eq!(result2.closed_fibers.len(), 1);
+        assert_eq!(result2.closed_fibers[0].reason, CloseReason::Pattern);
+        assert_eq!(
+            result2.closed_fibers[0].pattern.as_deref(),
+            Some(r"thread-(?P<thread_id>\d+) END")
+        );
         assert_eq!(processor.open_fiber_count(), 0);
     }
 
@@ -832,12 +1309,14 @@ mod tests {
                     attr_type: AttributeType::String,
                     key: true,
                     derived: None,
+                    mode: Default::default(),
                 },
                 AttributeConfig {
                     name: "key2".to_string(),
                     attr_type: AttributeType::String,
                     key: true,
                     derived: None,
+                    mode: Default::default(),
                 },
             ],
             sources: {
@@ -849,21 +1328,39 @@ mod tests {
                         patterns: vec![
                             PatternConfig {
                                 regex: r"K1=(?P<key1>\w+) K2=(?P<key2>\w+)".to_string(),
+                                fields: Default::default(),
+                                labels: Default::default(),
+                                json: Default::default(),
                                 release_matching_peer_keys: vec![],
                                 release_self_keys: vec![],
                                 close: false,
+                                when: vec![],
+                                mark: None,
+                                step: None,
                             },
                             PatternConfig {
                                 regex: r"K1=(?P<key1>\w+)".to_string(),
+                                fields: Default::default(),
+                                labels: Default::default(),
+                                json: Default::default(),
                                 release_matching_peer_keys: vec![],
                                 release_self_keys: vec![],
                                 close: false,
+                                when: vec![],
+                                mark: None,
+                                step: None,
                             },
                             PatternConfig {
                                 regex: r"K2=(?P<key2>\w+)".to_string(),
+                                fields: Default::default(),
+                                labels: Default::default(),
+                                json: Default::default(),
                                 release_matching_peer_keys: vec![],
                                 release_self_keys: vec![],
                                 close: false,
+                                when: vec![],
+                                mark: None,
+                                step: None,
                             },
                         ],
                     },
@@ -871,6 +1368,8 @@ mod tests {
                 sources
             },
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         };
 
         let compiled = CompiledFiberType::from_config("test", &config).unwrap();
@@ -892,6 +1391,16 @@ mod tests {
 
         assert_eq!(result3.merged_fiber_ids.len(), 1);
         assert_eq!(processor.open_fiber_count(), 1);
+
+        // The absorbed fiber is closed as merged
+        assert_eq!(
+            result3.closed_fibers,
+            vec![FiberClose {
+                fiber_id: result3.merged_fiber_ids[0],
+                reason: CloseReason::Merged,
+                pattern: None,
+            }]
+        );
     }
 
     #[test]
@@ -908,18 +1417,21 @@ mod tests {
                     attr_type: AttributeType::String,
                     key: false,
                     derived: None,
+                    mode: Default::default(),
                 },
                 AttributeConfig {
                     name: "port".to_string(),
                     attr_type: AttributeType::String,
                     key: false,
                     derived: None,
+                    mode: Default::default(),
                 },
                 AttributeConfig {
                     name: "endpoint".to_string(),
                     attr_type: AttributeType::String,
                     key: true,
                     derived: Some("${ip}:${port}".to_string()),
+                    mode: Default::default(),
                 },
             ],
             sources: {
@@ -929,15 +1441,23 @@ mod tests {
                     FiberSourceConfig {
                         patterns: vec![PatternConfig {
                             regex: r"(?P<ip>\d+\.\d+\.\d+\.\d+):(?P<port>\d+)".to_string(),
+                            fields: Default::default(),
+                            labels: Default::default(),
+                            json: Default::default(),
                             release_matching_peer_keys: vec![],
                             release_self_keys: vec![],
                             close: false,
+                            when: vec![],
+                            mark: None,
+                            step: None,
                         }],
                     },
                 );
                 sources
             },
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         };
 
         let compiled = CompiledFiberType::from_config("test", &config).unwrap();
@@ -956,6 +1476,153 @@ mod tests {
         assert_eq!(processor.open_fiber_count(), 1);
     }
 
+    #[test]
+    fn test_attribute_modes_in_records() {
+        let mut config = make_simple_fiber_type();
+        for (name, attr_type, mode) in [
+            ("backend", AttributeType::Ip, AttributeMode::Set),
+            ("bytes", AttributeType::Int, AttributeMode::Sum),
+        ] {
+            config.attributes.push(AttributeConfig {
+                name: name.to_string(),
+                attr_type,
+                key: false,
+                derived: None,
+                mode,
+            });
+        }
+        config.sources.get_mut("program1").unwrap().patterns[0].regex =
+            r"thread-(?P<thread_id>\d+) to (?P<backend>\S+) sent (?P<bytes>\d+)".to_string();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 to 10.0.0.1 sent 100"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "thread-1 to 10.0.0.2 sent 20"));
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-1 to 10.0.0.1 sent 3"));
+
+        let attributes = &result.updated_fibers[0].attributes;
+        assert_eq!(attributes["backend"], serde_json::json!(["10.0.0.1", "10.0.0.2"]));
+        assert_eq!(attributes["bytes"], 123);
+
+        // Accumulated values survive a checkpoint
+        let checkpoint = processor.create_checkpoint();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut restored = FiberTypeProcessor::new(compiled, 1);
+        restored.restore_from_checkpoint(&checkpoint);
+        let result = restored.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-1 to 10.0.0.3 sent 7"));
+        let attributes = &result.updated_fibers[0].attributes;
+        assert_eq!(attributes["backend"], serde_json::json!(["10.0.0.1", "10.0.0.2", "10.0.0.3"]));
+        assert_eq!(attributes["bytes"], 130);
+    }
+
+    #[test]
+    fn test_fiber_metrics_in_records() {
+        let mut config = make_simple_fiber_type();
+        let program1 = config.sources.get_mut("program1").unwrap();
+        let mut error_pattern = program1.patterns[0].clone();
+        error_pattern.regex = r"thread-(?P<thread_id>\d+) failed".to_string();
+        error_pattern.mark = Some(PatternMark::Error);
+        program1.patterns.insert(0, error_pattern);
+        let program2 = program1.clone();
+        config.sources.insert("program2".to_string(), program2);
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 started"));
+        processor.process_log(&make_log("program2", "2025-12-04T10:00:01Z", "thread-1 failed"));
+        let result = processor.process_log(&make_log("program2", "2025-12-04T10:00:02Z", "thread-1 retried"));
+
+        let metrics = &result.updated_fibers[0].metrics;
+        assert_eq!(metrics.log_count, 3);
+        assert_eq!(metrics.error_count, 1);
+        assert_eq!(metrics.source_counts["program1"], 1);
+        assert_eq!(metrics.source_counts["program2"], 2);
+        assert_eq!(result.updated_fibers[0].duration(), chrono::Duration::seconds(2));
+
+        // Metrics survive a checkpoint
+        let checkpoint = processor.create_checkpoint();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut restored = FiberTypeProcessor::new(compiled, 1);
+        restored.restore_from_checkpoint(&checkpoint);
+        let result = restored.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-1 failed"));
+        let metrics = &result.updated_fibers[0].metrics;
+        assert_eq!(metrics.log_count, 4);
+        assert_eq!(metrics.error_count, 2);
+    }
+
+    #[test]
+    fn test_sequence_violation_on_close() {
+        let mut config = make_simple_fiber_type();
+        let base = config.sources["program1"].patterns[0].clone();
+        let pattern = |word: &str, step: &str, close: bool| PatternConfig {
+            regex: format!(r"thread-(?P<thread_id>\d+) {}", word),
+            step: Some(step.to_string()),
+            close,
+            ..base.clone()
+        };
+        config.sources.get_mut("program1").unwrap().patterns = vec![
+            pattern("received", "received", false),
+            pattern("forwarded", "forwarded", false),
+            pattern("complete", "complete", true),
+        ];
+        config.sequence = serde_yaml::from_str(
+            "[{ step: received }, { step: forwarded, within: 2s }, { step: complete }]",
+        )
+        .unwrap();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        // The attributes a fiber is updated with as it closes
+        let verdict = |result: &ProcessResult| {
+            let closed = result.closed_fibers[0].fiber_id;
+            let record = result
+                .updated_fibers
+                .iter()
+                .rev()
+                .find(|f| f.fiber_id == closed)
+                .unwrap();
+            (
+                record.attributes[VIOLATION_ATTRIBUTE].as_bool().unwrap(),
+                record.attributes.get(VIOLATION_REASON_ATTRIBUTE).and_then(|v| v.as_str()).map(String::from),
+            )
+        };
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 received"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "thread-1 forwarded"));
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-1 complete"));
+        assert_eq!(verdict(&result), (false, None));
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-2 received"));
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:04Z", "thread-2 complete"));
+        assert_eq!(
+            verdict(&result),
+            (true, Some("skipped 'forwarded': 'complete' came first".to_string()))
+        );
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:05Z", "thread-3 received"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:09Z", "thread-3 forwarded"));
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:10Z", "thread-3 complete"));
+        assert_eq!(
+            verdict(&result),
+            (true, Some("'forwarded' came 4s after 'received' (limit 2s)".to_string()))
+        );
+
+        // Progress survives a checkpoint, and a fiber cut off by a flush
+        // gets no verdict, as it might still have reached its remaining steps
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:11Z", "thread-4 received"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:12Z", "thread-4 forwarded"));
+        let checkpoint = processor.create_checkpoint();
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut restored = FiberTypeProcessor::new(compiled, 1);
+        restored.restore_from_checkpoint(&checkpoint);
+        let result = restored.flush();
+        assert_eq!(result.closed_fibers.len(), 1);
+        assert!(result
+            .updated_fibers
+            .iter()
+            .all(|f| f.attributes.get(VIOLATION_ATTRIBUTE).is_none()));
+    }
+
     #[test]
     fn test_unmatched_source_ignored() {
         let config = make_simple_fiber_type();
@@ -985,7 +1652,239 @@ mod tests {
         assert_eq!(processor.open_fiber_count(), 3);
 
         let result = processor.flush();
-        assert_eq!(result.closed_fiber_ids.len(), 3);
+        assert_eq!(result.closed_fibers.len(), 3);
+        assert!(result.closed_fibers.iter().all(|c| c.reason == CloseReason::Flush));
         assert_eq!(processor.open_fiber_count(), 0);
     }
+
+    #[test]
+    fn test_pattern_matches_record_fields() {
+        let mut config = make_simple_fiber_type();
+        config.attributes.push(AttributeConfig {
+            name: "host".to_string(),
+            attr_type: AttributeType::String,
+            key: true,
+            derived: None,
+            mode: Default::default(),
+        });
+        config.sources.get_mut("program1").unwrap().patterns = vec![PatternConfig {
+            regex: String::new(),
+            fields: [
+                ("app_name".to_string(), "^sshd$".to_string()),
+                ("hostname".to_string(), "(?P<host>.+)".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            labels: Default::default(),
+            json: Default::default(),
+            release_matching_peer_keys: vec![],
+            release_self_keys: vec![],
+            close: false,
+            when: vec![],
+            mark: None,
+            step: None,
+        }];
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        let with_fields = |app_name: &str| {
+            let mut log = make_log("program1", "2025-12-04T10:00:00Z", "session opened");
+            log.fields.insert("app_name".to_string(), app_name.to_string());
+            log.fields.insert("hostname".to_string(), "gateway".to_string());
+            log
+        };
+
+        let result = processor.process_log(&with_fields("sshd"));
+        assert_eq!(result.new_fibers.len(), 1);
+        assert_eq!(result.new_fibers[0].attributes["host"], "gateway");
+
+        // A field regex that doesn't match rejects the pattern
+        let result = processor.process_log(&with_fields("cron"));
+        assert!(result.memberships.is_empty());
+
+        // So does a missing field
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "x"));
+        assert!(result.memberships.is_empty());
+    }
+
+    #[test]
+    fn test_first_matching_pattern_wins() {
+        let mut config = make_simple_fiber_type();
+        let pattern = |regex: &str, fields: &[(&str, &str)], close: bool| PatternConfig {
+            regex: regex.to_string(),
+            fields: fields
+                .iter()
+                .map(|(field, regex)| (field.to_string(), regex.to_string()))
+                .collect(),
+            labels: Default::default(),
+            json: Default::default(),
+            release_matching_peer_keys: vec![],
+            release_self_keys: vec![],
+            close,
+            when: vec![],
+            mark: None,
+            step: None,
+        };
+        config.sources.get_mut("program1").unwrap().patterns = vec![
+            pattern(r"thread-(?P<thread_id>\d+) done", &[("level", "^INFO$")], true),
+            pattern(r"thread-(?P<thread_id>\d+) done", &[], false),
+            pattern(r"thread-(?P<thread_id>\d+) paused", &[], false),
+            pattern(r"thread-(?P<thread_id>\d+) resumed", &[], false),
+            pattern(r"thread-(?P<thread_id>\d+) failed", &[], true),
+            pattern(r"thread-(?P<thread_id>\d+)", &[], true),
+        ];
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        // Both "done" patterns are candidates; the first fails its field
+        // check, so the second (which doesn't close) is used
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 done"));
+        assert_eq!(result.new_fibers.len(), 1);
+        assert!(result.closed_fibers.is_empty());
+
+        let mut log = make_log("program1", "2025-12-04T10:00:01Z", "thread-2 done");
+        log.fields.insert("level".to_string(), "INFO".to_string());
+        let result = processor.process_log(&log);
+        assert_eq!(result.closed_fibers.len(), 1);
+
+        // Only the last pattern matches
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-3 start"));
+        assert_eq!(result.closed_fibers.len(), 1);
+    }
+
+    #[test]
+    fn test_pattern_matches_source_labels() {
+        let mut config = make_simple_fiber_type();
+        config.attributes.push(AttributeConfig {
+            name: "host".to_string(),
+            attr_type: AttributeType::String,
+            key: false,
+            derived: None,
+            mode: Default::default(),
+        });
+        config.sources.get_mut("program1").unwrap().patterns[0]
+            .labels
+            .insert("host".to_string(), "(?P<host>.+)".to_string());
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        let mut log = make_log("program1", "2025-12-04T10:00:00Z", "thread-1 start");
+        log.labels.insert("host".to_string(), "web1".to_string());
+        let result = processor.process_log(&log);
+        assert_eq!(result.new_fibers.len(), 1);
+        assert_eq!(result.new_fibers[0].attributes["host"], "web1");
+
+        // Logs without the label don't match
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "thread-1 x"));
+        assert!(result.memberships.is_empty());
+    }
+
+    #[test]
+    fn test_pattern_guards() {
+        let mut config = make_simple_fiber_type();
+        for (name, attr_type) in [("status", AttributeType::Int), ("ip", AttributeType::Ip)] {
+            config.attributes.push(AttributeConfig {
+                name: name.to_string(),
+                attr_type,
+                key: false,
+                derived: None,
+                mode: Default::default(),
+            });
+        }
+        let pattern = |regex: &str, when: &[&str]| PatternConfig {
+            regex: regex.to_string(),
+            fields: Default::default(),
+            labels: Default::default(),
+            json: Default::default(),
+            release_matching_peer_keys: vec![],
+            release_self_keys: vec![],
+            close: !when.is_empty(),
+            when: when.iter().map(|guard| serde_yaml::from_str(guard).unwrap()).collect(),
+            mark: None,
+            step: None,
+        };
+        config.sources.get_mut("program1").unwrap().patterns = vec![
+            // Guard on an attribute this pattern extracts
+            pattern(
+                r"thread-(?P<thread_id>\d+) status=(?P<status>\d+)",
+                &["{ attribute: status, gte: 500 }"],
+            ),
+            // Guard on an attribute set by an earlier log
+            pattern(r"thread-(?P<thread_id>\d+) bye", &["{ attribute: ip, in: [10.0.0.0/8] }"]),
+            pattern(r"thread-(?P<thread_id>\d+)(?: from (?P<ip>\S+))?", &[]),
+        ];
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:00Z", "thread-1 status=404"));
+        assert_eq!(result.new_fibers.len(), 1);
+        assert!(result.closed_fibers.is_empty());
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:01Z", "thread-1 status=503"));
+        assert_eq!(result.closed_fibers.len(), 1);
+
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-2 from 192.168.0.9"));
+        processor.process_log(&make_log("program1", "2025-12-04T10:00:02Z", "thread-3 from 10.1.2.3"));
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-2 bye"));
+        assert!(result.closed_fibers.is_empty());
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:03Z", "thread-3 bye"));
+        assert_eq!(result.closed_fibers.len(), 1);
+
+        // A new fiber has no value for the guard to check
+        let result = processor.process_log(&make_log("program1", "2025-12-04T10:00:04Z", "thread-4 bye"));
+        assert_eq!(result.new_fibers.len(), 1);
+        assert!(result.closed_fibers.is_empty());
+    }
+
+    #[test]
+    fn test_pattern_extracts_json_attributes() {
+        let mut config = make_simple_fiber_type();
+        config.attributes.push(AttributeConfig {
+            name: "user".to_string(),
+            attr_type: AttributeType::String,
+            key: false,
+            derived: None,
+            mode: Default::default(),
+        });
+        config.sources.get_mut("program1").unwrap().patterns = vec![PatternConfig {
+            regex: r#""level":"info""#.to_string(),
+            fields: Default::default(),
+            labels: Default::default(),
+            json: [
+                ("thread_id".to_string(), "/req/thread".to_string()),
+                ("user".to_string(), "/user/name".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            release_matching_peer_keys: vec![],
+            release_self_keys: vec![],
+            close: false,
+            when: vec![],
+            mark: None,
+            step: None,
+        }];
+        let compiled = CompiledFiberType::from_config("test", &config).unwrap();
+        let mut processor = FiberTypeProcessor::new(compiled, 1);
+
+        let log = make_log(
+            "program1",
+            "2025-12-04T10:00:00Z",
+            r#"{"level":"info","req":{"thread":7},"user":{"name":"alice"}}"#,
+        );
+        let result = processor.process_log(&log);
+        assert_eq!(result.new_fibers.len(), 1);
+        assert_eq!(result.new_fibers[0].attributes["thread_id"], "7");
+        assert_eq!(result.new_fibers[0].attributes["user"], "alice");
+
+        // A pointer that doesn't resolve rejects the pattern
+        let log = make_log(
+            "program1",
+            "2025-12-04T10:00:01Z",
+            r#"{"level":"info","req":{"thread":7},"user":null}"#,
+        );
+        assert!(processor.process_log(&log).memberships.is_empty());
+
+        // Lines that aren't JSON never match
+        let log = make_log("program1", "2025-12-04T10:00:02Z", r#""level":"info" thread-7"#);
+        assert!(processor.process_log(&log).memberships.is_empty());
+    }
 }
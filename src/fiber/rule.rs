@@ -1,6 +1,11 @@
-use crate::config::types::{AttributeType, FiberTypeConfig, GapMode, PatternConfig};
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use crate::config::types::{
+    AttributeMode, AttributeType, FiberTypeConfig, GapMode, PatternConfig, PatternMark,
+};
+use crate::fiber::guard::{CompiledGuard, GuardError};
+use crate::fiber::sequence::CompiledSequence;
+use crate::source::discovery::parent_source_id;
+use regex::{Regex, RegexSet, SetMatchesIntoIter};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::Duration;
 use thiserror::Error;
 
@@ -31,6 +36,12 @@ pub enum RuleError {
 
     #[error("duplicate attribute name: {0}")]
     DuplicateAttribute(String),
+
+    #[error("invalid when guard: {0}")]
+    Guard(#[from] GuardError),
+
+    #[error("pattern step '{0}' is not in the fiber type's sequence")]
+    UnknownStep(String),
 }
 
 /// Temporal configuration for a fiber type
@@ -49,6 +60,7 @@ pub struct AttributeDef {
     pub attr_type: AttributeType,
     pub key: bool,
     pub derived: Option<String>,
+    pub mode: AttributeMode,
 }
 
 /// Template for derived attribute computation
@@ -122,32 +134,67 @@ impl DerivedTemplate {
 pub struct CompiledPattern {
     /// The compiled regex
     pub regex: Regex,
+    /// Compiled regexes for record fields that must also match
+    pub field_regexes: Vec<(String, Regex)>,
+    /// Compiled regexes for source labels that must also match
+    pub label_regexes: Vec<(String, Regex)>,
+    /// Attributes read from JSON log lines, as (attribute, JSON pointer)
+    pub json_pointers: Vec<(String, String)>,
     /// Keys to release from peer fibers before processing
     pub release_matching_peer_keys: Vec<String>,
     /// Keys to release from self after processing
     pub release_self_keys: Vec<String>,
     /// Whether to close the fiber after this pattern matches
     pub close: bool,
+    /// How the pattern is named in close records: its regex, or what it
+    /// matches when the regex is empty
+    pub description: String,
     /// Keys this pattern can extract (capture groups that are marked as keys)
     pub extracted_keys: HashSet<String>,
     /// All capture groups in this pattern
     pub capture_groups: HashSet<String>,
+    /// Conditions on attribute values that must hold for the pattern to apply
+    pub guards: Vec<CompiledGuard>,
+    /// Metric the matched log is counted in
+    pub mark: Option<PatternMark>,
+    /// Index of the sequence step the matched log reaches
+    pub step: Option<usize>,
 }
 
 impl CompiledPattern {
     fn from_config(
         config: &PatternConfig,
         key_names: &HashSet<String>,
+        attributes: &[AttributeDef],
+        sequence: Option<&CompiledSequence>,
     ) -> Result<Self, RuleError> {
         let regex = Regex::new(&config.regex).map_err(|e| RuleError::RegexCompilation {
             pattern: config.regex.clone(),
             source: e,
         })?;
 
+        let compile_all = |patterns: &BTreeMap<String, String>| {
+            patterns
+                .iter()
+                .map(|(name, pattern)| {
+                    Regex::new(pattern)
+                        .map(|regex| (name.clone(), regex))
+                        .map_err(|e| RuleError::RegexCompilation {
+                            pattern: pattern.clone(),
+                            source: e,
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let field_regexes = compile_all(&config.fields)?;
+        let label_regexes = compile_all(&config.labels)?;
+
         // Get all named capture groups
-        let capture_groups: HashSet<String> = regex
-            .capture_names()
-            .flatten()
+        let capture_groups: HashSet<String> = std::iter::once(&regex)
+            .chain(field_regexes.iter().map(|(_, regex)| regex))
+            .chain(label_regexes.iter().map(|(_, regex)| regex))
+            .flat_map(|regex| regex.capture_names().flatten())
+            .chain(config.json.keys().map(String::as_str))
             .map(|s| s.to_string())
             .collect();
 
@@ -175,17 +222,115 @@ impl CompiledPattern {
             }
         }
 
+        let guards = config
+            .when
+            .iter()
+            .map(|guard| {
+                let attr_type = attributes
+                    .iter()
+                    .find(|a| a.name == guard.attribute)
+                    .map(|a| a.attr_type);
+                CompiledGuard::new(guard, attr_type)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let step = match &config.step {
+            Some(name) => Some(
+                sequence
+                    .and_then(|sequence| sequence.position(name))
+                    .ok_or_else(|| RuleError::UnknownStep(name.clone()))?,
+            ),
+            None => None,
+        };
+
         Ok(Self {
             regex,
+            field_regexes,
+            label_regexes,
+            json_pointers: config
+                .json
+                .iter()
+                .map(|(attr, pointer)| (attr.clone(), pointer.clone()))
+                .collect(),
             release_matching_peer_keys: config.release_matching_peer_keys.clone(),
             release_self_keys: config.release_self_keys.clone(),
             close: config.close,
+            description: describe_pattern(config),
             extracted_keys,
             capture_groups,
+            guards,
+            mark: config.mark,
+            step,
         })
     }
 }
 
+/// Fewest patterns a source needs before they're prefiltered; below this,
+/// trying each regex in turn is about as cheap as scanning with the set
+const MIN_PREFILTER_PATTERNS: usize = 6;
+
+/// The patterns configured for one source, in configuration order.
+///
+/// When there are several patterns their regexes are also compiled into a
+/// single `RegexSet`, so one scan of a log line finds the patterns that can
+/// match it and captures only run for those.
+#[derive(Debug)]
+pub struct SourcePatterns {
+    pub patterns: Vec<CompiledPattern>,
+    prefilter: Option<RegexSet>,
+}
+
+impl SourcePatterns {
+    pub fn new(patterns: Vec<CompiledPattern>) -> Self {
+        // A set that fails to build (e.g. over the size limit) just means
+        // every pattern is tried in turn
+        let prefilter = (patterns.len() >= MIN_PREFILTER_PATTERNS)
+            .then(|| RegexSet::new(patterns.iter().map(|p| p.regex.as_str())).ok())
+            .flatten();
+        Self { patterns, prefilter }
+    }
+
+    /// Patterns whose regex matches `text`, in configuration order, so the
+    /// first candidate that passes its other checks is the first match
+    pub fn candidates(&self, text: &str) -> Candidates<'_> {
+        match &self.prefilter {
+            Some(set) => Candidates::Matched {
+                patterns: &self.patterns,
+                matches: set.matches(text).into_iter(),
+            },
+            None => Candidates::All(self.patterns.iter()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
+/// Iterator over the candidate patterns for a log line
+pub enum Candidates<'a> {
+    Matched {
+        patterns: &'a [CompiledPattern],
+        matches: SetMatchesIntoIter,
+    },
+    All(std::slice::Iter<'a, CompiledPattern>),
+}
+
+impl<'a> Iterator for Candidates<'a> {
+    type Item = &'a CompiledPattern;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Candidates::Matched { patterns, matches } => matches.next().map(|i| &patterns[i]),
+            Candidates::All(iter) => iter.next(),
+        }
+    }
+}
+
 /// A compiled fiber type ready for processing
 #[derive(Debug)]
 pub struct CompiledFiberType {
@@ -202,7 +347,9 @@ pub struct CompiledFiberType {
     /// Templates for derived attributes
     pub derived_templates: HashMap<String, DerivedTemplate>,
     /// Patterns for each source
-    pub source_patterns: HashMap<String, Vec<CompiledPattern>>,
+    pub source_patterns: HashMap<String, SourcePatterns>,
+    /// Expected order of pattern steps, if the type has one
+    pub sequence: Option<CompiledSequence>,
 }
 
 impl CompiledFiberType {
@@ -233,6 +380,7 @@ impl CompiledFiberType {
                 attr_type: a.attr_type,
                 key: a.key,
                 derived: a.derived.clone(),
+                mode: a.mode,
             })
             .collect();
 
@@ -259,15 +407,23 @@ impl CompiledFiberType {
         // Topological sort of derived attributes
         let derived_order = topological_sort_derived(&derived_templates)?;
 
+        let sequence = (!config.sequence.is_empty())
+            .then(|| CompiledSequence::new(&config.sequence));
+
         // Compile patterns for each source
         let mut source_patterns = HashMap::new();
         for (source_id, source_config) in &config.sources {
             let mut patterns = Vec::new();
             for pattern_config in &source_config.patterns {
-                let compiled = CompiledPattern::from_config(pattern_config, &key_names)?;
+                let compiled = CompiledPattern::from_config(
+                    pattern_config,
+                    &key_names,
+                    &attributes,
+                    sequence.as_ref(),
+                )?;
                 patterns.push(compiled);
             }
-            source_patterns.insert(source_id.clone(), patterns);
+            source_patterns.insert(source_id.clone(), SourcePatterns::new(patterns));
         }
 
         Ok(Self {
@@ -281,9 +437,29 @@ impl CompiledFiberType {
             derived_order,
             derived_templates,
             source_patterns,
+            sequence,
         })
     }
 
+    /// Get the patterns that apply to logs from a source.
+    ///
+    /// Files discovered by a glob/directory source have derived IDs like
+    /// `app/api.log`; when there are no patterns for the exact ID, the patterns
+    /// configured for the parent source (`app`) are used.
+    pub fn patterns_for_source(&self, source_id: &str) -> Option<&SourcePatterns> {
+        self.source_patterns.get(source_id).or_else(|| {
+            parent_source_id(source_id).and_then(|parent| self.source_patterns.get(parent))
+        })
+    }
+
+    /// Get how an attribute accumulates values (`last` if it isn't defined)
+    pub fn attribute_mode(&self, name: &str) -> AttributeMode {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map_or(AttributeMode::Last, |a| a.mode)
+    }
+
     /// Get the attribute type for a given attribute name
     pub fn get_attribute_type(&self, name: &str) -> Option<AttributeType> {
         self.attributes
@@ -342,6 +518,24 @@ fn topological_sort_derived(
     Ok(result)
 }
 
+fn describe_pattern(config: &PatternConfig) -> String {
+    if !config.regex.is_empty() {
+        return config.regex.clone();
+    }
+    let conditions: Vec<String> = config
+        .fields
+        .iter()
+        .map(|(field, regex)| format!("fields.{}: {}", field, regex))
+        .chain(config.labels.iter().map(|(label, regex)| format!("labels.{}: {}", label, regex)))
+        .chain(config.json.iter().map(|(attr, pointer)| format!("json.{}: {}", attr, pointer)))
+        .collect();
+    if conditions.is_empty() {
+        "(any line)".to_string()
+    } else {
+        conditions.join(", ")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,12 +557,14 @@ mod tests {
                     attr_type: AttributeType::String,
                     key: true,
                     derived: None,
+                    mode: Default::default(),
                 },
                 AttributeConfig {
                     name: "ip".to_string(),
                     attr_type: AttributeType::Ip,
                     key: false,
                     derived: None,
+                    mode: Default::default(),
                 },
             ],
             sources: {
@@ -378,15 +574,23 @@ mod tests {
                     FiberSourceConfig {
                         patterns: vec![PatternConfig {
                             regex: r"thread-(?P<thread_id>\d+)".to_string(),
+                            fields: Default::default(),
+                            labels: Default::default(),
+                            json: Default::default(),
                             release_matching_peer_keys: vec![],
                             release_self_keys: vec![],
                             close: false,
+                            when: vec![],
+                            mark: None,
+                            step: None,
                         }],
                     },
                 );
                 sources
             },
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         }
     }
 
@@ -448,6 +652,7 @@ mod tests {
             attr_type: AttributeType::String,
             key: false,
             derived: None,
+            mode: Default::default(),
         });
 
         let result = CompiledFiberType::from_config("test", &config);
@@ -472,6 +677,7 @@ mod tests {
             attr_type: AttributeType::String,
             key: true,
             derived: None,
+            mode: Default::default(),
         });
         config.sources.get_mut("program1").unwrap().patterns[0]
             .release_matching_peer_keys
@@ -507,16 +713,20 @@ mod tests {
                     attr_type: AttributeType::String,
                     key: false,
                     derived: Some("${b}".to_string()),
+                    mode: Default::default(),
                 },
                 AttributeConfig {
                     name: "b".to_string(),
                     attr_type: AttributeType::String,
                     key: false,
                     derived: Some("${a}".to_string()),
+                    mode: Default::default(),
                 },
             ],
             sources: HashMap::new(),
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         };
 
         let result = CompiledFiberType::from_config("test", &config);
@@ -536,9 +746,12 @@ mod tests {
                 attr_type: AttributeType::String,
                 key: false,
                 derived: Some("${undefined}".to_string()),
+                mode: Default::default(),
             }],
             sources: HashMap::new(),
             is_source_fiber: false,
+            parent: None,
+            sequence: vec![],
         };
 
         let result = CompiledFiberType::from_config("test", &config);
@@ -580,15 +793,55 @@ mod tests {
 
         let config = PatternConfig {
             regex: r"thread-(?P<thread_id>\d+).*MAC (?P<mac>[0-9a-f:]+)".to_string(),
+            fields: Default::default(),
+            labels: Default::default(),
+            json: Default::default(),
             release_matching_peer_keys: vec![],
             release_self_keys: vec![],
             close: false,
+            when: vec![],
+            mark: None,
+            step: None,
         };
 
-        let compiled = CompiledPattern::from_config(&config, &key_names).unwrap();
+        let compiled = CompiledPattern::from_config(&config, &key_names, &[], None).unwrap();
 
         assert!(compiled.extracted_keys.contains("thread_id"));
         assert!(compiled.extracted_keys.contains("mac"));
         assert_eq!(compiled.extracted_keys.len(), 2);
     }
+
+    #[test]
+    fn test_candidates_keep_configuration_order() {
+        let key_names = HashSet::new();
+        let compile = |regex: &str| {
+            let config = PatternConfig {
+                regex: regex.to_string(),
+                fields: Default::default(),
+                labels: Default::default(),
+                json: Default::default(),
+                release_matching_peer_keys: vec![],
+                release_self_keys: vec![],
+                close: false,
+                when: vec![],
+                mark: None,
+                step: None,
+            };
+            CompiledPattern::from_config(&config, &key_names, &[], None).unwrap()
+        };
+        let patterns = SourcePatterns::new(
+            ["GET", "POST", "PUT", "PATCH", "HEAD", r"\d{3}$"]
+                .into_iter()
+                .map(compile)
+                .collect(),
+        );
+        assert!(patterns.prefilter.is_some());
+
+        let regexes = |text: &str| -> Vec<&str> {
+            patterns.candidates(text).map(|p| p.regex.as_str()).collect()
+        };
+        assert_eq!(regexes("POST /login 200"), vec!["POST", r"\d{3}$"]);
+        assert_eq!(regexes("GET /"), vec!["GET"]);
+        assert!(regexes("DELETE /").is_empty());
+    }
 }
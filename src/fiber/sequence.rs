@@ -0,0 +1,203 @@
+use crate::config::types::SequenceStepConfig;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Set on closed fibers of a type with a `sequence`: whether the fiber broke it
+pub const VIOLATION_ATTRIBUTE: &str = "sequence_violation";
+/// Set with `sequence_violation`: how the fiber broke the sequence
+pub const VIOLATION_REASON_ATTRIBUTE: &str = "sequence_violation_reason";
+
+/// A fiber type's expected order of pattern steps
+#[derive(Debug, Clone)]
+pub struct CompiledSequence {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    name: String,
+    /// Time allowed since the previous step
+    within: Option<Duration>,
+}
+
+/// How far a fiber has got through its type's sequence
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SequenceProgress {
+    /// Number of steps reached, in order
+    pub reached: usize,
+    /// When the latest step was reached
+    pub last_step_at: Option<DateTime<Utc>>,
+    /// The first way the fiber broke the sequence
+    pub violation: Option<String>,
+}
+
+impl CompiledSequence {
+    pub fn new(steps: &[SequenceStepConfig]) -> Self {
+        Self {
+            steps: steps
+                .iter()
+                .map(|step| Step {
+                    name: step.step.clone(),
+                    within: step.within.and_then(|within| Duration::from_std(within).ok()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Index of the step with this name
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.steps.iter().position(|step| step.name == name)
+    }
+
+    /// Record a fiber's log reaching step `index` at `at`. Repeating the
+    /// latest step is allowed; skipping ahead or going back is a violation,
+    /// as is reaching a step later than its time limit.
+    pub fn advance(&self, progress: &mut SequenceProgress, index: usize, at: DateTime<Utc>) {
+        if progress.violation.is_some() {
+            return;
+        }
+        let step = &self.steps[index];
+        match index.cmp(&progress.reached) {
+            Ordering::Equal => {
+                if let (Some(within), Some(last)) = (step.within, progress.last_step_at) {
+                    if at - last > within {
+                        progress.violation = Some(format!(
+                            "'{}' came {} after '{}' (limit {})",
+                            step.name,
+                            format_duration(at - last),
+                            self.steps[index - 1].name,
+                            format_duration(within)
+                        ));
+                    }
+                }
+                progress.reached += 1;
+                progress.last_step_at = Some(at);
+            }
+            Ordering::Less if index + 1 == progress.reached => {}
+            Ordering::Less => {
+                progress.violation = Some(format!(
+                    "'{}' came after '{}'",
+                    step.name,
+                    self.steps[progress.reached - 1].name
+                ));
+            }
+            Ordering::Greater => {
+                progress.violation = Some(format!(
+                    "skipped '{}': '{}' came first",
+                    self.steps[progress.reached].name, step.name
+                ));
+            }
+        }
+    }
+
+    /// How a fiber closing at `now` broke the sequence, if it did. A
+    /// `finished` fiber that hasn't reached every step breaks it; one cut off
+    /// before it could (by a flush) only breaks it by running out of time.
+    pub fn violation(
+        &self,
+        progress: &SequenceProgress,
+        now: DateTime<Utc>,
+        finished: bool,
+    ) -> Option<String> {
+        if let Some(violation) = &progress.violation {
+            return Some(violation.clone());
+        }
+        let next = self.steps.get(progress.reached)?;
+        match (next.within, progress.last_step_at) {
+            (Some(within), Some(last)) if now - last > within => Some(format!(
+                "'{}' not reached within {} of '{}'",
+                next.name,
+                format_duration(within),
+                self.steps[progress.reached - 1].name
+            )),
+            _ if finished => Some(format!("never reached '{}'", next.name)),
+            _ => None,
+        }
+    }
+}
+
+impl SequenceProgress {
+    /// Combine with the progress of a fiber merged into this one
+    pub fn merge(&mut self, other: SequenceProgress) {
+        if self.violation.is_none() {
+            self.violation = other.violation;
+        }
+        if other.reached > self.reached {
+            self.reached = other.reached;
+            self.last_step_at = other.last_step_at;
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    humantime::format_duration(duration.to_std().unwrap_or_default()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+
+    fn sequence() -> CompiledSequence {
+        let steps: Vec<SequenceStepConfig> = serde_yaml::from_str(
+            "[{ step: received }, { step: forwarded, within: 2s }, { step: complete }]",
+        )
+        .unwrap();
+        CompiledSequence::new(&steps)
+    }
+
+    fn progress(steps: &[(usize, i64)]) -> SequenceProgress {
+        let mut progress = SequenceProgress::default();
+        for &(index, secs) in steps {
+            sequence().advance(&mut progress, index, at(secs));
+        }
+        progress
+    }
+
+    fn run(steps: &[(usize, i64)], closed_at: i64) -> Option<String> {
+        sequence().violation(&progress(steps), at(closed_at), true)
+    }
+
+    #[test]
+    fn test_sequence_violations() {
+        // In order, repeating a step
+        assert_eq!(run(&[(0, 0), (1, 1), (1, 2), (2, 3)], 3), None);
+
+        assert_eq!(
+            run(&[(0, 0), (2, 1)], 1).as_deref(),
+            Some("skipped 'forwarded': 'complete' came first")
+        );
+        assert_eq!(
+            run(&[(0, 0), (1, 1), (0, 2), (2, 3)], 3).as_deref(),
+            Some("'received' came after 'forwarded'")
+        );
+        assert_eq!(
+            run(&[(0, 0), (1, 3), (2, 4)], 4).as_deref(),
+            Some("'forwarded' came 3s after 'received' (limit 2s)")
+        );
+        assert_eq!(run(&[(0, 0), (1, 1)], 9).as_deref(), Some("never reached 'complete'"));
+        assert_eq!(
+            run(&[(0, 0)], 5).as_deref(),
+            Some("'forwarded' not reached within 2s of 'received'")
+        );
+
+        // A flushed fiber may still have reached its remaining steps
+        let flushed = |steps: &[(usize, i64)], at_secs: i64| {
+            sequence().violation(&progress(steps), at(at_secs), false)
+        };
+        assert_eq!(flushed(&[(0, 0), (1, 1)], 9), None);
+        assert_eq!(
+            flushed(&[(0, 0)], 5).as_deref(),
+            Some("'forwarded' not reached within 2s of 'received'")
+        );
+        assert_eq!(
+            flushed(&[(0, 0), (2, 1)], 1).as_deref(),
+            Some("skipped 'forwarded': 'complete' came first")
+        );
+    }
+}
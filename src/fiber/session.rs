@@ -1,6 +1,9 @@
-use crate::config::types::AttributeType;
+use crate::config::types::{AttributeMode, AttributeType};
+use crate::fiber::sequence::SequenceProgress;
+use crate::storage::metrics::FiberMetrics;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -11,6 +14,8 @@ pub enum AttributeValue {
     String(String),
     Int(i64),
     Float(f64),
+    /// Distinct values of a `set` attribute, in the order first seen
+    List(Vec<AttributeValue>),
 }
 
 impl AttributeValue {
@@ -20,6 +25,73 @@ impl AttributeValue {
             AttributeValue::String(s) => s.clone(),
             AttributeValue::Int(i) => i.to_string(),
             AttributeValue::Float(f) => f.to_string(),
+            AttributeValue::List(values) => values
+                .iter()
+                .map(AttributeValue::as_key_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Int(i) => Some(*i as f64),
+            AttributeValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Numbers compare numerically, anything else by its string form
+    fn compare(&self, other: &AttributeValue) -> Ordering {
+        match (self, other) {
+            (AttributeValue::Int(a), AttributeValue::Int(b)) => a.cmp(b),
+            _ => match (self.as_f64(), other.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => self.as_key_string().cmp(&other.as_key_string()),
+            },
+        }
+    }
+
+    fn values(self) -> Vec<AttributeValue> {
+        match self {
+            AttributeValue::List(values) => values,
+            value => vec![value],
+        }
+    }
+
+    /// The value a fiber starts with when an attribute is first seen
+    fn initial(self, mode: AttributeMode) -> AttributeValue {
+        match mode {
+            AttributeMode::Set => AttributeValue::List(self.values()),
+            AttributeMode::Count => AttributeValue::Int(1),
+            _ => self,
+        }
+    }
+
+    /// Combine two accumulated values of an attribute, `self` being the
+    /// earlier one
+    fn combine(self, later: AttributeValue, mode: AttributeMode) -> AttributeValue {
+        match mode {
+            AttributeMode::First => self,
+            AttributeMode::Last => later,
+            AttributeMode::Set => {
+                let mut values = self.values();
+                for value in later.values() {
+                    if !values.contains(&value) {
+                        values.push(value);
+                    }
+                }
+                AttributeValue::List(values)
+            }
+            AttributeMode::Count | AttributeMode::Sum => match (&self, &later) {
+                (AttributeValue::Int(a), AttributeValue::Int(b)) => AttributeValue::Int(a.saturating_add(*b)),
+                _ => match (self.as_f64(), later.as_f64()) {
+                    (Some(a), Some(b)) => AttributeValue::Float(a + b),
+                    _ => later,
+                },
+            },
+            AttributeMode::Min => std::cmp::min_by(self, later, AttributeValue::compare),
+            AttributeMode::Max => std::cmp::max_by(self, later, AttributeValue::compare),
         }
     }
 
@@ -92,6 +164,10 @@ pub struct OpenFiber {
     pub last_activity: DateTime<Utc>,
     /// IDs of all logs belonging to this fiber
     pub log_ids: Vec<Uuid>,
+    /// Counts of the logs belonging to this fiber
+    pub metrics: FiberMetrics,
+    /// Progress through the fiber type's sequence
+    pub sequence: SequenceProgress,
 }
 
 impl OpenFiber {
@@ -105,13 +181,16 @@ impl OpenFiber {
             first_activity: timestamp,
             last_activity: timestamp,
             log_ids: Vec::new(),
+            metrics: FiberMetrics::default(),
+            sequence: SequenceProgress::default(),
         }
     }
 
-    /// Add a log to this fiber
+    /// Add a log to this fiber. A late log can only widen the activity range.
     pub fn add_log(&mut self, log_id: Uuid, timestamp: DateTime<Utc>) {
         self.log_ids.push(log_id);
-        self.last_activity = timestamp;
+        self.first_activity = self.first_activity.min(timestamp);
+        self.last_activity = self.last_activity.max(timestamp);
     }
 
     /// Add or update a key
@@ -126,14 +205,26 @@ impl OpenFiber {
 
     /// Add or update an attribute, returning the old value if different
     pub fn set_attribute(&mut self, name: String, value: AttributeValue) -> Option<AttributeValue> {
-        let old_value = self.attributes.get(&name).cloned();
-        self.attributes.insert(name, value.clone());
+        self.record_attribute(name, value, AttributeMode::Last)
+    }
 
-        // Return old value only if different (for conflict detection)
-        match old_value {
-            Some(old) if old != value => Some(old),
-            _ => None,
-        }
+    /// Fold a value seen in a log into an attribute according to its mode.
+    /// Returns the old value when `last` mode replaced a different one.
+    pub fn record_attribute(
+        &mut self,
+        name: String,
+        value: AttributeValue,
+        mode: AttributeMode,
+    ) -> Option<AttributeValue> {
+        let value = value.initial(mode);
+        let Some(old_value) = self.attributes.remove(&name) else {
+            self.attributes.insert(name, value);
+            return None;
+        };
+
+        let conflict = (mode == AttributeMode::Last && old_value != value).then(|| old_value.clone());
+        self.attributes.insert(name, old_value.combine(value, mode));
+        conflict
     }
 
     /// Get all keys as a vec of (name, value) pairs
@@ -144,8 +235,21 @@ impl OpenFiber {
             .collect()
     }
 
-    /// Merge another fiber into this one (this fiber survives)
+    /// Merge another fiber into this one (this fiber survives), with every
+    /// attribute in `last` mode
     pub fn merge(&mut self, other: OpenFiber) -> Vec<(String, AttributeValue, AttributeValue)> {
+        self.merge_with_modes(other, |_| AttributeMode::Last)
+    }
+
+    /// Merge another fiber into this one (this fiber survives), combining
+    /// attributes by their modes. `first` and `last` take the value of the
+    /// fiber that started earlier or was active later; differing `last`
+    /// values are returned as (name, discarded, kept) conflicts.
+    pub fn merge_with_modes(
+        &mut self,
+        other: OpenFiber,
+        mode_of: impl Fn(&str) -> AttributeMode,
+    ) -> Vec<(String, AttributeValue, AttributeValue)> {
         let mut conflicts = Vec::new();
 
         // Merge keys
@@ -153,25 +257,36 @@ impl OpenFiber {
             self.keys.insert(key_name, value);
         }
 
-        // Merge attributes (latest wins, track conflicts)
         for (attr_name, other_value) in other.attributes {
-            if let Some(self_value) = self.attributes.get(&attr_name) {
-                if *self_value != other_value {
-                    // Conflict: use the value from the fiber with later last_activity
-                    if other.last_activity > self.last_activity {
-                        conflicts.push((attr_name.clone(), self_value.clone(), other_value.clone()));
-                        self.attributes.insert(attr_name, other_value);
+            let Some(self_value) = self.attributes.remove(&attr_name) else {
+                self.attributes.insert(attr_name, other_value);
+                continue;
+            };
+
+            let mode = mode_of(&attr_name);
+            let merged = match mode {
+                AttributeMode::First if other.first_activity < self.first_activity => other_value,
+                AttributeMode::First => self_value,
+                AttributeMode::Last => {
+                    let (discarded, kept) = if other.last_activity > self.last_activity {
+                        (self_value, other_value)
                     } else {
-                        conflicts.push((attr_name.clone(), other_value, self_value.clone()));
+                        (other_value, self_value)
+                    };
+                    if discarded != kept {
+                        conflicts.push((attr_name.clone(), discarded, kept.clone()));
                     }
+                    kept
                 }
-            } else {
-                self.attributes.insert(attr_name, other_value);
-            }
+                _ => self_value.combine(other_value, mode),
+            };
+            self.attributes.insert(attr_name, merged);
         }
 
         // Merge log_ids
         self.log_ids.extend(other.log_ids);
+        self.metrics.merge(other.metrics);
+        self.sequence.merge(other.sequence);
 
         // Update timestamps
         if other.first_activity < self.first_activity {
@@ -346,4 +461,71 @@ mod tests {
             Some(&AttributeValue::String("new".to_string()))
         );
     }
+
+    #[test]
+    fn test_record_attribute_modes() {
+        let ts: DateTime<Utc> = "2025-12-04T10:00:00Z".parse().unwrap();
+        let mut fiber = OpenFiber::new("test_type".to_string(), ts);
+        let ip = |s: &str| AttributeValue::String(s.to_string());
+
+        for (value, bytes) in [("10.0.0.1", 100), ("10.0.0.2", 50), ("10.0.0.1", 7)] {
+            for (name, mode, value) in [
+                ("first_ip", AttributeMode::First, ip(value)),
+                ("backends", AttributeMode::Set, ip(value)),
+                ("requests", AttributeMode::Count, ip(value)),
+                ("bytes", AttributeMode::Sum, AttributeValue::Int(bytes)),
+                ("smallest", AttributeMode::Min, AttributeValue::Int(bytes)),
+                ("largest", AttributeMode::Max, AttributeValue::Int(bytes)),
+            ] {
+                assert_eq!(fiber.record_attribute(name.to_string(), value, mode), None);
+            }
+        }
+
+        assert_eq!(fiber.attributes["first_ip"], ip("10.0.0.1"));
+        assert_eq!(
+            fiber.attributes["backends"],
+            AttributeValue::List(vec![ip("10.0.0.1"), ip("10.0.0.2")])
+        );
+        assert_eq!(fiber.attributes["requests"], AttributeValue::Int(3));
+        assert_eq!(fiber.attributes["bytes"], AttributeValue::Int(157));
+        assert_eq!(fiber.attributes["smallest"], AttributeValue::Int(7));
+        assert_eq!(fiber.attributes["largest"], AttributeValue::Int(100));
+        assert_eq!(
+            serde_json::to_value(&fiber.attributes["backends"]).unwrap(),
+            serde_json::json!(["10.0.0.1", "10.0.0.2"])
+        );
+    }
+
+    #[test]
+    fn test_fiber_merge_with_modes() {
+        let ts1: DateTime<Utc> = "2025-12-04T10:00:00Z".parse().unwrap();
+        let ts2: DateTime<Utc> = "2025-12-04T10:00:05Z".parse().unwrap();
+        let ip = |s: &str| AttributeValue::String(s.to_string());
+        let modes = |name: &str| match name {
+            "first_ip" => AttributeMode::First,
+            "backends" => AttributeMode::Set,
+            "bytes" => AttributeMode::Sum,
+            _ => AttributeMode::Last,
+        };
+
+        let mut later = OpenFiber::new("test_type".to_string(), ts2);
+        let mut earlier = OpenFiber::new("test_type".to_string(), ts1);
+        for (fiber, value, bytes) in [(&mut later, "10.0.0.2", 5), (&mut earlier, "10.0.0.1", 10)] {
+            for name in ["first_ip", "backends", "last_ip"] {
+                fiber.record_attribute(name.to_string(), ip(value), modes(name));
+            }
+            fiber.record_attribute("bytes".to_string(), AttributeValue::Int(bytes), AttributeMode::Sum);
+        }
+
+        let conflicts = later.merge_with_modes(earlier, modes);
+
+        assert_eq!(conflicts, vec![("last_ip".to_string(), ip("10.0.0.1"), ip("10.0.0.2"))]);
+        assert_eq!(later.attributes["first_ip"], ip("10.0.0.1"));
+        assert_eq!(later.attributes["last_ip"], ip("10.0.0.2"));
+        assert_eq!(
+            later.attributes["backends"],
+            AttributeValue::List(vec![ip("10.0.0.2"), ip("10.0.0.1")])
+        );
+        assert_eq!(later.attributes["bytes"], AttributeValue::Int(15));
+    }
 }
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use uuid::Uuid;
+
+/// Stale entries tolerated beyond this many before the heap is rebuilt
+const MIN_COMPACT_LEN: usize = 1024;
+
+/// Open fibers ordered by the time their gap is measured from.
+///
+/// Entries are never updated in place: a fiber is scheduled again whenever
+/// its reference time moves, and entries that no longer match the fiber (or
+/// whose fiber has closed) are skipped as they are popped. Checking for
+/// timeouts therefore only looks at fibers that are due.
+#[derive(Debug, Default)]
+pub struct TimeoutIndex {
+    heap: BinaryHeap<Reverse<(DateTime<Utc>, Uuid)>>,
+}
+
+impl TimeoutIndex {
+    /// Record `reference` as the time `fiber_id`'s gap is measured from
+    pub fn schedule(&mut self, fiber_id: Uuid, reference: DateTime<Utc>) {
+        self.heap.push(Reverse((reference, fiber_id)));
+    }
+
+    /// Pop the earliest entry if its reference time is before `cutoff`.
+    /// The entry may be stale; the caller checks it against the fiber.
+    pub fn pop_expired(&mut self, cutoff: DateTime<Utc>) -> Option<(Uuid, DateTime<Utc>)> {
+        let Reverse((reference, _)) = self.heap.peek()?;
+        if *reference >= cutoff {
+            return None;
+        }
+        let Reverse((reference, fiber_id)) = self.heap.pop()?;
+        Some((fiber_id, reference))
+    }
+
+    /// Whether stale entries outnumber the `open` fibers enough to rebuild
+    pub fn needs_compaction(&self, open: usize) -> bool {
+        self.heap.len() > MIN_COMPACT_LEN.max(open * 2)
+    }
+
+    /// Rebuild from the current reference times of the open fibers
+    pub fn rebuild(&mut self, fibers: impl IntoIterator<Item = (Uuid, DateTime<Utc>)>) {
+        self.heap = fibers
+            .into_iter()
+            .map(|(fiber_id, reference)| Reverse((reference, fiber_id)))
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_pops_in_deadline_order_up_to_cutoff() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut index = TimeoutIndex::default();
+        index.schedule(a, at(30));
+        index.schedule(b, at(10));
+        index.schedule(c, at(20));
+
+        assert_eq!(index.pop_expired(at(25)), Some((b, at(10))));
+        assert_eq!(index.pop_expired(at(25)), Some((c, at(20))));
+        assert_eq!(index.pop_expired(at(25)), None);
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_drops_stale_entries() {
+        let fiber_id = Uuid::new_v4();
+        let mut index = TimeoutIndex::default();
+        for secs in 0..2000 {
+            index.schedule(fiber_id, at(secs));
+        }
+        assert!(index.needs_compaction(1));
+
+        index.rebuild([(fiber_id, at(1999))]);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.pop_expired(at(2000)), Some((fiber_id, at(1999))));
+        assert!(index.is_empty());
+    }
+}
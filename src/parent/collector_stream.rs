@@ -4,6 +4,9 @@ use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
 use thiserror::Error;
 
+/// Label set on every log to the id of the collector it was pulled from
+pub const COLLECTOR_LABEL: &str = "collector";
+
 #[derive(Debug, Error)]
 pub enum CollectorStreamError {
     #[error("Collector client error: {0}")]
@@ -168,9 +171,13 @@ impl CollectorStream {
                 }
             }
 
-            // Queue all logs from this batch
+            // Queue all logs from this batch, labelled with their collector
             let log_count = batch.logs.len();
-            self.batch_queue.extend(batch.logs);
+            self.batch_queue.extend(batch.logs.into_iter().map(|mut log| {
+                log.labels
+                    .insert(COLLECTOR_LABEL.to_string(), self.collector_id.clone());
+                log
+            }));
             newly_enqueued += log_count;
 
             // Update last sequence
@@ -206,6 +206,7 @@ impl ParentRunner {
                         );
                     }
                 }
+                processor_guard.restore_links();
             }
         }
 
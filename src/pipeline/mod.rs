@@ -1,7 +1,9 @@
 pub mod channel;
 pub mod backpressure;
 pub mod runner;
+pub mod sources;
 
 pub use channel::{create_channel, Receiver, Sender};
 pub use backpressure::BackpressureHandler;
 pub use runner::{run_processor, run_writer, FiberUpdate, PipelineError};
+pub use sources::{SourceControl, SourceControlError};
@@ -1,8 +1,8 @@
-use crate::config::types::{Config, StorageConfig};
+use crate::config::types::{Config, LateRecordPolicy, StorageConfig};
 use crate::fiber::processor::ProcessResult;
 use crate::fiber::FiberProcessor;
 use crate::source::reader::LogRecord;
-use crate::storage::traits::{FiberMembership, FiberRecord, Storage, StorageError, StoredLog};
+use crate::storage::traits::{FiberClose, FiberLink, FiberMembership, FiberRecord, Storage, StorageError, StoredLog};
 use chrono::Utc;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -30,6 +30,9 @@ pub enum PipelineError {
     Sequencer(String),
 }
 
+/// Most logs taken from the input for one pass through the fiber processor
+const MAX_PROCESS_BATCH: usize = 512;
+
 /// Update from fiber processor to storage writer
 #[derive(Debug)]
 pub struct FiberUpdate {
@@ -39,8 +42,10 @@ pub struct FiberUpdate {
     pub new_fibers: Vec<FiberRecord>,
     /// Updated fibers (attributes changed, merged, etc.)
     pub updated_fibers: Vec<FiberRecord>,
-    /// IDs of fibers that were closed
-    pub closed_fiber_ids: Vec<uuid::Uuid>,
+    /// Fibers that were closed, with the reason
+    pub closed_fibers: Vec<FiberClose>,
+    /// Links from fibers to the fibers they nest inside
+    pub links: Vec<FiberLink>,
 }
 
 impl From<ProcessResult> for FiberUpdate {
@@ -49,7 +54,8 @@ impl From<ProcessResult> for FiberUpdate {
             memberships: result.memberships,
             new_fibers: result.new_fibers,
             updated_fibers: result.updated_fibers,
-            closed_fiber_ids: result.closed_fiber_ids,
+            closed_fibers: result.closed_fibers,
+            links: result.links,
         }
     }
 }
@@ -58,10 +64,16 @@ impl From<ProcessResult> for FiberUpdate {
 ///
 /// Receives log records from the sequencer, writes raw logs to storage,
 /// processes logs through fiber rules, and sends fiber updates to the writer.
+/// With `late_records: side_table`, late records go to the `late_logs` table
+/// instead and are not processed.
+///
+/// Logs already waiting on the input are processed together (up to
+/// `MAX_PROCESS_BATCH`), so the fiber processor's workers each get a whole
+/// batch at a time.
 ///
 /// The processor is shared via Arc<RwLock<>> to enable hot-reload from the web server.
-/// During normal operation, this function holds a write lock while processing each log.
-/// Hot-reload requests will wait for the lock between log processing.
+/// During normal operation, this function holds a write lock while processing each batch.
+/// Hot-reload requests will wait for the lock between batches.
 pub async fn run_processor(
     mut input: mpsc::Receiver<LogRecord>,
     output: mpsc::Sender<FiberUpdate>,
@@ -75,6 +87,9 @@ pub async fn run_processor(
     let flush_interval_secs = config.storage.flush_interval_seconds;
 
     let mut log_batch: Vec<StoredLog> = Vec::with_capacity(batch_size);
+    // Late records kept out of fiber processing
+    let mut late_batch: Vec<StoredLog> = Vec::new();
+    let side_table_late = config.sequencer.late_records == LateRecordPolicy::SideTable;
     let mut flush_interval = tokio::time::interval(Duration::from_secs(flush_interval_secs));
 
     // Track source IDs we've already checked for dynamic fiber type registration
@@ -92,45 +107,76 @@ pub async fn run_processor(
         tokio::select! {
             result = input.recv() => {
                 match result {
-                    Some(log) => {
-                        debug!(
-                            log_id = %log.id,
-                            source = %log.source_id,
-                            timestamp = %log.timestamp,
-                            "Processing log record"
-                        );
+                    Some(first) => {
+                        // Take whatever else is already waiting
+                        let mut logs = vec![first];
+                        while logs.len() < MAX_PROCESS_BATCH {
+                            match input.try_recv() {
+                                Ok(log) => logs.push(log),
+                                Err(_) => break,
+                            }
+                        }
 
                         // Read current config version
                         let current_version = *config_version.read().await;
 
-                        // Create stored log
-                        let stored_log = StoredLog {
-                            log_id: log.id,
-                            timestamp: log.timestamp,
-                            source_id: log.source_id.clone(),
-                            raw_text: log.raw_text.clone(),
-                            ingestion_time: Utc::now(),
-                            config_version: current_version,
-                        };
-
-                        // Add to batch
-                        log_batch.push(stored_log);
-
-                        // Flush if batch is full
-                        if log_batch.len() >= batch_size {
-                            storage.write_logs(&log_batch).await?;
-                            debug!(count = log_batch.len(), "Flushed log batch");
-                            log_batch.clear();
+                        let mut to_process = Vec::with_capacity(logs.len());
+                        for log in logs {
+                            debug!(
+                                log_id = %log.id,
+                                source = %log.source_id,
+                                timestamp = %log.timestamp,
+                                "Processing log record"
+                            );
+
+                            // Create stored log
+                            let stored_log = StoredLog {
+                                log_id: log.id,
+                                timestamp: log.timestamp,
+                                source_id: log.source_id.clone(),
+                                raw_text: log.raw_text.clone(),
+                                ingestion_time: Utc::now(),
+                                config_version: current_version,
+                                fields: log.fields.clone(),
+                                labels: log.labels.clone(),
+                            };
+
+                            if log.late && side_table_late {
+                                late_batch.push(stored_log);
+                                if late_batch.len() >= batch_size {
+                                    storage.write_late_logs(&late_batch).await?;
+                                    late_batch.clear();
+                                }
+                                continue;
+                            }
+
+                            // Add to batch
+                            log_batch.push(stored_log);
+
+                            // Flush if batch is full
+                            if log_batch.len() >= batch_size {
+                                storage.write_logs(&log_batch).await?;
+                                debug!(count = log_batch.len(), "Flushed log batch");
+                                log_batch.clear();
+                            }
+
+                            to_process.push(log);
+                        }
+                        if to_process.is_empty() {
+                            continue;
                         }
 
-                        // Acquire write lock to process log (releases between logs for hot-reload)
-                        let mut processor_guard = processor.write().await;
+                        // Acquire write lock to process the batch (releases between batches for hot-reload)
+                        let mut processor_guard = Arc::clone(&processor).write_owned().await;
 
-                        // Dynamically add source fiber type if needed.
+                        // Dynamically add source fiber types if needed.
                         // We do this for every new source regardless of whether other processors
                         // (like traced fiber types) handle it, because source fibers provide a
                         // separate "all logs from source" view independent of traced fibers.
-                        if enable_dynamic_source_fibers && !known_sources.contains(&log.source_id) {
+                        for log in &to_process {
+                            if !enable_dynamic_source_fibers || known_sources.contains(&log.source_id) {
+                                continue;
+                            }
                             known_sources.insert(log.source_id.clone());
 
                             match processor_guard.add_source_fiber_type(&log.source_id, current_version) {
@@ -152,25 +198,28 @@ pub async fn run_processor(
                             }
                         }
 
-                        // Process for fibers
-                        let results = processor_guard.process_log(&log);
-
-                        // Update shared checkpoint state if enabled
-                        if let Some(ref shared_state) = shared_fiber_state {
-                            if let Ok(mut guard) = shared_state.lock() {
-                                *guard = processor_guard.create_checkpoint();
+                        // Process for fibers off the async threads, updating the
+                        // shared checkpoint state if enabled. The lock is released
+                        // before sending to channel.
+                        let shared_state = shared_fiber_state.clone();
+                        let results = tokio::task::spawn_blocking(move || {
+                            let results = processor_guard.process_batch(&to_process);
+                            if let Some(shared_state) = shared_state {
+                                if let Ok(mut guard) = shared_state.lock() {
+                                    *guard = processor_guard.create_checkpoint();
+                                }
                             }
-                        }
-
-                        // Release lock before sending to channel
-                        drop(processor_guard);
+                            results
+                        })
+                        .await
+                        .map_err(|e| PipelineError::FiberProcessor(e.to_string()))?;
 
                         // Send fiber updates
                         for result in results {
                             if !result.memberships.is_empty()
                                 || !result.new_fibers.is_empty()
                                 || !result.updated_fibers.is_empty()
-                                || !result.closed_fiber_ids.is_empty()
+                                || !result.closed_fibers.is_empty()
                             {
                                 let update = FiberUpdate::from(result);
                                 if output.send(update).await.is_err() {
@@ -194,6 +243,11 @@ pub async fn run_processor(
                     debug!(count = log_batch.len(), "Flushed log batch on interval");
                     log_batch.clear();
                 }
+                if !late_batch.is_empty() {
+                    storage.write_late_logs(&late_batch).await?;
+                    debug!(count = late_batch.len(), "Flushed late log batch on interval");
+                    late_batch.clear();
+                }
             }
         }
     }
@@ -203,6 +257,10 @@ pub async fn run_processor(
         storage.write_logs(&log_batch).await?;
         info!(count = log_batch.len(), "Final log batch flush");
     }
+    if !late_batch.is_empty() {
+        storage.write_late_logs(&late_batch).await?;
+        info!(count = late_batch.len(), "Final late log batch flush");
+    }
 
     // Acquire write lock to flush all open fibers
     let mut processor_guard = processor.write().await;
@@ -224,7 +282,7 @@ pub async fn run_processor(
         if !result.memberships.is_empty()
             || !result.new_fibers.is_empty()
             || !result.updated_fibers.is_empty()
-            || !result.closed_fiber_ids.is_empty()
+            || !result.closed_fibers.is_empty()
         {
             let update = FiberUpdate::from(result);
             let _ = output.send(update).await;
@@ -276,11 +334,17 @@ pub async fn run_writer(
                             }
                         }
 
+                        if let Err(e) = storage.write_fiber_links(&update.links).await {
+                            error!(error = %e, "Failed to write fiber links");
+                        }
+
                         // Handle closed fibers - mark them as closed
-                        for fiber_id in &update.closed_fiber_ids {
+                        for FiberClose { fiber_id, reason, pattern } in &update.closed_fibers {
                             // Fetch current fiber, mark as closed, and update
                             if let Ok(Some(mut fiber)) = storage.get_fiber(*fiber_id).await {
                                 fiber.closed = true;
+                                fiber.close_reason = Some(*reason);
+                                fiber.closing_pattern = pattern.clone();
                                 if let Err(e) = storage.update_fiber(&fiber).await {
                                     error!(fiber_id = %fiber_id, error = %e, "Failed to mark fiber as closed");
                                 } else {
@@ -343,7 +407,7 @@ mod tests {
     use crate::config::types::{
         AttributeConfig, AttributeType, BackpressureConfig, BackpressureStrategy, CheckpointConfig,
         ErrorConfig, FiberSourceConfig, FiberTypeConfig, GapMode, ParseErrorStrategy,
-        PatternConfig, PipelineConfig, SequencerConfig, SourceConfig, SourceType,
+        PatternConfig, PipelineConfig, SequencerConfig, SourceConfig, SourceFormat, SourceType,
         StorageConfig, TemporalConfig, TimestampConfig, ReadConfig, ReadStart, WebConfig,
     };
     use crate::storage::duckdb::DuckDbStorage;
@@ -358,14 +422,31 @@ mod tests {
             SourceConfig {
                 source_type: SourceType::File,
                 path: PathBuf::from("/tmp/test.log"),
+                command: Vec::new(),
+                restart: None,
+                syslog: None,
+                http: None,
+                format: SourceFormat::Text,
                 timestamp: TimestampConfig {
                     pattern: r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)".to_string(),
+                    pointer: None,
                     format: "iso8601".to_string(),
+                    fallbacks: Vec::new(),
+                    timezone: None,
+                    clock_offset: None,
+                    assume_year: None,
                 },
                 read: ReadConfig {
                     start: ReadStart::Beginning,
                     follow: false,
+                    discover_interval: None,
+                    include_rotated: false,
+                    multiline: None,
+                    idle_timeout: None,
                 },
+                labels: Default::default(),
+                drop_if: Vec::new(),
+                redact: Vec::new(),
             },
         );
 
@@ -375,9 +456,15 @@ mod tests {
             FiberSourceConfig {
                 patterns: vec![PatternConfig {
                     regex: r"thread-(?P<thread_id>\d+)".to_string(),
+                    fields: Default::default(),
+                    labels: Default::default(),
+                    json: Default::default(),
                     release_matching_peer_keys: vec![],
                     release_self_keys: vec![],
                     close: false,
+                    when: vec![],
+                    mark: None,
+                    step: None,
                 }],
             },
         );
@@ -396,9 +483,12 @@ mod tests {
                     attr_type: AttributeType::String,
                     key: true,
                     derived: None,
+                    mode: Default::default(),
                 }],
                 sources: fiber_sources,
                 is_source_fiber: false,
+                parent: None,
+                sequence: vec![],
             },
         );
 
@@ -420,10 +510,13 @@ mod tests {
                     enabled: false,
                     interval_seconds: 30,
                 },
+                fiber_workers: None,
             },
             sequencer: SequencerConfig {
                 batch_epoch_duration: None,
                 watermark_safety_margin: Some(Duration::from_secs(1)),
+                idle_timeout: None,
+                late_records: Default::default(),
             },
             storage: StorageConfig {
                 path: PathBuf::from("/tmp/test.duckdb"),
@@ -443,6 +536,9 @@ mod tests {
             source_id: source.to_string(),
             raw_text: text.to_string(),
             file_offset: 0,
+            fields: Default::default(),
+            labels: Default::default(),
+            late: false,
         }
     }
 
@@ -528,9 +624,13 @@ mod tests {
                 first_activity: timestamp,
                 last_activity: timestamp,
                 closed: false,
+                metrics: Default::default(),
+                close_reason: None,
+                closing_pattern: None,
             }],
             updated_fibers: vec![],
-            closed_fiber_ids: vec![],
+            closed_fibers: vec![],
+            links: vec![],
         };
 
         input_tx.send(update).await.unwrap();
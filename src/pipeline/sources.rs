@@ -0,0 +1,239 @@
+use crate::config::types::{ParseErrorStrategy, SourceConfig, SourceType};
+use crate::sequencer::merge::{SourceRegistrar, SourceRetirer};
+use crate::source::discovery::{parent_source_id, run_discovery, SourceDiscovery};
+use crate::source::reader::{ReaderError, SourceReader};
+use crate::storage::checkpoint::{SharedSourceStates, SourceCheckpoint};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+#[derive(Debug, Error)]
+pub enum SourceControlError {
+    #[error("failed to open source: {0}")]
+    Reader(#[from] ReaderError),
+
+    #[error("{0:?} sources can't be added at runtime")]
+    Unsupported(SourceType),
+
+    #[error("the sequencer has stopped")]
+    Stopped,
+}
+
+/// Starts and retires local sources while the pipeline runs.
+///
+/// New readers go to the running sequencer through its registrar, and the
+/// shared source states are kept in step so checkpoints and
+/// `/api/sources/stats` cover them.
+pub struct SourceControl {
+    registrar: SourceRegistrar,
+    retirer: SourceRetirer,
+    source_states: SharedSourceStates,
+    on_parse_error: ParseErrorStrategy,
+    // Rescan tasks of followed glob/directory sources started here
+    discoveries: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl SourceControl {
+    pub fn new(
+        registrar: SourceRegistrar,
+        retirer: SourceRetirer,
+        source_states: SharedSourceStates,
+        on_parse_error: ParseErrorStrategy,
+    ) -> Self {
+        Self {
+            registrar,
+            retirer,
+            source_states,
+            on_parse_error,
+            discoveries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open a source and hand its readers to the sequencer. Files with a
+    /// checkpoint in `checkpoints` resume from it; others are read from the
+    /// configured start.
+    pub async fn start(
+        &self,
+        source_id: &str,
+        config: &SourceConfig,
+        checkpoints: Option<&HashMap<String, SourceCheckpoint>>,
+    ) -> Result<(), SourceControlError> {
+        if matches!(config.source_type, SourceType::Stdin | SourceType::Http) {
+            return Err(SourceControlError::Unsupported(config.source_type));
+        }
+
+        let mut discovery = None;
+        let readers = if config.source_type.is_discovered() {
+            let mut source_discovery = SourceDiscovery::new(source_id, config);
+            let readers = source_discovery.initial_readers(checkpoints, self.on_parse_error)?;
+            info!(source_id = %source_id, files = readers.len(), "Discovered source files");
+            discovery = config.read.follow.then_some(source_discovery);
+            readers
+        } else {
+            let checkpoint = checkpoints.and_then(|checkpoints| checkpoints.get(source_id));
+            vec![SourceReader::resume(
+                source_id.to_string(),
+                config,
+                self.on_parse_error,
+                checkpoint,
+            )?]
+        };
+
+        for reader in readers {
+            let (reader, state) = reader.with_shared_state();
+            self.source_states
+                .write()
+                .await
+                .insert(reader.source_id().to_string(), state);
+            self.registrar
+                .send(reader)
+                .await
+                .map_err(|_| SourceControlError::Stopped)?;
+        }
+
+        if let Some(discovery) = discovery {
+            let handle = tokio::spawn(run_discovery(
+                discovery,
+                self.registrar.clone(),
+                self.source_states.clone(),
+                self.on_parse_error,
+            ));
+            self.discoveries
+                .lock()
+                .unwrap()
+                .insert(source_id.to_string(), handle);
+        }
+
+        info!(source_id = %source_id, "Started source");
+        Ok(())
+    }
+
+    /// Stop reading a source and every file discovered for it. Records
+    /// already read still go through the pipeline.
+    ///
+    /// Returns where each of the source's files was left, so a restarted
+    /// source can carry on from there.
+    pub async fn retire(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, SourceCheckpoint>, SourceControlError> {
+        let discovery = self.discoveries.lock().unwrap().remove(source_id);
+        if let Some(handle) = discovery {
+            // Wait for the task to stop so no reader is registered after the
+            // retirement below
+            handle.abort();
+            let _ = handle.await;
+        }
+
+        let (stopped, on_stopped) = oneshot::channel();
+        self.retirer
+            .send((source_id.to_string(), stopped))
+            .await
+            .map_err(|_| SourceControlError::Stopped)?;
+        on_stopped.await.map_err(|_| SourceControlError::Stopped)?;
+
+        let mut checkpoints = HashMap::new();
+        self.source_states.write().await.retain(|id, state| {
+            if id != source_id && parent_source_id(id) != Some(source_id) {
+                return true;
+            }
+            if let Ok(state) = state.lock() {
+                checkpoints.insert(
+                    id.clone(),
+                    SourceCheckpoint {
+                        path: state.path.clone(),
+                        offset: state.offset,
+                        inode: state.inode,
+                        last_timestamp: state.last_timestamp,
+                        fingerprint: state.fingerprint,
+                    },
+                );
+            }
+            false
+        });
+
+        info!(source_id = %source_id, "Retired source");
+        Ok(checkpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::merge::{run_sequencer_with_control, SequencerRunConfig};
+    use crate::source::reader::LogRecord;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::NamedTempFile;
+    use tokio::sync::mpsc;
+
+    async fn next_text(output: &mut mpsc::Receiver<LogRecord>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), output.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .raw_text
+    }
+
+    #[tokio::test]
+    async fn test_restarted_source_resumes() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "2025-12-04T10:00:00Z one").unwrap();
+        writeln!(file, "2025-12-04T10:00:01Z two").unwrap();
+        file.flush().unwrap();
+
+        let config: SourceConfig = serde_yaml::from_str(&format!(
+            r#"
+type: file
+path: {}
+timestamp:
+  pattern: '^(?P<ts>\S+)'
+  format: iso8601
+read:
+  start: beginning
+  follow: true
+"#,
+            file.path().display()
+        ))
+        .unwrap();
+
+        let (output_tx, mut output_rx) = mpsc::channel(100);
+        let (registrar, registrations) = mpsc::channel(10);
+        let (retirer, retirements) = mpsc::channel(10);
+        let run_config = SequencerRunConfig {
+            safety_margin: Duration::from_secs(0),
+            emit_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let handle =
+            run_sequencer_with_control(vec![], registrations, retirements, output_tx, run_config)
+                .await
+                .unwrap();
+        let control = SourceControl::new(
+            registrar,
+            retirer,
+            Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            ParseErrorStrategy::Drop,
+        );
+
+        control.start("app", &config, None).await.unwrap();
+        assert_eq!(next_text(&mut output_rx).await, "2025-12-04T10:00:00Z one");
+
+        let checkpoints = control.retire("app").await.unwrap();
+        writeln!(file, "2025-12-04T10:00:02Z three").unwrap();
+        writeln!(file, "2025-12-04T10:00:03Z four").unwrap();
+        file.flush().unwrap();
+        control.start("app", &config, Some(&checkpoints)).await.unwrap();
+
+        // Reading carries on after the lines already read, which aren't
+        // read again
+        assert_eq!(next_text(&mut output_rx).await, "2025-12-04T10:00:01Z two");
+        assert_eq!(next_text(&mut output_rx).await, "2025-12-04T10:00:02Z three");
+
+        handle.abort();
+    }
+}
@@ -124,6 +124,9 @@ pub async fn run_reprocessing(
                 source_id: stored_log.source_id.clone(),
                 raw_text: stored_log.raw_text.clone(),
                 file_offset: 0, // Not relevant for reprocessing from storage
+                fields: stored_log.fields.clone(),
+                labels: stored_log.labels.clone(),
+                late: false,
             };
 
             let results = processor.process_log(&log_record);
@@ -141,6 +144,8 @@ pub async fn run_reprocessing(
                     storage.update_fiber(fiber).await?;
                 }
 
+                storage.write_fiber_links(&result.links).await?;
+
                 // Write memberships
                 if !result.memberships.is_empty() {
                     storage.write_memberships(&result.memberships).await?;
@@ -1,23 +1,66 @@
+use crate::config::types::LateRecordPolicy;
 use crate::source::reader::LogRecord;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::time::Duration;
 
 /// Local sequencer that merges multiple source streams into global timestamp order.
 ///
 /// Uses watermarks to determine when it's safe to emit records without risking
-/// out-of-order delivery.
+/// out-of-order delivery. A record older than one already emitted is late and
+/// handled by the [`LateRecordPolicy`].
 pub struct Sequencer {
     sources: HashMap<String, SourceState>,
     heap: BinaryHeap<Reverse<HeapEntry>>,
     safety_margin: Duration,
+    late_records: LateRecordPolicy,
+    /// Timestamp of the newest record emitted so far
+    emitted_up_to: Option<DateTime<Utc>>,
+    /// Late records seen per source
+    late_counts: BTreeMap<String, u64>,
 }
 
 #[derive(Debug, Clone)]
 struct SourceState {
     watermark: Option<DateTime<Utc>>,
     active: bool,
+    /// How long the source may go without advancing its watermark before
+    /// it stops holding back the others
+    idle_timeout: Option<Duration>,
+    /// Wall-clock time the watermark last advanced (or the source was added)
+    advanced_at: DateTime<Utc>,
+}
+
+impl SourceState {
+    fn new() -> Self {
+        Self {
+            watermark: None,
+            active: true,
+            idle_timeout: None,
+            advanced_at: Utc::now(),
+        }
+    }
+
+    fn is_idle(&self, now: DateTime<Utc>) -> bool {
+        self.idle_timeout
+            .and_then(|timeout| chrono::Duration::from_std(timeout).ok())
+            .is_some_and(|timeout| now - self.advanced_at >= timeout)
+    }
+}
+
+/// Which source is holding back emission, as reported by [`Sequencer::status`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SequencerStatus {
+    /// The active source with the lowest watermark (or none yet)
+    pub bottleneck: Option<String>,
+    /// The minimum watermark records are emitted up to
+    pub watermark: Option<DateTime<Utc>>,
+    /// Sources whose watermark is following the wall clock
+    pub idle_sources: Vec<String>,
+    /// Records that arrived behind the emitted watermark, per source
+    pub late_records: BTreeMap<String, u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -56,26 +99,63 @@ impl Sequencer {
     pub fn new(source_ids: Vec<String>, safety_margin: Duration) -> Self {
         let sources = source_ids
             .into_iter()
-            .map(|id| {
-                (
-                    id,
-                    SourceState {
-                        watermark: None,
-                        active: true,
-                    },
-                )
-            })
+            .map(|id| (id, SourceState::new()))
             .collect();
 
         Self {
             sources,
             heap: BinaryHeap::new(),
             safety_margin,
+            late_records: LateRecordPolicy::default(),
+            emitted_up_to: None,
+            late_counts: BTreeMap::new(),
+        }
+    }
+
+    /// Set how records older than already emitted ones are handled.
+    pub fn with_late_records(mut self, policy: LateRecordPolicy) -> Self {
+        self.late_records = policy;
+        self
+    }
+
+    /// Register a new source at runtime.
+    ///
+    /// The source starts without a watermark, so emission pauses until it
+    /// reports its first record (or is marked done). Re-adding a source that
+    /// was previously marked done reactivates it.
+    pub fn add_source(&mut self, source_id: String) {
+        self.sources.insert(source_id, SourceState::new());
+    }
+
+    /// Set how long a source may go without advancing its watermark before
+    /// its watermark follows the wall clock (minus the safety margin) instead.
+    ///
+    /// This keeps a followed file that stopped writing from stalling every
+    /// other source. The source's own watermark takes over again as soon as
+    /// it advances.
+    pub fn set_idle_timeout(&mut self, source_id: &str, idle_timeout: Option<Duration>) {
+        if let Some(state) = self.sources.get_mut(source_id) {
+            state.idle_timeout = idle_timeout;
         }
     }
 
+    /// Check if a source is registered and still active.
+    pub fn is_source_active(&self, source_id: &str) -> bool {
+        self.sources.get(source_id).is_some_and(|s| s.active)
+    }
+
     /// Add a record to the heap.
-    pub fn push(&mut self, record: LogRecord) {
+    ///
+    /// A late record is counted, then dropped or tagged `late` and emitted
+    /// with the next ready records, depending on the policy.
+    pub fn push(&mut self, mut record: LogRecord) {
+        if self.emitted_up_to.is_some_and(|emitted| record.timestamp < emitted) {
+            *self.late_counts.entry(record.source_id.clone()).or_default() += 1;
+            if self.late_records == LateRecordPolicy::Drop {
+                return;
+            }
+            record.late = true;
+        }
         self.heap.push(Reverse(HeapEntry {
             timestamp: record.timestamp,
             record,
@@ -88,6 +168,9 @@ impl Sequencer {
     /// with timestamps earlier than the given watermark.
     pub fn update_watermark(&mut self, source_id: &str, watermark: DateTime<Utc>) {
         if let Some(state) = self.sources.get_mut(source_id) {
+            if state.watermark.is_none_or(|current| watermark > current) {
+                state.advanced_at = Utc::now();
+            }
             state.watermark = Some(watermark);
         }
     }
@@ -109,7 +192,11 @@ impl Sequencer {
     /// Watermark semantics: a watermark of T means no records with timestamp < T
     /// will arrive from that source.
     pub fn emit_ready(&mut self) -> Vec<LogRecord> {
-        let min_watermark = self.compute_min_watermark();
+        self.emit_ready_at(Utc::now())
+    }
+
+    fn emit_ready_at(&mut self, now: DateTime<Utc>) -> Vec<LogRecord> {
+        let min_watermark = self.compute_min_watermark(now);
         let Some(threshold) = min_watermark else {
             return vec![]; // No watermarks yet, can't emit
         };
@@ -125,15 +212,22 @@ impl Sequencer {
                 break;
             }
         }
+        self.note_emitted(&result);
         result
     }
 
+    fn note_emitted(&mut self, records: &[LogRecord]) {
+        let newest = records.iter().map(|record| record.timestamp).max();
+        self.emitted_up_to = self.emitted_up_to.max(newest);
+    }
+
     /// Flush all remaining records (called at shutdown or when all sources are done).
     pub fn flush_all(&mut self) -> Vec<LogRecord> {
         let mut result = vec![];
         while let Some(Reverse(entry)) = self.heap.pop() {
             result.push(entry.record);
         }
+        self.note_emitted(&result);
         result
     }
 
@@ -168,23 +262,68 @@ impl Sequencer {
         }
     }
 
+    /// Report which source currently limits emission.
+    pub fn status(&self) -> SequencerStatus {
+        self.status_at(Utc::now())
+    }
+
+    fn status_at(&self, now: DateTime<Utc>) -> SequencerStatus {
+        let mut idle_sources: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|(_, state)| state.active && state.is_idle(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        idle_sources.sort();
+
+        // A source without a watermark sorts first: it blocks everything
+        let bottleneck = self
+            .sources
+            .iter()
+            .filter(|(_, state)| state.active)
+            .map(|(id, state)| (self.effective_watermark(state, now), id))
+            .min();
+
+        SequencerStatus {
+            bottleneck: bottleneck.map(|(_, id)| id.clone()),
+            watermark: self.compute_min_watermark(now),
+            idle_sources,
+            late_records: self.late_counts.clone(),
+        }
+    }
+
+    /// A source's watermark, or the wall clock minus the safety margin while
+    /// it is idle
+    fn effective_watermark(&self, state: &SourceState, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !state.is_idle(now) {
+            return state.watermark;
+        }
+        let clock = now - chrono::Duration::from_std(self.safety_margin).unwrap();
+        Some(state.watermark.map_or(clock, |watermark| watermark.max(clock)))
+    }
+
     /// Compute the minimum watermark across all active sources.
     ///
     /// Returns None if any active source has no watermark yet.
-    fn compute_min_watermark(&self) -> Option<DateTime<Utc>> {
-        let active_sources: Vec<_> = self.sources.values().filter(|s| s.active).collect();
+    fn compute_min_watermark(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let watermarks: Vec<_> = self
+            .sources
+            .values()
+            .filter(|s| s.active)
+            .map(|s| self.effective_watermark(s, now))
+            .collect();
 
-        if active_sources.is_empty() {
+        if watermarks.is_empty() {
             return None;
         }
 
         // If any active source has no watermark, we can't compute min
-        if active_sources.iter().any(|s| s.watermark.is_none()) {
+        if watermarks.iter().any(Option::is_none) {
             return None;
         }
 
         // All active sources have watermarks, return the minimum
-        active_sources.iter().filter_map(|s| s.watermark).min()
+        watermarks.into_iter().flatten().min()
     }
 }
 
@@ -200,6 +339,9 @@ mod tests {
             source_id: source.to_string(),
             raw_text: text.to_string(),
             file_offset: 0,
+            fields: Default::default(),
+            labels: Default::default(),
+            late: false,
         }
     }
 
@@ -440,4 +582,113 @@ mod tests {
         let _ready = seq.emit_ready();
         assert_eq!(seq.buffered_count(), 0);
     }
+
+    #[test]
+    fn test_add_source_holds_emission_until_watermark() {
+        let mut seq = Sequencer::new(vec!["source1".to_string()], Duration::from_secs(0));
+
+        seq.push(make_record("source1", "2025-12-04T10:00:01Z", "s1-1"));
+        seq.update_watermark("source1", "2025-12-04T10:00:05Z".parse().unwrap());
+
+        // A newly added source has no watermark yet, so nothing is safe to emit
+        seq.add_source("source2".to_string());
+        assert!(seq.is_source_active("source2"));
+        assert!(seq.emit_ready().is_empty());
+
+        seq.push(make_record("source2", "2025-12-04T10:00:00Z", "s2-1"));
+        seq.update_watermark("source2", "2025-12-04T10:00:03Z".parse().unwrap());
+
+        let ready = seq.emit_ready();
+        assert_eq!(ready.len(), 2);
+        assert_eq!(ready[0].raw_text, "s2-1");
+        assert_eq!(ready[1].raw_text, "s1-1");
+    }
+
+    #[test]
+    fn test_add_source_reactivates_done_source() {
+        let mut seq = Sequencer::new(vec!["source1".to_string()], Duration::from_secs(0));
+
+        seq.mark_source_done("source1");
+        assert!(!seq.is_source_active("source1"));
+        assert!(seq.all_sources_done());
+
+        seq.add_source("source1".to_string());
+        assert!(seq.is_source_active("source1"));
+        assert!(!seq.all_sources_done());
+    }
+
+    #[test]
+    fn test_idle_source_follows_wall_clock() {
+        let mut seq = Sequencer::new(
+            vec!["busy".to_string(), "quiet".to_string()],
+            Duration::from_secs(1),
+        );
+        seq.set_idle_timeout("quiet", Some(Duration::from_secs(30)));
+
+        let now = Utc::now();
+        seq.push(make_record("busy", &(now - chrono::Duration::seconds(20)).to_rfc3339(), "b1"));
+        seq.update_watermark("busy", now - chrono::Duration::seconds(10));
+        seq.update_watermark("quiet", now - chrono::Duration::hours(1));
+
+        // Until the timeout expires the quiet source holds everything back
+        assert!(seq.emit_ready_at(now).is_empty());
+        let status = seq.status_at(now);
+        assert_eq!(status.bottleneck.as_deref(), Some("quiet"));
+        assert!(status.idle_sources.is_empty());
+
+        // Afterwards its watermark follows the clock and the busy source leads
+        let later = now + chrono::Duration::seconds(31);
+        assert_eq!(seq.emit_ready_at(later).len(), 1);
+        let status = seq.status_at(later);
+        assert_eq!(status.bottleneck.as_deref(), Some("busy"));
+        assert_eq!(status.idle_sources, vec!["quiet".to_string()]);
+        assert_eq!(status.watermark, Some(now - chrono::Duration::seconds(10)));
+
+        // Producing data again makes its own watermark count
+        seq.update_watermark("quiet", now - chrono::Duration::minutes(30));
+        let status = seq.status_at(Utc::now());
+        assert_eq!(status.bottleneck.as_deref(), Some("quiet"));
+        assert!(status.idle_sources.is_empty());
+    }
+
+    #[test]
+    fn test_idle_source_without_watermark() {
+        let mut seq = Sequencer::new(
+            vec!["busy".to_string(), "empty".to_string()],
+            Duration::from_secs(0),
+        );
+        seq.set_idle_timeout("empty", Some(Duration::from_secs(5)));
+        seq.push(make_record("busy", "2025-12-04T10:00:01Z", "b1"));
+        seq.update_watermark("busy", "2025-12-04T10:00:02Z".parse().unwrap());
+
+        let now = Utc::now();
+        assert!(seq.emit_ready_at(now).is_empty());
+        assert_eq!(seq.status_at(now).bottleneck.as_deref(), Some("empty"));
+        assert_eq!(seq.emit_ready_at(now + chrono::Duration::seconds(5)).len(), 1);
+    }
+
+    #[test]
+    fn test_late_records() {
+        let mut seq = Sequencer::new(vec!["source1".to_string()], Duration::from_secs(0));
+        seq.push(make_record("source1", "2025-12-04T10:00:05Z", "on time"));
+        seq.update_watermark("source1", "2025-12-04T10:00:06Z".parse().unwrap());
+        assert_eq!(seq.emit_ready().len(), 1);
+
+        // Older than what was emitted: tagged and emitted with the next batch
+        seq.push(make_record("source1", "2025-12-04T10:00:01Z", "late"));
+        seq.push(make_record("source1", "2025-12-04T10:00:05Z", "same time"));
+        let ready = seq.emit_ready();
+        assert_eq!(ready.len(), 2);
+        assert!(ready[0].late);
+        assert!(!ready[1].late);
+        assert_eq!(seq.status().late_records["source1"], 1);
+
+        let mut seq = Sequencer::new(vec!["source1".to_string()], Duration::from_secs(0))
+            .with_late_records(LateRecordPolicy::Drop);
+        seq.push(make_record("source1", "2025-12-04T10:00:05Z", "on time"));
+        assert_eq!(seq.flush_all().len(), 1);
+        seq.push(make_record("source1", "2025-12-04T10:00:01Z", "late"));
+        assert_eq!(seq.buffered_count(), 0);
+        assert_eq!(seq.status().late_records["source1"], 1);
+    }
 }
@@ -1,10 +1,13 @@
-use crate::config::types::SequencerConfig;
-use crate::sequencer::local::Sequencer;
+use crate::config::types::{LateRecordPolicy, SequencerConfig};
+use crate::sequencer::local::{Sequencer, SequencerStatus};
+use crate::source::discovery::parent_source_id;
 use crate::source::reader::{LogRecord, SourceReader};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::mpsc;
-use tokio::task::JoinHandle;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::{AbortHandle, JoinHandle};
 
 #[derive(Debug, Error)]
 pub enum SequencerError {
@@ -18,10 +21,17 @@ pub enum SequencerError {
     Join(#[from] tokio::task::JoinError),
 }
 
+/// Shared view of the running sequencer's status, refreshed every emit interval
+pub type SharedSequencerStatus = Arc<Mutex<SequencerStatus>>;
+
 /// Configuration for the async sequencer runner
 pub struct SequencerRunConfig {
     pub safety_margin: Duration,
     pub emit_interval: Duration,
+    /// Idle timeout for sources that don't set their own
+    pub idle_timeout: Option<Duration>,
+    pub late_records: LateRecordPolicy,
+    pub status: SharedSequencerStatus,
 }
 
 impl Default for SequencerRunConfig {
@@ -29,6 +39,9 @@ impl Default for SequencerRunConfig {
         Self {
             safety_margin: Duration::from_secs(1),
             emit_interval: Duration::from_millis(100),
+            idle_timeout: None,
+            late_records: LateRecordPolicy::default(),
+            status: SharedSequencerStatus::default(),
         }
     }
 }
@@ -40,10 +53,21 @@ impl From<&SequencerConfig> for SequencerRunConfig {
                 .watermark_safety_margin
                 .unwrap_or(Duration::from_secs(1)),
             emit_interval: Duration::from_millis(100),
+            idle_timeout: config.idle_timeout,
+            late_records: config.late_records,
+            status: SharedSequencerStatus::default(),
         }
     }
 }
 
+/// Channel used to hand new source readers to a running sequencer.
+pub type SourceRegistrar = mpsc::Sender<SourceReader>;
+
+/// Channel used to retire a source (and the files discovered for it) from a
+/// running sequencer. The sender is told once the source's readers have
+/// stopped.
+pub type SourceRetirer = mpsc::Sender<(String, oneshot::Sender<()>)>;
+
 /// Run the sequencer with multiple source readers.
 ///
 /// Spawns a task for each source reader, collects records via channels,
@@ -67,70 +91,101 @@ pub async fn run_sequencer(
         ));
     }
 
-    let source_ids: Vec<String> = sources.iter().map(|s| s.source_id().to_string()).collect();
+    // No runtime registration: the registrar is dropped immediately
+    let (_, registrations) = mpsc::channel(1);
+    run_sequencer_with_registrations(sources, registrations, output, config).await
+}
+
+/// Run the sequencer, accepting additional source readers at runtime.
+///
+/// Readers received on `registrations` are spawned and added to the watermark
+/// set as they arrive (used by glob/directory discovery). The sequencer exits
+/// once every source is done and all registrars have been dropped. If a reader
+/// arrives for a source ID that is still active, it is started once the
+/// previous reader for that ID finishes.
+pub async fn run_sequencer_with_registrations(
+    sources: Vec<SourceReader>,
+    registrations: mpsc::Receiver<SourceReader>,
+    output: mpsc::Sender<LogRecord>,
+    config: SequencerRunConfig,
+) -> Result<SequencerHandle, SequencerError> {
+    // No runtime retirement: the retirer is dropped immediately
+    let (_, retirements) = mpsc::channel(1);
+    run_sequencer_with_control(sources, registrations, retirements, output, config).await
+}
 
-    // Create channels for each source
-    let (source_txs, mut source_rxs): (Vec<_>, Vec<_>) = source_ids
+/// Run the sequencer, accepting additional source readers and retiring
+/// sources at runtime.
+///
+/// Like [`run_sequencer_with_registrations`], plus source IDs received on
+/// `retirements` have their readers stopped and are marked done, along with
+/// every file discovered for them. Records already read are still emitted.
+pub async fn run_sequencer_with_control(
+    sources: Vec<SourceReader>,
+    mut registrations: mpsc::Receiver<SourceReader>,
+    mut retirements: mpsc::Receiver<(String, oneshot::Sender<()>)>,
+    output: mpsc::Sender<LogRecord>,
+    config: SequencerRunConfig,
+) -> Result<SequencerHandle, SequencerError> {
+    let source_ids: Vec<String> = sources.iter().map(|s| s.source_id().to_string()).collect();
+    let idle_timeouts: Vec<Option<Duration>> = sources
         .iter()
-        .map(|_| mpsc::channel::<SourceEvent>(1000))
-        .unzip();
+        .map(|s| s.idle_timeout().or(config.idle_timeout))
+        .collect();
 
     // Spawn a task for each source reader
-    let mut source_handles = Vec::new();
-    for (mut reader, tx) in sources.into_iter().zip(source_txs.into_iter()) {
-        let handle = tokio::spawn(async move {
-            loop {
-                match reader.next_record().await {
-                    Ok(Some(record)) => {
-                        let watermark = reader.watermark();
-                        if tx
-                            .send(SourceEvent::Record {
-                                record,
-                                watermark: watermark.unwrap(),
-                            })
-                            .await
-                            .is_err()
-                        {
-                            break; // Receiver dropped
-                        }
-                    }
-                    Ok(None) => {
-                        // EOF reached
-                        let _ = tx.send(SourceEvent::Done).await;
-                        break;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(SourceEvent::Error(format!("{}", e))).await;
-                        break;
-                    }
-                }
-            }
-        });
-        source_handles.push(handle);
+    let source_handles = Arc::new(Mutex::new(Vec::new()));
+    let mut readers = RunningReaders::default();
+    for reader in sources {
+        let source_id = reader.source_id().to_string();
+        let (stop, on_stop) = oneshot::channel();
+        let (handle, rx) = spawn_source_task(reader, on_stop);
+        readers.push(source_id, &handle, rx, stop);
+        source_handles.lock().unwrap().push(handle);
     }
 
     // Spawn the main sequencer task
+    let task_handles = Arc::clone(&source_handles);
     let sequencer_handle = tokio::spawn(async move {
-        let mut sequencer = Sequencer::new(source_ids.clone(), config.safety_margin);
+        let mut sequencer = Sequencer::new(source_ids.clone(), config.safety_margin)
+            .with_late_records(config.late_records);
+        for (source_id, idle_timeout) in source_ids.iter().zip(idle_timeouts) {
+            sequencer.set_idle_timeout(source_id, idle_timeout);
+        }
         let mut emit_interval = tokio::time::interval(config.emit_interval);
         let mut active_sources = source_ids.len();
+        let mut registrations_open = true;
+        let mut retirements_open = true;
+        let mut pending: HashMap<String, VecDeque<SourceReader>> = HashMap::new();
 
         loop {
             tokio::select! {
                 // Check each source channel
-                Some((source_idx, event)) = receive_any(&mut source_rxs) => {
-                    let source_id = &source_ids[source_idx];
+                Some((source_idx, event)) = receive_any(&mut readers.rxs) => {
+                    let source_id = readers.ids[source_idx].clone();
 
                     match event {
                         SourceEvent::Record { record, watermark } => {
                             sequencer.push(record);
-                            sequencer.update_watermark(source_id, watermark);
+                            sequencer.update_watermark(&source_id, watermark);
+                        }
+                        SourceEvent::Watermark(watermark) => {
+                            sequencer.update_watermark(&source_id, watermark);
                         }
                         SourceEvent::Done => {
-                            sequencer.mark_source_done(source_id);
+                            sequencer.mark_source_done(&source_id);
                             active_sources -= 1;
 
-                            if active_sources == 0 {
+                            // The reader task has exited; stop polling its channel
+                            readers.remove(&source_id);
+
+                            // Start a reader that was waiting on this source ID
+                            if let Some(reader) = pending.get_mut(&source_id).and_then(|q| q.pop_front()) {
+                                start_reader(reader, &config, &mut sequencer, &mut readers, &task_handles);
+                                active_sources += 1;
+                            }
+
+                            if active_sources == 0 && !registrations_open {
                                 // All sources done, flush and exit
                                 let remaining = sequencer.flush_all();
                                 for record in remaining {
@@ -155,8 +210,113 @@ pub async fn run_sequencer(
                     }
                 }
 
+                // Sources registered at runtime
+                registration = registrations.recv(), if registrations_open => {
+                    match registration {
+                        Some(reader) => {
+                            let source_id = reader.source_id().to_string();
+                            if sequencer.is_source_active(&source_id) {
+                                pending.entry(source_id).or_default().push_back(reader);
+                                continue;
+                            }
+
+                            tracing::info!(source_id = %source_id, "Registered source with sequencer");
+                            start_reader(reader, &config, &mut sequencer, &mut readers, &task_handles);
+                            active_sources += 1;
+                        }
+                        None => {
+                            registrations_open = false;
+                            if active_sources == 0 {
+                                let remaining = sequencer.flush_all();
+                                for record in remaining {
+                                    if output.send(record).await.is_err() {
+                                        return Err(SequencerError::ChannelSend);
+                                    }
+                                }
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+
+                // Sources retired at runtime
+                retirement = retirements.recv(), if retirements_open => {
+                    let Some((retired, stopped)) = retirement else {
+                        retirements_open = false;
+                        continue;
+                    };
+
+                    let belongs = |source_id: &str| {
+                        source_id == retired || parent_source_id(source_id) == Some(retired.as_str())
+                    };
+
+                    // Readers registered before the retirement was sent must
+                    // not outlive it
+                    while let Ok(reader) = registrations.try_recv() {
+                        let source_id = reader.source_id().to_string();
+                        if belongs(&source_id) {
+                            continue;
+                        }
+                        if sequencer.is_source_active(&source_id) {
+                            pending.entry(source_id).or_default().push_back(reader);
+                            continue;
+                        }
+                        tracing::info!(source_id = %source_id, "Registered source with sequencer");
+                        start_reader(reader, &config, &mut sequencer, &mut readers, &task_handles);
+                        active_sources += 1;
+                    }
+
+                    pending.retain(|source_id, _| !belongs(source_id));
+                    let retired_ids: Vec<String> = readers
+                        .ids
+                        .iter()
+                        .filter(|source_id| belongs(source_id))
+                        .cloned()
+                        .collect();
+                    for source_id in retired_ids {
+                        tracing::info!(source_id = %source_id, "Retired source from sequencer");
+                        // Records read before the retirement still go through,
+                        // since the source's checkpoint is past them
+                        for event in readers.drain(&source_id).await {
+                            match event {
+                                SourceEvent::Record { record, watermark } => {
+                                    sequencer.push(record);
+                                    sequencer.update_watermark(&source_id, watermark);
+                                }
+                                SourceEvent::Watermark(watermark) => {
+                                    sequencer.update_watermark(&source_id, watermark);
+                                }
+                                SourceEvent::Done | SourceEvent::Error(_) => {}
+                            }
+                        }
+                        readers.remove(&source_id);
+                        sequencer.mark_source_done(&source_id);
+                        active_sources -= 1;
+                    }
+                    let _ = stopped.send(());
+
+                    if active_sources == 0 && !registrations_open {
+                        let remaining = sequencer.flush_all();
+                        for record in remaining {
+                            if output.send(record).await.is_err() {
+                                return Err(SequencerError::ChannelSend);
+                            }
+                        }
+                        return Ok(());
+                    }
+
+                    // The retired sources no longer hold back the others
+                    let ready = sequencer.emit_ready();
+                    for record in ready {
+                        if output.send(record).await.is_err() {
+                            return Err(SequencerError::ChannelSend);
+                        }
+                    }
+                }
+
                 // Periodic emit check
                 _ = emit_interval.tick() => {
+                    publish_status(&sequencer, &config.status);
                     let ready = sequencer.emit_ready();
                     for record in ready {
                         if output.send(record).await.is_err() {
@@ -174,6 +334,164 @@ pub async fn run_sequencer(
     })
 }
 
+/// Channels of the spawned source reader tasks, indexed alike by source ID
+#[derive(Default)]
+struct RunningReaders {
+    ids: Vec<String>,
+    rxs: Vec<mpsc::Receiver<SourceEvent>>,
+    aborts: HashMap<String, AbortHandle>,
+    stops: HashMap<String, oneshot::Sender<()>>,
+}
+
+impl RunningReaders {
+    fn push(
+        &mut self,
+        source_id: String,
+        handle: &JoinHandle<()>,
+        rx: mpsc::Receiver<SourceEvent>,
+        stop: oneshot::Sender<()>,
+    ) {
+        self.aborts.insert(source_id.clone(), handle.abort_handle());
+        self.stops.insert(source_id.clone(), stop);
+        self.ids.push(source_id);
+        self.rxs.push(rx);
+    }
+
+    /// Stop polling a source's channel, aborting its task if still running
+    fn remove(&mut self, source_id: &str) {
+        self.stops.remove(source_id);
+        if let Some(handle) = self.aborts.remove(source_id) {
+            handle.abort();
+        }
+        if let Some(idx) = self.ids.iter().position(|id| id == source_id) {
+            self.ids.swap_remove(idx);
+            self.rxs.swap_remove(idx);
+        }
+    }
+
+    /// Stop a source's task between records and take every event it sent,
+    /// including a record it was still waiting to send. The reader's
+    /// checkpoint is then at the last record taken.
+    async fn drain(&mut self, source_id: &str) -> Vec<SourceEvent> {
+        if let Some(stop) = self.stops.remove(source_id) {
+            let _ = stop.send(());
+        }
+        let mut events = Vec::new();
+        if let Some(idx) = self.ids.iter().position(|id| id == source_id) {
+            // The channel closes once the task has stopped
+            while let Some(event) = self.rxs[idx].recv().await {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+/// Spawn a reader task and add its source to the sequencer
+fn start_reader(
+    reader: SourceReader,
+    config: &SequencerRunConfig,
+    sequencer: &mut Sequencer,
+    readers: &mut RunningReaders,
+    task_handles: &Mutex<Vec<JoinHandle<()>>>,
+) {
+    let source_id = reader.source_id().to_string();
+    let idle_timeout = reader.idle_timeout().or(config.idle_timeout);
+    let (stop, on_stop) = oneshot::channel();
+    let (handle, rx) = spawn_source_task(reader, on_stop);
+    readers.push(source_id.clone(), &handle, rx, stop);
+    task_handles.lock().unwrap().push(handle);
+    sequencer.add_source(source_id.clone());
+    sequencer.set_idle_timeout(&source_id, idle_timeout);
+}
+
+/// Refresh the shared status, logging sources that went idle or the
+/// bottleneck changing
+fn publish_status(sequencer: &Sequencer, shared: &SharedSequencerStatus) {
+    let status = sequencer.status();
+    let mut previous = shared.lock().unwrap();
+    for source_id in &status.idle_sources {
+        if !previous.idle_sources.contains(source_id) {
+            tracing::info!(source_id = %source_id, "Source idle, its watermark now follows the wall clock");
+        }
+    }
+    if status.bottleneck != previous.bottleneck {
+        tracing::debug!(
+            bottleneck = ?status.bottleneck,
+            watermark = ?status.watermark,
+            "Sequencer bottleneck changed"
+        );
+    }
+    *previous = status;
+}
+
+/// Spawn a task that drives a source reader and forwards its events.
+///
+/// The task ends when `stop` fires or its sender is dropped. It only checks
+/// between records: a reader's checkpoint moves past a record just as it
+/// returns it, so a pending read can be cancelled but a record read is
+/// always sent.
+fn spawn_source_task(
+    mut reader: SourceReader,
+    mut stop: oneshot::Receiver<()>,
+) -> (JoinHandle<()>, mpsc::Receiver<SourceEvent>) {
+    let (tx, rx) = mpsc::channel::<SourceEvent>(1000);
+    let handle = tokio::spawn(async move {
+        loop {
+            let read = async {
+                match reader.idle_watermark_interval() {
+                    Some(interval) => tokio::time::timeout(interval, reader.next_record()).await.ok(),
+                    None => Some(reader.next_record().await),
+                }
+            };
+            let next = tokio::select! {
+                biased;
+                _ = &mut stop => break,
+                next = read => next,
+            };
+            let next = match next {
+                Some(next) => next,
+                // Listener sources advance their watermark while idle so they
+                // don't hold back the other sources
+                None => {
+                    if let Some(watermark) = reader.watermark() {
+                        if tx.send(SourceEvent::Watermark(watermark)).await.is_err() {
+                            break; // Receiver dropped
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            match next {
+                Ok(Some(record)) => {
+                    let watermark = reader.watermark();
+                    if tx
+                        .send(SourceEvent::Record {
+                            record,
+                            watermark: watermark.unwrap(),
+                        })
+                        .await
+                        .is_err()
+                    {
+                        break; // Receiver dropped
+                    }
+                }
+                Ok(None) => {
+                    // EOF reached
+                    let _ = tx.send(SourceEvent::Done).await;
+                    break;
+                }
+                Err(e) => {
+                    let _ = tx.send(SourceEvent::Error(format!("{}", e))).await;
+                    break;
+                }
+            }
+        }
+    });
+    (handle, rx)
+}
+
 /// Events sent from source reader tasks to the main sequencer
 #[derive(Debug)]
 enum SourceEvent {
@@ -181,6 +499,8 @@ enum SourceEvent {
         record: LogRecord,
         watermark: chrono::DateTime<chrono::Utc>,
     },
+    /// Watermark advanced without a record
+    Watermark(chrono::DateTime<chrono::Utc>),
     Done,
     Error(String),
 }
@@ -188,7 +508,7 @@ enum SourceEvent {
 /// Handle to the running sequencer
 pub struct SequencerHandle {
     sequencer_task: Option<JoinHandle<Result<(), SequencerError>>>,
-    source_tasks: Vec<JoinHandle<()>>,
+    source_tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl SequencerHandle {
@@ -200,8 +520,13 @@ impl SequencerHandle {
         }
 
         // Wait for all source tasks to complete
-        for task in self.source_tasks.drain(..) {
-            task.await?;
+        let tasks: Vec<_> = self.source_tasks.lock().unwrap().drain(..).collect();
+        for task in tasks {
+            match task.await {
+                // Readers of retired sources are aborted
+                Err(e) if e.is_cancelled() => {}
+                result => result?,
+            }
         }
 
         Ok(())
@@ -212,7 +537,7 @@ impl SequencerHandle {
         if let Some(ref task) = self.sequencer_task {
             task.abort();
         }
-        for task in &self.source_tasks {
+        for task in self.source_tasks.lock().unwrap().iter() {
             task.abort();
         }
     }
@@ -261,7 +586,8 @@ async fn receive_any(
 mod tests {
     use super::*;
     use crate::config::types::{
-        ParseErrorStrategy, ReadConfig, ReadStart, SourceConfig, SourceType, TimestampConfig,
+        ParseErrorStrategy, ReadConfig, ReadStart, SourceConfig, SourceFormat, SourceType,
+        TimestampConfig,
     };
     use std::io::Write;
     use std::path::PathBuf;
@@ -271,14 +597,31 @@ mod tests {
         SourceConfig {
             source_type: SourceType::File,
             path,
+            command: Vec::new(),
+            restart: None,
+            syslog: None,
+            http: None,
+            format: SourceFormat::Text,
             timestamp: TimestampConfig {
                 pattern: pattern.to_string(),
+                pointer: None,
                 format: format.to_string(),
+                fallbacks: Vec::new(),
+                timezone: None,
+                clock_offset: None,
+                assume_year: None,
             },
             read: ReadConfig {
                 start: ReadStart::Beginning,
                 follow: false,
+                discover_interval: None,
+                include_rotated: false,
+                multiline: None,
+                idle_timeout: None,
             },
+            labels: Default::default(),
+            drop_if: Vec::new(),
+            redact: Vec::new(),
         }
     }
 
@@ -304,6 +647,7 @@ mod tests {
         let run_config = SequencerRunConfig {
             safety_margin: Duration::from_secs(0),
             emit_interval: Duration::from_millis(10),
+            ..Default::default()
         };
 
         let mut handle = run_sequencer(vec![reader], output_tx, run_config)
@@ -361,6 +705,7 @@ mod tests {
         let run_config = SequencerRunConfig {
             safety_margin: Duration::from_secs(0),
             emit_interval: Duration::from_millis(10),
+            ..Default::default()
         };
 
         let mut handle = run_sequencer(vec![reader1, reader2], output_tx, run_config)
@@ -403,6 +748,7 @@ mod tests {
         let run_config = SequencerRunConfig {
             safety_margin: Duration::from_secs(0),
             emit_interval: Duration::from_millis(10),
+            ..Default::default()
         };
 
         let mut handle = run_sequencer(vec![reader], output_tx, run_config)
@@ -466,6 +812,7 @@ mod tests {
         let run_config = SequencerRunConfig {
             safety_margin: Duration::from_secs(0),
             emit_interval: Duration::from_millis(10),
+            ..Default::default()
         };
 
         let mut handle = run_sequencer(vec![reader1, reader2, reader3], output_tx, run_config)
@@ -488,4 +835,195 @@ mod tests {
         assert_eq!(records[4].raw_text, "2025-12-04T10:00:04Z S2-2");
         assert_eq!(records[5].raw_text, "2025-12-04T10:00:05Z S3-2");
     }
+
+    #[tokio::test]
+    async fn test_source_registered_at_runtime() {
+        let mut temp_file1 = NamedTempFile::new().unwrap();
+        writeln!(temp_file1, "2025-12-04T10:00:00Z S1-1").unwrap();
+        writeln!(temp_file1, "2025-12-04T10:00:02Z S1-2").unwrap();
+        temp_file1.flush().unwrap();
+
+        let mut temp_file2 = NamedTempFile::new().unwrap();
+        writeln!(temp_file2, "2025-12-04T10:00:01Z S2-1").unwrap();
+        writeln!(temp_file2, "2025-12-04T10:00:03Z S2-2").unwrap();
+        temp_file2.flush().unwrap();
+
+        let pattern = r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+        let config1 = create_test_config(temp_file1.path().to_path_buf(), pattern, "iso8601");
+        let config2 = create_test_config(temp_file2.path().to_path_buf(), pattern, "iso8601");
+
+        let reader1 =
+            SourceReader::new("source1".to_string(), &config1, ParseErrorStrategy::Panic).unwrap();
+        let reader2 =
+            SourceReader::new("source2".to_string(), &config2, ParseErrorStrategy::Panic).unwrap();
+
+        let (output_tx, mut output_rx) = mpsc::channel(100);
+        let (registrar, registrations) = mpsc::channel(10);
+
+        let run_config = SequencerRunConfig {
+            safety_margin: Duration::from_secs(0),
+            emit_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        let mut handle =
+            run_sequencer_with_registrations(vec![reader1], registrations, output_tx, run_config)
+                .await
+                .unwrap();
+
+        registrar.send(reader2).await.unwrap();
+        drop(registrar);
+
+        let mut records = Vec::new();
+        while let Some(record) = output_rx.recv().await {
+            records.push(record);
+        }
+
+        handle.wait().await.unwrap();
+
+        let texts: Vec<_> = records.iter().map(|r| r.raw_text.as_str()).collect();
+        assert_eq!(texts.len(), 4);
+        assert!(texts.contains(&"2025-12-04T10:00:01Z S2-1"));
+        assert!(texts.contains(&"2025-12-04T10:00:03Z S2-2"));
+    }
+
+    #[tokio::test]
+    async fn test_no_initial_sources_with_registrations() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z Line 1").unwrap();
+        temp_file.flush().unwrap();
+
+        let config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        let reader =
+            SourceReader::new("late".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let (output_tx, mut output_rx) = mpsc::channel(100);
+        let (registrar, registrations) = mpsc::channel(10);
+
+        let run_config = SequencerRunConfig {
+            safety_margin: Duration::from_secs(0),
+            emit_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        let mut handle =
+            run_sequencer_with_registrations(vec![], registrations, output_tx, run_config)
+                .await
+                .unwrap();
+
+        registrar.send(reader).await.unwrap();
+        drop(registrar);
+
+        let mut records = Vec::new();
+        while let Some(record) = output_rx.recv().await {
+            records.push(record);
+        }
+
+        handle.wait().await.unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].source_id, "late");
+    }
+
+    #[tokio::test]
+    async fn test_source_retired_at_runtime() {
+        let mut temp_file1 = NamedTempFile::new().unwrap();
+        writeln!(temp_file1, "2025-12-04T10:00:00Z S1-1").unwrap();
+        writeln!(temp_file1, "2025-12-04T10:00:01Z S1-2").unwrap();
+        temp_file1.flush().unwrap();
+
+        // A followed source that never writes holds back every other source
+        let temp_file2 = NamedTempFile::new().unwrap();
+
+        let pattern = r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+        let config1 = create_test_config(temp_file1.path().to_path_buf(), pattern, "iso8601");
+        let mut config2 = create_test_config(temp_file2.path().to_path_buf(), pattern, "iso8601");
+        config2.read.follow = true;
+
+        let reader1 =
+            SourceReader::new("source1".to_string(), &config1, ParseErrorStrategy::Panic).unwrap();
+        let reader2 =
+            SourceReader::new("source2".to_string(), &config2, ParseErrorStrategy::Panic).unwrap();
+
+        let (output_tx, mut output_rx) = mpsc::channel(100);
+        let (registrar, registrations) = mpsc::channel(10);
+        let (retirer, retirements) = mpsc::channel(10);
+
+        let run_config = SequencerRunConfig {
+            safety_margin: Duration::from_secs(0),
+            emit_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        let mut handle = run_sequencer_with_control(
+            vec![reader1, reader2],
+            registrations,
+            retirements,
+            output_tx,
+            run_config,
+        )
+        .await
+        .unwrap();
+        drop(registrar);
+
+        let (stopped, on_stopped) = oneshot::channel();
+        retirer.send(("source2".to_string(), stopped)).await.unwrap();
+        on_stopped.await.unwrap();
+
+        let mut records = Vec::new();
+        let collect = async {
+            while let Some(record) = output_rx.recv().await {
+                records.push(record);
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), collect)
+            .await
+            .expect("sequencer should exit once the followed source is retired");
+
+        handle.wait().await.unwrap();
+
+        let texts: Vec<_> = records.iter().map(|r| r.raw_text.as_str()).collect();
+        assert_eq!(texts, ["2025-12-04T10:00:00Z S1-1", "2025-12-04T10:00:01Z S1-2"]);
+    }
+    #[tokio::test]
+    async fn test_stopped_source_task_sends_pending_record() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 0..1002 {
+            writeln!(temp_file, "2025-12-04T10:00:00Z line-{}", i).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let pattern = r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+        let mut config = create_test_config(temp_file.path().to_path_buf(), pattern, "iso8601");
+        config.read.follow = true;
+        let reader =
+            SourceReader::new("source1".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        // With nothing received, the task fills the channel and waits to
+        // send the next record
+        let (stop, on_stop) = oneshot::channel();
+        let (handle, rx) = spawn_source_task(reader, on_stop);
+        let mut readers = RunningReaders::default();
+        readers.push("source1".to_string(), &handle, rx, stop);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let events = tokio::time::timeout(Duration::from_secs(5), readers.drain("source1"))
+            .await
+            .expect("the task should stop once its pending record is taken");
+        let texts: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                SourceEvent::Record { record, .. } => Some(record.raw_text.as_str()),
+                _ => None,
+            })
+            .collect();
+        // The followed file's last line is still held for a continuation
+        assert_eq!(texts.len(), 1001);
+        assert_eq!(texts[1000], "2025-12-04T10:00:00Z line-1000");
+        handle.await.unwrap();
+    }
 }
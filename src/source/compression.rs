@@ -0,0 +1,143 @@
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression format of a log file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect compression from the file extension, falling back to magic bytes.
+    ///
+    /// The file position is restored to the start before returning.
+    pub fn detect(path: &Path, file: &mut File) -> io::Result<Self> {
+        if let Some(compression) = Self::from_extension(path) {
+            return Ok(compression);
+        }
+
+        let mut magic = [0u8; 4];
+        let mut filled = 0;
+        while filled < magic.len() {
+            let n = file.read(&mut magic[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        file.seek(SeekFrom::Start(0))?;
+
+        Ok(Self::from_magic(&magic[..filled]))
+    }
+
+    /// Detect compression from a `.gz` / `.zst` style extension
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Detect compression from the leading bytes of a file
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if bytes.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Returns true if the file content must be streamed through a decompressor
+    pub fn is_compressed(&self) -> bool {
+        *self != Compression::None
+    }
+
+    /// Wrap a file in the matching decompressor.
+    ///
+    /// Offsets read from the returned reader are positions in the uncompressed
+    /// stream, not in the file on disk.
+    pub fn decoder(&self, file: File) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::None => Box::new(file),
+            // Multi-member aware so concatenated gzip streams (e.g. `cat a.gz b.gz`) read fully
+            Compression::Gzip => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        })
+    }
+}
+
+/// Skip `count` bytes of a (possibly decompressing) stream.
+///
+/// Compressed streams can't seek, so resuming at an uncompressed offset means
+/// decompressing and discarding everything before it. Returns the number of
+/// bytes actually skipped, which is less than `count` if the stream ended early.
+pub fn skip_bytes<R: Read>(reader: &mut R, count: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(count), &mut io::sink())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            Compression::from_extension(Path::new("/var/log/app.log.1.gz")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::from_extension(Path::new("app.log.zst")),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::from_extension(Path::new("app.log")), None);
+        assert_eq!(Compression::from_extension(Path::new("app.log.1")), None);
+    }
+
+    #[test]
+    fn test_from_magic() {
+        assert_eq!(Compression::from_magic(&[0x1f, 0x8b, 0x08, 0x00]), Compression::Gzip);
+        assert_eq!(Compression::from_magic(&ZSTD_MAGIC), Compression::Zstd);
+        assert_eq!(Compression::from_magic(b"2025"), Compression::None);
+        assert_eq!(Compression::from_magic(&[]), Compression::None);
+    }
+
+    #[test]
+    fn test_detect_by_magic_without_extension() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello\n").unwrap();
+        temp_file.write_all(&encoder.finish().unwrap()).unwrap();
+        temp_file.flush().unwrap();
+
+        let mut file = File::open(temp_file.path()).unwrap();
+        let compression = Compression::detect(temp_file.path(), &mut file).unwrap();
+        assert_eq!(compression, Compression::Gzip);
+
+        // Detection must leave the file at the start so the decoder sees the header
+        let mut text = String::new();
+        compression.decoder(file).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello\n");
+    }
+
+    #[test]
+    fn test_skip_bytes() {
+        let mut reader: &[u8] = b"0123456789";
+        assert_eq!(skip_bytes(&mut reader, 4).unwrap(), 4);
+        assert_eq!(reader, b"456789");
+        assert_eq!(skip_bytes(&mut reader, 100).unwrap(), 6);
+    }
+}
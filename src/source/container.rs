@@ -0,0 +1,286 @@
+use crate::config::types::SourceFormat;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ContainerLineError {
+    #[error("invalid docker json-file line: {0}")]
+    Docker(String),
+
+    #[error("invalid CRI log line: {0}")]
+    Cri(String),
+}
+
+/// A complete log message written by a container, reassembled from the
+/// runtime's partial lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerEntry {
+    /// Runtime timestamp of the first fragment
+    pub timestamp: DateTime<Utc>,
+    /// `stdout` or `stderr`
+    pub stream: String,
+    pub message: String,
+    /// File offset of the first fragment
+    pub start_offset: u64,
+}
+
+/// A line of Docker's json-file log driver
+#[derive(Deserialize)]
+struct DockerLine {
+    log: String,
+    #[serde(default)]
+    stream: String,
+    time: String,
+}
+
+/// Parses Docker json-file or CRI log lines and joins partial lines.
+///
+/// Runtimes split long messages into fragments: Docker leaves the trailing
+/// newline off every fragment but the last, and CRI tags fragments `P` and
+/// the last one `F`. Fragments are joined per stream, since stdout and
+/// stderr fragments can interleave.
+#[derive(Debug)]
+pub struct ContainerLineParser {
+    format: SourceFormat,
+    pending: BTreeMap<String, ContainerEntry>,
+}
+
+impl ContainerLineParser {
+    /// Returns None for formats that aren't container runtime formats
+    pub fn new(format: SourceFormat) -> Option<Self> {
+        format.is_container().then(|| Self {
+            format,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    /// Parse a line read at `offset` (without its trailing newline).
+    ///
+    /// Returns the completed entry, or None if the line was a partial
+    /// fragment that is now waiting for the rest of its message.
+    pub fn push(
+        &mut self,
+        line: &str,
+        offset: u64,
+    ) -> Result<Option<ContainerEntry>, ContainerLineError> {
+        let (timestamp, stream, message, partial) = match self.format {
+            SourceFormat::Cri => parse_cri(line)?,
+            _ => parse_docker(line)?,
+        };
+
+        let entry = match self.pending.remove(&stream) {
+            Some(mut entry) => {
+                entry.message.push_str(&message);
+                entry
+            }
+            None => ContainerEntry {
+                timestamp,
+                stream: stream.clone(),
+                message,
+                start_offset: offset,
+            },
+        };
+
+        if partial {
+            self.pending.insert(stream, entry);
+            return Ok(None);
+        }
+        Ok(Some(entry))
+    }
+
+    /// Take the oldest incomplete message, for when the input has ended
+    pub fn flush(&mut self) -> Option<ContainerEntry> {
+        let stream = self
+            .pending
+            .iter()
+            .min_by_key(|(_, entry)| entry.start_offset)
+            .map(|(stream, _)| stream.clone())?;
+        self.pending.remove(&stream)
+    }
+}
+
+/// `{"log":"message\n","stream":"stdout","time":"2024-01-01T00:00:00.000000000Z"}`
+fn parse_docker(line: &str) -> Result<(DateTime<Utc>, String, String, bool), ContainerLineError> {
+    let parsed: DockerLine =
+        serde_json::from_str(line).map_err(|e| ContainerLineError::Docker(e.to_string()))?;
+    let timestamp = parse_runtime_timestamp(&parsed.time)
+        .ok_or_else(|| ContainerLineError::Docker(format!("invalid time '{}'", parsed.time)))?;
+
+    let (message, partial) = match parsed.log.strip_suffix('\n') {
+        Some(message) => (message.strip_suffix('\r').unwrap_or(message).to_string(), false),
+        None => (parsed.log, true),
+    };
+    Ok((timestamp, parsed.stream, message, partial))
+}
+
+/// `2024-01-01T00:00:00.000000000Z stdout F message`
+fn parse_cri(line: &str) -> Result<(DateTime<Utc>, String, String, bool), ContainerLineError> {
+    let mut parts = line.splitn(4, ' ');
+    let (Some(time), Some(stream), Some(tags)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(ContainerLineError::Cri(line.to_string()));
+    };
+    let message = parts.next().unwrap_or_default().to_string();
+
+    let timestamp = parse_runtime_timestamp(time)
+        .ok_or_else(|| ContainerLineError::Cri(format!("invalid time '{}'", time)))?;
+    // Tags are colon-separated; the first is P(artial) or F(ull)
+    let partial = match tags.split(':').next() {
+        Some("P") => true,
+        Some("F") => false,
+        _ => return Err(ContainerLineError::Cri(format!("invalid tag '{}'", tags))),
+    };
+    Ok((timestamp, stream.to_string(), message, partial))
+}
+
+fn parse_runtime_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Fields describing the container that wrote a log file, taken from the
+/// kubelet and Docker log file layouts:
+///
+/// - `/var/log/pods/<namespace>_<pod>_<uid>/<container>/<n>.log`
+/// - `/var/log/containers/<pod>_<namespace>_<container>-<id>.log`
+/// - `/var/lib/docker/containers/<id>/<id>-json.log`
+///
+/// Other paths yield no fields.
+pub fn path_fields(path: &Path) -> BTreeMap<String, String> {
+    let parts: Vec<&str> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let mut fields = BTreeMap::new();
+    let mut insert = |name: &str, value: &str| {
+        fields.insert(name.to_string(), value.to_string());
+    };
+
+    match parts.as_slice() {
+        [.., "pods", pod_dir, container, _file] => {
+            let mut pod_parts = pod_dir.splitn(3, '_');
+            if let (Some(namespace), Some(pod), Some(uid)) =
+                (pod_parts.next(), pod_parts.next(), pod_parts.next())
+            {
+                insert("namespace", namespace);
+                insert("pod", pod);
+                insert("pod_uid", uid);
+                insert("container", container);
+            }
+        }
+        [.., "containers", file] => {
+            let name = file.strip_suffix(".log").unwrap_or(file);
+            if let Some((name, id)) = name.rsplit_once('-') {
+                let mut name_parts = name.splitn(3, '_');
+                if let (Some(pod), Some(namespace), Some(container)) =
+                    (name_parts.next(), name_parts.next(), name_parts.next())
+                {
+                    insert("pod", pod);
+                    insert("namespace", namespace);
+                    insert("container", container);
+                    insert("container_id", id);
+                }
+            }
+        }
+        [.., "containers", id, file] if file.strip_suffix("-json.log") == Some(*id) => {
+            insert("container_id", id);
+        }
+        _ => {}
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_docker_partial_lines() {
+        let mut parser = ContainerLineParser::new(SourceFormat::Docker).unwrap();
+
+        let first = r#"{"log":"hello ","stream":"stdout","time":"2025-12-04T10:00:00.1Z"}"#;
+        let err = r#"{"log":"oops\n","stream":"stderr","time":"2025-12-04T10:00:00.2Z"}"#;
+        let rest = r#"{"log":"world\n","stream":"stdout","time":"2025-12-04T10:00:00.3Z"}"#;
+
+        assert_eq!(parser.push(first, 0).unwrap(), None);
+        // Stderr isn't held up by a partial stdout message
+        let entry = parser.push(err, 70).unwrap().unwrap();
+        assert_eq!(entry.stream, "stderr");
+        assert_eq!(entry.message, "oops");
+
+        let entry = parser.push(rest, 140).unwrap().unwrap();
+        assert_eq!(
+            entry,
+            ContainerEntry {
+                timestamp: ts("2025-12-04T10:00:00.1Z"),
+                stream: "stdout".to_string(),
+                message: "hello world".to_string(),
+                start_offset: 0,
+            }
+        );
+
+        assert!(parser.push("not json", 210).is_err());
+    }
+
+    #[test]
+    fn test_cri_partial_lines() {
+        let mut parser = ContainerLineParser::new(SourceFormat::Cri).unwrap();
+
+        assert_eq!(
+            parser.push("2025-12-04T10:00:00.000000001Z stdout P part one, ", 0).unwrap(),
+            None
+        );
+        let entry = parser
+            .push("2025-12-04T10:00:00.000000002Z stdout F part two", 50)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.message, "part one, part two");
+        assert_eq!(entry.timestamp, ts("2025-12-04T10:00:00.000000001Z"));
+        assert_eq!(entry.start_offset, 0);
+
+        // An empty message has no text after the tag
+        let entry = parser.push("2025-12-04T10:00:01Z stderr F", 100).unwrap().unwrap();
+        assert_eq!(entry.message, "");
+
+        assert_eq!(parser.push("2025-12-04T10:00:02Z stdout P cut", 130).unwrap(), None);
+        assert_eq!(parser.flush().unwrap().message, "cut");
+        assert_eq!(parser.flush(), None);
+
+        assert!(parser.push("2025-12-04T10:00:03Z stdout X bad tag", 160).is_err());
+        assert!(parser.push("garbage", 200).is_err());
+    }
+
+    #[test]
+    fn test_path_fields() {
+        let fields = path_fields(Path::new(
+            "/var/log/pods/prod_api-7d9f_0b1c-22/server/0.log",
+        ));
+        assert_eq!(fields["namespace"], "prod");
+        assert_eq!(fields["pod"], "api-7d9f");
+        assert_eq!(fields["pod_uid"], "0b1c-22");
+        assert_eq!(fields["container"], "server");
+
+        let fields = path_fields(Path::new(
+            "/var/log/containers/api-7d9f_prod_server-3f2a9c.log",
+        ));
+        assert_eq!(fields["pod"], "api-7d9f");
+        assert_eq!(fields["namespace"], "prod");
+        assert_eq!(fields["container"], "server");
+        assert_eq!(fields["container_id"], "3f2a9c");
+
+        let fields = path_fields(Path::new("/var/lib/docker/containers/3f2a9c/3f2a9c-json.log"));
+        assert_eq!(fields["container_id"], "3f2a9c");
+
+        assert!(path_fields(Path::new("/var/log/syslog")).is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+use crate::config::types::{ParseErrorStrategy, ReadStart, SourceConfig, SourceType};
+use crate::sequencer::merge::SourceRegistrar;
+use crate::source::reader::{get_inode, ReaderError, SourceReader};
+use crate::storage::checkpoint::{SharedSourceStates, SourceCheckpoint};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Rescan interval for following glob/directory sources when not configured
+pub const DEFAULT_DISCOVER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A file matched by a glob or directory source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredFile {
+    pub source_id: String,
+    pub path: PathBuf,
+    pub inode: u64,
+}
+
+/// Changes found by a discovery scan
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    /// Files that are new, or that replaced a previously seen file at the same path
+    pub added: Vec<DiscoveredFile>,
+    /// Files that are no longer present
+    pub removed: Vec<DiscoveredFile>,
+}
+
+/// Expands a glob or directory source into the files it matches and tracks
+/// which of them have already been handed out as readers.
+///
+/// Each file gets a derived source ID of the form `<source>/<relative path>`,
+/// where the relative path is taken from the non-wildcard base of the pattern.
+/// The ID is stable across restarts, so per-file checkpoints keep working.
+pub struct SourceDiscovery {
+    source_name: String,
+    config: SourceConfig,
+    base: PathBuf,
+    known: HashMap<PathBuf, DiscoveredFile>,
+}
+
+impl SourceDiscovery {
+    pub fn new(source_name: &str, config: &SourceConfig) -> Self {
+        let base = match config.source_type {
+            SourceType::Directory => config.path.clone(),
+            _ => glob_base(&config.path),
+        };
+
+        Self {
+            source_name: source_name.to_string(),
+            config: config.clone(),
+            base,
+            known: HashMap::new(),
+        }
+    }
+
+    /// Get the configured source name
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// Rescan the pattern and return files added or removed since the last scan
+    pub fn scan(&mut self) -> Result<ScanResult, ReaderError> {
+        let mut current = HashMap::new();
+        for path in self.list_files()? {
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+
+            let file = DiscoveredFile {
+                source_id: derive_source_id(&self.source_name, &self.base, &path),
+                inode: get_inode(&metadata),
+                path: path.clone(),
+            };
+            current.insert(path, file);
+        }
+
+        let mut result = ScanResult::default();
+        for (path, file) in &current {
+            match self.known.get(path) {
+                Some(known) if known.inode == file.inode => {}
+                _ => result.added.push(file.clone()),
+            }
+        }
+        for (path, file) in &self.known {
+            if !current.contains_key(path) {
+                result.removed.push(file.clone());
+            }
+        }
+
+        result.added.sort_by(|a, b| a.path.cmp(&b.path));
+        result.removed.sort_by(|a, b| a.path.cmp(&b.path));
+        self.known = current;
+        Ok(result)
+    }
+
+    /// Run the initial scan and create a reader for every matching file.
+    ///
+    /// Files with a checkpoint entry resume from their stored offset; the rest
+    /// start according to the source's `read.start` setting.
+    pub fn initial_readers(
+        &mut self,
+        checkpoints: Option<&HashMap<String, SourceCheckpoint>>,
+        parse_error_strategy: ParseErrorStrategy,
+    ) -> Result<Vec<SourceReader>, ReaderError> {
+        let result = self.scan()?;
+        let mut readers = Vec::with_capacity(result.added.len());
+        for file in &result.added {
+            let checkpoint = checkpoints.and_then(|cp| cp.get(&file.source_id));
+            let reader = SourceReader::resume(
+                file.source_id.clone(),
+                &self.file_config(file),
+                parse_error_strategy,
+                checkpoint,
+            )?;
+            readers.push(reader.finish_on_rotation());
+        }
+        Ok(readers)
+    }
+
+    /// Create a reader for a file discovered after startup. These files are
+    /// new, so they are always read from the beginning.
+    pub fn new_file_reader(
+        &self,
+        file: &DiscoveredFile,
+        parse_error_strategy: ParseErrorStrategy,
+    ) -> Result<SourceReader, ReaderError> {
+        let mut config = self.file_config(file);
+        config.read.start = ReadStart::Beginning;
+        let reader = SourceReader::new(file.source_id.clone(), &config, parse_error_strategy)?;
+        Ok(reader.finish_on_rotation())
+    }
+
+    /// Per-file source config for a discovered file
+    fn file_config(&self, file: &DiscoveredFile) -> SourceConfig {
+        SourceConfig {
+            source_type: SourceType::File,
+            path: file.path.clone(),
+            ..self.config.clone()
+        }
+    }
+
+    fn list_files(&self) -> Result<Vec<PathBuf>, ReaderError> {
+        match self.config.source_type {
+            SourceType::Directory => {
+                let entries = match std::fs::read_dir(&self.config.path) {
+                    Ok(entries) => entries,
+                    // Directory may not exist yet (e.g. before the first pod starts)
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(e.into()),
+                };
+                let mut files = Vec::new();
+                for entry in entries {
+                    files.push(entry?.path());
+                }
+                Ok(files)
+            }
+            _ => {
+                let pattern = self.config.path.to_string_lossy();
+                let paths = glob::glob(&pattern).map_err(|e| {
+                    ReaderError::Discovery(format!("invalid glob pattern '{}': {}", pattern, e))
+                })?;
+                // Entries that can't be read (e.g. permission denied) are skipped
+                Ok(paths.filter_map(Result::ok).collect())
+            }
+        }
+    }
+}
+
+/// Periodically rescan a glob/directory source, registering readers for new
+/// files with the sequencer and keeping the checkpoint state registry in sync.
+///
+/// Runs until the sequencer stops accepting registrations.
+pub async fn run_discovery(
+    mut discovery: SourceDiscovery,
+    registrar: SourceRegistrar,
+    states: SharedSourceStates,
+    parse_error_strategy: ParseErrorStrategy,
+) {
+    let interval = discovery
+        .config
+        .read
+        .discover_interval
+        .unwrap_or(DEFAULT_DISCOVER_INTERVAL);
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick fires immediately; the initial scan has already been done
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+
+        let result = match discovery.scan() {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(source = %discovery.source_name, error = %e, "File discovery scan failed");
+                continue;
+            }
+        };
+
+        for file in &result.removed {
+            info!(source_id = %file.source_id, path = %file.path.display(), "Discovered file removed");
+            states.write().await.remove(&file.source_id);
+        }
+
+        for file in &result.added {
+            info!(source_id = %file.source_id, path = %file.path.display(), "Discovered new file");
+            let reader = match discovery.new_file_reader(file, parse_error_strategy) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    warn!(source_id = %file.source_id, error = %e, "Failed to create reader for discovered file");
+                    continue;
+                }
+            };
+
+            let (reader, state) = reader.with_shared_state();
+            states.write().await.insert(file.source_id.clone(), state);
+            if registrar.send(reader).await.is_err() {
+                // Sequencer has shut down
+                return;
+            }
+        }
+    }
+}
+
+/// Derive a stable source ID for a discovered file: `<source>/<path relative to base>`
+pub fn derive_source_id(source_name: &str, base: &Path, path: &Path) -> String {
+    let relative = path
+        .strip_prefix(base)
+        .ok()
+        .filter(|rel| !rel.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.file_name().map(PathBuf::from).unwrap_or_default());
+
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    format!("{}/{}", source_name, parts.join("/"))
+}
+
+/// Get the configured source name a derived source ID belongs to, if any
+pub fn parent_source_id(source_id: &str) -> Option<&str> {
+    source_id.split_once('/').map(|(parent, _)| parent)
+}
+
+/// Get the directory prefix of a glob pattern that contains no wildcards
+pub fn glob_base(pattern: &Path) -> PathBuf {
+    let mut base = PathBuf::new();
+    for component in pattern.components() {
+        if let Component::Normal(part) = component {
+            if part.to_string_lossy().contains(['*', '?', '[']) {
+                return base;
+            }
+        }
+        base.push(component);
+    }
+
+    // No wildcards: the pattern names a single file
+    pattern.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::{ReadConfig, SourceFormat, TimestampConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_config(source_type: SourceType, path: PathBuf) -> SourceConfig {
+        SourceConfig {
+            source_type,
+            path,
+            command: Vec::new(),
+            restart: None,
+            syslog: None,
+            http: None,
+            format: SourceFormat::Text,
+            timestamp: TimestampConfig {
+                pattern: r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)".to_string(),
+                pointer: None,
+                format: "iso8601".to_string(),
+                fallbacks: Vec::new(),
+                timezone: None,
+                clock_offset: None,
+                assume_year: None,
+            },
+            read: ReadConfig {
+                start: ReadStart::Beginning,
+                follow: false,
+                discover_interval: None,
+                include_rotated: false,
+                multiline: None,
+                idle_timeout: None,
+            },
+            labels: Default::default(),
+            drop_if: Vec::new(),
+            redact: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_glob_base() {
+        assert_eq!(glob_base(Path::new("/var/log/app/*.log")), PathBuf::from("/var/log/app"));
+        assert_eq!(glob_base(Path::new("/var/log/pods/*/*/*.log")), PathBuf::from("/var/log/pods"));
+        assert_eq!(glob_base(Path::new("/var/log/app.log")), PathBuf::from("/var/log"));
+    }
+
+    #[test]
+    fn test_derive_source_id() {
+        let base = Path::new("/var/log/pods");
+        assert_eq!(
+            derive_source_id("pods", base, Path::new("/var/log/pods/default_web/nginx/0.log")),
+            "pods/default_web/nginx/0.log"
+        );
+        assert_eq!(
+            derive_source_id("app", Path::new("/var/log"), Path::new("/var/log/app.log")),
+            "app/app.log"
+        );
+    }
+
+    #[test]
+    fn test_parent_source_id() {
+        assert_eq!(parent_source_id("app/api.log"), Some("app"));
+        assert_eq!(parent_source_id("app"), None);
+    }
+
+    #[test]
+    fn test_scan_glob_detects_added_and_removed() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.log"), "").unwrap();
+        fs::write(dir.path().join("b.log"), "").unwrap();
+        fs::write(dir.path().join("ignored.txt"), "").unwrap();
+
+        let config = create_test_config(SourceType::Glob, dir.path().join("*.log"));
+        let mut discovery = SourceDiscovery::new("app", &config);
+
+        let result = discovery.scan().unwrap();
+        let ids: Vec<_> = result.added.iter().map(|f| f.source_id.as_str()).collect();
+        assert_eq!(ids, vec!["app/a.log", "app/b.log"]);
+        assert!(result.removed.is_empty());
+
+        // Nothing changed
+        let result = discovery.scan().unwrap();
+        assert!(result.added.is_empty());
+        assert!(result.removed.is_empty());
+
+        fs::write(dir.path().join("c.log"), "").unwrap();
+        fs::remove_file(dir.path().join("a.log")).unwrap();
+
+        let result = discovery.scan().unwrap();
+        assert_eq!(result.added.len(), 1);
+        assert_eq!(result.added[0].source_id, "app/c.log");
+        assert_eq!(result.removed.len(), 1);
+        assert_eq!(result.removed[0].source_id, "app/a.log");
+    }
+
+    #[test]
+    fn test_scan_directory() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("one.log"), "").unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+
+        let config = create_test_config(SourceType::Directory, dir.path().to_path_buf());
+        let mut discovery = SourceDiscovery::new("dir", &config);
+
+        let result = discovery.scan().unwrap();
+        assert_eq!(result.added.len(), 1);
+        assert_eq!(result.added[0].source_id, "dir/one.log");
+    }
+
+    #[test]
+    fn test_scan_missing_directory_is_empty() {
+        let dir = TempDir::new().unwrap();
+        let config = create_test_config(SourceType::Directory, dir.path().join("missing"));
+        let mut discovery = SourceDiscovery::new("dir", &config);
+
+        assert!(discovery.scan().unwrap().added.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_initial_readers_read_each_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.log"), "2025-12-04T10:00:00Z from a\n").unwrap();
+        fs::write(dir.path().join("b.log"), "2025-12-04T10:00:01Z from b\n").unwrap();
+
+        let config = create_test_config(SourceType::Glob, dir.path().join("*.log"));
+        let mut discovery = SourceDiscovery::new("app", &config);
+        let readers = discovery
+            .initial_readers(None, ParseErrorStrategy::Panic)
+            .unwrap();
+        assert_eq!(readers.len(), 2);
+
+        for mut reader in readers {
+            let record = reader.next_record().await.unwrap().unwrap();
+            assert_eq!(record.source_id, reader.source_id());
+            assert!(record.raw_text.ends_with(&format!(
+                "from {}",
+                &reader.source_id()["app/".len().."app/".len() + 1]
+            )));
+        }
+    }
+}
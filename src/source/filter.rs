@@ -0,0 +1,284 @@
+use crate::config::expand_tilde;
+use crate::config::types::RedactRule;
+use crate::source::stats::{FilterStats, RuleStats};
+use hmac::{Hmac, Mac};
+use regex::{Captures, Regex};
+use sha2::Sha256;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Hex digits of the HMAC kept in place of a hashed value
+const HASH_LEN: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum FilterError {
+    #[error("invalid drop_if or redact pattern: {0}")]
+    Regex(#[from] regex::Error),
+
+    #[error("redact hash key variable '{0}' is not set")]
+    KeyEnv(String),
+
+    #[error("failed to read redact hash key file {path}: {source}")]
+    KeyFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("redact hash key from {0} is empty")]
+    EmptyKey(String),
+}
+
+/// What a redaction puts in place of a match
+#[derive(Debug)]
+enum Redaction {
+    /// A replacement template, expanded against the match's capture groups
+    Replace(String),
+    /// A keyed hash of the matched value
+    Hash(Hmac<Sha256>),
+}
+
+#[derive(Debug)]
+struct RedactPattern {
+    pattern: Regex,
+    redaction: Redaction,
+    // Values redacted so far
+    count: u64,
+}
+
+impl RedactPattern {
+    fn apply<'t>(&mut self, text: &'t str) -> Cow<'t, str> {
+        let mut count = 0;
+        let redacted = self.pattern.replace_all(text, |captures: &Captures| {
+            count += 1;
+            let whole = captures.get(0).expect("group 0 always matches");
+            // Only the `value` group is rewritten when there is one
+            let value = captures.name("value").unwrap_or(whole);
+            let mut replaced = String::new();
+            replaced.push_str(&text[whole.start()..value.start()]);
+            match &self.redaction {
+                Redaction::Replace(template) => captures.expand(template, &mut replaced),
+                Redaction::Hash(mac) => replaced.push_str(&keyed_hash(mac, value.as_str())),
+            }
+            replaced.push_str(&text[value.end()..whole.end()]);
+            replaced
+        });
+        self.count += count;
+        redacted
+    }
+}
+
+/// A rule's hash key, from `hash_key_env` or `hash_key_file`
+fn hash_key(rule: &RedactRule) -> Result<Option<Vec<u8>>, FilterError> {
+    let (key, from) = if let Some(var) = &rule.hash_key_env {
+        let key = std::env::var(var).map_err(|_| FilterError::KeyEnv(var.clone()))?;
+        (key.into_bytes(), format!("${}", var))
+    } else if let Some(path) = &rule.hash_key_file {
+        let path = expand_tilde(path);
+        let mut key = std::fs::read(&path).map_err(|source| FilterError::KeyFile {
+            path: path.clone(),
+            source,
+        })?;
+        while key.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+            key.pop();
+        }
+        (key, path.display().to_string())
+    } else {
+        return Ok(None);
+    };
+
+    if key.is_empty() {
+        return Err(FilterError::EmptyKey(from));
+    }
+    Ok(Some(key))
+}
+
+/// The first `HASH_LEN` hex digits of the HMAC of `value`
+fn keyed_hash(mac: &Hmac<Sha256>, value: &str) -> String {
+    let mut mac = mac.clone();
+    mac.update(value.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    let mut hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    hex.truncate(HASH_LEN);
+    hex
+}
+
+/// A source's `drop_if` and `redact` rules, applied to each record before
+/// it is emitted
+#[derive(Debug)]
+pub struct RecordFilter {
+    drop_if: Vec<(Regex, u64)>,
+    redact: Vec<RedactPattern>,
+}
+
+impl RecordFilter {
+    /// Compile a source's rules, or None if it has none. Hash keys are read
+    /// here, from their environment variable or file.
+    pub fn new(drop_if: &[String], redact: &[RedactRule]) -> Result<Option<Self>, FilterError> {
+        if drop_if.is_empty() && redact.is_empty() {
+            return Ok(None);
+        }
+
+        let drop_if = drop_if
+            .iter()
+            .map(|pattern| Ok((Regex::new(pattern)?, 0)))
+            .collect::<Result<_, regex::Error>>()?;
+        let redact = redact
+            .iter()
+            .map(|rule| {
+                let redaction = match hash_key(rule)? {
+                    Some(key) => Redaction::Hash(
+                        Hmac::new_from_slice(&key).expect("HMAC takes keys of any size"),
+                    ),
+                    None => Redaction::Replace(rule.replace.clone().unwrap_or_default()),
+                };
+                Ok(RedactPattern {
+                    pattern: Regex::new(&rule.pattern)?,
+                    redaction,
+                    count: 0,
+                })
+            })
+            .collect::<Result<_, FilterError>>()?;
+
+        Ok(Some(Self { drop_if, redact }))
+    }
+
+    /// Whether the record should be dropped, counting the rule that matched
+    pub fn drops(&mut self, text: &str) -> bool {
+        match self.drop_if.iter_mut().find(|(pattern, _)| pattern.is_match(text)) {
+            Some((_, count)) => {
+                *count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply the redactions to a record's text and field values
+    pub fn redact(&mut self, text: &mut String, fields: &mut BTreeMap<String, String>) {
+        for rule in &mut self.redact {
+            if let Cow::Owned(redacted) = rule.apply(text) {
+                *text = redacted;
+            }
+            for value in fields.values_mut() {
+                if let Cow::Owned(redacted) = rule.apply(value) {
+                    *value = redacted;
+                }
+            }
+        }
+    }
+
+    /// Copy rule counts into `stats`, reusing its allocations
+    pub fn write_stats(&self, stats: &mut FilterStats) {
+        let dropped = self.drop_if.iter().map(|(pattern, count)| (pattern, *count));
+        write_rule_stats(&mut stats.dropped, dropped);
+        let redacted = self.redact.iter().map(|rule| (&rule.pattern, rule.count));
+        write_rule_stats(&mut stats.redacted, redacted);
+    }
+}
+
+fn write_rule_stats<'a>(
+    stats: &mut Vec<RuleStats>,
+    rules: impl ExactSizeIterator<Item = (&'a Regex, u64)>,
+) {
+    if stats.len() != rules.len() {
+        *stats = rules
+            .map(|(pattern, count)| RuleStats {
+                pattern: pattern.as_str().to_string(),
+                count,
+            })
+            .collect();
+        return;
+    }
+    for (stats, (_, count)) in stats.iter_mut().zip(rules) {
+        stats.count = count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn rule(pattern: &str, replace: Option<&str>, hash_key_file: Option<&NamedTempFile>) -> RedactRule {
+        RedactRule {
+            pattern: pattern.to_string(),
+            replace: replace.map(str::to_string),
+            hash_key_env: None,
+            hash_key_file: hash_key_file.map(|file| file.path().to_path_buf()),
+        }
+    }
+
+    #[test]
+    fn test_drop_and_replace() {
+        let mut filter = RecordFilter::new(
+            &["GET /health".to_string()],
+            &[rule(r"[\w.+-]+@[\w-]+\.[\w.]+", Some("<email>"), None)],
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(filter.drops("10.0.0.1 GET /health 200"));
+        assert!(!filter.drops("10.0.0.1 GET /orders 200"));
+
+        let mut text = "signup alice@example.com bob@example.org".to_string();
+        let mut fields = BTreeMap::from([("user".to_string(), "carol@example.net".to_string())]);
+        filter.redact(&mut text, &mut fields);
+        assert_eq!(text, "signup <email> <email>");
+        assert_eq!(fields["user"], "<email>");
+
+        let mut stats = FilterStats::default();
+        filter.write_stats(&mut stats);
+        assert_eq!(stats.dropped[0].count, 1);
+        assert_eq!(stats.redacted[0].count, 3);
+    }
+
+    #[test]
+    fn test_keyed_hash_of_value_group() {
+        let mut key = NamedTempFile::new().unwrap();
+        writeln!(key, "secret").unwrap();
+        let mut filter = RecordFilter::new(
+            &[],
+            &[rule(r"token=(?P<value>\w+)", None, Some(&key))],
+        )
+        .unwrap()
+        .unwrap();
+
+        let redact = |filter: &mut RecordFilter, line: &str| {
+            let mut text = line.to_string();
+            filter.redact(&mut text, &mut BTreeMap::new());
+            text
+        };
+        let first = redact(&mut filter, "login token=abc123 ok");
+        let second = redact(&mut filter, "logout token=abc123");
+        let other = redact(&mut filter, "login token=xyz789 ok");
+
+        // The key name is kept and equal values hash alike
+        let hash = first.strip_prefix("login token=").unwrap().strip_suffix(" ok").unwrap();
+        assert_eq!(hash.len(), HASH_LEN);
+        assert!(!first.contains("abc123"));
+        assert_eq!(second, format!("logout token={}", hash));
+        assert_ne!(other, first);
+
+        // The same key from the environment, without the file's newline,
+        // hashes alike
+        std::env::set_var("NOIL_TEST_REDACT_KEY", "secret");
+        let from_env = RedactRule {
+            hash_key_env: Some("NOIL_TEST_REDACT_KEY".to_string()),
+            ..rule(r"token=(?P<value>\w+)", None, None)
+        };
+        let mut filter = RecordFilter::new(&[], &[from_env]).unwrap().unwrap();
+        assert_eq!(redact(&mut filter, "login token=abc123 ok"), first);
+
+        let unset = RedactRule {
+            hash_key_env: Some("NOIL_TEST_REDACT_KEY_UNSET".to_string()),
+            ..rule(r"token=(?P<value>\w+)", None, None)
+        };
+        assert!(matches!(
+            RecordFilter::new(&[], &[unset]),
+            Err(FilterError::KeyEnv(var)) if var == "NOIL_TEST_REDACT_KEY_UNSET"
+        ));
+    }
+}
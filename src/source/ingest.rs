@@ -0,0 +1,549 @@
+use crate::config::types::BackpressureStrategy;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// How long a request waits for buffer space under the `block` strategy
+/// before the shipper is told to retry
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Object keys checked, in order, for a record's timestamp
+const TIMESTAMP_KEYS: [&str; 4] = ["@timestamp", "timestamp", "time", "ts"];
+
+/// Object keys checked, in order, for a record's log line
+const MESSAGE_KEYS: [&str; 4] = ["message", "msg", "log", "line"];
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error("line {line}: invalid JSON: {source}")]
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("line {line}: expected a JSON object or string")]
+    NotARecord { line: usize },
+
+    #[error("line {line}: invalid timestamp '{value}'")]
+    Timestamp { line: usize, value: String },
+
+    #[error("line {line}: unsupported bulk action '{action}'")]
+    BulkAction { line: usize, action: String },
+
+    #[error("line {line}: bulk action has no document")]
+    MissingDocument { line: usize },
+
+    #[error("invalid Loki push body: {0}")]
+    Loki(serde_json::Error),
+
+    #[error("invalid Loki timestamp '{0}'")]
+    LokiTimestamp(String),
+
+    #[error("unknown http source '{0}'")]
+    UnknownSource(String),
+
+    #[error("{0} http sources are configured; name one in the URL (/ingest/<source>)")]
+    AmbiguousSource(usize),
+
+    #[error("request has {records} records but at most {capacity} can be buffered")]
+    TooLarge { records: usize, capacity: usize },
+
+    #[error("ingest buffer for source '{0}' is full")]
+    Full(String),
+
+    #[error("ingest buffer for source '{0}' stayed full for {1:?}")]
+    Busy(String, Duration),
+
+    #[error("source '{0}' is not accepting records")]
+    Closed(String),
+}
+
+/// A record pushed over HTTP, before it is assigned to its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestRecord {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Receiving end of an http source's buffer, read by its `SourceReader`
+pub type IngestReceiver = mpsc::Receiver<IngestRecord>;
+
+/// Routes records pushed to `/ingest` into the buffers of the http sources.
+///
+/// Each http source has a bounded buffer of `pipeline.backpressure.buffer_limit`
+/// records. A request is accepted whole or not at all: under the `block`
+/// strategy it waits for room, under `drop` and `buffer_in_memory` it is
+/// rejected as soon as the buffer can't hold it.
+pub struct IngestHub {
+    strategy: BackpressureStrategy,
+    capacity: usize,
+    senders: HashMap<String, mpsc::Sender<IngestRecord>>,
+}
+
+impl IngestHub {
+    pub fn new(strategy: BackpressureStrategy, capacity: usize) -> Self {
+        Self {
+            strategy,
+            capacity: capacity.max(1),
+            senders: HashMap::new(),
+        }
+    }
+
+    /// Create the buffer for an http source and return its receiving end
+    pub fn register(&mut self, source_id: &str) -> IngestReceiver {
+        let (tx, rx) = mpsc::channel(self.capacity);
+        self.senders.insert(source_id.to_string(), tx);
+        rx
+    }
+
+    /// Returns true if no http sources are registered
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Find the buffer for `source`, or the only http source if none is named
+    fn resolve(
+        &self,
+        source: Option<&str>,
+    ) -> Result<(&str, &mpsc::Sender<IngestRecord>), IngestError> {
+        match source {
+            Some(source) => self
+                .senders
+                .get_key_value(source)
+                .map(|(id, tx)| (id.as_str(), tx))
+                .ok_or_else(|| IngestError::UnknownSource(source.to_string())),
+            None if self.senders.len() == 1 => {
+                let (id, tx) = self.senders.iter().next().unwrap();
+                Ok((id.as_str(), tx))
+            }
+            None => Err(IngestError::AmbiguousSource(self.senders.len())),
+        }
+    }
+
+    /// Queue records for an http source, applying the backpressure strategy.
+    ///
+    /// Returns the number of records accepted.
+    pub async fn submit(
+        &self,
+        source: Option<&str>,
+        records: Vec<IngestRecord>,
+    ) -> Result<usize, IngestError> {
+        let (source_id, tx) = self.resolve(source)?;
+        let count = records.len();
+        if count == 0 {
+            return Ok(0);
+        }
+        if count > tx.max_capacity() {
+            return Err(IngestError::TooLarge {
+                records: count,
+                capacity: tx.max_capacity(),
+            });
+        }
+
+        let permits = match self.strategy {
+            BackpressureStrategy::Block => {
+                match tokio::time::timeout(BLOCK_TIMEOUT, tx.reserve_many(count)).await {
+                    Ok(Ok(permits)) => permits,
+                    Ok(Err(_)) => return Err(IngestError::Closed(source_id.to_string())),
+                    Err(_) => return Err(IngestError::Busy(source_id.to_string(), BLOCK_TIMEOUT)),
+                }
+            }
+            BackpressureStrategy::Drop | BackpressureStrategy::BufferInMemory => {
+                tx.try_reserve_many(count).map_err(|e| match e {
+                    mpsc::error::TrySendError::Full(_) => IngestError::Full(source_id.to_string()),
+                    mpsc::error::TrySendError::Closed(_) => {
+                        IngestError::Closed(source_id.to_string())
+                    }
+                })?
+            }
+        };
+
+        for (permit, record) in permits.zip(records) {
+            permit.send(record);
+        }
+        Ok(count)
+    }
+}
+
+/// Decode newline-delimited JSON. Each line is either a JSON object or a JSON
+/// string holding the raw log line; blank lines are skipped.
+pub fn decode_ndjson(body: &str, received_at: DateTime<Utc>) -> Result<Vec<IngestRecord>, IngestError> {
+    let mut records = Vec::new();
+    for (idx, line) in body.lines().enumerate() {
+        let line_no = idx + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line).map_err(|source| IngestError::Json {
+            line: line_no,
+            source,
+        })?;
+        records.push(value_record(value, line_no, received_at)?);
+    }
+    Ok(records)
+}
+
+/// An `index` or `create` action from an Elasticsearch `_bulk` body
+#[derive(Debug)]
+pub struct BulkItem {
+    pub action: String,
+    pub record: IngestRecord,
+}
+
+/// Decode an Elasticsearch `_bulk` body: action lines, each followed by the
+/// document to index. Only `index` and `create` actions are supported.
+pub fn decode_bulk(body: &str, received_at: DateTime<Utc>) -> Result<Vec<BulkItem>, IngestError> {
+    let mut items = Vec::new();
+    let mut lines = body
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    while let Some((line_no, line)) = lines.next() {
+        let action: Map<String, Value> =
+            serde_json::from_str(line).map_err(|source| IngestError::Json {
+                line: line_no,
+                source,
+            })?;
+        let mut actions = action.keys();
+        let action = match (actions.next(), actions.next()) {
+            (Some(action), None) => action.clone(),
+            _ => return Err(IngestError::NotARecord { line: line_no }),
+        };
+        if action != "index" && action != "create" {
+            return Err(IngestError::BulkAction {
+                line: line_no,
+                action,
+            });
+        }
+
+        let (doc_line_no, doc) = lines
+            .next()
+            .ok_or(IngestError::MissingDocument { line: line_no })?;
+        let doc: Value = serde_json::from_str(doc).map_err(|source| IngestError::Json {
+            line: doc_line_no,
+            source,
+        })?;
+        items.push(BulkItem {
+            action,
+            record: value_record(doc, doc_line_no, received_at)?,
+        });
+    }
+    Ok(items)
+}
+
+#[derive(Deserialize)]
+struct LokiPush {
+    streams: Vec<LokiStream>,
+}
+
+#[derive(Deserialize)]
+struct LokiStream {
+    #[serde(default)]
+    stream: BTreeMap<String, String>,
+    values: Vec<LokiEntry>,
+}
+
+/// `[<unix nanoseconds>, <line>]`, optionally followed by structured metadata
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LokiEntry {
+    Line(String, String),
+    WithMetadata(String, String, BTreeMap<String, String>),
+}
+
+/// Decode a Loki push request (JSON encoding). Stream labels and structured
+/// metadata become record fields.
+pub fn decode_loki(body: &[u8]) -> Result<Vec<IngestRecord>, IngestError> {
+    let push: LokiPush = serde_json::from_slice(body).map_err(IngestError::Loki)?;
+
+    let mut records = Vec::new();
+    for stream in push.streams {
+        for entry in stream.values {
+            let (ts, text, metadata) = match entry {
+                LokiEntry::Line(ts, text) => (ts, text, BTreeMap::new()),
+                LokiEntry::WithMetadata(ts, text, metadata) => (ts, text, metadata),
+            };
+            let timestamp = ts
+                .parse::<i64>()
+                .ok()
+                .map(DateTime::from_timestamp_nanos)
+                .ok_or(IngestError::LokiTimestamp(ts))?;
+
+            let mut fields = stream.stream.clone();
+            fields.extend(metadata);
+            records.push(IngestRecord {
+                timestamp,
+                text,
+                fields,
+            });
+        }
+    }
+    Ok(records)
+}
+
+/// Turn a decoded JSON value into a record.
+///
+/// Objects take their timestamp and log line from the first of
+/// [`TIMESTAMP_KEYS`] and [`MESSAGE_KEYS`] present; the remaining keys are
+/// flattened into fields (`kubernetes.pod_name`). Without a message key the
+/// whole object is the log line. Records without a timestamp are stamped with
+/// the time they were received.
+fn value_record(
+    value: Value,
+    line: usize,
+    received_at: DateTime<Utc>,
+) -> Result<IngestRecord, IngestError> {
+    let mut object = match value {
+        Value::Object(object) => object,
+        Value::String(text) => {
+            return Ok(IngestRecord {
+                timestamp: received_at,
+                text,
+                fields: BTreeMap::new(),
+            })
+        }
+        _ => return Err(IngestError::NotARecord { line }),
+    };
+    let original = (!MESSAGE_KEYS.iter().any(|key| object.get(*key).is_some_and(Value::is_string)))
+        .then(|| Value::Object(object.clone()).to_string());
+
+    let timestamp = match TIMESTAMP_KEYS.iter().find_map(|key| object.remove(*key)) {
+        Some(value) => parse_timestamp(&value).ok_or_else(|| IngestError::Timestamp {
+            line,
+            value: value.to_string(),
+        })?,
+        None => received_at,
+    };
+
+    let text = match original {
+        Some(text) => text,
+        None => MESSAGE_KEYS
+            .iter()
+            .find_map(|key| match object.get(*key) {
+                Some(Value::String(_)) => object.remove(*key),
+                _ => None,
+            })
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    };
+
+    let mut fields = BTreeMap::new();
+    for (key, value) in object {
+        flatten_into(&mut fields, key, value);
+    }
+
+    Ok(IngestRecord {
+        timestamp,
+        text,
+        fields,
+    })
+}
+
+/// Parse an RFC 3339 string or a Unix epoch number. Epoch values are read as
+/// seconds, milliseconds, microseconds or nanoseconds depending on magnitude.
+fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|ts| ts.with_timezone(&Utc)),
+        Value::Number(n) => {
+            let epoch = n.as_f64()?;
+            let nanos = match epoch.abs() {
+                e if e >= 1e17 => epoch,
+                e if e >= 1e14 => epoch * 1e3,
+                e if e >= 1e11 => epoch * 1e6,
+                _ => epoch * 1e9,
+            };
+            if !nanos.is_finite() || nanos.abs() >= i64::MAX as f64 {
+                return None;
+            }
+            Some(DateTime::from_timestamp_nanos(nanos as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Insert `value` under `key`, flattening nested objects with dotted keys.
+/// Nulls are skipped and arrays are kept as JSON text.
+fn flatten_into(fields: &mut BTreeMap<String, String>, key: String, value: Value) {
+    match value {
+        Value::Null => {}
+        Value::String(s) => {
+            fields.insert(key, s);
+        }
+        Value::Object(object) => {
+            for (child, value) in object {
+                flatten_into(fields, format!("{}.{}", key, child), value);
+            }
+        }
+        other => {
+            fields.insert(key, other.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn received() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap()
+    }
+
+    fn record(text: &str) -> IngestRecord {
+        IngestRecord {
+            timestamp: received(),
+            text: text.to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_decode_ndjson() {
+        let body = concat!(
+            r#"{"timestamp":"2025-03-10T11:59:58Z","message":"GET /","level":"info","kubernetes":{"pod_name":"api-0"}}"#,
+            "\n\n",
+            r#""plain line""#,
+            "\n",
+            r#"{"ts":1741607999.5,"status":200}"#,
+            "\n",
+        );
+        let records = decode_ndjson(body, received()).unwrap();
+        assert_eq!(records.len(), 3);
+
+        assert_eq!(records[0].timestamp, Utc.with_ymd_and_hms(2025, 3, 10, 11, 59, 58).unwrap());
+        assert_eq!(records[0].text, "GET /");
+        assert_eq!(records[0].fields["level"], "info");
+        assert_eq!(records[0].fields["kubernetes.pod_name"], "api-0");
+        assert!(!records[0].fields.contains_key("message"));
+
+        assert_eq!(records[1], record("plain line"));
+
+        // Without a message key the whole object is the line
+        assert_eq!(
+            records[2].timestamp,
+            Utc.timestamp_millis_opt(1_741_607_999_500).unwrap()
+        );
+        assert_eq!(records[2].text, r#"{"status":200,"ts":1741607999.5}"#);
+        assert_eq!(records[2].fields["status"], "200");
+    }
+
+    #[test]
+    fn test_decode_ndjson_errors() {
+        let err = decode_ndjson("{\"message\":\"ok\"}\n{bad", received()).unwrap_err();
+        assert!(matches!(err, IngestError::Json { line: 2, .. }));
+
+        let err = decode_ndjson("42", received()).unwrap_err();
+        assert!(matches!(err, IngestError::NotARecord { line: 1 }));
+
+        let err = decode_ndjson(r#"{"time":"yesterday","msg":"x"}"#, received()).unwrap_err();
+        assert!(matches!(err, IngestError::Timestamp { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_epoch_units() {
+        let expected = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        for epoch in [
+            "1741608000",
+            "1741608000000",
+            "1741608000000000",
+            "1741608000000000000",
+        ] {
+            let value: Value = serde_json::from_str(epoch).unwrap();
+            assert_eq!(parse_timestamp(&value), Some(expected), "epoch {}", epoch);
+        }
+    }
+
+    #[test]
+    fn test_decode_bulk() {
+        let body = concat!(
+            r#"{"index":{"_index":"logs"}}"#,
+            "\n",
+            r#"{"@timestamp":"2025-03-10T11:00:00.250Z","log":"started","host":"web-1"}"#,
+            "\n",
+            r#"{"create":{}}"#,
+            "\n",
+            r#"{"message":"second"}"#,
+            "\n",
+        );
+        let items = decode_bulk(body, received()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].action, "index");
+        assert_eq!(items[0].record.text, "started");
+        assert_eq!(items[0].record.fields["host"], "web-1");
+        assert_eq!(
+            items[0].record.timestamp,
+            Utc.timestamp_millis_opt(1_741_604_400_250).unwrap()
+        );
+        assert_eq!(items[1].action, "create");
+        assert_eq!(items[1].record, record("second"));
+
+        let err = decode_bulk("{\"delete\":{\"_id\":\"1\"}}\n", received()).unwrap_err();
+        assert!(matches!(err, IngestError::BulkAction { line: 1, .. }));
+
+        let err = decode_bulk("{\"index\":{}}\n", received()).unwrap_err();
+        assert!(matches!(err, IngestError::MissingDocument { line: 1 }));
+    }
+
+    #[test]
+    fn test_decode_loki() {
+        let body = br#"{"streams":[{"stream":{"job":"api","namespace":"prod"},"values":[
+            ["1741608000000000000","first"],
+            ["1741608001000000000","second",{"trace_id":"abc"}]
+        ]}]}"#;
+        let records = decode_loki(body).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, received());
+        assert_eq!(records[0].text, "first");
+        assert_eq!(records[0].fields["job"], "api");
+        assert_eq!(records[1].fields["namespace"], "prod");
+        assert_eq!(records[1].fields["trace_id"], "abc");
+
+        let err = decode_loki(br#"{"streams":[{"stream":{},"values":[["soon","x"]]}]}"#).unwrap_err();
+        assert!(matches!(err, IngestError::LokiTimestamp(_)));
+    }
+
+    #[tokio::test]
+    async fn test_submit_backpressure() {
+        let mut hub = IngestHub::new(BackpressureStrategy::Drop, 2);
+        let mut rx = hub.register("push");
+
+        assert_eq!(hub.submit(None, vec![record("a")]).await.unwrap(), 1);
+        assert!(matches!(
+            hub.submit(Some("push"), vec![record("b"), record("c")]).await,
+            Err(IngestError::Full(_))
+        ));
+        assert!(matches!(
+            hub.submit(None, vec![record("x"); 3]).await,
+            Err(IngestError::TooLarge { records: 3, capacity: 2 })
+        ));
+        assert!(matches!(
+            hub.submit(Some("other"), vec![record("x")]).await,
+            Err(IngestError::UnknownSource(_))
+        ));
+
+        assert_eq!(rx.recv().await.unwrap(), record("a"));
+        assert_eq!(hub.submit(None, vec![record("b"), record("c")]).await.unwrap(), 2);
+        assert_eq!(rx.recv().await.unwrap(), record("b"));
+
+        hub.register("second");
+        assert!(matches!(
+            hub.submit(None, vec![record("x")]).await,
+            Err(IngestError::AmbiguousSource(2))
+        ));
+
+        drop(rx);
+        assert!(matches!(
+            hub.submit(Some("push"), vec![record("x")]).await,
+            Err(IngestError::Closed(_))
+        ));
+    }
+}
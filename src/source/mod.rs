@@ -1,2 +1,12 @@
+pub mod compression;
+pub mod container;
+pub mod discovery;
+pub mod filter;
+pub mod ingest;
+pub mod multiline;
 pub mod reader;
+pub mod rotation;
+pub mod stats;
+pub mod stream;
+pub mod syslog;
 pub mod timestamp;
@@ -0,0 +1,101 @@
+use crate::config::types::MultilineConfig;
+use regex::Regex;
+use std::time::Duration;
+
+/// Which lines the multiline pattern matches
+#[derive(Debug)]
+enum Boundary {
+    /// Lines that start a record
+    Start(Regex),
+    /// Lines that continue a record
+    Continue(Regex),
+}
+
+/// Compiled `read.multiline` rules
+#[derive(Debug)]
+pub struct Multiline {
+    boundary: Option<Boundary>,
+    max_lines: Option<usize>,
+    max_bytes: Option<usize>,
+    flush_after: Option<Duration>,
+}
+
+impl Multiline {
+    pub fn new(config: &MultilineConfig) -> Result<Self, regex::Error> {
+        let boundary = match (&config.start, &config.continue_pattern) {
+            (Some(start), _) => Some(Boundary::Start(Regex::new(start)?)),
+            (None, Some(continue_pattern)) => Some(Boundary::Continue(Regex::new(continue_pattern)?)),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            boundary,
+            max_lines: config.max_lines,
+            max_bytes: config.max_bytes,
+            flush_after: config.flush_after,
+        })
+    }
+
+    /// Whether `line` starts a new record, or None when records are told
+    /// apart by their timestamps
+    pub fn starts_record(&self, line: &str) -> Option<bool> {
+        match self.boundary.as_ref()? {
+            Boundary::Start(pattern) => Some(pattern.is_match(line)),
+            Boundary::Continue(pattern) => Some(!pattern.is_match(line)),
+        }
+    }
+
+    /// Whether a record of `lines` lines and `bytes` bytes has room for
+    /// another line of `line_len` bytes
+    pub fn fits(&self, lines: usize, bytes: usize, line_len: usize) -> bool {
+        // The joined record has a newline before each added line
+        self.max_lines.is_none_or(|max| lines < max)
+            && self.max_bytes.is_none_or(|max| bytes + 1 + line_len <= max)
+    }
+
+    /// How long the last record of a followed input waits for more lines,
+    /// or None to wait for the next record
+    pub fn flush_after(&self) -> Option<Duration> {
+        self.flush_after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundaries() {
+        let start = Multiline::new(&MultilineConfig {
+            start: Some(r"^BEGIN ".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(start.starts_record("BEGIN request"), Some(true));
+        assert_eq!(start.starts_record("2025-12-04T10:00:00Z detail"), Some(false));
+
+        let continued = Multiline::new(&MultilineConfig {
+            continue_pattern: Some(r"^\s".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(continued.starts_record("Exception in thread main"), Some(true));
+        assert_eq!(continued.starts_record("    at Main.run"), Some(false));
+
+        let by_timestamp = Multiline::new(&MultilineConfig::default()).unwrap();
+        assert_eq!(by_timestamp.starts_record("anything"), None);
+    }
+
+    #[test]
+    fn test_limits() {
+        let multiline = Multiline::new(&MultilineConfig {
+            max_lines: Some(2),
+            max_bytes: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(multiline.fits(1, 4, 5));
+        assert!(!multiline.fits(1, 4, 6));
+        assert!(!multiline.fits(2, 1, 1));
+    }
+}
@@ -1,13 +1,26 @@
-use crate::config::types::{ParseErrorStrategy, ReadConfig, ReadStart, SourceConfig};
-use crate::source::timestamp::{TimestampError, TimestampExtractor};
-use crate::storage::checkpoint::SharedSourceState;
+use crate::config::types::{
+    HttpSourceConfig, ParseErrorStrategy, ReadConfig, ReadStart, RestartConfig, SourceConfig,
+    SourceFormat, SourceType, SyslogConfig,
+};
+use crate::source::compression::{skip_bytes, Compression};
+use crate::source::container::{self, ContainerEntry, ContainerLineParser};
+use crate::source::filter::{FilterError, RecordFilter};
+use crate::source::ingest::IngestReceiver;
+use crate::source::multiline::Multiline;
+use crate::source::rotation::{fingerprint, fingerprint_bytes, read_head, rotated_generations, FINGERPRINT_LEN};
+use crate::source::stream::LineStream;
+use crate::source::syslog::{self, SyslogListener};
+use crate::source::timestamp::{parse_timezone, TimestampError, TimestampExtractor};
+use crate::storage::checkpoint::{SharedSourceState, SourceCheckpoint};
 use chrono::{DateTime, Utc};
 use futures::Future;
 use std::fs::{File, Metadata};
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
 use uuid::Uuid;
@@ -22,6 +35,15 @@ pub enum ReaderError {
 
     #[error("parse error: {0}")]
     ParseError(String),
+
+    #[error("discovery error: {0}")]
+    Discovery(String),
+
+    #[error("invalid multiline pattern: {0}")]
+    Multiline(regex::Error),
+
+    #[error("{0}")]
+    Filter(FilterError),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -31,30 +53,108 @@ pub struct LogRecord {
     pub source_id: String,
     pub raw_text: String,
     pub file_offset: u64,
+    /// Structured fields parsed by the source (e.g. syslog `hostname` and
+    /// `app_name`), matchable by fiber patterns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Labels of the source (e.g. `host`, `env`), plus `collector` for
+    /// records pulled from a remote collector
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Set by the sequencer on a record older than records it already
+    /// emitted (`late_records: emit_anyway` or `side_table`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub late: bool,
 }
 
 #[derive(Debug)]
 struct BufferedLine {
     text: String,
-    timestamp: DateTime<Utc>,
+    // None until one of the record's lines has a timestamp (multiline
+    // records may start without one)
+    timestamp: Option<DateTime<Utc>>,
     start_offset: u64,
+    fields: BTreeMap<String, String>,
+    lines: usize,
+    // When the last line was added, for `multiline.flush_after`
+    updated_at: Instant,
+}
+
+impl BufferedLine {
+    fn new(
+        text: String,
+        timestamp: Option<DateTime<Utc>>,
+        start_offset: u64,
+        fields: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            text,
+            timestamp,
+            start_offset,
+            fields,
+            lines: 1,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.text.push('\n');
+        self.text.push_str(line);
+        self.lines += 1;
+        self.updated_at = Instant::now();
+    }
 }
 
 pub struct SourceReader {
     source_id: String,
+    source_type: SourceType,
     path: PathBuf,
-    timestamp_extractor: TimestampExtractor,
+    command: Vec<String>,
+    restart: RestartConfig,
+    syslog: Option<SyslogConfig>,
+    // None for listener sources, whose records carry their own timestamps
+    timestamp_extractor: Option<TimestampExtractor>,
+    // Correction for a skewed host clock, added to every timestamp
+    clock_offset: chrono::Duration,
+    // How far a listener source's watermark trails the current time
+    lateness: Duration,
+    // Joins partial lines of docker and cri sources
+    container: Option<ContainerLineParser>,
+    // Fields parsed from a container log file's path
+    path_fields: BTreeMap<String, String>,
+    // Labels attached to every record
+    labels: BTreeMap<String, String>,
+    // Explicit rules for grouping lines into records
+    multiline: Option<Multiline>,
+    // Drop and redaction rules applied before records are emitted
+    filter: Option<RecordFilter>,
     read_config: ReadConfig,
     parse_error_strategy: ParseErrorStrategy,
 
+    // Older rotated generations still to be read before `path`, oldest first
+    rotated_backlog: VecDeque<PathBuf>,
+
     // Internal state
-    file: Option<BufReader<File>>,
-    current_offset: u64,
+    file: Option<BufReader<Box<dyn Read + Send>>>,
+    // Input for stdin and command sources (offsets count bytes read from the pipe)
+    stream: Option<LineStream>,
+    // Input for syslog sources (offsets count bytes received)
+    listener: Option<SyslogListener>,
+    // Input for http sources, fed by the web server (offsets count bytes received)
+    ingest: Option<IngestReceiver>,
+    compression: Compression,
+    current_offset: u64, // Offset in the uncompressed stream for compressed files
     buffered_line: Option<BufferedLine>,
     last_watermark: Option<DateTime<Utc>>,
     last_emitted_offset: u64, // Offset after the last emitted record (for checkpointing)
     file_inode: Option<u64>,
+    // First FINGERPRINT_LEN bytes of the current file, for checkpoint fingerprints
+    head: Vec<u8>,
     eof_reached: bool,
+    // The live file was rotated; drain the old handle before reopening
+    draining_rotated: bool,
+    // Stop at EOF instead of reopening when the file is rotated or removed
+    finish_on_rotation: bool,
 
     // Shared state for checkpointing
     shared_state: Option<SharedSourceState>,
@@ -67,22 +167,96 @@ impl SourceReader {
         config: &SourceConfig,
         parse_error_strategy: ParseErrorStrategy,
     ) -> Result<Self, ReaderError> {
-        let timestamp_extractor =
-            TimestampExtractor::new(&config.timestamp.pattern, &config.timestamp.format)?;
+        let timestamp_extractor = match (config.source_type.is_listener(), config.format) {
+            (true, _) => None,
+            (false, SourceFormat::Text) => Some(TimestampExtractor::new(
+                &config.timestamp.pattern,
+                &config.timestamp.format,
+            )?),
+            (false, SourceFormat::Json) => Some(TimestampExtractor::json(
+                config.timestamp.pointer.as_deref().unwrap_or_default(),
+                &config.timestamp.format,
+            )?),
+            // The runtime timestamps each message; a pattern is only needed
+            // to group multiline messages by a timestamp in the text
+            (false, SourceFormat::Docker | SourceFormat::Cri) => {
+                if !config.timestamp.finds_timestamps() {
+                    None
+                } else {
+                    Some(TimestampExtractor::new(
+                        &config.timestamp.pattern,
+                        &config.timestamp.format,
+                    )?)
+                }
+            }
+        };
+        let timezone = config.timestamp.timezone.as_deref().map(parse_timezone).transpose()?;
+        let timestamp_extractor = timestamp_extractor
+            .map(|mut extractor| {
+                for fallback in &config.timestamp.fallbacks {
+                    extractor = extractor.with_fallback(&fallback.pattern, &fallback.format)?;
+                }
+                if let Some(zone) = timezone {
+                    extractor = extractor.with_timezone(zone);
+                }
+                if let Some(year) = config.timestamp.assume_year {
+                    extractor = extractor.with_assume_year(year);
+                }
+                Ok::<_, TimestampError>(extractor)
+            })
+            .transpose()?;
+        let path_fields = if config.format.is_container() {
+            container::path_fields(&config.path)
+        } else {
+            BTreeMap::new()
+        };
+        let multiline = config
+            .read
+            .multiline
+            .as_ref()
+            .map(Multiline::new)
+            .transpose()
+            .map_err(ReaderError::Multiline)?;
+        let filter =
+            RecordFilter::new(&config.drop_if, &config.redact).map_err(ReaderError::Filter)?;
+        let lateness = match (&config.syslog, &config.http) {
+            (Some(syslog), _) => syslog.lateness,
+            (None, Some(http)) => http.lateness,
+            (None, None) => HttpSourceConfig::default().lateness,
+        };
 
         Ok(Self {
             source_id,
+            source_type: config.source_type,
             path: config.path.clone(),
+            command: config.command.clone(),
+            restart: config.restart.clone().unwrap_or_default(),
+            syslog: config.syslog.clone(),
             timestamp_extractor,
+            clock_offset: config.timestamp.clock_offset.unwrap_or_default(),
+            lateness,
+            container: ContainerLineParser::new(config.format),
+            path_fields,
+            labels: config.labels.clone(),
+            multiline,
+            filter,
             read_config: config.read.clone(),
             parse_error_strategy,
+            rotated_backlog: VecDeque::new(),
             file: None,
+            stream: None,
+            listener: None,
+            ingest: None,
+            compression: Compression::None,
             current_offset: 0,
             buffered_line: None,
             last_watermark: None,
             last_emitted_offset: 0,
             file_inode: None,
+            head: Vec::new(),
             eof_reached: false,
+            draining_rotated: false,
+            finish_on_rotation: false,
             shared_state: None,
         })
     }
@@ -103,37 +277,231 @@ impl SourceReader {
         Ok(reader)
     }
 
+    /// Create a SourceReader that resumes from a checkpoint.
+    ///
+    /// The checkpointed offset is only used if the file at `config.path` still has
+    /// the checkpointed inode; otherwise the file was rotated or replaced and the
+    /// reader starts fresh according to `config.read.start`.
+    pub fn resume(
+        source_id: String,
+        config: &SourceConfig,
+        parse_error_strategy: ParseErrorStrategy,
+        checkpoint: Option<&SourceCheckpoint>,
+    ) -> Result<Self, ReaderError> {
+        if config.read.include_rotated {
+            return Self::resume_rotation_chain(source_id, config, parse_error_strategy, checkpoint);
+        }
+
+        // A pipe can't be rewound, so stream sources always start fresh
+        if config.source_type.is_stream() {
+            return Self::new(source_id, config, parse_error_strategy);
+        }
+
+        let Some(checkpoint) = checkpoint else {
+            return Self::new(source_id, config, parse_error_strategy);
+        };
+
+        match std::fs::metadata(&config.path) {
+            Ok(metadata) if get_inode(&metadata) == checkpoint.inode => {
+                tracing::info!(
+                    source_id = %source_id,
+                    offset = checkpoint.offset,
+                    "Restoring source reader from checkpoint"
+                );
+                Self::new_with_offset(source_id, config, parse_error_strategy, checkpoint.offset)
+            }
+            Ok(_) => {
+                tracing::warn!(
+                    source_id = %source_id,
+                    "File inode changed (rotation detected), starting from beginning"
+                );
+                Self::new(source_id, config, parse_error_strategy)
+            }
+            Err(_) => {
+                tracing::warn!(source_id = %source_id, "Cannot read file metadata, starting from beginning");
+                Self::new(source_id, config, parse_error_strategy)
+            }
+        }
+    }
+
+    /// Create a reader for a file and its rotated generations.
+    ///
+    /// Without a checkpoint, every older generation is read (oldest first)
+    /// before the live file when starting from the beginning. With a
+    /// checkpoint, the checkpointed file is located among the generations by
+    /// inode or content fingerprint (it may have been renamed or compressed
+    /// since), reading resumes at its offset, and newer generations follow.
+    fn resume_rotation_chain(
+        source_id: String,
+        config: &SourceConfig,
+        parse_error_strategy: ParseErrorStrategy,
+        checkpoint: Option<&SourceCheckpoint>,
+    ) -> Result<Self, ReaderError> {
+        let generations = rotated_generations(&config.path)?;
+
+        let Some(checkpoint) = checkpoint else {
+            let mut reader = Self::new(source_id, config, parse_error_strategy)?;
+            if matches!(config.read.start, ReadStart::Beginning) {
+                reader.rotated_backlog = generations.into();
+            }
+            return Ok(reader);
+        };
+
+        // Candidates newest first: the live file, then each older generation
+        let mut candidates: Vec<PathBuf> = generations.clone();
+        candidates.push(config.path.clone());
+        candidates.reverse();
+
+        let fingerprint_matches = |path: &Path| match checkpoint.fingerprint {
+            Some(expected) => fingerprint(path, checkpoint.offset.min(FINGERPRINT_LEN))
+                .ok()
+                .flatten()
+                == Some(expected),
+            None => false,
+        };
+        let inode_matches = |path: &Path| {
+            std::fs::metadata(path).is_ok_and(|m| get_inode(&m) == checkpoint.inode)
+        };
+
+        // Prefer a renamed file (same inode, same content); fall back to the
+        // content alone, which also finds generations that were compressed
+        let position = candidates
+            .iter()
+            .position(|path| {
+                inode_matches(path) && (checkpoint.fingerprint.is_none() || fingerprint_matches(path))
+            })
+            .or_else(|| candidates.iter().position(|path| fingerprint_matches(path)));
+
+        let Some(position) = position else {
+            tracing::warn!(
+                source_id = %source_id,
+                path = %checkpoint.path.display(),
+                "Checkpointed file not found among rotated generations, starting from live file"
+            );
+            return Self::new(source_id, config, parse_error_strategy);
+        };
+
+        tracing::info!(
+            source_id = %source_id,
+            path = %candidates[position].display(),
+            offset = checkpoint.offset,
+            "Restoring source reader from checkpoint"
+        );
+
+        let mut reader =
+            Self::new_with_offset(source_id, config, parse_error_strategy, checkpoint.offset)?;
+        // The checkpointed file and every newer generation, oldest first,
+        // excluding the live file itself
+        reader.rotated_backlog = candidates[..=position]
+            .iter()
+            .rev()
+            .filter(|path| **path != config.path)
+            .cloned()
+            .collect();
+        Ok(reader)
+    }
+
     /// Get the source ID
     pub fn source_id(&self) -> &str {
         &self.source_id
     }
 
     /// Open the file and seek to the appropriate position
+    ///
+    /// Gzip and zstd files are streamed through a decompressor; offsets for
+    /// them refer to the uncompressed stream. Stdin and command sources start
+    /// reading their pipe instead.
     pub fn open(&mut self) -> Result<(), ReaderError> {
-        let file = File::open(&self.path)?;
+        match self.source_type {
+            SourceType::Stdin => {
+                self.stream = Some(LineStream::stdin());
+                return Ok(());
+            }
+            SourceType::Command => {
+                self.stream = Some(LineStream::command(
+                    self.source_id.clone(),
+                    self.command.clone(),
+                    self.restart.clone(),
+                ));
+                return Ok(());
+            }
+            SourceType::Syslog => {
+                let config = self.syslog.as_ref().ok_or_else(|| {
+                    ReaderError::ParseError("syslog source has no listener config".to_string())
+                })?;
+                self.listener = Some(SyslogListener::bind(self.source_id.clone(), config)?);
+                return Ok(());
+            }
+            SourceType::Http => {
+                if self.ingest.is_none() {
+                    return Err(ReaderError::ParseError(format!(
+                        "http source '{}' is not attached to an ingest endpoint",
+                        self.source_id
+                    )));
+                }
+                return Ok(());
+            }
+            SourceType::File | SourceType::Glob | SourceType::Directory => {}
+        }
+
+        let path = self.current_path().to_path_buf();
+        let mut file = File::open(&path)?;
         let metadata = file.metadata()?;
         self.file_inode = Some(get_inode(&metadata));
+        self.compression = Compression::detect(&path, &mut file)?;
 
-        let mut buf_reader = BufReader::new(file);
+        let buf_reader = if self.compression.is_compressed() {
+            let mut buf_reader = BufReader::new(self.compression.decoder(file)?);
 
-        // Seek based on read config
-        match self.read_config.start {
-            ReadStart::Beginning => {
-                buf_reader.seek(SeekFrom::Start(0))?;
-                self.current_offset = 0;
-            }
-            ReadStart::End => {
-                let end = buf_reader.seek(SeekFrom::End(0))?;
-                self.current_offset = end;
+            // Compressed streams can't seek, so skip forward by decompressing
+            match self.read_config.start {
+                ReadStart::Beginning => {
+                    self.current_offset = 0;
+                }
+                ReadStart::End => {
+                    self.current_offset = skip_bytes(&mut buf_reader, u64::MAX)?;
+                }
+                ReadStart::StoredOffset => {
+                    let skipped = skip_bytes(&mut buf_reader, self.current_offset)?;
+                    if skipped < self.current_offset {
+                        tracing::warn!(
+                            source_id = %self.source_id,
+                            offset = self.current_offset,
+                            length = skipped,
+                            "Stored offset is past the end of the compressed file"
+                        );
+                        self.current_offset = skipped;
+                    }
+                }
             }
-            ReadStart::StoredOffset => {
-                buf_reader.seek(SeekFrom::Start(self.current_offset))?;
+
+            buf_reader
+        } else {
+            // Seek based on read config
+            match self.read_config.start {
+                ReadStart::Beginning => {
+                    file.seek(SeekFrom::Start(0))?;
+                    self.current_offset = 0;
+                }
+                ReadStart::End => {
+                    let end = file.seek(SeekFrom::End(0))?;
+                    self.current_offset = end;
+                }
+                ReadStart::StoredOffset => {
+                    file.seek(SeekFrom::Start(self.current_offset))?;
+                }
             }
-        }
+
+            BufReader::new(self.compression.decoder(file)?)
+        };
 
         self.file = Some(buf_reader);
         self.eof_reached = false;
 
+        // Capture the part of the file head that was skipped; the rest is
+        // captured as lines are read
+        self.head = read_head(&path, self.current_offset.min(FINGERPRINT_LEN))?;
+
         // Update shared state with the file inode so checkpoints capture it
         // even if no records are emitted (e.g., when resuming at EOF)
         self.update_shared_state();
@@ -143,58 +511,140 @@ impl SourceReader {
 
     /// Read the next log record
     pub async fn next_record(&mut self) -> Result<Option<LogRecord>, ReaderError> {
+        match self.source_type {
+            SourceType::Syslog => return self.next_syslog_record().await,
+            SourceType::Http => return self.next_ingest_record().await,
+            _ => {}
+        }
+
         loop {
             // Ensure file is open
-            if self.file.is_none() {
+            if self.file.is_none() && self.stream.is_none() {
                 self.open()?;
             }
 
             // Try to read next line
             let mut line = String::new();
-            let bytes_read = {
-                let file = self.file.as_mut().unwrap();
-                file.read_line(&mut line)?
+            let bytes_read = match self.stream.as_mut() {
+                Some(stream) => match stream.try_next_line() {
+                    Some(next) => {
+                        line = next?;
+                        line.len()
+                    }
+                    None => 0,
+                },
+                None => {
+                    let file = self.file.as_mut().unwrap();
+                    file.read_line(&mut line)?
+                }
             };
 
             // Handle EOF
             if bytes_read == 0 {
+                // Give a pipe a moment to deliver the rest of a multiline record
+                // before emitting the buffered line
+                if let Some(stream) = self.stream.as_mut() {
+                    if self.buffered_line.is_some()
+                        && !stream.is_closed()
+                        && tokio::time::timeout(Duration::from_millis(100), stream.wait())
+                            .await
+                            .is_ok()
+                    {
+                        continue;
+                    }
+                }
+
                 self.eof_reached = true;
 
-                // If we have a buffered line, emit it
-                if let Some(buffered) = self.buffered_line.take() {
-                    let record = LogRecord {
-                        id: Uuid::new_v4(),
-                        timestamp: buffered.timestamp,
-                        source_id: self.source_id.clone(),
-                        raw_text: buffered.text,
-                        file_offset: buffered.start_offset,
-                    };
-                    self.last_watermark = Some(record.timestamp);
-                    // Update checkpoint offset to current position (EOF reached)
-                    self.last_emitted_offset = self.current_offset;
-                    self.update_shared_state();
-                    return Ok(Some(record));
+                // The input has ended, so a partial container message will
+                // never be completed
+                let input_ended = match self.stream.as_ref() {
+                    Some(stream) => stream.is_closed(),
+                    None => !self.read_config.follow && self.rotated_backlog.is_empty(),
+                };
+                if input_ended {
+                    if let Some(next) = self.flush_container_partial() {
+                        let start_offset = next.start_offset;
+                        if let Some(buffered) = self.buffered_line.replace(next) {
+                            if let Some(record) = self.finish_record(buffered, start_offset)? {
+                                return Ok(Some(record));
+                            }
+                        }
+                        continue;
+                    }
+                }
+
+                // If we have a complete buffered line, emit it. A finished
+                // rotated generation gets no more lines either.
+                if self.buffered_record_complete(input_ended || !self.rotated_backlog.is_empty()) {
+                    if let Some(buffered) = self.buffered_line.take() {
+                        // Update checkpoint offset to current position (EOF reached)
+                        let offset = self.current_offset;
+                        if let Some(record) = self.finish_record(buffered, offset)? {
+                            return Ok(Some(record));
+                        }
+                        continue;
+                    }
+                }
+
+                // Pipes are read until they close, regardless of `follow`
+                let flush_delay = self.flush_delay();
+                if let Some(stream) = self.stream.as_mut() {
+                    if stream.is_closed() {
+                        return Ok(None);
+                    }
+                    match flush_delay {
+                        Some(delay) => {
+                            let _ = tokio::time::timeout(delay, stream.wait()).await;
+                        }
+                        None => stream.wait().await,
+                    }
+                    continue;
+                }
+
+                // A rotated generation is complete; move on to the next newer file
+                if !self.rotated_backlog.is_empty() {
+                    self.rotated_backlog.pop_front();
+                    self.advance_to_next_file();
+                    continue;
                 }
 
                 // Check if we should follow
                 if self.read_config.follow {
+                    // Discovered files are not reopened; the replacement file is
+                    // picked up by discovery as a new reader.
+                    if self.finish_on_rotation && self.check_file_replaced()? {
+                        // A held multiline record gets no more lines
+                        if let Some(buffered) = self.buffered_line.take() {
+                            let offset = self.current_offset;
+                            if let Some(record) = self.finish_record(buffered, offset)? {
+                                return Ok(Some(record));
+                            }
+                        }
+                        return Ok(None);
+                    }
+
                     // Check for file rotation
                     if self.check_file_rotation()? {
-                        // File was rotated, reopen from beginning
-                        self.file = None;
-                        self.current_offset = 0;
-                        self.eof_reached = false;
+                        if !self.draining_rotated {
+                            // Read anything written to the old file before it was rotated
+                            self.draining_rotated = true;
+                            continue;
+                        }
+
+                        // Old file fully drained, reopen from beginning
+                        self.draining_rotated = false;
+                        self.advance_to_next_file();
                         continue;
                     }
 
                     // Wait briefly for new content
                     sleep(Duration::from_millis(100)).await;
 
-                    // Check if file has grown
-                    if let Some(file) = &mut self.file {
-                        let current_pos = file.stream_position()?;
-                        let file_len = file.get_ref().metadata()?.len();
-                        if file_len > current_pos {
+                    // Check if file has grown (compressed files are read as complete archives)
+                    if !self.compression.is_compressed() {
+                        let file_len = std::fs::metadata(&self.path).map_or(0, |m| m.len());
+                        if file_len > self.current_offset {
                             // File has grown, continue reading
                             self.eof_reached = false;
                             continue;
@@ -209,111 +659,342 @@ impl SourceReader {
             }
 
             // Update offset
-            let line_start_offset = self.current_offset;
+            let mut line_start_offset = self.current_offset;
             self.current_offset += bytes_read as u64;
 
+            if line_start_offset == self.head.len() as u64 && self.head.len() < FINGERPRINT_LEN as usize {
+                let take = (FINGERPRINT_LEN as usize - self.head.len()).min(line.len());
+                self.head.extend_from_slice(&line.as_bytes()[..take]);
+            }
+
             // Remove trailing newline
-            let line = line.trim_end_matches(&['\n', '\r'][..]).to_string();
+            let mut line = line.trim_end_matches(&['\n', '\r'][..]).to_string();
+
+            // Unwrap container runtime lines, waiting for the rest of a
+            // partial message
+            let mut runtime_timestamp = None;
+            let mut fields = BTreeMap::new();
+            if let Some(parser) = self.container.as_mut() {
+                match parser.push(&line, line_start_offset) {
+                    Ok(Some(entry)) => {
+                        line_start_offset = entry.start_offset;
+                        runtime_timestamp = Some(entry.timestamp);
+                        fields = self.container_fields(&entry);
+                        line = entry.message;
+                    }
+                    Ok(None) => continue,
+                    Err(e) => match self.parse_error_strategy {
+                        ParseErrorStrategy::Drop => continue,
+                        ParseErrorStrategy::Panic => {
+                            return Err(ReaderError::ParseError(e.to_string()));
+                        }
+                    },
+                }
+            }
 
-            // Try to extract timestamp
-            match self.timestamp_extractor.extract(&line) {
-                Ok(Some(timestamp)) => {
-                    // This is a new log line with timestamp
-                    // If we have a buffered line, emit it first
-                    if let Some(buffered) = self.buffered_line.take() {
-                        let record = LogRecord {
-                            id: Uuid::new_v4(),
-                            timestamp: buffered.timestamp,
-                            source_id: self.source_id.clone(),
-                            raw_text: buffered.text,
-                            file_offset: buffered.start_offset,
-                        };
-                        self.last_watermark = Some(record.timestamp);
-
-                        // Update checkpoint offset to point after this emitted record
-                        self.last_emitted_offset = line_start_offset;
-                        self.update_shared_state();
-
-                        // Buffer the new line
-                        self.buffered_line = Some(BufferedLine {
-                            text: line,
-                            timestamp,
-                            start_offset: line_start_offset,
-                        });
+            // Records are told apart by the multiline pattern when there is
+            // one, and otherwise by which lines have timestamps
+            let starts_record = self
+                .multiline
+                .as_ref()
+                .and_then(|multiline| multiline.starts_record(&line));
+            let timestamp = match starts_record {
+                // Continuation lines are only searched for the timestamp of
+                // a record that has none yet
+                Some(false)
+                    if self
+                        .buffered_line
+                        .as_ref()
+                        .is_none_or(|buffered| buffered.timestamp.is_some()) =>
+                {
+                    Ok(None)
+                }
+                _ => self.line_timestamp(&line, runtime_timestamp),
+            };
+            let timestamp = match (timestamp, self.parse_error_strategy) {
+                (Ok(timestamp), _) => timestamp,
+                (Err(e), ParseErrorStrategy::Panic) => return Err(ReaderError::Timestamp(e)),
+                // The pattern still places the line in a record, which is
+                // dropped if none of its lines has a timestamp
+                (Err(_), ParseErrorStrategy::Drop) if starts_record.is_some() => None,
+                (Err(_), ParseErrorStrategy::Drop) => continue,
+            };
 
+            if starts_record.unwrap_or(timestamp.is_some()) {
+                // This is a new record; emit the buffered one first, with the
+                // checkpoint offset pointing after it
+                let next = BufferedLine::new(line, timestamp, line_start_offset, fields);
+                if let Some(buffered) = self.buffered_line.replace(next) {
+                    if let Some(record) = self.finish_record(buffered, line_start_offset)? {
                         return Ok(Some(record));
-                    } else {
-                        // No buffered line, just buffer this one
-                        self.buffered_line = Some(BufferedLine {
-                            text: line,
-                            timestamp,
-                            start_offset: line_start_offset,
-                        });
                     }
                 }
-                Ok(None) => {
-                    // No timestamp found - check if this looks like a continuation line
-                    // Continuation lines typically start with whitespace
-                    let is_continuation = line.starts_with(char::is_whitespace);
-
-                    if is_continuation {
-                        if let Some(buffered) = &mut self.buffered_line {
-                            // Append to buffered line
-                            buffered.text.push('\n');
-                            buffered.text.push_str(&line);
-                        } else {
-                            // No buffered line to append to
-                            // This is a malformed log (continuation without initial line)
-                            match self.parse_error_strategy {
-                                ParseErrorStrategy::Drop => {
-                                    // Skip this line
-                                    continue;
-                                }
-                                ParseErrorStrategy::Panic => {
-                                    return Err(ReaderError::ParseError(format!(
-                                        "continuation line without initial timestamp: {}",
-                                        line
-                                    )));
-                                }
-                            }
-                        }
-                    } else {
-                        // Not a continuation line and no timestamp - parse error
-                        match self.parse_error_strategy {
-                            ParseErrorStrategy::Drop => {
-                                // Skip this line
-                                continue;
-                            }
-                            ParseErrorStrategy::Panic => {
-                                return Err(ReaderError::ParseError(format!(
-                                    "line without timestamp: {}",
-                                    line
-                                )));
-                            }
-                        }
+                continue;
+            }
+
+            // Without a multiline pattern, continuation lines are those that
+            // start with whitespace
+            if starts_record.is_none() && !line.starts_with(char::is_whitespace) {
+                // Not a continuation line and no timestamp - parse error
+                match self.parse_error_strategy {
+                    ParseErrorStrategy::Drop => continue,
+                    ParseErrorStrategy::Panic => {
+                        return Err(ReaderError::ParseError(format!(
+                            "line without timestamp: {}",
+                            line
+                        )));
                     }
                 }
-                Err(e) => {
-                    // Timestamp extraction failed
-                    match self.parse_error_strategy {
-                        ParseErrorStrategy::Drop => {
-                            // Skip this line
-                            continue;
-                        }
-                        ParseErrorStrategy::Panic => {
-                            return Err(ReaderError::Timestamp(e));
-                        }
+            }
+
+            let Some(buffered) = self.buffered_line.as_mut() else {
+                // No buffered line to append to
+                // This is a malformed log (continuation without initial line)
+                match self.parse_error_strategy {
+                    ParseErrorStrategy::Drop => continue,
+                    ParseErrorStrategy::Panic => {
+                        return Err(ReaderError::ParseError(format!(
+                            "continuation line without initial timestamp: {}",
+                            line
+                        )));
                     }
                 }
+            };
+
+            let fits = self.multiline.as_ref().is_none_or(|multiline| {
+                multiline.fits(buffered.lines, buffered.text.len(), line.len())
+            });
+            if fits {
+                buffered.push_line(&line);
+                buffered.timestamp = buffered.timestamp.or(timestamp);
+                continue;
+            }
+
+            // The record is full; the rest of it continues as a new record
+            // with the same timestamp
+            let next = BufferedLine::new(
+                line,
+                buffered.timestamp.or(timestamp),
+                line_start_offset,
+                fields,
+            );
+            if let Some(buffered) = self.buffered_line.replace(next) {
+                if let Some(record) = self.finish_record(buffered, line_start_offset)? {
+                    return Ok(Some(record));
+                }
             }
         }
     }
 
+    /// The timestamp of a line, with the clock offset applied. Container
+    /// lines without an extractor have the runtime's timestamp.
+    fn line_timestamp(
+        &mut self,
+        line: &str,
+        runtime_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Option<DateTime<Utc>>, TimestampError> {
+        let extracted = match self.timestamp_extractor.as_mut() {
+            Some(extractor) => extractor.extract(line)?,
+            None => runtime_timestamp,
+        };
+        Ok(extracted.map(|ts| ts + self.clock_offset))
+    }
+
+    /// Turn a finished buffered record into a LogRecord, advancing the
+    /// watermark and the checkpoint offset to `next_offset`. Returns None if
+    /// the record never found a timestamp or is filtered out.
+    fn finish_record(
+        &mut self,
+        buffered: BufferedLine,
+        next_offset: u64,
+    ) -> Result<Option<LogRecord>, ReaderError> {
+        let Some(timestamp) = buffered.timestamp else {
+            return match self.parse_error_strategy {
+                ParseErrorStrategy::Drop => Ok(None),
+                ParseErrorStrategy::Panic => Err(ReaderError::ParseError(format!(
+                    "record without timestamp: {}",
+                    buffered.text
+                ))),
+            };
+        };
+
+        let mut record = LogRecord {
+            id: Uuid::new_v4(),
+            timestamp,
+            source_id: self.source_id.clone(),
+            raw_text: buffered.text,
+            file_offset: buffered.start_offset,
+            fields: buffered.fields,
+            labels: self.labels.clone(),
+            late: false,
+        };
+        // A dropped record is consumed all the same
+        let keep = self.filter_record(&mut record);
+        self.last_watermark = Some(record.timestamp);
+        self.last_emitted_offset = next_offset;
+        self.update_shared_state();
+        Ok(keep.then_some(record))
+    }
+
+    /// Apply the source's `drop_if` and `redact` rules. Returns false if the
+    /// record is dropped.
+    fn filter_record(&mut self, record: &mut LogRecord) -> bool {
+        let Some(filter) = self.filter.as_mut() else {
+            return true;
+        };
+        if filter.drops(&record.raw_text) {
+            return false;
+        }
+        filter.redact(&mut record.raw_text, &mut record.fields);
+        true
+    }
+
+    /// Whether the buffered record is complete at EOF. Without multiline
+    /// rules it is; with them, more of a followed input's record may still
+    /// be written until `flush_after` has passed.
+    fn buffered_record_complete(&self, input_ended: bool) -> bool {
+        match (&self.multiline, &self.buffered_line) {
+            (Some(multiline), Some(buffered)) if !input_ended => multiline
+                .flush_after()
+                .is_some_and(|after| buffered.updated_at.elapsed() >= after),
+            _ => true,
+        }
+    }
+
+    /// Time left before a held multiline record is flushed, if it ever is
+    fn flush_delay(&self) -> Option<Duration> {
+        let after = self.multiline.as_ref()?.flush_after()?;
+        let buffered = self.buffered_line.as_ref()?;
+        Some(after.saturating_sub(buffered.updated_at.elapsed()))
+    }
+
     /// Get the watermark (timestamp of last emitted record)
     pub fn watermark(&self) -> Option<DateTime<Utc>> {
+        if self.source_type.is_listener() {
+            // A listener has no position to order by: a record arriving now is
+            // assumed to be at most `lateness` behind its own timestamp
+            let lateness = chrono::Duration::from_std(self.lateness).unwrap_or(chrono::Duration::MAX);
+            return Some(Utc::now().checked_sub_signed(lateness).unwrap_or(DateTime::<Utc>::MIN_UTC));
+        }
         self.last_watermark
     }
 
+    /// How often the watermark should be re-read while no records arrive.
+    ///
+    /// Only listener sources advance their watermark without emitting records.
+    pub fn idle_watermark_interval(&self) -> Option<Duration> {
+        self.source_type.is_listener().then(|| Duration::from_millis(100))
+    }
+
+    /// The source's own `read.idle_timeout`, if it overrides the sequencer's
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.read_config.idle_timeout
+    }
+
+    /// Receive the next syslog message and turn it into a record
+    async fn next_syslog_record(&mut self) -> Result<Option<LogRecord>, ReaderError> {
+        if self.listener.is_none() {
+            self.open()?;
+        }
+
+        loop {
+            let Some(message) = self.listener.as_mut().unwrap().recv().await else {
+                return Ok(None);
+            };
+
+            let offset = self.current_offset;
+            self.current_offset += message.text.len() as u64;
+
+            let Some(parsed) = syslog::parse_message(&message.text, message.received_at) else {
+                match self.parse_error_strategy {
+                    ParseErrorStrategy::Drop => continue,
+                    ParseErrorStrategy::Panic => {
+                        return Err(ReaderError::ParseError(format!(
+                            "invalid syslog message: {}",
+                            message.text
+                        )));
+                    }
+                }
+            };
+
+            let mut record = LogRecord {
+                id: Uuid::new_v4(),
+                timestamp: parsed.timestamp.unwrap_or(message.received_at) + self.clock_offset,
+                source_id: self.source_id.clone(),
+                raw_text: message.text,
+                file_offset: offset,
+                fields: parsed.fields,
+                labels: self.labels.clone(),
+                late: false,
+            };
+            let keep = self.filter_record(&mut record);
+            self.last_watermark = Some(record.timestamp);
+            self.last_emitted_offset = self.current_offset;
+            self.update_shared_state();
+            if keep {
+                return Ok(Some(record));
+            }
+        }
+    }
+
+    /// Receive the next record pushed to this source's ingest endpoint
+    async fn next_ingest_record(&mut self) -> Result<Option<LogRecord>, ReaderError> {
+        if self.ingest.is_none() {
+            self.open()?;
+        }
+
+        loop {
+            let Some(pushed) = self.ingest.as_mut().unwrap().recv().await else {
+                return Ok(None);
+            };
+
+            let offset = self.current_offset;
+            self.current_offset += pushed.text.len() as u64;
+
+            let mut record = LogRecord {
+                id: Uuid::new_v4(),
+                timestamp: pushed.timestamp + self.clock_offset,
+                source_id: self.source_id.clone(),
+                raw_text: pushed.text,
+                file_offset: offset,
+                fields: pushed.fields,
+                labels: self.labels.clone(),
+                late: false,
+            };
+            let keep = self.filter_record(&mut record);
+            self.last_watermark = Some(record.timestamp);
+            self.last_emitted_offset = self.current_offset;
+            self.update_shared_state();
+            if keep {
+                return Ok(Some(record));
+            }
+        }
+    }
+
+    /// Take a container message whose final fragment never arrived, ready
+    /// to be buffered as a line
+    fn flush_container_partial(&mut self) -> Option<BufferedLine> {
+        let entry = self.container.as_mut()?.flush()?;
+        let timestamp = self
+            .timestamp_extractor
+            .as_mut()
+            .and_then(|extractor| extractor.extract(&entry.message).ok().flatten())
+            .unwrap_or(entry.timestamp)
+            + self.clock_offset;
+        let fields = self.container_fields(&entry);
+        Some(BufferedLine::new(entry.message, Some(timestamp), entry.start_offset, fields))
+    }
+
+    /// Fields for a container message: those from the file path and the
+    /// stream it was written to
+    fn container_fields(&self, entry: &ContainerEntry) -> BTreeMap<String, String> {
+        let mut fields = self.path_fields.clone();
+        if !entry.stream.is_empty() {
+            fields.insert("stream".to_string(), entry.stream.clone());
+        }
+        fields
+    }
+
     /// Get the checkpoint offset (byte position to resume from after last emitted record)
     pub fn checkpoint_offset(&self) -> u64 {
         self.last_emitted_offset
@@ -329,15 +1010,53 @@ impl SourceReader {
         &self.path
     }
 
+    /// Path of the file currently being read: the oldest unread rotated
+    /// generation, or the live file
+    fn current_path(&self) -> &Path {
+        self.rotated_backlog.front().unwrap_or(&self.path)
+    }
+
+    /// Close the current file so the next read opens the next one from the start
+    fn advance_to_next_file(&mut self) {
+        self.file = None;
+        self.current_offset = 0;
+        self.last_emitted_offset = 0;
+        self.head.clear();
+        self.eof_reached = false;
+        self.read_config.start = ReadStart::Beginning;
+    }
+
+    /// Fingerprint of the part of the current file that has been emitted
+    fn checkpoint_fingerprint(&self) -> Option<u64> {
+        let len = self.last_emitted_offset.min(FINGERPRINT_LEN) as usize;
+        self.head.get(..len).map(fingerprint_bytes)
+    }
+
+    /// Stop at EOF once the file has been rotated or removed, instead of
+    /// reopening the path. Used for files found by glob/directory discovery.
+    pub fn finish_on_rotation(mut self) -> Self {
+        self.finish_on_rotation = true;
+        self
+    }
+
+    /// Feed an http source from an ingest buffer (see `IngestHub::register`)
+    pub fn with_ingest(mut self, receiver: IngestReceiver) -> Self {
+        self.ingest = Some(receiver);
+        self
+    }
+
     /// Attach shared state for checkpointing and return both the reader and the state
     pub fn with_shared_state(mut self) -> (Self, SharedSourceState) {
         use crate::storage::checkpoint::SourceCheckpointState;
         use std::sync::{Arc, Mutex};
 
         let state = Arc::new(Mutex::new(SourceCheckpointState {
+            path: self.current_path().to_path_buf(),
             offset: self.last_emitted_offset,
             inode: self.file_inode.unwrap_or(0),
             last_timestamp: self.last_watermark,
+            fingerprint: self.checkpoint_fingerprint(),
+            stats: Default::default(),
         }));
         self.shared_state = Some(state.clone());
         (self, state)
@@ -347,9 +1066,19 @@ impl SourceReader {
     fn update_shared_state(&self) {
         if let Some(ref state) = self.shared_state {
             if let Ok(mut guard) = state.lock() {
+                if guard.path != self.current_path() {
+                    guard.path = self.current_path().to_path_buf();
+                }
                 guard.offset = self.last_emitted_offset;
                 guard.inode = self.file_inode.unwrap_or(0);
                 guard.last_timestamp = self.last_watermark;
+                guard.fingerprint = self.checkpoint_fingerprint();
+                if let Some(extractor) = &self.timestamp_extractor {
+                    extractor.write_stats(&mut guard.stats.timestamps);
+                }
+                if let Some(filter) = &self.filter {
+                    filter.write_stats(&mut guard.stats.filter);
+                }
             }
         }
     }
@@ -373,6 +1102,15 @@ impl SourceReader {
         }
     }
 
+    /// Check if the file has been rotated or removed since it was opened
+    fn check_file_replaced(&self) -> Result<bool, ReaderError> {
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) => Ok(self.file_inode.is_some_and(|inode| inode != get_inode(&metadata))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Convert into a stream
     pub fn into_stream(self) -> SourceReaderStream {
         SourceReaderStream { reader: self }
@@ -403,13 +1141,13 @@ impl futures::Stream for SourceReaderStream {
 
 // Platform-specific inode retrieval
 #[cfg(unix)]
-fn get_inode(metadata: &Metadata) -> u64 {
+pub(crate) fn get_inode(metadata: &Metadata) -> u64 {
     use std::os::unix::fs::MetadataExt;
     metadata.ino()
 }
 
 #[cfg(not(unix))]
-fn get_inode(metadata: &Metadata) -> u64 {
+pub(crate) fn get_inode(metadata: &Metadata) -> u64 {
     // On non-Unix platforms, use file size and modified time as a proxy
     // This is not perfect but better than nothing
     use std::hash::{Hash, Hasher};
@@ -424,7 +1162,10 @@ fn get_inode(metadata: &Metadata) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::types::{SourceConfig, SourceType, TimestampConfig};
+    use crate::config::types::{
+        MultilineConfig, RedactRule, SourceConfig, SourceFormat, SourceType, TimestampConfig,
+        TimestampPattern,
+    };
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -432,14 +1173,31 @@ mod tests {
         SourceConfig {
             source_type: SourceType::File,
             path,
+            command: Vec::new(),
+            restart: None,
+            syslog: None,
+            http: None,
+            format: SourceFormat::Text,
             timestamp: TimestampConfig {
                 pattern: pattern.to_string(),
+                pointer: None,
                 format: format.to_string(),
+                fallbacks: Vec::new(),
+                timezone: None,
+                clock_offset: None,
+                assume_year: None,
             },
             read: ReadConfig {
                 start: ReadStart::Beginning,
                 follow: false,
+                discover_interval: None,
+                include_rotated: false,
+                multiline: None,
+                idle_timeout: None,
             },
+            labels: Default::default(),
+            drop_if: Vec::new(),
+            redact: Vec::new(),
         }
     }
 
@@ -476,6 +1234,165 @@ mod tests {
         assert!(record3.is_none());
     }
 
+    #[tokio::test]
+    async fn test_clock_offset_and_year() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "Dec 31 23:59:59 host app: old year").unwrap();
+        writeln!(temp_file, "Jan  1 00:00:01 host app: new year").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\w{3} [ \d]\d \d{2}:\d{2}:\d{2})",
+            "%b %e %H:%M:%S",
+        );
+        config.timestamp.assume_year = Some(2024);
+        config.timestamp.clock_offset = Some(chrono::Duration::seconds(-2));
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.timestamp, "2024-12-31T23:59:57Z".parse::<DateTime<Utc>>().unwrap());
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.timestamp, "2024-12-31T23:59:59Z".parse::<DateTime<Utc>>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_fallbacks_and_stats() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z app started").unwrap();
+        writeln!(temp_file, "[04/Dec/2025:10:00:01 +0000] GET /").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:02Z app stopped").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\S+Z) ",
+            "iso8601",
+        );
+        config.timestamp.fallbacks = vec![TimestampPattern {
+            pattern: r"^\[(?P<ts>[^\]]+)\]".to_string(),
+            format: "%d/%b/%Y:%H:%M:%S %z".to_string(),
+        }];
+
+        let (mut reader, state) =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic)
+                .unwrap()
+                .with_shared_state();
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.timestamp, "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.timestamp, "2025-12-04T10:00:01Z".parse::<DateTime<Utc>>().unwrap());
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.timestamp, "2025-12-04T10:00:02Z".parse::<DateTime<Utc>>().unwrap());
+        assert!(reader.next_record().await.unwrap().is_none());
+
+        let stats = state.lock().unwrap().stats.timestamps.clone();
+        let matches: Vec<u64> = stats.candidates.iter().map(|c| c.matches).collect();
+        assert_eq!(matches, vec![2, 1]);
+        assert_eq!(stats.unmatched, 0);
+    }
+
+    #[tokio::test]
+    async fn test_json_lines() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, r#"{{"time":"2025-12-04T10:00:00Z","msg":"first"}}"#).unwrap();
+        writeln!(temp_file, "not json").unwrap();
+        writeln!(temp_file, r#"{{"time":"2025-12-04T10:00:01Z","msg":"second"}}"#).unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(temp_file.path().to_path_buf(), "", "iso8601");
+        config.format = SourceFormat::Json;
+        config.timestamp.pointer = Some("/time".to_string());
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Drop).unwrap();
+
+        // Lines are kept as-is; the non-JSON line is dropped
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.raw_text, r#"{"time":"2025-12-04T10:00:00Z","msg":"first"}"#);
+        assert_eq!(record1.timestamp, "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, r#"{"time":"2025-12-04T10:00:01Z","msg":"second"}"#);
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cri_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let pod_dir = dir.path().join("pods/prod_api-7d9f_0b1c/server");
+        std::fs::create_dir_all(&pod_dir).unwrap();
+        let path = pod_dir.join("0.log");
+        std::fs::write(
+            &path,
+            "2025-12-04T10:00:00.5Z stdout P request \n\
+             2025-12-04T10:00:00.6Z stderr F warning\n\
+             2025-12-04T10:00:00.7Z stdout F started\n\
+             2025-12-04T10:00:01Z stdout P cut off\n",
+        )
+        .unwrap();
+
+        let mut config = create_test_config(path, "", "iso8601");
+        config.format = SourceFormat::Cri;
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.raw_text, "warning");
+        assert_eq!(record1.fields["stream"], "stderr");
+
+        // Fragments are joined and keep the first fragment's timestamp and offset
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "request started");
+        assert_eq!(record2.timestamp, "2025-12-04T10:00:00.5Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(record2.file_offset, 0);
+        assert_eq!(record2.fields["namespace"], "prod");
+        assert_eq!(record2.fields["pod"], "api-7d9f");
+        assert_eq!(record2.fields["container"], "server");
+        assert_eq!(record2.fields["stream"], "stdout");
+
+        // A trailing partial is emitted once the file is finished
+        let record3 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record3.raw_text, "cut off");
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_docker_multiline() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for (time, log) in [
+            ("2025-12-04T10:00:00.1Z", r"2025-12-04 10:00:00 ERROR failed\n"),
+            ("2025-12-04T10:00:00.2Z", r"  at handler()\n"),
+            ("2025-12-04T10:00:01.1Z", r"2025-12-04 10:00:01 INFO retried\n"),
+        ] {
+            writeln!(temp_file, r#"{{"log":"{}","stream":"stdout","time":"{}"}}"#, log, time).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        // With a pattern, messages are grouped by the timestamp in the text
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})",
+            "%Y-%m-%d %H:%M:%S",
+        );
+        config.format = SourceFormat::Docker;
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.raw_text, "2025-12-04 10:00:00 ERROR failed\n  at handler()");
+        assert_eq!(record1.timestamp, "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "2025-12-04 10:00:01 INFO retried");
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_multiline_log() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -512,6 +1429,153 @@ mod tests {
         assert!(record3.is_none());
     }
 
+    #[tokio::test]
+    async fn test_multiline_start_pattern() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        // Records begin with a marker; every line carries a timestamp, and
+        // the one of the second record only follows the marker
+        writeln!(temp_file, "=== request 1").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z received").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:02Z answered").unwrap();
+        writeln!(temp_file, "=== request 2 at 2025-12-04T10:00:03Z").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:04Z received").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.read.multiline = Some(MultilineConfig {
+            start: Some("^=== ".to_string()),
+            ..Default::default()
+        });
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(
+            record1.raw_text,
+            "=== request 1\n2025-12-04T10:00:00Z received\n2025-12-04T10:00:02Z answered"
+        );
+        assert_eq!(record1.timestamp, "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.timestamp, "2025-12-04T10:00:03Z".parse::<DateTime<Utc>>().unwrap());
+        assert!(record2.raw_text.ends_with("10:00:04Z received"));
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multiline_continue_pattern_and_limits() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z error").unwrap();
+        writeln!(temp_file, "at frame 1").unwrap();
+        writeln!(temp_file, "at frame 2").unwrap();
+        writeln!(temp_file, "at frame 3").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:01Z done").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.read.multiline = Some(MultilineConfig {
+            continue_pattern: Some("^at ".to_string()),
+            max_lines: Some(3),
+            ..Default::default()
+        });
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.raw_text, "2025-12-04T10:00:00Z error\nat frame 1\nat frame 2");
+
+        // The rest of the record is split off with the same timestamp
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "at frame 3");
+        assert_eq!(record2.timestamp, record1.timestamp);
+
+        let record3 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record3.raw_text, "2025-12-04T10:00:01Z done");
+    }
+
+    #[tokio::test]
+    async fn test_multiline_flush_after() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z first").unwrap();
+        writeln!(temp_file, "  detail").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.read.follow = true;
+        config.read.multiline = Some(MultilineConfig {
+            flush_after: Some(Duration::from_millis(200)),
+            ..Default::default()
+        });
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        // The trailing record of a followed file is held for more lines...
+        let held = tokio::time::timeout(Duration::from_millis(100), reader.next_record()).await;
+        assert!(held.is_err());
+        writeln!(temp_file, "  more detail").unwrap();
+        temp_file.flush().unwrap();
+
+        // ...and emitted once none have arrived for a while
+        let record = tokio::time::timeout(Duration::from_secs(2), reader.next_record())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:00Z first\n  detail\n  more detail");
+    }
+
+    #[tokio::test]
+    async fn test_drop_and_redact() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:00Z GET /health 200").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:01Z signup alice@example.com").unwrap();
+        writeln!(temp_file, "2025-12-04T10:00:02Z GET /health 200").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut config = create_test_config(
+            temp_file.path().to_path_buf(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.drop_if = vec!["GET /health".to_string()];
+        config.redact = vec![RedactRule {
+            pattern: r"[\w.+-]+@[\w-]+\.\w+".to_string(),
+            replace: Some("<email>".to_string()),
+            hash_key_env: None,
+            hash_key_file: None,
+        }];
+
+        let (mut reader, state) =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic)
+                .unwrap()
+                .with_shared_state();
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:01Z signup <email>");
+        assert!(reader.next_record().await.unwrap().is_none());
+        // Dropped records still count as read
+        assert_eq!(reader.checkpoint_offset(), temp_file.as_file().metadata().unwrap().len());
+
+        let stats = state.lock().unwrap().stats.filter.clone();
+        assert_eq!(stats.dropped[0].count, 2);
+        assert_eq!(stats.redacted[0].count, 1);
+    }
+
     #[tokio::test]
     async fn test_watermark() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -709,6 +1773,106 @@ mod tests {
         assert!(record.is_none());
     }
 
+    fn write_gzip(path: &std::path::Path, content: &str) {
+        use flate2::write::GzEncoder;
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_gzip_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.log.1.gz");
+        write_gzip(
+            &path,
+            "2025-12-04T10:00:00Z Starting process\n  Stack trace line 1\n2025-12-04T10:00:01Z Process complete\n",
+        );
+
+        let config = create_test_config(
+            path,
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        // Multiline grouping works on the decompressed content
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(
+            record1.raw_text,
+            "2025-12-04T10:00:00Z Starting process\n  Stack trace line 1"
+        );
+
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "2025-12-04T10:00:01Z Process complete");
+        assert_eq!(record2.file_offset, 59);
+
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_file_detected_by_magic() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // No extension: detection falls back to magic bytes
+        let path = dir.path().join("archived");
+        let compressed =
+            zstd::encode_all(&b"2025-12-04T10:00:00Z First\n2025-12-04T10:00:01Z Second\n"[..], 0)
+                .unwrap();
+        std::fs::write(&path, compressed).unwrap();
+
+        let config = create_test_config(
+            path,
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+
+        let mut reader =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record1 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record1.raw_text, "2025-12-04T10:00:00Z First");
+        let record2 = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "2025-12-04T10:00:01Z Second");
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_compressed_from_uncompressed_offset() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.log.gz");
+        write_gzip(
+            &path,
+            "2025-12-04T10:00:00Z First\n2025-12-04T10:00:01Z Second\n",
+        );
+
+        let config = create_test_config(
+            path,
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+
+        let mut reader1 =
+            SourceReader::new("test".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+        reader1.next_record().await.unwrap().unwrap();
+        let checkpoint_offset = reader1.checkpoint_offset();
+        assert_eq!(checkpoint_offset, 27);
+
+        let mut reader2 = SourceReader::new_with_offset(
+            "test".to_string(),
+            &config,
+            ParseErrorStrategy::Panic,
+            checkpoint_offset,
+        )
+        .unwrap();
+
+        let record2 = reader2.next_record().await.unwrap().unwrap();
+        assert_eq!(record2.raw_text, "2025-12-04T10:00:01Z Second");
+        assert_eq!(record2.file_offset, 27);
+        assert!(reader2.next_record().await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_last_line_without_timestamp_emitted_at_eof() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -734,4 +1898,226 @@ mod tests {
         let record2 = reader.next_record().await.unwrap();
         assert!(record2.is_none());
     }
+
+    fn rotated_config(path: PathBuf) -> SourceConfig {
+        let mut config = create_test_config(
+            path,
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.read.include_rotated = true;
+        config
+    }
+
+    /// Read one record and return the checkpoint taken after it
+    async fn checkpoint_after_first_record(config: &SourceConfig) -> SourceCheckpoint {
+        let reader =
+            SourceReader::resume("app".to_string(), config, ParseErrorStrategy::Panic, None)
+                .unwrap();
+        let (mut reader, state) = reader.with_shared_state();
+        reader.next_record().await.unwrap().unwrap();
+
+        let state = state.lock().unwrap();
+        SourceCheckpoint {
+            path: state.path.clone(),
+            offset: state.offset,
+            inode: state.inode,
+            last_timestamp: state.last_timestamp,
+            fingerprint: state.fingerprint,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotation_chain_read_oldest_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_gzip(&dir.path().join("app.log.2.gz"), "2025-12-04T10:00:00Z Oldest\n");
+        std::fs::write(dir.path().join("app.log.1"), "2025-12-04T10:00:01Z Older\n").unwrap();
+        std::fs::write(dir.path().join("app.log"), "2025-12-04T10:00:02Z Live\n").unwrap();
+
+        let config = rotated_config(dir.path().join("app.log"));
+        let mut reader =
+            SourceReader::resume("app".to_string(), &config, ParseErrorStrategy::Panic, None)
+                .unwrap();
+
+        let mut texts = Vec::new();
+        while let Some(record) = reader.next_record().await.unwrap() {
+            assert_eq!(record.source_id, "app");
+            texts.push(record.raw_text);
+        }
+        assert_eq!(
+            texts,
+            vec![
+                "2025-12-04T10:00:00Z Oldest",
+                "2025-12-04T10:00:01Z Older",
+                "2025-12-04T10:00:02Z Live",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation_chain_resumes_in_rotated_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let live = dir.path().join("app.log");
+        std::fs::write(&live, "2025-12-04T10:00:00Z First\n2025-12-04T10:00:01Z Second\n")
+            .unwrap();
+
+        let config = rotated_config(live.clone());
+        let checkpoint = checkpoint_after_first_record(&config).await;
+
+        // Rotate while stopped: app.log -> app.log.1, new app.log
+        std::fs::rename(&live, dir.path().join("app.log.1")).unwrap();
+        std::fs::write(&live, "2025-12-04T10:00:02Z Third\n").unwrap();
+
+        let mut reader = SourceReader::resume(
+            "app".to_string(),
+            &config,
+            ParseErrorStrategy::Panic,
+            Some(&checkpoint),
+        )
+        .unwrap();
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:01Z Second");
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:02Z Third");
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_chain_finds_compressed_generation_by_fingerprint() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let live = dir.path().join("app.log");
+        let content = "2025-12-04T10:00:00Z First\n2025-12-04T10:00:01Z Second\n";
+        std::fs::write(&live, content).unwrap();
+
+        let config = rotated_config(live.clone());
+        let checkpoint = checkpoint_after_first_record(&config).await;
+        assert!(checkpoint.fingerprint.is_some());
+
+        // Rotated and compressed while stopped: the inode no longer matches
+        std::fs::remove_file(&live).unwrap();
+        write_gzip(&dir.path().join("app.log.1.gz"), content);
+        std::fs::write(&live, "2025-12-04T10:00:02Z Third\n").unwrap();
+
+        let mut reader = SourceReader::resume(
+            "app".to_string(),
+            &config,
+            ParseErrorStrategy::Panic,
+            Some(&checkpoint),
+        )
+        .unwrap();
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:01Z Second");
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:02Z Third");
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_source() {
+        let mut config = create_test_config(
+            PathBuf::new(),
+            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)",
+            "iso8601",
+        );
+        config.source_type = SourceType::Command;
+        config.command = vec![
+            "printf".to_string(),
+            "2025-12-04T10:00:00Z Error\n  at main\n2025-12-04T10:00:01Z Done\n".to_string(),
+        ];
+
+        let mut reader =
+            SourceReader::new("cmd".to_string(), &config, ParseErrorStrategy::Panic).unwrap();
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:00Z Error\n  at main");
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "2025-12-04T10:00:01Z Done");
+        assert_eq!(record.file_offset, 37);
+        // The default restart policy only restarts on failure
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_syslog_source() {
+        use crate::config::types::{SyslogConfig, SyslogProtocol};
+
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut config = create_test_config(PathBuf::new(), "", "");
+        config.source_type = SourceType::Syslog;
+        config.timestamp = TimestampConfig::default();
+        config.syslog = Some(SyslogConfig {
+            listen: addr.to_string(),
+            protocol: SyslogProtocol::Udp,
+            lateness: Duration::from_secs(30),
+        });
+
+        let mut reader =
+            SourceReader::new("syslog".to_string(), &config, ParseErrorStrategy::Drop).unwrap();
+        reader.open().unwrap();
+        assert_eq!(reader.idle_watermark_interval(), Some(Duration::from_millis(100)));
+
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(b"not syslog", addr).unwrap();
+        sender
+            .send_to(b"<34>1 2025-12-04T10:00:00Z web01 app 42 - - started", addr)
+            .unwrap();
+
+        // The invalid message is dropped
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record.raw_text, "<34>1 2025-12-04T10:00:00Z web01 app 42 - - started");
+        assert_eq!(record.timestamp, "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(record.fields["hostname"], "web01");
+        assert_eq!(record.fields["app_name"], "app");
+        assert_eq!(record.fields["procid"], "42");
+
+        // The watermark follows arrival time, not the record timestamp
+        let watermark = reader.watermark().unwrap();
+        assert!(watermark > record.timestamp);
+        assert!(watermark <= Utc::now() - chrono::Duration::seconds(30));
+    }
+
+    #[tokio::test]
+    async fn test_http_source() {
+        use crate::config::types::BackpressureStrategy;
+        use crate::source::ingest::{IngestHub, IngestRecord};
+
+        let mut config = create_test_config(PathBuf::new(), "", "");
+        config.source_type = SourceType::Http;
+        config.timestamp = TimestampConfig::default();
+
+        let mut reader =
+            SourceReader::new("push".to_string(), &config, ParseErrorStrategy::Drop).unwrap();
+        assert!(reader.open().is_err());
+
+        let mut hub = IngestHub::new(BackpressureStrategy::Block, 10);
+        let mut reader = reader.with_ingest(hub.register("push"));
+
+        let timestamp = "2025-12-04T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let pushed = IngestRecord {
+            timestamp,
+            text: "started".to_string(),
+            fields: BTreeMap::from([("job".to_string(), "api".to_string())]),
+        };
+        hub.submit(None, vec![pushed.clone(), pushed]).await.unwrap();
+
+        let first = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(first.source_id, "push");
+        assert_eq!(first.timestamp, timestamp);
+        assert_eq!(first.raw_text, "started");
+        assert_eq!(first.fields["job"], "api");
+        assert_eq!(first.file_offset, 0);
+        assert_eq!(reader.next_record().await.unwrap().unwrap().file_offset, 7);
+
+        // Default lateness is 5s
+        assert!(reader.watermark().unwrap() <= Utc::now() - chrono::Duration::seconds(5));
+
+        // The source ends once the ingest endpoint is gone
+        drop(hub);
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,135 @@
+use crate::source::compression::Compression;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Number of leading (uncompressed) bytes used to fingerprint a file.
+///
+/// Fingerprints identify a file by content rather than inode, so a checkpoint
+/// still finds its file after `app.log` is renamed to `app.log.1` or
+/// compressed into `app.log.2.gz`.
+pub const FINGERPRINT_LEN: u64 = 1024;
+
+/// Find the older rotated generations of a log file, oldest first.
+///
+/// Generations are files next to `path` named `<name>.<N>`, optionally
+/// followed by a compression extension (`app.log.1`, `app.log.2.gz`,
+/// `app.log.3.zst`). A higher `N` is an older generation.
+pub fn rotated_generations(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut generations = Vec::new();
+    for entry in entries {
+        let entry_path = entry?.path();
+        let Some(generation) = entry_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| rotation_generation(name, n))
+        else {
+            continue;
+        };
+        if entry_path.is_file() {
+            generations.push((generation, entry_path));
+        }
+    }
+
+    // Oldest (highest generation number) first
+    generations.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(generations.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Get the generation number of `candidate` if it is a rotated copy of `name`
+fn rotation_generation(name: &str, candidate: &str) -> Option<u32> {
+    let suffix = candidate.strip_prefix(name)?.strip_prefix('.')?;
+    let number = match Compression::from_extension(Path::new(suffix)) {
+        Some(_) => Path::new(suffix).file_stem()?.to_str()?,
+        None => suffix,
+    };
+
+    if !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok().filter(|&n| n > 0)
+}
+
+/// Fingerprint the first `len` bytes of a file's (decompressed) content.
+///
+/// Returns `None` if the file holds fewer than `len` bytes.
+pub fn fingerprint(path: &Path, len: u64) -> io::Result<Option<u64>> {
+    let head = read_head(path, len)?;
+    if (head.len() as u64) < len {
+        return Ok(None);
+    }
+    Ok(Some(fingerprint_bytes(&head)))
+}
+
+/// Read up to `len` leading bytes of a file's (decompressed) content
+pub fn read_head(path: &Path, len: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let compression = Compression::detect(path, &mut file)?;
+    let mut head = Vec::new();
+    compression.decoder(file)?.take(len).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Stable fingerprint of a byte slice (persisted in checkpoints)
+pub fn fingerprint_bytes(bytes: &[u8]) -> u64 {
+    let digest = Sha256::digest(bytes);
+    u64::from_be_bytes(digest[..8].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotation_generation() {
+        assert_eq!(rotation_generation("app.log", "app.log.1"), Some(1));
+        assert_eq!(rotation_generation("app.log", "app.log.12.gz"), Some(12));
+        assert_eq!(rotation_generation("app.log", "app.log.3.zst"), Some(3));
+        assert_eq!(rotation_generation("app.log", "app.log"), None);
+        assert_eq!(rotation_generation("app.log", "app.log.bak"), None);
+        assert_eq!(rotation_generation("app.log", "app.log.0"), None);
+        assert_eq!(rotation_generation("app.log", "other.log.1"), None);
+    }
+
+    #[test]
+    fn test_rotated_generations_oldest_first() {
+        let dir = TempDir::new().unwrap();
+        for name in ["app.log", "app.log.1", "app.log.2.gz", "app.log.10.zst", "app.log.old"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let generations = rotated_generations(&dir.path().join("app.log")).unwrap();
+        let names: Vec<_> = generations
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["app.log.10.zst", "app.log.2.gz", "app.log.1"]);
+    }
+
+    #[test]
+    fn test_fingerprint_requires_full_length() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "0123456789").unwrap();
+
+        assert_eq!(fingerprint(&path, 4).unwrap(), Some(fingerprint_bytes(b"0123")));
+        assert_eq!(fingerprint(&path, 20).unwrap(), None);
+    }
+}
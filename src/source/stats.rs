@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+/// Runtime counters for a source, served at `/api/sources/stats`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceStats {
+    pub timestamps: TimestampStats,
+    pub filter: FilterStats,
+}
+
+/// How a source's lines were timestamped
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimestampStats {
+    /// The configured patterns, in the order they are tried
+    pub candidates: Vec<CandidateStats>,
+    /// Lines where no pattern found a timestamp
+    pub unmatched: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateStats {
+    pub pattern: String,
+    pub format: String,
+    /// For the `auto` format, the layout that was recognized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected: Option<&'static str>,
+    /// Lines timestamped by this pattern
+    pub matches: u64,
+}
+
+/// What a source's `drop_if` and `redact` rules did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilterStats {
+    /// Records dropped by each `drop_if` pattern
+    pub dropped: Vec<RuleStats>,
+    /// Values rewritten by each `redact` rule
+    pub redacted: Vec<RuleStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleStats {
+    pub pattern: String,
+    pub count: u64,
+}
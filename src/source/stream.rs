@@ -0,0 +1,269 @@
+use crate::config::types::{RestartConfig, RestartPolicy};
+use std::io;
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+/// Lines buffered between the background reader task and the source reader
+const LINE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Lines read from a pipe (stdin or a child process's stdout).
+///
+/// The pipe is read by a background task so a source waiting for input never
+/// blocks the runtime. Lines keep their trailing newline, matching
+/// `BufRead::read_line`.
+pub struct LineStream {
+    rx: mpsc::Receiver<io::Result<String>>,
+    pending: Option<io::Result<String>>,
+    closed: bool,
+}
+
+impl LineStream {
+    /// Read lines from noil's standard input until it is closed
+    pub fn stdin() -> Self {
+        let (tx, rx) = mpsc::channel(LINE_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let mut stdin = BufReader::new(tokio::io::stdin());
+            if let Err(e) = forward_lines(&mut stdin, &tx).await {
+                let _ = tx.send(Err(e)).await;
+            }
+        });
+        Self::from_receiver(rx)
+    }
+
+    /// Run `argv` and read lines from its stdout, restarting the process when
+    /// it exits according to `restart`
+    pub fn command(source_id: String, argv: Vec<String>, restart: RestartConfig) -> Self {
+        let (tx, rx) = mpsc::channel(LINE_CHANNEL_CAPACITY);
+        tokio::spawn(run_command(source_id, argv, restart, tx));
+        Self::from_receiver(rx)
+    }
+
+    fn from_receiver(rx: mpsc::Receiver<io::Result<String>>) -> Self {
+        Self {
+            rx,
+            pending: None,
+            closed: false,
+        }
+    }
+
+    /// Take the next line if one is available without waiting
+    pub fn try_next_line(&mut self) -> Option<io::Result<String>> {
+        if let Some(line) = self.pending.take() {
+            return Some(line);
+        }
+        match self.rx.try_recv() {
+            Ok(line) => Some(line),
+            Err(mpsc::error::TryRecvError::Empty) => None,
+            Err(mpsc::error::TryRecvError::Disconnected) => {
+                self.closed = true;
+                None
+            }
+        }
+    }
+
+    /// Wait until a line is available or the stream closes
+    pub async fn wait(&mut self) {
+        if self.pending.is_some() || self.closed {
+            return;
+        }
+        match self.rx.recv().await {
+            Some(line) => self.pending = Some(line),
+            None => self.closed = true,
+        }
+    }
+
+    /// Returns true once the stream has ended and every line has been taken
+    pub fn is_closed(&self) -> bool {
+        self.closed && self.pending.is_none()
+    }
+}
+
+/// Why a command run ended
+enum RunOutcome {
+    Exited(ExitStatus),
+    /// The source reader was dropped; stop without restarting
+    ReceiverClosed,
+}
+
+/// Supervise a command: run it, forward its output, and restart it with
+/// exponential backoff as the restart policy allows
+async fn run_command(
+    source_id: String,
+    argv: Vec<String>,
+    restart: RestartConfig,
+    tx: mpsc::Sender<io::Result<String>>,
+) {
+    let mut backoff = restart.backoff;
+
+    loop {
+        let started = Instant::now();
+        info!(source_id = %source_id, command = ?argv, "Starting source command");
+
+        let succeeded = match run_once(&argv, &tx).await {
+            Ok(RunOutcome::ReceiverClosed) => return,
+            Ok(RunOutcome::Exited(status)) if status.success() => {
+                info!(source_id = %source_id, "Source command exited");
+                true
+            }
+            Ok(RunOutcome::Exited(status)) => {
+                warn!(source_id = %source_id, status = %status, "Source command failed");
+                false
+            }
+            Err(e) => {
+                warn!(source_id = %source_id, error = %e, "Failed to run source command");
+                false
+            }
+        };
+
+        let should_restart = match restart.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !succeeded,
+            RestartPolicy::Always => true,
+        };
+        if !should_restart || tx.is_closed() {
+            return;
+        }
+
+        // A long-running process is healthy; only back off on quick exits
+        if started.elapsed() >= restart.max_backoff {
+            backoff = restart.backoff;
+        }
+
+        info!(source_id = %source_id, delay = ?backoff, "Restarting source command");
+        tokio::select! {
+            _ = sleep(backoff) => {}
+            _ = tx.closed() => return,
+        }
+        backoff = (backoff * 2).min(restart.max_backoff);
+    }
+}
+
+/// Run the command once, forwarding stdout until the process exits
+async fn run_once(
+    argv: &[String],
+    tx: &mpsc::Sender<io::Result<String>>,
+) -> io::Result<RunOutcome> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    match forward_lines(&mut BufReader::new(stdout), tx).await {
+        Ok(true) => {}
+        // Dropping the child kills it
+        Ok(false) => return Ok(RunOutcome::ReceiverClosed),
+        Err(e) => {
+            let _ = child.kill().await;
+            return Err(e);
+        }
+    }
+
+    Ok(RunOutcome::Exited(child.wait().await?))
+}
+
+/// Forward lines until EOF. Returns false if the receiver went away first.
+///
+/// Invalid UTF-8 is replaced rather than failing the source.
+async fn forward_lines<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    tx: &mpsc::Sender<io::Result<String>>,
+) -> io::Result<bool> {
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).await? == 0 {
+            return Ok(true);
+        }
+        let line = String::from_utf8_lossy(&buf).into_owned();
+        if tx.send(Ok(line)).await.is_err() {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn collect_lines(mut stream: LineStream) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            match stream.try_next_line() {
+                Some(line) => lines.push(line.unwrap()),
+                None if stream.is_closed() => return lines,
+                None => stream.wait().await,
+            }
+        }
+    }
+
+    fn restart(policy: RestartPolicy) -> RestartConfig {
+        RestartConfig {
+            policy,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+        }
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[tokio::test]
+    async fn test_command_lines() {
+        let stream = LineStream::command(
+            "cmd".to_string(),
+            sh("echo first; printf 'second'"),
+            restart(RestartPolicy::Never),
+        );
+        assert_eq!(collect_lines(stream).await, vec!["first\n", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_command_restarts_on_failure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let marker = dir.path().join("ran");
+        // Fails on the first run, succeeds on the second
+        let script = format!(
+            "if [ -e {0} ]; then echo second; else touch {0}; echo first; exit 1; fi",
+            marker.display()
+        );
+
+        let stream =
+            LineStream::command("cmd".to_string(), sh(&script), restart(RestartPolicy::OnFailure));
+        assert_eq!(collect_lines(stream).await, vec!["first\n", "second\n"]);
+    }
+
+    #[tokio::test]
+    async fn test_command_always_restarts() {
+        let mut stream =
+            LineStream::command("cmd".to_string(), sh("echo tick"), restart(RestartPolicy::Always));
+
+        for _ in 0..3 {
+            stream.wait().await;
+            assert_eq!(stream.try_next_line().unwrap().unwrap(), "tick\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_program_closes_stream() {
+        let stream = LineStream::command(
+            "cmd".to_string(),
+            vec!["/nonexistent/noil-test-program".to_string()],
+            restart(RestartPolicy::Never),
+        );
+        assert!(collect_lines(stream).await.is_empty());
+    }
+}
@@ -0,0 +1,468 @@
+use crate::config::types::{SyslogConfig, SyslogProtocol};
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Messages buffered between the listener tasks and the source reader
+const MESSAGE_CHANNEL_CAPACITY: usize = 4096;
+
+/// Largest message accepted, for both UDP datagrams and TCP frames
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron",
+    "authpriv", "ftp", "ntp", "security", "console", "solaris-cron", "local0", "local1",
+    "local2", "local3", "local4", "local5", "local6", "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// A message as received from the network
+#[derive(Debug)]
+pub struct SyslogMessage {
+    pub text: String,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Header data parsed from a syslog message
+#[derive(Debug, PartialEq)]
+pub struct ParsedSyslog {
+    /// Header timestamp, if present and parseable
+    pub timestamp: Option<DateTime<Utc>>,
+    /// `facility`, `severity`, `hostname`, `app_name`, `procid` and `msgid`,
+    /// where present
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Parse an RFC 5424 or RFC 3164 message.
+///
+/// Returns `None` if the message doesn't start with a valid `<PRI>`. RFC 3164
+/// timestamps carry no year or zone; they are read as UTC in the year the
+/// message was received (the previous year for December messages received
+/// in early January).
+pub fn parse_message(text: &str, received_at: DateTime<Utc>) -> Option<ParsedSyslog> {
+    let rest = text.strip_prefix('<')?;
+    let (pri, rest) = rest.split_once('>')?;
+    if pri.is_empty() || pri.len() > 3 || !pri.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let pri: usize = pri.parse().ok()?;
+    let (facility, severity) = (pri / 8, pri % 8);
+    let facility = FACILITIES.get(facility)?;
+
+    let mut fields = BTreeMap::new();
+    fields.insert("facility".to_string(), facility.to_string());
+    fields.insert("severity".to_string(), SEVERITIES[severity].to_string());
+
+    let timestamp = match rfc5424_header(rest) {
+        Some(header) => parse_rfc5424(header, &mut fields),
+        None => parse_rfc3164(rest, received_at, &mut fields),
+    };
+
+    Some(ParsedSyslog { timestamp, fields })
+}
+
+/// The header after `VERSION SP`, if the message is RFC 5424
+fn rfc5424_header(rest: &str) -> Option<&str> {
+    let (version, header) = rest.split_once(' ')?;
+    let is_version = !version.is_empty()
+        && version.len() <= 2
+        && version.bytes().all(|b| b.is_ascii_digit())
+        && !version.starts_with('0');
+    is_version.then_some(header)
+}
+
+/// `TIMESTAMP SP HOSTNAME SP APP-NAME SP PROCID SP MSGID SP ...`
+fn parse_rfc5424(header: &str, fields: &mut BTreeMap<String, String>) -> Option<DateTime<Utc>> {
+    let mut parts = header.splitn(6, ' ');
+    let timestamp = parts
+        .next()
+        .filter(|ts| *ts != "-")
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc));
+
+    for name in ["hostname", "app_name", "procid", "msgid"] {
+        match parts.next() {
+            Some("-") | Some("") => {}
+            Some(value) => {
+                fields.insert(name.to_string(), value.to_string());
+            }
+            None => break,
+        }
+    }
+
+    timestamp
+}
+
+/// `TIMESTAMP SP [HOSTNAME SP] TAG[PID]: MSG`
+fn parse_rfc3164(
+    rest: &str,
+    received_at: DateTime<Utc>,
+    fields: &mut BTreeMap<String, String>,
+) -> Option<DateTime<Utc>> {
+    // Without a recognizable header the whole message is content
+    let (timestamp, rest) = match parse_bsd_timestamp(rest, received_at) {
+        Some(timestamp) => (timestamp, rest.get(15..).unwrap_or("").trim_start()),
+        // Some relays send an RFC 3339 timestamp in an otherwise BSD-style header
+        None => {
+            let (token, rest) = rest.split_once(' ')?;
+            let timestamp = DateTime::parse_from_rfc3339(token).ok()?;
+            (timestamp.with_timezone(&Utc), rest)
+        }
+    };
+
+    // The hostname is optional; a tag ends with ':' or carries a '[pid]'
+    let is_tag = |token: &str| token.ends_with(':') || token.contains('[');
+    let mut tokens = rest.splitn(3, ' ');
+    let tag = match tokens.next() {
+        Some(first) if is_tag(first) => Some(first),
+        Some(first) if !first.is_empty() => {
+            fields.insert("hostname".to_string(), first.to_string());
+            tokens.next().filter(|second| is_tag(second))
+        }
+        _ => None,
+    };
+
+    if let Some(tag) = tag {
+        let tag = tag.trim_end_matches(':');
+        let (app_name, procid) = match tag.split_once('[') {
+            Some((app_name, pid)) => (app_name, pid.split(']').next()),
+            None => (tag, None),
+        };
+        if !app_name.is_empty() {
+            fields.insert("app_name".to_string(), app_name.to_string());
+        }
+        if let Some(procid) = procid.filter(|p| !p.is_empty()) {
+            fields.insert("procid".to_string(), procid.to_string());
+        }
+    }
+
+    Some(timestamp)
+}
+
+/// Parse a `Mmm dd hh:mm:ss` timestamp
+fn parse_bsd_timestamp(rest: &str, received_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let raw = rest.get(..15)?;
+    let in_year = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{} {}", year, raw), "%Y %b %e %H:%M:%S")
+            .ok()
+            .map(|naive| Utc.from_utc_datetime(&naive))
+    };
+
+    let timestamp = in_year(received_at.year())?;
+    if timestamp > received_at + chrono::Duration::days(1) {
+        return in_year(received_at.year() - 1);
+    }
+    Some(timestamp)
+}
+
+/// Receives syslog messages on a UDP or TCP socket.
+///
+/// Sockets are served by background tasks that stop once the listener is
+/// dropped.
+pub struct SyslogListener {
+    rx: mpsc::Receiver<SyslogMessage>,
+}
+
+impl SyslogListener {
+    /// Bind the configured address and start receiving
+    pub fn bind(source_id: String, config: &SyslogConfig) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+
+        match config.protocol {
+            SyslogProtocol::Udp => {
+                let socket = std::net::UdpSocket::bind(&config.listen)?;
+                socket.set_nonblocking(true)?;
+                let socket = UdpSocket::from_std(socket)?;
+                tokio::spawn(serve_udp(source_id, socket, tx));
+            }
+            SyslogProtocol::Tcp => {
+                let listener = std::net::TcpListener::bind(&config.listen)?;
+                listener.set_nonblocking(true)?;
+                let listener = TcpListener::from_std(listener)?;
+                tokio::spawn(serve_tcp(source_id, listener, tx));
+            }
+        }
+
+        Ok(Self { rx })
+    }
+
+    /// Wait for the next message. Returns `None` if the listener task stopped.
+    pub async fn recv(&mut self) -> Option<SyslogMessage> {
+        self.rx.recv().await
+    }
+}
+
+async fn serve_udp(
+    source_id: String,
+    socket: UdpSocket,
+    tx: mpsc::Sender<SyslogMessage>,
+) {
+    let mut buf = vec![0u8; MAX_MESSAGE_LEN];
+    loop {
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buf) => received,
+            _ = tx.closed() => return,
+        };
+
+        match received {
+            Ok((len, _)) => {
+                let text = String::from_utf8_lossy(&buf[..len]);
+                let text = text.trim_end_matches(['\n', '\r', '\0']);
+                if text.is_empty() {
+                    continue;
+                }
+                let message = SyslogMessage {
+                    text: text.to_string(),
+                    received_at: Utc::now(),
+                };
+                if tx.send(message).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!(source_id = %source_id, error = %e, "Failed to receive syslog datagram");
+            }
+        }
+    }
+}
+
+async fn serve_tcp(
+    source_id: String,
+    listener: TcpListener,
+    tx: mpsc::Sender<SyslogMessage>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = tx.closed() => return,
+        };
+
+        match accepted {
+            Ok((stream, peer)) => {
+                debug!(source_id = %source_id, peer = %peer, "Accepted syslog connection");
+                let source_id = source_id.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, &tx).await {
+                        debug!(source_id = %source_id, peer = %peer, error = %e, "Syslog connection closed");
+                    }
+                });
+            }
+            Err(e) => {
+                warn!(source_id = %source_id, error = %e, "Failed to accept syslog connection");
+            }
+        }
+    }
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    tx: &mpsc::Sender<SyslogMessage>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    tokio::select! {
+        result = read_frames(&mut reader, tx) => result,
+        _ = tx.closed() => Ok(()),
+    }
+}
+
+/// Read RFC 6587 frames until EOF.
+///
+/// Each frame is either octet-counted (`<len> <message>`) or terminated by a
+/// newline; a frame starting with a digit is octet-counted, since a syslog
+/// message itself starts with `<`.
+async fn read_frames<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    tx: &mpsc::Sender<SyslogMessage>,
+) -> io::Result<()> {
+    let mut frame = Vec::new();
+    loop {
+        let Some(&first) = reader.fill_buf().await?.first() else {
+            return Ok(());
+        };
+
+        frame.clear();
+        if first.is_ascii_digit() {
+            let mut len = Vec::new();
+            reader.read_until(b' ', &mut len).await?;
+            let len = std::str::from_utf8(&len)
+                .ok()
+                .and_then(|len| len.trim_end().parse::<usize>().ok())
+                .filter(|&len| len <= MAX_MESSAGE_LEN)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid octet count"))?;
+            frame.resize(len, 0);
+            reader.read_exact(&mut frame).await?;
+        } else {
+            (&mut *reader)
+                .take(MAX_MESSAGE_LEN as u64)
+                .read_until(b'\n', &mut frame)
+                .await?;
+        }
+
+        let text = String::from_utf8_lossy(&frame);
+        let text = text.trim_end_matches(['\n', '\r', '\0']);
+        if text.is_empty() {
+            continue;
+        }
+        let message = SyslogMessage {
+            text: text.to_string(),
+            received_at: Utc::now(),
+        };
+        if tx.send(message).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received_at() -> DateTime<Utc> {
+        "2025-03-10T12:00:00Z".parse().unwrap()
+    }
+
+    fn fields(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_rfc5424() {
+        let parsed = parse_message(
+            "<165>1 2025-03-10T11:59:58.123Z web01 nginx 4242 ACCESS [meta x=\"1\"] GET /",
+            received_at(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.timestamp,
+            Some("2025-03-10T11:59:58.123Z".parse().unwrap())
+        );
+        assert_eq!(
+            parsed.fields,
+            fields(&[
+                ("facility", "local4"),
+                ("severity", "notice"),
+                ("hostname", "web01"),
+                ("app_name", "nginx"),
+                ("procid", "4242"),
+                ("msgid", "ACCESS"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc5424_nil_values() {
+        let parsed = parse_message("<14>1 - - app - - -", received_at()).unwrap();
+        assert_eq!(parsed.timestamp, None);
+        assert_eq!(
+            parsed.fields,
+            fields(&[("facility", "user"), ("severity", "info"), ("app_name", "app")])
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc3164() {
+        let parsed = parse_message(
+            "<38>Mar  9 08:15:02 gateway sshd[1234]: Accepted publickey for root",
+            received_at(),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.timestamp, Some("2025-03-09T08:15:02Z".parse().unwrap()));
+        assert_eq!(
+            parsed.fields,
+            fields(&[
+                ("facility", "auth"),
+                ("severity", "info"),
+                ("hostname", "gateway"),
+                ("app_name", "sshd"),
+                ("procid", "1234"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc3164_without_hostname() {
+        let parsed = parse_message("<13>Mar 10 11:00:00 cron: job started", received_at()).unwrap();
+        assert_eq!(parsed.fields.get("hostname"), None);
+        assert_eq!(parsed.fields.get("app_name").map(String::as_str), Some("cron"));
+    }
+
+    #[test]
+    fn test_parse_rfc3164_year_rollover() {
+        let new_year: DateTime<Utc> = "2026-01-01T00:00:05Z".parse().unwrap();
+        let parsed = parse_message("<13>Dec 31 23:59:59 host app: late", new_year).unwrap();
+        assert_eq!(parsed.timestamp, Some("2025-12-31T23:59:59Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_rfc3339_in_bsd_header() {
+        let parsed = parse_message(
+            "<30>2025-03-10T11:59:00+01:00 host app[7]: started",
+            received_at(),
+        )
+        .unwrap();
+        assert_eq!(parsed.timestamp, Some("2025-03-10T10:59:00Z".parse().unwrap()));
+        assert_eq!(parsed.fields.get("procid").map(String::as_str), Some("7"));
+    }
+
+    #[test]
+    fn test_parse_invalid_pri() {
+        assert_eq!(parse_message("no pri", received_at()), None);
+        assert_eq!(parse_message("<192>1 - - - - - -", received_at()), None);
+        assert_eq!(parse_message("<1a>Mar 10 11:00:00 x", received_at()), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_frames_mixed_framing() {
+        let data = b"25 <13>1 - host app - - - hi<13>Mar 10 11:00:00 host app: plain\n\n13 <13>1 - - - -";
+        let (tx, mut rx) = mpsc::channel(10);
+        let mut reader = BufReader::new(&data[..]);
+        read_frames(&mut reader, &tx).await.unwrap();
+        drop(tx);
+
+        let mut texts = Vec::new();
+        while let Some(message) = rx.recv().await {
+            texts.push(message.text);
+        }
+        assert_eq!(
+            texts,
+            vec![
+                "<13>1 - host app - - - hi",
+                "<13>Mar 10 11:00:00 host app: plain",
+                "<13>1 - - - -",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_udp_listener() {
+        // Find a free port for the listener
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config = SyslogConfig {
+            listen: addr.to_string(),
+            protocol: SyslogProtocol::Udp,
+            lateness: std::time::Duration::from_secs(5),
+        };
+        let mut listener = SyslogListener::bind("syslog".to_string(), &config).unwrap();
+
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender
+            .send_to(b"<13>Mar 10 11:00:00 host app: hello\n", addr)
+            .unwrap();
+
+        let message = listener.recv().await.unwrap();
+        assert_eq!(message.text, "<13>Mar 10 11:00:00 host app: hello");
+    }
+}
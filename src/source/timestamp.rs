@@ -1,5 +1,9 @@
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use crate::source::stats::{CandidateStats, TimestampStats};
+use chrono::{DateTime, Datelike, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,6 +14,12 @@ pub enum TimestampError {
     #[error("pattern missing 'ts' capture group")]
     MissingTsGroup,
 
+    #[error("invalid JSON pointer '{0}': must be empty or start with '/'")]
+    InvalidPointer(String),
+
+    #[error("unknown time zone '{0}'")]
+    UnknownTimeZone(String),
+
     #[error("failed to parse timestamp '{value}' with format '{format}': {source}")]
     ParseError {
         value: String,
@@ -25,21 +35,360 @@ pub enum TimestampFormat {
     Iso8601,
     Epoch,
     EpochMs,
+    EpochUs,
+    EpochNs,
+    /// Any of the layouts in [`AUTO_LAYOUTS`]
+    Auto,
 }
 
+impl TimestampFormat {
+    /// One of: strptime format string, 'iso8601', 'epoch', 'epoch_ms',
+    /// 'epoch_us', 'epoch_ns', 'auto'
+    pub fn parse(format: &str) -> Self {
+        match format {
+            "iso8601" => TimestampFormat::Iso8601,
+            "epoch" => TimestampFormat::Epoch,
+            "epoch_ms" => TimestampFormat::EpochMs,
+            "epoch_us" => TimestampFormat::EpochUs,
+            "epoch_ns" => TimestampFormat::EpochNs,
+            "auto" => TimestampFormat::Auto,
+            other => TimestampFormat::Strptime(other.to_string()),
+        }
+    }
+
+    /// Whether timestamps in this format can lack a year, which then has to
+    /// be inferred
+    pub fn lacks_year(&self) -> bool {
+        match self {
+            TimestampFormat::Strptime(format) => !["%Y", "%y", "%G", "%g", "%C", "%s", "%F", "%D", "%c", "%+"]
+                .iter()
+                .any(|spec| format.contains(spec)),
+            // Syslog timestamps have no year
+            TimestampFormat::Auto => true,
+            _ => false,
+        }
+    }
+}
+
+/// A common timestamp layout, recognized by the `auto` format
 #[derive(Debug)]
-pub struct TimestampExtractor {
-    pattern: Regex,
+pub struct AutoLayout {
+    pub name: &'static str,
+    /// Regex with a `ts` capture group
+    pub pattern: &'static str,
+    /// Format of the captured value
+    pub format: &'static str,
+}
+
+/// Layouts tried by the `auto` format, in order
+pub const AUTO_LAYOUTS: &[AutoLayout] = &[
+    AutoLayout {
+        name: "rfc3339",
+        pattern: r"(?P<ts>\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:[Zz]|[+-]\d{2}:\d{2}))",
+        format: "iso8601",
+    },
+    AutoLayout {
+        name: "iso8601",
+        pattern: r"(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?)",
+        format: "%Y-%m-%dT%H:%M:%S%.f",
+    },
+    AutoLayout {
+        name: "iso8601_space",
+        pattern: r"(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?)",
+        format: "%Y-%m-%d %H:%M:%S%.f",
+    },
+    AutoLayout {
+        name: "clf",
+        pattern: r"(?P<ts>\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4})",
+        format: "%d/%b/%Y:%H:%M:%S %z",
+    },
+    AutoLayout {
+        name: "syslog",
+        pattern: r"^(?:<\d{1,3}>)?(?P<ts>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})",
+        format: "%b %e %H:%M:%S",
+    },
+    AutoLayout {
+        name: "epoch",
+        pattern: r"^(?P<ts>\d{10}(?:\.\d+)?)\b",
+        format: "epoch",
+    },
+    AutoLayout {
+        name: "epoch_ms",
+        pattern: r"^(?P<ts>\d{13})\b",
+        format: "epoch_ms",
+    },
+    AutoLayout {
+        name: "epoch_us",
+        pattern: r"^(?P<ts>\d{16})\b",
+        format: "epoch_us",
+    },
+    AutoLayout {
+        name: "epoch_ns",
+        pattern: r"^(?P<ts>\d{19})\b",
+        format: "epoch_ns",
+    },
+];
+
+/// Find the layout that parses the most sample lines, for suggesting a
+/// source's timestamp pattern and format
+pub fn detect_layout<S: AsRef<str>>(samples: &[S]) -> Option<&'static AutoLayout> {
+    let detector = AutoDetector::new();
+    let mut years = YearInference::default();
+    detector
+        .layouts
+        .iter()
+        .map(|(layout, regex, format)| {
+            let parsed = samples
+                .iter()
+                .filter_map(|line| regex.captures(line.as_ref()))
+                .filter(|captures| {
+                    parse_value(format, &captures["ts"], None, None, Some(&mut years)).is_ok()
+                })
+                .count();
+            (*layout, parsed)
+        })
+        .filter(|(_, parsed)| *parsed > 0)
+        // The earliest of the layouts parsing the most lines
+        .rev()
+        .max_by_key(|(_, parsed)| *parsed)
+        .map(|(layout, _)| layout)
+}
+
+/// Compiled [`AUTO_LAYOUTS`], remembering which one last matched
+#[derive(Debug)]
+struct AutoDetector {
+    layouts: Vec<(&'static AutoLayout, Regex, TimestampFormat)>,
+    detected: Option<usize>,
+}
+
+impl AutoDetector {
+    fn new() -> Self {
+        let layouts = AUTO_LAYOUTS
+            .iter()
+            .map(|layout| {
+                let regex = Regex::new(layout.pattern).expect("auto layout patterns are valid");
+                (layout, regex, TimestampFormat::parse(layout.format))
+            })
+            .collect();
+        Self {
+            layouts,
+            detected: None,
+        }
+    }
+
+    fn detected_name(&self) -> Option<&'static str> {
+        self.detected.map(|i| self.layouts[i].0.name)
+    }
+
+    /// Find a timestamp anywhere in a line. The first layout found is used
+    /// for every later line, so that lines of other shapes (e.g. numbers at
+    /// the start of continuation lines) aren't mistaken for timestamps.
+    fn find_in_line(
+        &mut self,
+        line: &str,
+        timezone: Option<&Tz>,
+        mut years: Option<&mut YearInference>,
+    ) -> Result<Option<DateTime<Utc>>, TimestampError> {
+        if let Some(i) = self.detected {
+            let (_, regex, format) = &self.layouts[i];
+            return match regex.captures(line) {
+                Some(captures) => parse_value(format, &captures["ts"], None, timezone, years).map(Some),
+                None => Ok(None),
+            };
+        }
+
+        for (i, (_, regex, format)) in self.layouts.iter().enumerate() {
+            let Some(captures) = regex.captures(line) else {
+                continue;
+            };
+            if let Ok(timestamp) =
+                parse_value(format, &captures["ts"], None, timezone, years.as_deref_mut())
+            {
+                self.detected = Some(i);
+                return Ok(Some(timestamp));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parse a value that is a timestamp in one of the layouts
+    fn parse_value(
+        &mut self,
+        value: &str,
+        timezone: Option<&Tz>,
+        mut years: Option<&mut YearInference>,
+    ) -> Result<DateTime<Utc>, TimestampError> {
+        for (i, (_, regex, format)) in self.layouts.iter().enumerate() {
+            let whole = regex
+                .captures(value)
+                .and_then(|captures| captures.name("ts"))
+                .is_some_and(|ts| ts.start() == 0 && ts.end() == value.len());
+            if !whole {
+                continue;
+            }
+            if let Ok(timestamp) = parse_value(format, value, None, timezone, years.as_deref_mut()) {
+                self.detected = Some(i);
+                return Ok(timestamp);
+            }
+        }
+
+        Err(TimestampError::ParseError {
+            value: value.to_string(),
+            format: "auto".to_string(),
+            source: Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "no known timestamp layout matches",
+            )),
+        })
+    }
+}
+
+/// Where the timestamp is found in a line
+#[derive(Debug)]
+enum TimestampLocator {
+    /// The `ts` capture group of a regex
+    Regex(Regex),
+    /// A JSON pointer into a line holding a JSON object
+    JsonPointer(String),
+    /// Anywhere in the line, in any of the `auto` layouts
+    Auto,
+}
+
+/// One way of finding and parsing a line's timestamp
+#[derive(Debug)]
+struct Candidate {
+    locator: TimestampLocator,
     format: TimestampFormat,
+    // Set for the `auto` format
+    auto: Option<AutoDetector>,
+    // The configured pattern (or pointer) and format, for stats
+    pattern: String,
+    format_name: String,
+    matches: u64,
+}
+
+impl Candidate {
+    fn new(locator: TimestampLocator, pattern: &str, format: &str) -> Self {
+        let parsed = TimestampFormat::parse(format);
+        let auto = matches!(parsed, TimestampFormat::Auto).then(AutoDetector::new);
+        Self {
+            locator,
+            format: parsed,
+            auto,
+            pattern: pattern.to_string(),
+            format_name: format.to_string(),
+            matches: 0,
+        }
+    }
+
+    fn extract(
+        &mut self,
+        line: &str,
+        timezone: Option<&Tz>,
+        years: Option<&mut YearInference>,
+    ) -> Result<Option<DateTime<Utc>>, TimestampError> {
+        let ts_value: Cow<str> = match &self.locator {
+            TimestampLocator::Regex(pattern) => {
+                let Some(captures) = pattern.captures(line) else {
+                    return Ok(None);
+                };
+                let ts = captures.name("ts").expect("ts capture group must exist");
+                Cow::Borrowed(ts.as_str())
+            }
+            TimestampLocator::JsonPointer(pointer) => {
+                let Ok(value) = serde_json::from_str::<Value>(line) else {
+                    return Ok(None);
+                };
+                match value.pointer(pointer) {
+                    Some(Value::String(s)) => Cow::Owned(s.clone()),
+                    Some(Value::Number(n)) => Cow::Owned(n.to_string()),
+                    _ => return Ok(None),
+                }
+            }
+            TimestampLocator::Auto => {
+                let auto = self.auto.as_mut().expect("auto locators have a detector");
+                return auto.find_in_line(line, timezone, years);
+            }
+        };
+
+        parse_value(&self.format, ts_value.as_ref(), self.auto.as_mut(), timezone, years).map(Some)
+    }
+
+    fn stats(&self) -> CandidateStats {
+        CandidateStats {
+            pattern: self.pattern.clone(),
+            format: self.format_name.clone(),
+            detected: self.auto.as_ref().and_then(AutoDetector::detected_name),
+            matches: self.matches,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TimestampExtractor {
+    // Tried in order; the first to find and parse a timestamp wins
+    candidates: Vec<Candidate>,
+    // Zone of timestamps without an offset; UTC if unset
+    timezone: Option<Tz>,
+    // Set for formats without a year
+    years: Option<YearInference>,
+    // Lines where no candidate found a timestamp
+    unmatched: u64,
+}
+
+/// Chooses years for timestamps whose format has none
+#[derive(Debug, Default)]
+struct YearInference {
+    // Year of the first timestamp; inferred from the current date if unset
+    assume_year: Option<i32>,
+    // Latest timestamp read, which later ones are placed near so that years
+    // roll over at New Year
+    last: Option<NaiveDateTime>,
 }
 
 impl TimestampExtractor {
     /// Create a new TimestampExtractor
     ///
     /// # Arguments
-    /// * `pattern` - Regex pattern that must contain a named capture group 'ts'
-    /// * `format` - One of: strptime format string, 'iso8601', 'epoch', 'epoch_ms'
+    /// * `pattern` - Regex pattern that must contain a named capture group 'ts'.
+    ///   May be empty with the 'auto' format, which then finds the timestamp
+    ///   anywhere in the line.
+    /// * `format` - One of: strptime format string, 'iso8601', 'epoch',
+    ///   'epoch_ms', 'epoch_us', 'epoch_ns', 'auto'
     pub fn new(pattern: &str, format: &str) -> Result<Self, TimestampError> {
+        Ok(Self::with_candidate(Self::regex_candidate(pattern, format)?))
+    }
+
+    /// Create a TimestampExtractor for JSON lines
+    ///
+    /// # Arguments
+    /// * `pointer` - JSON pointer to the timestamp value (e.g. `/time`)
+    /// * `format` - Same as for [`TimestampExtractor::new`]; numeric values are
+    ///   formatted as text first
+    pub fn json(pointer: &str, format: &str) -> Result<Self, TimestampError> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(TimestampError::InvalidPointer(pointer.to_string()));
+        }
+
+        let locator = TimestampLocator::JsonPointer(pointer.to_string());
+        Ok(Self::with_candidate(Candidate::new(locator, pointer, format)))
+    }
+
+    fn with_candidate(candidate: Candidate) -> Self {
+        let years = candidate.format.lacks_year().then(YearInference::default);
+        Self {
+            candidates: vec![candidate],
+            timezone: None,
+            years,
+            unmatched: 0,
+        }
+    }
+
+    fn regex_candidate(pattern: &str, format: &str) -> Result<Candidate, TimestampError> {
+        if pattern.is_empty() && format == "auto" {
+            return Ok(Candidate::new(TimestampLocator::Auto, pattern, format));
+        }
+
         let regex = Regex::new(pattern)?;
 
         // Validate that the pattern has a 'ts' capture group
@@ -47,122 +396,290 @@ impl TimestampExtractor {
             return Err(TimestampError::MissingTsGroup);
         }
 
-        let timestamp_format = match format {
-            "iso8601" => TimestampFormat::Iso8601,
-            "epoch" => TimestampFormat::Epoch,
-            "epoch_ms" => TimestampFormat::EpochMs,
-            other => TimestampFormat::Strptime(other.to_string()),
-        };
+        Ok(Candidate::new(TimestampLocator::Regex(regex), pattern, format))
+    }
 
-        Ok(Self {
-            pattern: regex,
-            format: timestamp_format,
-        })
+    /// Try another pattern and format on lines where the previous ones find
+    /// no timestamp
+    pub fn with_fallback(mut self, pattern: &str, format: &str) -> Result<Self, TimestampError> {
+        let candidate = Self::regex_candidate(pattern, format)?;
+        if candidate.format.lacks_year() && self.years.is_none() {
+            self.years = Some(YearInference::default());
+        }
+        self.candidates.push(candidate);
+        Ok(self)
+    }
+
+    /// Read timestamps without an offset as local time in `zone`
+    pub fn with_timezone(mut self, zone: Tz) -> Self {
+        self.timezone = Some(zone);
+        self
+    }
+
+    /// Place the first timestamp in `year` when the format has no year,
+    /// instead of inferring it from the current date
+    pub fn with_assume_year(mut self, year: i32) -> Self {
+        if let Some(years) = &mut self.years {
+            years.assume_year = Some(year);
+        }
+        self
     }
 
     /// Extract timestamp from a log line
     ///
-    /// Returns None if the pattern doesn't match the line.
-    /// Returns Some(DateTime<Utc>) if matched and successfully parsed.
-    pub fn extract(&self, line: &str) -> Result<Option<DateTime<Utc>>, TimestampError> {
-        let Some(captures) = self.pattern.captures(line) else {
-            return Ok(None);
-        };
+    /// Candidates are tried in order. Returns None if no candidate's pattern
+    /// matches the line (for JSON lines: the line isn't JSON or the pointer
+    /// doesn't resolve to a string or number). Returns Some(DateTime<Utc>)
+    /// from the first candidate that matches and parses; if candidates match
+    /// but none parses, the first parse error is returned.
+    ///
+    /// For formats without a year, each timestamp is placed in the year that
+    /// puts it closest to the previous one, so extraction is stateful.
+    pub fn extract(&mut self, line: &str) -> Result<Option<DateTime<Utc>>, TimestampError> {
+        let mut first_error = None;
+        for candidate in &mut self.candidates {
+            match candidate.extract(line, self.timezone.as_ref(), self.years.as_mut()) {
+                Ok(Some(timestamp)) => {
+                    candidate.matches += 1;
+                    return Ok(Some(timestamp));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
 
-        let ts_value = captures
-            .name("ts")
-            .expect("ts capture group must exist")
-            .as_str();
+        self.unmatched += 1;
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
 
-        let datetime = match &self.format {
-            TimestampFormat::Iso8601 => self.parse_iso8601(ts_value)?,
-            TimestampFormat::Epoch => self.parse_epoch(ts_value)?,
-            TimestampFormat::EpochMs => self.parse_epoch_ms(ts_value)?,
-            TimestampFormat::Strptime(fmt) => self.parse_strptime(ts_value, fmt)?,
-        };
+    /// Copy match counts into `stats`, reusing its allocations
+    pub fn write_stats(&self, stats: &mut TimestampStats) {
+        stats.unmatched = self.unmatched;
+        if stats.candidates.len() != self.candidates.len() {
+            stats.candidates = self.candidates.iter().map(Candidate::stats).collect();
+            return;
+        }
+        for (stats, candidate) in stats.candidates.iter_mut().zip(&self.candidates) {
+            stats.matches = candidate.matches;
+            stats.detected = candidate.auto.as_ref().and_then(AutoDetector::detected_name);
+        }
+    }
+}
+
+/// Parse a located timestamp value. `auto` is the detector of an `auto`
+/// format.
+fn parse_value(
+    format: &TimestampFormat,
+    value: &str,
+    auto: Option<&mut AutoDetector>,
+    timezone: Option<&Tz>,
+    years: Option<&mut YearInference>,
+) -> Result<DateTime<Utc>, TimestampError> {
+    match format {
+        TimestampFormat::Iso8601 => parse_iso8601(value),
+        TimestampFormat::Epoch => parse_epoch(value),
+        TimestampFormat::EpochMs => parse_epoch_units(value, 1_000, "epoch_ms"),
+        TimestampFormat::EpochUs => parse_epoch_units(value, 1_000_000, "epoch_us"),
+        TimestampFormat::EpochNs => parse_epoch_units(value, 1_000_000_000, "epoch_ns"),
+        TimestampFormat::Strptime(fmt) => parse_strptime(value, fmt, timezone, years),
+        TimestampFormat::Auto => match auto {
+            Some(auto) => auto.parse_value(value, timezone, years),
+            None => AutoDetector::new().parse_value(value, timezone, years),
+        },
+    }
+}
+
+fn parse_iso8601(value: &str) -> Result<DateTime<Utc>, TimestampError> {
+    // Try parsing as RFC3339 (which handles ISO8601 with timezone)
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| TimestampError::ParseError {
+            value: value.to_string(),
+            format: "iso8601".to_string(),
+            source: Box::new(e),
+        })
+}
 
-        Ok(Some(datetime))
+fn parse_epoch(value: &str) -> Result<DateTime<Utc>, TimestampError> {
+    // Fractional seconds, as written by many JSON loggers
+    if let Some((secs, frac)) = value.split_once('.') {
+        let nanos = format!("{:0<9.9}", frac);
+        if let (Ok(seconds), Ok(nanos)) = (secs.parse::<i64>(), nanos.parse::<u32>()) {
+            if let Some(datetime) = Utc.timestamp_opt(seconds, nanos).single() {
+                return Ok(datetime);
+            }
+        }
     }
 
-    fn parse_iso8601(&self, value: &str) -> Result<DateTime<Utc>, TimestampError> {
-        // Try parsing as RFC3339 (which handles ISO8601 with timezone)
-        DateTime::parse_from_rfc3339(value)
+    let seconds: i64 =
+        value
+            .parse()
+            .map_err(|e: std::num::ParseIntError| TimestampError::ParseError {
+                value: value.to_string(),
+                format: "epoch".to_string(),
+                source: Box::new(e),
+            })?;
+
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .ok_or_else(|| TimestampError::ParseError {
+            value: value.to_string(),
+            format: "epoch".to_string(),
+            source: Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "timestamp out of range",
+            )),
+        })
+}
+
+/// Integer epoch timestamps in units of `1 / per_second` seconds
+fn parse_epoch_units(
+    value: &str,
+    per_second: i64,
+    format: &str,
+) -> Result<DateTime<Utc>, TimestampError> {
+    let units: i64 =
+        value
+            .parse()
+            .map_err(|e: std::num::ParseIntError| TimestampError::ParseError {
+                value: value.to_string(),
+                format: format.to_string(),
+                source: Box::new(e),
+            })?;
+
+    let seconds = units.div_euclid(per_second);
+    let nanos = (units.rem_euclid(per_second) * (1_000_000_000 / per_second)) as u32;
+
+    Utc.timestamp_opt(seconds, nanos)
+        .single()
+        .ok_or_else(|| TimestampError::ParseError {
+            value: value.to_string(),
+            format: format.to_string(),
+            source: Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "timestamp out of range",
+            )),
+        })
+}
+
+fn parse_strptime(
+    value: &str,
+    format: &str,
+    timezone: Option<&Tz>,
+    years: Option<&mut YearInference>,
+) -> Result<DateTime<Utc>, TimestampError> {
+    // Check if format contains timezone information
+    if format.contains("%z") || format.contains("%Z") || format.contains("%:z") {
+        // Parse with timezone and convert to UTC
+        DateTime::parse_from_str(value, format)
             .map(|dt| dt.with_timezone(&Utc))
             .map_err(|e| TimestampError::ParseError {
                 value: value.to_string(),
-                format: "iso8601".to_string(),
+                format: format.to_string(),
                 source: Box::new(e),
             })
-    }
-
-    fn parse_epoch(&self, value: &str) -> Result<DateTime<Utc>, TimestampError> {
-        let seconds: i64 =
-            value
-                .parse()
-                .map_err(|e: std::num::ParseIntError| TimestampError::ParseError {
+    } else {
+        // Parse as naive datetime in the configured zone (UTC by default)
+        let naive = if let Some(years) = years {
+            years.parse(value, format)?
+        } else {
+            NaiveDateTime::parse_from_str(value, format).map_err(|e| {
+                TimestampError::ParseError {
                     value: value.to_string(),
-                    format: "epoch".to_string(),
+                    format: format.to_string(),
                     source: Box::new(e),
-                })?;
+                }
+            })?
+        };
+        Ok(match timezone {
+            Some(zone) => local_to_utc(zone, &naive),
+            None => Utc.from_utc_datetime(&naive),
+        })
+    }
+}
 
-        Utc.timestamp_opt(seconds, 0)
-            .single()
-            .ok_or_else(|| TimestampError::ParseError {
-                value: value.to_string(),
-                format: "epoch".to_string(),
-                source: Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "timestamp out of range",
-                )),
-            })
+/// Look up an IANA time zone by name (e.g. `Europe/Berlin`)
+pub fn parse_timezone(name: &str) -> Result<Tz, TimestampError> {
+    name.parse()
+        .map_err(|_| TimestampError::UnknownTimeZone(name.to_string()))
+}
+
+/// Convert a wall-clock time in `zone` to UTC.
+///
+/// Ambiguous times (when clocks go back) resolve to the earlier instant;
+/// times skipped when clocks go forward are read with the offset from
+/// before the change.
+fn local_to_utc(zone: &Tz, local: &NaiveDateTime) -> DateTime<Utc> {
+    match zone.from_local_datetime(local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.with_timezone(&Utc),
+        LocalResult::None => {
+            // Step back out of the gap to find the offset before it
+            let offset = (1..=48)
+                .map(|hours| *local - chrono::Duration::hours(hours))
+                .find_map(|earlier| zone.from_local_datetime(&earlier).earliest())
+                .map_or(0, |time| time.offset().fix().local_minus_utc());
+            Utc.from_utc_datetime(&(*local - chrono::Duration::seconds(offset.into())))
+        }
     }
+}
 
-    fn parse_epoch_ms(&self, value: &str) -> Result<DateTime<Utc>, TimestampError> {
-        let millis: i64 =
-            value
-                .parse()
-                .map_err(|e: std::num::ParseIntError| TimestampError::ParseError {
-                    value: value.to_string(),
-                    format: "epoch_ms".to_string(),
-                    source: Box::new(e),
-                })?;
+impl YearInference {
+    /// Parse a timestamp whose format has no year, choosing the year
+    fn parse(&mut self, value: &str, format: &str) -> Result<NaiveDateTime, TimestampError> {
+        let in_year = |year: i32| {
+            NaiveDateTime::parse_from_str(&format!("{} {}", year, value), &format!("%Y {}", format))
+        };
 
-        let seconds = millis / 1000;
-        let nanos = ((millis % 1000) * 1_000_000) as u32;
+        let (year, parsed) = match (self.last, self.assume_year) {
+            // The year closest to the previous timestamp: Dec 31 followed by
+            // Jan 1 moves to the next year, and a slightly late Dec 31 line
+            // after Jan 1 stays in the previous one
+            (Some(last), _) => {
+                let closest = [last.year(), last.year() + 1, last.year() - 1]
+                    .into_iter()
+                    .filter_map(|year| in_year(year).ok())
+                    .min_by_key(|candidate| (*candidate - last).num_seconds().abs());
+                (last.year(), closest)
+            }
+            (None, Some(year)) => (year, in_year(year).ok()),
+            // Logs are read near the time they were written, so a timestamp
+            // more than a day ahead of now belongs to last year
+            (None, None) => {
+                let now = Utc::now().naive_utc();
+                let limit = now + chrono::Duration::days(1);
+                let parsed = in_year(now.year())
+                    .ok()
+                    .filter(|parsed| *parsed <= limit)
+                    .or_else(|| in_year(now.year() - 1).ok());
+                (now.year(), parsed)
+            }
+        };
 
-        Utc.timestamp_opt(seconds, nanos)
-            .single()
-            .ok_or_else(|| TimestampError::ParseError {
-                value: value.to_string(),
-                format: "epoch_ms".to_string(),
-                source: Box::new(std::io::Error::new(
+        let Some(parsed) = parsed else {
+            let source: Box<dyn std::error::Error + Send + Sync> = match in_year(year) {
+                Err(e) => Box::new(e),
+                // e.g. Feb 29 outside a leap year
+                Ok(_) => Box::new(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
-                    "timestamp out of range",
+                    "no nearby year fits the timestamp",
                 )),
-            })
-    }
+            };
+            return Err(TimestampError::ParseError {
+                value: value.to_string(),
+                format: format.to_string(),
+                source,
+            });
+        };
 
-    fn parse_strptime(&self, value: &str, format: &str) -> Result<DateTime<Utc>, TimestampError> {
-        // Check if format contains timezone information
-        if format.contains("%z") || format.contains("%Z") || format.contains("%:z") {
-            // Parse with timezone and convert to UTC
-            DateTime::parse_from_str(value, format)
-                .map(|dt| dt.with_timezone(&Utc))
-                .map_err(|e| TimestampError::ParseError {
-                    value: value.to_string(),
-                    format: format.to_string(),
-                    source: Box::new(e),
-                })
-        } else {
-            // Parse as naive datetime and assume UTC
-            NaiveDateTime::parse_from_str(value, format)
-                .map(|ndt| Utc.from_utc_datetime(&ndt))
-                .map_err(|e| TimestampError::ParseError {
-                    value: value.to_string(),
-                    format: format.to_string(),
-                    source: Box::new(e),
-                })
+        if self.last.is_none_or(|last| parsed > last) {
+            self.last = Some(parsed);
         }
+        Ok(parsed)
     }
 }
 
@@ -179,7 +696,7 @@ mod tests {
 
     #[test]
     fn test_iso8601_basic() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z)",
             "iso8601",
         )
@@ -195,7 +712,7 @@ mod tests {
 
     #[test]
     fn test_iso8601_with_offset() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}[+-]\d{2}:\d{2})",
             "iso8601",
         )
@@ -212,7 +729,7 @@ mod tests {
 
     #[test]
     fn test_nginx_format() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"\[(?P<ts>\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4})\]",
             "%d/%b/%Y:%H:%M:%S %z",
         )
@@ -228,7 +745,7 @@ mod tests {
 
     #[test]
     fn test_epoch_seconds() {
-        let extractor = TimestampExtractor::new(r"^(?P<ts>\d{10})", "epoch").unwrap();
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\d{10})", "epoch").unwrap();
 
         let result = extractor
             .extract("1733280131 log message")
@@ -240,7 +757,7 @@ mod tests {
 
     #[test]
     fn test_epoch_milliseconds() {
-        let extractor = TimestampExtractor::new(r"^(?P<ts>\d{13})", "epoch_ms").unwrap();
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\d{13})", "epoch_ms").unwrap();
 
         let result = extractor
             .extract("1733280131011 log message")
@@ -253,7 +770,7 @@ mod tests {
 
     #[test]
     fn test_custom_strptime_comma_millis() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2},\d{3})",
             "%Y-%m-%d %H:%M:%S,%3f",
         )
@@ -269,7 +786,7 @@ mod tests {
 
     #[test]
     fn test_no_match_returns_none() {
-        let extractor = TimestampExtractor::new(r"^(?P<ts>\d{4}-\d{2}-\d{2})", "iso8601").unwrap();
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\d{4}-\d{2}-\d{2})", "iso8601").unwrap();
 
         let result = extractor.extract("this line has no timestamp").unwrap();
 
@@ -292,16 +809,43 @@ mod tests {
 
     #[test]
     fn test_unparseable_timestamp() {
-        let extractor = TimestampExtractor::new(r"^(?P<ts>\S+)", "epoch").unwrap();
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\S+)", "epoch").unwrap();
 
         let result = extractor.extract("not_a_number log message");
 
         assert!(matches!(result, Err(TimestampError::ParseError { .. })));
     }
 
+    #[test]
+    fn test_json_pointer() {
+        let mut extractor = TimestampExtractor::json("/@timestamp", "iso8601").unwrap();
+        let result = extractor
+            .extract(r#"{"@timestamp":"2025-12-04T02:42:11.011Z","msg":"hi"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11.011+00:00");
+
+        let mut extractor = TimestampExtractor::json("/meta/ts", "epoch").unwrap();
+        let result = extractor
+            .extract(r#"{"meta":{"ts":1733280131.25}}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.timestamp(), 1733280131);
+        assert_eq!(result.timestamp_subsec_millis(), 250);
+
+        // Non-JSON lines and missing values don't match
+        assert!(extractor.extract("plain text").unwrap().is_none());
+        assert!(extractor.extract(r#"{"meta":{}}"#).unwrap().is_none());
+
+        assert!(matches!(
+            TimestampExtractor::json("time", "iso8601"),
+            Err(TimestampError::InvalidPointer(_))
+        ));
+    }
+
     #[test]
     fn test_timezone_aware_strptime() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} [+-]\d{4})",
             "%Y-%m-%d %H:%M:%S %z",
         )
@@ -318,7 +862,7 @@ mod tests {
 
     #[test]
     fn test_timezone_naive_assumes_utc() {
-        let extractor = TimestampExtractor::new(
+        let mut extractor = TimestampExtractor::new(
             r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})",
             "%Y-%m-%d %H:%M:%S",
         )
@@ -332,4 +876,167 @@ mod tests {
         // Should be interpreted as UTC
         assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11+00:00");
     }
+
+    #[test]
+    fn test_timezone() {
+        let mut extractor = TimestampExtractor::new(
+            r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})",
+            "%Y-%m-%d %H:%M:%S",
+        )
+        .unwrap()
+        .with_timezone(parse_timezone("UTC").unwrap());
+
+        let result = extractor.extract("2025-12-04 02:42:11 log message").unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11+00:00");
+
+        assert!(matches!(
+            parse_timezone("Mars/Olympus_Mons"),
+            Err(TimestampError::UnknownTimeZone(_))
+        ));
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        let mut extractor = TimestampExtractor::new(
+            r"^(?P<ts>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})",
+            "%Y-%m-%d %H:%M:%S",
+        )
+        .unwrap()
+        .with_timezone(berlin);
+
+        let winter = extractor.extract("2025-12-04 02:42:11 log message").unwrap().unwrap();
+        assert_eq!(winter.to_rfc3339(), "2025-12-04T01:42:11+00:00");
+        let summer = extractor.extract("2025-07-04 02:42:11 log message").unwrap().unwrap();
+        assert_eq!(summer.to_rfc3339(), "2025-07-04T00:42:11+00:00");
+
+        let mut extract = |line: &str| extractor.extract(line).unwrap().unwrap().to_rfc3339();
+        // Clocks go back at 03:00 CEST on 2025-10-26; the repeated hour
+        // resolves to the earlier instant
+        assert_eq!(extract("2025-10-26 01:59:59 before"), "2025-10-25T23:59:59+00:00");
+        assert_eq!(extract("2025-10-26 02:30:00 repeated"), "2025-10-26T00:30:00+00:00");
+        assert_eq!(extract("2025-10-26 03:00:00 after"), "2025-10-26T02:00:00+00:00");
+        // Clocks go forward at 02:00 CET on 2025-03-30; the skipped hour is
+        // read with the winter offset
+        assert_eq!(extract("2025-03-30 01:59:59 before"), "2025-03-30T00:59:59+00:00");
+        assert_eq!(extract("2025-03-30 02:30:00 skipped"), "2025-03-30T01:30:00+00:00");
+        assert_eq!(extract("2025-03-30 03:00:00 after"), "2025-03-30T01:00:00+00:00");
+    }
+
+    #[test]
+    fn test_assume_year_rolls_over() {
+        let mut extractor = TimestampExtractor::new(
+            r"^(?P<ts>\w{3} [ \d]\d \d{2}:\d{2}:\d{2})",
+            "%b %e %H:%M:%S",
+        )
+        .unwrap()
+        .with_assume_year(2024);
+
+        let mut extract = |line: &str| extractor.extract(line).unwrap().unwrap().to_rfc3339();
+        assert_eq!(extract("Dec 31 23:59:58 host app: one"), "2024-12-31T23:59:58+00:00");
+        assert_eq!(extract("Jan  1 00:00:01 host app: two"), "2025-01-01T00:00:01+00:00");
+        // A slightly late line from the old year stays in it
+        assert_eq!(extract("Dec 31 23:59:59 host app: late"), "2024-12-31T23:59:59+00:00");
+        assert_eq!(extract("Jan  1 00:00:02 host app: three"), "2025-01-01T00:00:02+00:00");
+    }
+
+    #[test]
+    fn test_year_inferred_from_clock() {
+        let mut extractor =
+            TimestampExtractor::new(r"^(?P<ts>\w{3} \d{2} \d{2}:\d{2}:\d{2})", "%b %d %H:%M:%S")
+                .unwrap();
+
+        // A timestamp from the past day is in the current year, or last
+        // year's December shortly after New Year
+        let now = Utc::now() - chrono::Duration::hours(1);
+        let line = now.format("%b %d %H:%M:%S").to_string();
+        let result = extractor.extract(&line).unwrap().unwrap();
+        assert_eq!(result.year(), now.year());
+    }
+
+    #[test]
+    fn test_epoch_micro_and_nanoseconds() {
+        let mut micros = TimestampExtractor::new(r"^(?P<ts>\d{16})", "epoch_us").unwrap();
+        let result = micros.extract("1733280131011123 some log message").unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2024-12-04T02:42:11.011123+00:00");
+
+        let mut nanos = TimestampExtractor::new(r"^(?P<ts>\d{19})", "epoch_ns").unwrap();
+        let result = nanos.extract("1733280131011123456 some log message").unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2024-12-04T02:42:11.011123456+00:00");
+    }
+
+    #[test]
+    fn test_fallbacks_tried_in_order() {
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\S+Z) ", "iso8601")
+            .unwrap()
+            .with_fallback(r"^\[(?P<ts>[^\]]+)\]", "%d/%b/%Y:%H:%M:%S %z")
+            .unwrap()
+            .with_fallback(r"^(?P<ts>\d{10}) ", "epoch")
+            .unwrap();
+
+        let mut extract = |line: &str| extractor.extract(line).unwrap().map(|ts| ts.to_rfc3339());
+        assert_eq!(extract("2025-12-04T02:42:11Z a").as_deref(), Some("2025-12-04T02:42:11+00:00"));
+        assert_eq!(extract("[04/Dec/2025:02:42:12 +0000] b").as_deref(), Some("2025-12-04T02:42:12+00:00"));
+        assert_eq!(extract("1764816133 c").as_deref(), Some("2025-12-04T02:42:13+00:00"));
+        assert_eq!(extract("2025-12-04T02:42:14Z d").as_deref(), Some("2025-12-04T02:42:14+00:00"));
+        assert_eq!(extract("continuation line"), None);
+
+        let mut stats = TimestampStats::default();
+        extractor.write_stats(&mut stats);
+        let matches: Vec<u64> = stats.candidates.iter().map(|c| c.matches).collect();
+        assert_eq!(matches, vec![2, 1, 1]);
+        assert_eq!(stats.candidates[1].format, "%d/%b/%Y:%H:%M:%S %z");
+        assert_eq!(stats.unmatched, 1);
+    }
+
+    #[test]
+    fn test_fallback_after_parse_error() {
+        let mut extractor = TimestampExtractor::new(r"^(?P<ts>\S+)", "iso8601")
+            .unwrap()
+            .with_fallback(r"^(?P<ts>\d{10})", "epoch")
+            .unwrap();
+
+        // The first pattern matches but can't parse; the fallback can
+        let result = extractor.extract("1764816131 message").unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11+00:00");
+        // Neither parses: the first candidate's error is reported
+        let err = extractor.extract("garbage message").unwrap_err();
+        assert!(err.to_string().contains("iso8601"), "{}", err);
+    }
+
+    #[test]
+    fn test_auto_format() {
+        let mut extractor = TimestampExtractor::new("", "auto").unwrap();
+        let result = extractor
+            .extract("INFO 2025-12-04T02:42:11.5+01:00 request served")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T01:42:11.500+00:00");
+        // Locked onto the layout first seen: other shapes are not timestamps
+        assert_eq!(extractor.extract("1764816131 retries left").unwrap(), None);
+
+        let mut stats = TimestampStats::default();
+        extractor.write_stats(&mut stats);
+        assert_eq!(stats.candidates[0].detected, Some("rfc3339"));
+
+        // With a pattern, auto parses the captured value in any layout
+        let mut extractor = TimestampExtractor::new(r#"ts="(?P<ts>[^"]+)""#, "auto").unwrap();
+        let result = extractor.extract(r#"ts="1764816131123" msg"#).unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11.123+00:00");
+        let result = extractor.extract(r#"ts="04/Dec/2025:02:42:11 +0000" msg"#).unwrap().unwrap();
+        assert_eq!(result.to_rfc3339(), "2025-12-04T02:42:11+00:00");
+    }
+
+    #[test]
+    fn test_detect_layout() {
+        let samples = [
+            "127.0.0.1 - - [04/Dec/2025:02:42:11 +0000] \"GET / HTTP/1.1\" 200 612",
+            "127.0.0.1 - - [04/Dec/2025:02:42:12 +0000] \"GET /a HTTP/1.1\" 404 0",
+            "a line without a timestamp",
+        ];
+        let layout = detect_layout(&samples).unwrap();
+        assert_eq!(layout.name, "clf");
+
+        let samples = ["<34>Oct 11 22:14:15 mymachine su: 'su root' failed"];
+        assert_eq!(detect_layout(&samples).unwrap().name, "syslog");
+        let samples = ["2025-12-04 02:42:11,123 INFO started"];
+        assert_eq!(detect_layout(&samples).unwrap().name, "iso8601_space");
+        assert!(detect_layout(&["no timestamps here"]).is_none());
+    }
 }
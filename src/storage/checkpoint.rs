@@ -1,4 +1,7 @@
+use super::metrics::FiberMetrics;
 use super::traits::{Storage, StorageError};
+use crate::fiber::sequence::SequenceProgress;
+use crate::source::stats::SourceStats;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -26,14 +29,22 @@ pub type Result<T> = std::result::Result<T, CheckpointError>;
 /// for checkpoint collection without blocking the reader
 #[derive(Debug, Default)]
 pub struct SourceCheckpointState {
+    pub path: PathBuf,
     pub offset: u64,
     pub inode: u64,
     pub last_timestamp: Option<DateTime<Utc>>,
+    pub fingerprint: Option<u64>,
+    /// Counters reported by the web API; not checkpointed
+    pub stats: SourceStats,
 }
 
 /// Shared reference to source checkpoint state
 pub type SharedSourceState = Arc<Mutex<SourceCheckpointState>>;
 
+/// Registry of per-source checkpoint states, keyed by source ID.
+/// Glob and directory sources add entries at runtime as files are discovered.
+pub type SharedSourceStates = Arc<tokio::sync::RwLock<HashMap<String, SharedSourceState>>>;
+
 /// Shared reference to fiber processor checkpoint state
 pub type SharedFiberProcessorState = Arc<Mutex<HashMap<String, FiberProcessorCheckpoint>>>;
 
@@ -50,9 +61,15 @@ pub struct Checkpoint {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceCheckpoint {
     pub path: PathBuf,
+    /// Byte offset to resume from. For gzip/zstd files this is an offset into
+    /// the decompressed stream.
     pub offset: u64,
     pub inode: u64,
     pub last_timestamp: Option<DateTime<Utc>>,
+    /// Content fingerprint of the file's first `min(offset, FINGERPRINT_LEN)`
+    /// bytes, used to find it again after it has been rotated or compressed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +91,10 @@ pub struct OpenFiberCheckpoint {
     pub first_activity: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub log_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub metrics: FiberMetrics,
+    #[serde(default)]
+    pub sequence: SequenceProgress,
 }
 
 /// Collector mode checkpoint structure
@@ -387,6 +408,7 @@ mod tests {
                 offset: 12345,
                 inode: 67890,
                 last_timestamp: Some(Utc::now()),
+                fingerprint: None,
             },
         );
 
@@ -1,8 +1,13 @@
 use super::checkpoint::Checkpoint;
-use super::traits::{ConfigSource, ConfigState, ConfigVersion, FiberMembership, FiberRecord, Storage, StorageError, StoredLog};
+use super::metrics::{FiberMetric, FiberMetrics};
+use super::traits::{
+    ConfigSource, ConfigState, ConfigVersion, FiberLink, FiberMembership, FiberQuery, FiberRecord, Storage,
+    StorageError, StoredLog, UnknownCloseReason,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use duckdb::Connection;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
@@ -156,11 +161,18 @@ impl Storage for DuckDbStorage {
                     source_id VARCHAR NOT NULL,
                     raw_text VARCHAR NOT NULL,
                     ingestion_time TIMESTAMPTZ NOT NULL,
-                    config_version UBIGINT NOT NULL
+                    config_version UBIGINT NOT NULL,
+                    fields VARCHAR,
+                    labels VARCHAR
                 )",
                 [],
             )?;
 
+            // Databases created before records carried fields
+            conn.execute("ALTER TABLE raw_logs ADD COLUMN IF NOT EXISTS fields VARCHAR", [])?;
+            // ...or source labels
+            conn.execute("ALTER TABLE raw_logs ADD COLUMN IF NOT EXISTS labels VARCHAR", [])?;
+
             conn.execute(
                 "CREATE INDEX IF NOT EXISTS idx_raw_logs_timestamp ON raw_logs(timestamp)",
                 [],
@@ -171,6 +183,21 @@ impl Storage for DuckDbStorage {
                 [],
             )?;
 
+            // Records that arrived behind the watermark, kept for inspection
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS late_logs (
+                    log_id UUID PRIMARY KEY,
+                    timestamp TIMESTAMPTZ NOT NULL,
+                    source_id VARCHAR NOT NULL,
+                    raw_text VARCHAR NOT NULL,
+                    ingestion_time TIMESTAMPTZ NOT NULL,
+                    config_version UBIGINT NOT NULL,
+                    fields VARCHAR,
+                    labels VARCHAR
+                )",
+                [],
+            )?;
+
             // Create fibers table
             conn.execute(
                 "CREATE TABLE IF NOT EXISTS fibers (
@@ -180,11 +207,33 @@ impl Storage for DuckDbStorage {
                     attributes JSON,
                     first_activity TIMESTAMPTZ NOT NULL,
                     last_activity TIMESTAMPTZ NOT NULL,
-                    closed BOOLEAN NOT NULL DEFAULT FALSE
+                    closed BOOLEAN NOT NULL DEFAULT FALSE,
+                    log_count UBIGINT,
+                    source_counts JSON,
+                    error_count UBIGINT,
+                    duration_us BIGINT,
+                    close_reason VARCHAR,
+                    closing_pattern VARCHAR
                 )",
                 [],
             )?;
 
+            // Databases created before fibers kept metrics. Durations are
+            // filled in here and log counts once the memberships table
+            // exists; error counts start at zero.
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS log_count UBIGINT", [])?;
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS source_counts JSON", [])?;
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS error_count UBIGINT", [])?;
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS duration_us BIGINT", [])?;
+            // Fibers closed before close reasons were kept have none
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS close_reason VARCHAR", [])?;
+            conn.execute("ALTER TABLE fibers ADD COLUMN IF NOT EXISTS closing_pattern VARCHAR", [])?;
+            conn.execute(
+                "UPDATE fibers SET duration_us = epoch_us(last_activity) - epoch_us(first_activity)
+                 WHERE duration_us IS NULL",
+                [],
+            )?;
+
             conn.execute(
                 "CREATE INDEX IF NOT EXISTS idx_fibers_type ON fibers(fiber_type)",
                 [],
@@ -205,6 +254,30 @@ impl Storage for DuckDbStorage {
                 "CREATE INDEX IF NOT EXISTS idx_memberships_fiber ON fiber_memberships(fiber_id)",
                 [],
             )?;
+            conn.execute(
+                "UPDATE fibers SET log_count = (
+                     SELECT count(*) FROM fiber_memberships m WHERE m.fiber_id = fibers.fiber_id
+                 )
+                 WHERE log_count IS NULL",
+                [],
+            )?;
+
+            // Create fiber_links table (parent/child fibers of related types).
+            // A child has one parent, so it is keyed on the child alone.
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS fiber_links (
+                    child_fiber_id UUID PRIMARY KEY,
+                    parent_fiber_id UUID NOT NULL,
+                    linked_at TIMESTAMPTZ NOT NULL,
+                    config_version UBIGINT NOT NULL
+                )",
+                [],
+            )?;
+
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_fiber_links_parent ON fiber_links(parent_fiber_id)",
+                [],
+            )?;
 
             // Create checkpoints table
             conn.execute(
@@ -299,23 +372,55 @@ impl Storage for DuckDbStorage {
 
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            let mut stmt = conn.prepare(
-                "INSERT OR IGNORE INTO raw_logs (log_id, timestamp, source_id, raw_text, ingestion_time, config_version)
-                 VALUES (?, to_timestamp(? / 1000000.0), ?, ?, to_timestamp(? / 1000000.0), ?)",
+            insert_logs(&conn, "raw_logs", &logs)
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
+    }
+
+    async fn write_late_logs(&self, logs: &[StoredLog]) -> Result<(), StorageError> {
+        if logs.is_empty() {
+            return Ok(());
+        }
+
+        let conn = self.conn.clone();
+        let logs = logs.to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            insert_logs(&conn, "late_logs", &logs)
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
+    }
+
+    async fn query_late_logs(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<StoredLog>, StorageError> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {}
+                 FROM late_logs
+                 ORDER BY ingestion_time DESC, timestamp DESC
+                 LIMIT ? OFFSET ?",
+                LOG_COLUMNS
+            ))?;
+
+            let rows = stmt.query_map(
+                duckdb::params![limit as i64, offset as i64],
+                parse_stored_log_row,
             )?;
 
-            for log in logs {
-                stmt.execute(duckdb::params![
-                    log.log_id.to_string(),
-                    log.timestamp.timestamp_micros(),
-                    log.source_id,
-                    log.raw_text,
-                    log.ingestion_time.timestamp_micros(),
-                    log.config_version,
-                ])?;
+            let mut logs = Vec::new();
+            for row in rows {
+                logs.push(row?);
             }
-
-            Ok::<(), StorageError>(())
+            Ok(logs)
         })
         .await
         .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
@@ -328,7 +433,7 @@ impl Storage for DuckDbStorage {
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
-                "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+                "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                  FROM raw_logs WHERE log_id = ?",
             )?;
 
@@ -357,6 +462,8 @@ impl Storage for DuckDbStorage {
                             Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
                         ))?,
                     config_version: row.get(5)?,
+                    fields: parse_fields_column(row, 6)?,
+                    labels: parse_fields_column(row, 7)?,
                 };
                 Ok(Some(log))
             } else {
@@ -380,7 +487,7 @@ impl Storage for DuckDbStorage {
             .collect::<Vec<String>>()
             .join(", ");
         let query = format!(
-            "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version\n             FROM raw_logs WHERE log_id IN ({})",
+            "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels\n             FROM raw_logs WHERE log_id IN ({})",
             placeholders
         );
 
@@ -410,6 +517,8 @@ impl Storage for DuckDbStorage {
                             Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
                         ))?,
                     config_version: row.get(5)?,
+                    fields: parse_fields_column(row, 6)?,
+                    labels: parse_fields_column(row, 7)?,
                 })
             })?;
 
@@ -428,22 +537,28 @@ impl Storage for DuckDbStorage {
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        label_filters: &std::collections::HashMap<String, String>,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<StoredLog>, StorageError> {
         let conn = self.conn.clone();
         let start_micros = start.timestamp_micros();
         let end_micros = end.timestamp_micros();
+        let label_clause: String = label_conditions("labels", label_filters)
+            .into_iter()
+            .map(|condition| format!(" AND {}", condition))
+            .collect();
 
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            let mut stmt = conn.prepare(
-                "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+            let mut stmt = conn.prepare(&format!(
+                "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                  FROM raw_logs
-                 WHERE timestamp >= to_timestamp(? / 1000000.0) AND timestamp <= to_timestamp(? / 1000000.0)
+                 WHERE timestamp >= to_timestamp(? / 1000000.0) AND timestamp <= to_timestamp(? / 1000000.0){}
                  ORDER BY timestamp
                  LIMIT ? OFFSET ?",
-            )?;
+                label_clause
+            ))?;
 
             let rows = stmt.query_map(
                 duckdb::params![start_micros, end_micros, limit as i64, offset as i64],
@@ -470,6 +585,8 @@ impl Storage for DuckDbStorage {
                                 Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
                             ))?,
                         config_version: row.get(5)?,
+                        fields: parse_fields_column(row, 6)?,
+                        labels: parse_fields_column(row, 7)?,
                     })
                 },
             )?;
@@ -491,10 +608,12 @@ impl Storage for DuckDbStorage {
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let attributes_json = serde_json::to_string(&fiber.attributes)?;
+            let source_counts_json = serde_json::to_string(&fiber.metrics.source_counts)?;
 
             conn.execute(
-                "INSERT INTO fibers (fiber_id, fiber_type, config_version, attributes, first_activity, last_activity, closed)
-                 VALUES (?, ?, ?, ?, to_timestamp(? / 1000000.0), to_timestamp(? / 1000000.0), ?)",
+                "INSERT INTO fibers (fiber_id, fiber_type, config_version, attributes, first_activity, last_activity, closed,
+                                     log_count, source_counts, error_count, duration_us, close_reason, closing_pattern)
+                 VALUES (?, ?, ?, ?, to_timestamp(? / 1000000.0), to_timestamp(? / 1000000.0), ?, ?, ?, ?, ?, ?, ?)",
                 duckdb::params![
                     fiber.fiber_id.to_string(),
                     fiber.fiber_type,
@@ -503,6 +622,12 @@ impl Storage for DuckDbStorage {
                     fiber.first_activity.timestamp_micros(),
                     fiber.last_activity.timestamp_micros(),
                     fiber.closed,
+                    fiber.metrics.log_count,
+                    source_counts_json,
+                    fiber.metrics.error_count,
+                    fiber.duration().num_microseconds().unwrap_or(i64::MAX),
+                    fiber.close_reason.map(|reason| reason.as_str()),
+                    fiber.closing_pattern,
                 ],
             )?;
 
@@ -519,10 +644,12 @@ impl Storage for DuckDbStorage {
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let attributes_json = serde_json::to_string(&fiber.attributes)?;
+            let source_counts_json = serde_json::to_string(&fiber.metrics.source_counts)?;
 
             conn.execute(
                 "UPDATE fibers
-                 SET fiber_type = ?, config_version = ?, attributes = ?, first_activity = to_timestamp(? / 1000000.0), last_activity = to_timestamp(? / 1000000.0), closed = ?
+                 SET fiber_type = ?, config_version = ?, attributes = ?, first_activity = to_timestamp(? / 1000000.0), last_activity = to_timestamp(? / 1000000.0), closed = ?,
+                     log_count = ?, source_counts = ?, error_count = ?, duration_us = ?, close_reason = ?, closing_pattern = ?
                  WHERE fiber_id = ?",
                 duckdb::params![
                     fiber.fiber_type,
@@ -531,6 +658,12 @@ impl Storage for DuckDbStorage {
                     fiber.first_activity.timestamp_micros(),
                     fiber.last_activity.timestamp_micros(),
                     fiber.closed,
+                    fiber.metrics.log_count,
+                    source_counts_json,
+                    fiber.metrics.error_count,
+                    fiber.duration().num_microseconds().unwrap_or(i64::MAX),
+                    fiber.close_reason.map(|reason| reason.as_str()),
+                    fiber.closing_pattern,
                     fiber.fiber_id.to_string(),
                 ],
             )?;
@@ -548,37 +681,13 @@ impl Storage for DuckDbStorage {
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
-                "SELECT fiber_id, fiber_type, config_version, attributes, epoch_us(first_activity), epoch_us(last_activity), closed
-                 FROM fibers WHERE fiber_id = ?",
+                &format!("SELECT {} FROM fibers WHERE fiber_id = ?", FIBER_COLUMNS),
             )?;
 
             let mut rows = stmt.query(duckdb::params![fiber_id_str])?;
 
             if let Some(row) = rows.next()? {
-                let fiber = FiberRecord {
-                    fiber_id: Uuid::parse_str(&row.get::<_, String>(0)?)
-                        .map_err(|e| duckdb::Error::FromSqlConversionFailure(
-                            0,
-                            duckdb::types::Type::Text,
-                            Box::new(e),
-                        ))?,
-                    fiber_type: row.get(1)?,
-                    config_version: row.get(2)?,
-                    attributes: serde_json::from_str(&row.get::<_, String>(3)?)?,
-                    first_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(4)?)
-                        .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                            4,
-                            duckdb::types::Type::BigInt,
-                            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                        ))?,
-                    last_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(5)?)
-                        .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                            5,
-                            duckdb::types::Type::BigInt,
-                            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                        ))?,
-                    closed: row.get(6)?,
-                };
+                let fiber = parse_fiber_row(row)?;
                 Ok(Some(fiber))
             } else {
                 Ok(None)
@@ -599,47 +708,18 @@ impl Storage for DuckDbStorage {
 
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            let mut stmt = conn.prepare(
-                "SELECT fiber_id, fiber_type, config_version, attributes, epoch_us(first_activity), epoch_us(last_activity), closed
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {}
                  FROM fibers
                  WHERE fiber_type = ?
                  ORDER BY first_activity
                  LIMIT ? OFFSET ?",
-            )?;
+                FIBER_COLUMNS
+            ))?;
 
             let rows = stmt.query_map(
                 duckdb::params![fiber_type, limit as i64, offset as i64],
-                |row| {
-                    Ok(FiberRecord {
-                        fiber_id: Uuid::parse_str(&row.get::<_, String>(0)?)
-                            .map_err(|e| duckdb::Error::FromSqlConversionFailure(
-                                0,
-                                duckdb::types::Type::Text,
-                                Box::new(e),
-                            ))?,
-                        fiber_type: row.get(1)?,
-                        config_version: row.get(2)?,
-                        attributes: serde_json::from_str(&row.get::<_, String>(3)?)
-                            .map_err(|e| duckdb::Error::FromSqlConversionFailure(
-                                3,
-                                duckdb::types::Type::Text,
-                                Box::new(e),
-                            ))?,
-                        first_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(4)?)
-                            .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                                4,
-                                duckdb::types::Type::BigInt,
-                                Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                            ))?,
-                        last_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(5)?)
-                            .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                                5,
-                                duckdb::types::Type::BigInt,
-                                Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                            ))?,
-                        closed: row.get(6)?,
-                    })
-                },
+                parse_fiber_row,
             )?;
 
             let mut fibers = Vec::new();
@@ -723,7 +803,7 @@ impl Storage for DuckDbStorage {
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
-                "SELECT l.log_id, epoch_us(l.timestamp), l.source_id, l.raw_text, epoch_us(l.ingestion_time), l.config_version
+                "SELECT l.log_id, epoch_us(l.timestamp), l.source_id, l.raw_text, epoch_us(l.ingestion_time), l.config_version, l.fields, l.labels
                  FROM raw_logs l
                  INNER JOIN fiber_memberships m ON l.log_id = m.log_id
                  WHERE m.fiber_id = ?
@@ -756,6 +836,8 @@ impl Storage for DuckDbStorage {
                                 Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
                             ))?,
                         config_version: row.get(5)?,
+                        fields: parse_fields_column(row, 6)?,
+                        labels: parse_fields_column(row, 7)?,
                     })
                 },
             )?;
@@ -816,6 +898,97 @@ impl Storage for DuckDbStorage {
         .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
     }
 
+    async fn write_fiber_links(&self, links: &[FiberLink]) -> Result<(), StorageError> {
+        if links.is_empty() {
+            return Ok(());
+        }
+
+        let conn = self.conn.clone();
+        let links = links.to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "INSERT OR IGNORE INTO fiber_links (parent_fiber_id, child_fiber_id, linked_at, config_version)
+                 VALUES (?, ?, to_timestamp(? / 1000000.0), ?)",
+            )?;
+
+            for link in links {
+                stmt.execute(duckdb::params![
+                    link.parent_fiber_id.to_string(),
+                    link.child_fiber_id.to_string(),
+                    link.linked_at.timestamp_micros(),
+                    link.config_version,
+                ])?;
+            }
+
+            Ok::<(), StorageError>(())
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
+    }
+
+    async fn get_child_fibers(
+        &self,
+        fiber_id: Uuid,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<FiberRecord>, StorageError> {
+        let conn = self.conn.clone();
+        let fiber_id_str = fiber_id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {}
+                 FROM fibers
+                 WHERE fiber_id IN (SELECT child_fiber_id FROM fiber_links WHERE parent_fiber_id = ?)
+                 ORDER BY first_activity
+                 LIMIT ? OFFSET ?",
+                FIBER_COLUMNS
+            ))?;
+
+            let rows = stmt.query_map(
+                duckdb::params![fiber_id_str, limit as i64, offset as i64],
+                parse_fiber_row,
+            )?;
+
+            let mut fibers = Vec::new();
+            for row in rows {
+                fibers.push(row?);
+            }
+            Ok(fibers)
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
+    }
+
+    async fn get_parent_fibers(&self, fiber_id: Uuid) -> Result<Vec<FiberRecord>, StorageError> {
+        let conn = self.conn.clone();
+        let fiber_id_str = fiber_id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {}
+                 FROM fibers
+                 WHERE fiber_id IN (SELECT parent_fiber_id FROM fiber_links WHERE child_fiber_id = ?)
+                 ORDER BY first_activity",
+                FIBER_COLUMNS
+            ))?;
+
+            let rows = stmt.query_map(duckdb::params![fiber_id_str], parse_fiber_row)?;
+
+            let mut fibers = Vec::new();
+            for row in rows {
+                fibers.push(row?);
+            }
+            Ok(fibers)
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Task join error: {}", e)))?
+    }
+
     async fn load_checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
         let conn = self.conn.clone();
 
@@ -895,7 +1068,7 @@ impl Storage for DuckDbStorage {
                 if !checkpointed_ids.contains(&fiber_id) {
                     // This fiber is orphaned - close it
                     conn.execute(
-                        "UPDATE fibers SET closed = TRUE WHERE fiber_id = ?",
+                        "UPDATE fibers SET closed = TRUE, close_reason = 'orphaned' WHERE fiber_id = ?",
                         duckdb::params![fiber_id.to_string()],
                     )?;
                     closed_count += 1;
@@ -1246,28 +1419,28 @@ impl Storage for DuckDbStorage {
             // Build query with optional time range filters
             let query = match (start, end) {
                 (Some(_), Some(_)) => {
-                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                      FROM raw_logs
                      WHERE timestamp >= to_timestamp(? / 1000000.0) AND timestamp <= to_timestamp(? / 1000000.0)
                      ORDER BY timestamp
                      LIMIT ? OFFSET ?"
                 }
                 (Some(_), None) => {
-                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                      FROM raw_logs
                      WHERE timestamp >= to_timestamp(? / 1000000.0)
                      ORDER BY timestamp
                      LIMIT ? OFFSET ?"
                 }
                 (None, Some(_)) => {
-                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                      FROM raw_logs
                      WHERE timestamp <= to_timestamp(? / 1000000.0)
                      ORDER BY timestamp
                      LIMIT ? OFFSET ?"
                 }
                 (None, None) => {
-                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version
+                    "SELECT log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels
                      FROM raw_logs
                      ORDER BY timestamp
                      LIMIT ? OFFSET ?"
@@ -1384,6 +1557,7 @@ impl Storage for DuckDbStorage {
             // Delete ALL fibers, not just those matching the current config version.
             // This ensures reprocessing clears out fibers from previous config versions,
             // preventing duplicates when fiber rules change across hot reloads.
+            conn.execute("DELETE FROM fiber_links", [])?;
             let rows_affected = conn.execute(
                 "DELETE FROM fibers",
                 [],
@@ -1549,19 +1723,13 @@ impl Storage for DuckDbStorage {
 
     async fn query_fibers_filtered(
         &self,
-        fiber_types: Option<&[String]>,
-        attribute_filters: &std::collections::HashMap<String, String>,
-        closed: Option<bool>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-        max_fibers: usize,
-        offset: usize,
+        query: &FiberQuery,
     ) -> Result<(Vec<FiberRecord>, usize), StorageError> {
         let conn = self.conn.clone();
-        let fiber_types = fiber_types.map(|t| t.to_vec());
-        let attribute_filters = attribute_filters.clone();
-        let start_micros = start_time.map(|t| t.timestamp_micros());
-        let end_micros = end_time.map(|t| t.timestamp_micros());
+        let query = query.clone();
+        let label_conditions = label_conditions("l.labels", &query.labels);
+        let start_micros = query.start_time.map(|t| t.timestamp_micros());
+        let end_micros = query.end_time.map(|t| t.timestamp_micros());
 
         tokio::task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
@@ -1570,17 +1738,18 @@ impl Storage for DuckDbStorage {
             let mut where_clauses: Vec<String> = vec![];
 
             // Type filtering
-            if let Some(ref types) = fiber_types {
-                if !types.is_empty() {
-                    let placeholders = types.iter().map(|t| format!("'{}'", t.replace('\'', "''"))).collect::<Vec<_>>().join(", ");
-                    where_clauses.push(format!("fiber_type IN ({})", placeholders));
-                }
+            if !query.fiber_types.is_empty() {
+                let placeholders = query.fiber_types.iter().map(|t| format!("'{}'", t.replace('\'', "''"))).collect::<Vec<_>>().join(", ");
+                where_clauses.push(format!("fiber_type IN ({})", placeholders));
             }
 
             // Closed filter
-            if let Some(is_closed) = closed {
+            if let Some(is_closed) = query.closed {
                 where_clauses.push(format!("closed = {}", is_closed));
             }
+            if let Some(reason) = query.close_reason {
+                where_clauses.push(format!("close_reason = '{}'", reason.as_str()));
+            }
 
             // Time overlap filter: fiber overlaps [start, end] range
             // This means: first_activity <= end AND last_activity >= start
@@ -1591,16 +1760,38 @@ impl Storage for DuckDbStorage {
                 where_clauses.push(format!("last_activity >= to_timestamp({} / 1000000.0)", start_us));
             }
 
-            // Attribute filtering using DuckDB JSON functions
-            for (key, value) in &attribute_filters {
+            // Attribute filtering using DuckDB JSON functions. A `set`
+            // attribute is a list, and matches if it holds the value.
+            for (key, value) in &query.attributes {
                 let escaped_key = key.replace('\'', "''");
                 let escaped_value = value.replace('\'', "''");
                 where_clauses.push(format!(
-                    "json_extract_string(attributes, '$.{}') = '{}'",
+                    "(json_extract_string(attributes, '$.{0}') = '{1}' \
+                     OR list_contains(json_extract_string(attributes, '$.{0}[*]'), '{1}'))",
                     escaped_key, escaped_value
                 ));
             }
 
+            // Values bound to the `?` placeholders of the WHERE clause
+            let mut where_params = Vec::new();
+            for filter in &query.metrics {
+                where_clauses.push(format!(
+                    "{} {} {}",
+                    metric_column(&filter.metric, &mut where_params),
+                    filter.comparison.as_sql(),
+                    filter.value
+                ));
+            }
+
+            // Label filtering: some member log carries every label
+            if !label_conditions.is_empty() {
+                where_clauses.push(format!(
+                    "fiber_id IN (SELECT m.fiber_id FROM fiber_memberships m
+                     INNER JOIN raw_logs l ON m.log_id = l.log_id WHERE {})",
+                    label_conditions.join(" AND ")
+                ));
+            }
+
             let where_clause = if where_clauses.is_empty() {
                 String::new()
             } else {
@@ -1610,7 +1801,7 @@ impl Storage for DuckDbStorage {
             // Count query
             let count_query = format!("SELECT COUNT(*) FROM fibers {}", where_clause);
             let mut count_stmt = conn.prepare(&count_query)?;
-            let mut count_rows = count_stmt.query([])?;
+            let mut count_rows = count_stmt.query(duckdb::params_from_iter(&where_params))?;
             let total_matching: usize = if let Some(row) = count_rows.next()? {
                 let count: i64 = row.get(0)?;
                 count as usize
@@ -1618,48 +1809,30 @@ impl Storage for DuckDbStorage {
                 0
             };
 
-            // Main query with ordering by duration (longest first), then by start time
-            let query = format!(
-                "SELECT fiber_id, fiber_type, config_version, attributes, epoch_us(first_activity), epoch_us(last_activity), closed
+            // Main query ordered by the sort metric (by default duration,
+            // longest first), then by start time
+            let mut select_params = where_params;
+            let order = query.sort.as_ref().map_or_else(
+                || "duration_us DESC".to_string(),
+                |sort| {
+                    format!(
+                        "{} {}",
+                        metric_column(&sort.metric, &mut select_params),
+                        if sort.descending { "DESC" } else { "ASC" }
+                    )
+                },
+            );
+            let select = format!(
+                "SELECT {}
                  FROM fibers
                  {}
-                 ORDER BY (epoch_us(last_activity) - epoch_us(first_activity)) DESC, first_activity ASC
+                 ORDER BY {}, first_activity ASC
                  LIMIT {} OFFSET {}",
-                where_clause, max_fibers, offset
+                FIBER_COLUMNS, where_clause, order, query.limit, query.offset
             );
 
-            let mut stmt = conn.prepare(&query)?;
-            let rows = stmt.query_map([], |row| {
-                Ok(FiberRecord {
-                    fiber_id: Uuid::parse_str(&row.get::<_, String>(0)?)
-                        .map_err(|e| duckdb::Error::FromSqlConversionFailure(
-                            0,
-                            duckdb::types::Type::Text,
-                            Box::new(e),
-                        ))?,
-                    fiber_type: row.get(1)?,
-                    config_version: row.get(2)?,
-                    attributes: serde_json::from_str(&row.get::<_, String>(3)?)
-                        .map_err(|e| duckdb::Error::FromSqlConversionFailure(
-                            3,
-                            duckdb::types::Type::Text,
-                            Box::new(e),
-                        ))?,
-                    first_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(4)?)
-                        .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                            4,
-                            duckdb::types::Type::BigInt,
-                            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                        ))?,
-                    last_activity: DateTime::from_timestamp_micros(row.get::<_, i64>(5)?)
-                        .ok_or_else(|| duckdb::Error::FromSqlConversionFailure(
-                            5,
-                            duckdb::types::Type::BigInt,
-                            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
-                        ))?,
-                    closed: row.get(6)?,
-                })
-            })?;
+            let mut stmt = conn.prepare(&select)?;
+            let rows = stmt.query_map(duckdb::params_from_iter(&select_params), parse_fiber_row)?;
 
             let mut fibers = Vec::new();
             for row in rows {
@@ -1734,7 +1907,143 @@ impl Storage for DuckDbStorage {
     }
 }
 
-// Helper function to parse a row into StoredLog
+// Insert logs into raw_logs or late_logs, which share their columns
+fn insert_logs(conn: &Connection, table: &str, logs: &[StoredLog]) -> Result<(), StorageError> {
+    let mut stmt = conn.prepare(&format!(
+        "INSERT OR IGNORE INTO {} (log_id, timestamp, source_id, raw_text, ingestion_time, config_version, fields, labels)
+         VALUES (?, to_timestamp(? / 1000000.0), ?, ?, to_timestamp(? / 1000000.0), ?, ?, ?)",
+        table
+    ))?;
+
+    for log in logs {
+        let fields = if log.fields.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&log.fields)?)
+        };
+        let labels = if log.labels.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&log.labels)?)
+        };
+        stmt.execute(duckdb::params![
+            log.log_id.to_string(),
+            log.timestamp.timestamp_micros(),
+            log.source_id,
+            log.raw_text,
+            log.ingestion_time.timestamp_micros(),
+            log.config_version,
+            fields,
+            labels,
+        ])?;
+    }
+
+    Ok(())
+}
+
+const FIBER_COLUMNS: &str = "fiber_id, fiber_type, config_version, attributes, epoch_us(first_activity), \
+     epoch_us(last_activity), closed, log_count, source_counts, error_count, close_reason, closing_pattern";
+
+// Parse a row selected with FIBER_COLUMNS
+fn parse_fiber_row(row: &duckdb::Row) -> Result<FiberRecord, duckdb::Error> {
+    fn parse_json<T: serde::de::DeserializeOwned>(idx: usize, json: &str) -> Result<T, duckdb::Error> {
+        serde_json::from_str(json).map_err(|e| {
+            duckdb::Error::FromSqlConversionFailure(idx, duckdb::types::Type::Text, Box::new(e))
+        })
+    }
+    let timestamp = |idx: usize| {
+        DateTime::from_timestamp_micros(row.get::<_, i64>(idx)?).ok_or_else(|| {
+            duckdb::Error::FromSqlConversionFailure(
+                idx,
+                duckdb::types::Type::BigInt,
+                Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
+            )
+        })
+    };
+
+    Ok(FiberRecord {
+        fiber_id: Uuid::parse_str(&row.get::<_, String>(0)?)
+            .map_err(|e| duckdb::Error::FromSqlConversionFailure(
+                0,
+                duckdb::types::Type::Text,
+                Box::new(e),
+            ))?,
+        fiber_type: row.get(1)?,
+        config_version: row.get(2)?,
+        attributes: parse_json(3, &row.get::<_, String>(3)?)?,
+        first_activity: timestamp(4)?,
+        last_activity: timestamp(5)?,
+        closed: row.get(6)?,
+        // NULL in rows written before fibers kept metrics
+        metrics: FiberMetrics {
+            log_count: row.get::<_, Option<u64>>(7)?.unwrap_or_default(),
+            source_counts: match row.get::<_, Option<String>>(8)? {
+                Some(json) => parse_json(8, &json)?,
+                None => BTreeMap::new(),
+            },
+            error_count: row.get::<_, Option<u64>>(9)?.unwrap_or_default(),
+        },
+        close_reason: row
+            .get::<_, Option<String>>(10)?
+            .map(|reason| {
+                reason.parse().map_err(|e: UnknownCloseReason| {
+                    duckdb::Error::FromSqlConversionFailure(10, duckdb::types::Type::Text, Box::new(e))
+                })
+            })
+            .transpose()?,
+        closing_pattern: row.get(11)?,
+    })
+}
+
+// SQL expression for a fiber metric. The JSON path of a source count is
+// bound as a parameter, pushed onto `params`.
+fn metric_column(metric: &FiberMetric, params: &mut Vec<String>) -> String {
+    match metric {
+        FiberMetric::Duration => "duration_us".to_string(),
+        FiberMetric::LogCount => "COALESCE(log_count, 0)".to_string(),
+        FiberMetric::ErrorCount => "COALESCE(error_count, 0)".to_string(),
+        FiberMetric::SourceCount(source_id) => {
+            params.push(format!("$.\"{}\"", source_id));
+            "COALESCE(CAST(json_extract(source_counts, ?) AS BIGINT), 0)".to_string()
+        }
+    }
+}
+
+// Conditions that the JSON labels in `column` include each filter's label
+fn label_conditions(
+    column: &str,
+    label_filters: &std::collections::HashMap<String, String>,
+) -> Vec<String> {
+    label_filters
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "json_extract_string({}, '$.{}') = '{}'",
+                column,
+                name.replace('\'', "''"),
+                value.replace('\'', "''")
+            )
+        })
+        .collect()
+}
+
+// Record fields and labels are stored as JSON objects, or NULL when there are none
+fn parse_fields_column(
+    row: &duckdb::Row,
+    idx: usize,
+) -> Result<BTreeMap<String, String>, duckdb::Error> {
+    match row.get::<_, Option<String>>(idx)? {
+        Some(json) => serde_json::from_str(&json).map_err(|e| {
+            duckdb::Error::FromSqlConversionFailure(idx, duckdb::types::Type::Text, Box::new(e))
+        }),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+const LOG_COLUMNS: &str =
+    "log_id, epoch_us(timestamp), source_id, raw_text, epoch_us(ingestion_time), config_version, fields, labels";
+
+// Helper function to parse a row selected with LOG_COLUMNS into StoredLog
 fn parse_stored_log_row(row: &duckdb::Row) -> Result<StoredLog, duckdb::Error> {
     Ok(StoredLog {
         log_id: Uuid::parse_str(&row.get::<_, String>(0)?)
@@ -1758,13 +2067,18 @@ fn parse_stored_log_row(row: &duckdb::Row) -> Result<StoredLog, duckdb::Error> {
                 Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid timestamp")),
             ))?,
         config_version: row.get(5)?,
+        fields: parse_fields_column(row, 6)?,
+        labels: parse_fields_column(row, 7)?,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::metrics::{MetricFilter, MetricSort};
+    use crate::storage::traits::CloseReason;
     use chrono::Utc;
+    use std::collections::HashMap;
 
     async fn setup_storage() -> DuckDbStorage {
         let storage = DuckDbStorage::in_memory().unwrap();
@@ -1791,6 +2105,8 @@ mod tests {
             raw_text: "test log line".to_string(),
             ingestion_time: timestamp,
             config_version: 1,
+            fields: Default::default(),
+            labels: Default::default(),
         };
 
         storage.write_logs(&[log.clone()]).await.unwrap();
@@ -1816,6 +2132,8 @@ mod tests {
                 raw_text: "log 1".to_string(),
                 ingestion_time: timestamp,
                 config_version: 1,
+                fields: Default::default(),
+                labels: Default::default(),
             },
             StoredLog {
                 log_id: Uuid::new_v4(),
@@ -1824,6 +2142,8 @@ mod tests {
                 raw_text: "log 2".to_string(),
                 ingestion_time: timestamp,
                 config_version: 1,
+                fields: Default::default(),
+                labels: Default::default(),
             },
         ];
 
@@ -1850,6 +2170,8 @@ mod tests {
                 raw_text: "log 1".to_string(),
                 ingestion_time: now,
                 config_version: 1,
+                fields: Default::default(),
+                labels: Default::default(),
             },
             StoredLog {
                 log_id: Uuid::new_v4(),
@@ -1858,19 +2180,53 @@ mod tests {
                 raw_text: "log 2".to_string(),
                 ingestion_time: now,
                 config_version: 1,
+                fields: Default::default(),
+                labels: BTreeMap::from([("host".to_string(), "web1".to_string())]),
             },
         ];
 
         storage.write_logs(&logs).await.unwrap();
 
         let results = storage
-            .query_logs_by_time(earlier, later, 10, 0)
+            .query_logs_by_time(earlier, later, &HashMap::new(), 10, 0)
             .await
             .unwrap();
 
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].raw_text, "log 1");
         assert_eq!(results[1].raw_text, "log 2");
+        assert_eq!(results[1].labels["host"], "web1");
+
+        let label_filters = HashMap::from([("host".to_string(), "web1".to_string())]);
+        let results = storage
+            .query_logs_by_time(earlier, later, &label_filters, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].raw_text, "log 2");
+    }
+
+    #[tokio::test]
+    async fn test_late_logs_side_table() {
+        let storage = setup_storage().await;
+        let now = Utc::now();
+        let late = StoredLog {
+            log_id: Uuid::new_v4(),
+            timestamp: now - chrono::Duration::minutes(5),
+            source_id: "test".to_string(),
+            raw_text: "late log".to_string(),
+            ingestion_time: now,
+            config_version: 1,
+            fields: Default::default(),
+            labels: Default::default(),
+        };
+
+        storage.write_late_logs(std::slice::from_ref(&late)).await.unwrap();
+
+        let results = storage.query_late_logs(10, 0).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].log_id, late.log_id);
+        assert!(storage.get_log(late.log_id).await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -1892,6 +2248,9 @@ mod tests {
             first_activity: timestamp,
             last_activity: timestamp,
             closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
         };
 
         storage.write_fiber(&fiber).await.unwrap();
@@ -1920,6 +2279,9 @@ mod tests {
             first_activity: timestamp,
             last_activity: timestamp,
             closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
         };
 
         storage.write_fiber(&fiber).await.unwrap();
@@ -1947,6 +2309,9 @@ mod tests {
                 first_activity: timestamp,
                 last_activity: timestamp,
                 closed: false,
+                metrics: Default::default(),
+                close_reason: None,
+                closing_pattern: None,
             },
             FiberRecord {
                 fiber_id: Uuid::new_v4(),
@@ -1956,6 +2321,9 @@ mod tests {
                 first_activity: timestamp + chrono::Duration::minutes(1),
                 last_activity: timestamp,
                 closed: false,
+                metrics: Default::default(),
+                close_reason: None,
+                closing_pattern: None,
             },
             FiberRecord {
                 fiber_id: Uuid::new_v4(),
@@ -1965,6 +2333,9 @@ mod tests {
                 first_activity: timestamp,
                 last_activity: timestamp,
                 closed: false,
+                metrics: Default::default(),
+                close_reason: None,
+                closing_pattern: None,
             },
         ];
 
@@ -1983,6 +2354,119 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_query_fibers_by_metrics() {
+        let storage = setup_storage().await;
+        let start = Utc::now();
+
+        let fiber = |secs: i64, errors: u64, api_logs: u64| {
+            let mut metrics = FiberMetrics {
+                log_count: api_logs + 1,
+                error_count: errors,
+                ..Default::default()
+            };
+            metrics.source_counts.insert("nginx".to_string(), 1);
+            metrics.source_counts.insert("api".to_string(), api_logs);
+            FiberRecord {
+                fiber_id: Uuid::new_v4(),
+                fiber_type: "request".to_string(),
+                config_version: 1,
+                attributes: serde_json::json!({}),
+                first_activity: start,
+                last_activity: start + chrono::Duration::seconds(secs),
+                closed: true,
+                metrics,
+                close_reason: Some(CloseReason::Timeout),
+                closing_pattern: None,
+            }
+        };
+        let fibers = [fiber(1, 0, 1), fiber(5, 2, 4), fiber(3, 1, 2)];
+        for fiber in &fibers {
+            storage.write_fiber(fiber).await.unwrap();
+        }
+
+        let query = |filters: &[&str], sort: Option<&str>| {
+            let filters: Vec<MetricFilter> = filters.iter().map(|f| f.parse().unwrap()).collect();
+            let sort: Option<MetricSort> = sort.map(|s| s.parse().unwrap());
+            let query = FiberQuery { metrics: filters, sort, limit: 10, ..Default::default() };
+            let storage = &storage;
+            async move {
+                let (results, total) = storage.query_fibers_filtered(&query).await.unwrap();
+                assert_eq!(results.len(), total);
+                results.into_iter().map(|f| f.fiber_id).collect::<Vec<_>>()
+            }
+        };
+
+        // Longest first by default
+        assert_eq!(
+            query(&[], None).await,
+            vec![fibers[1].fiber_id, fibers[2].fiber_id, fibers[0].fiber_id]
+        );
+        assert_eq!(
+            query(&["duration > 2s", "error_count < 2"], None).await,
+            vec![fibers[2].fiber_id]
+        );
+        assert_eq!(
+            query(&["source_counts.api >= 2"], Some("log_count asc")).await,
+            vec![fibers[2].fiber_id, fibers[1].fiber_id]
+        );
+
+        let stored = storage.get_fiber(fibers[1].fiber_id).await.unwrap().unwrap();
+        assert_eq!(stored.metrics, fibers[1].metrics);
+    }
+
+    #[tokio::test]
+    async fn test_query_fibers_by_set_attribute() {
+        let storage = setup_storage().await;
+        let now = Utc::now();
+
+        let fiber = |attributes: serde_json::Value| FiberRecord {
+            fiber_id: Uuid::new_v4(),
+            fiber_type: "request".to_string(),
+            config_version: 1,
+            attributes,
+            first_activity: now,
+            last_activity: now,
+            closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
+        };
+        let fibers = [
+            fiber(serde_json::json!({"backend": "10.0.0.1", "port": 80})),
+            fiber(serde_json::json!({"backend": ["10.0.0.2", "10.0.0.1"], "port": [80, 443]})),
+            fiber(serde_json::json!({"backend": ["10.0.0.2"], "port": 443})),
+        ];
+        for fiber in &fibers {
+            storage.write_fiber(fiber).await.unwrap();
+        }
+
+        let matching = |key: &str, value: &str| {
+            let query = FiberQuery {
+                attributes: [(key.to_string(), value.to_string())].into(),
+                limit: 10,
+                ..Default::default()
+            };
+            let storage = &storage;
+            async move {
+                let (results, _) = storage.query_fibers_filtered(&query).await.unwrap();
+                let mut ids: Vec<_> = results.into_iter().map(|f| f.fiber_id).collect();
+                ids.sort();
+                ids
+            }
+        };
+        let ids = |indices: &[usize]| {
+            let mut ids: Vec<_> = indices.iter().map(|&i| fibers[i].fiber_id).collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(matching("backend", "10.0.0.1").await, ids(&[0, 1]));
+        assert_eq!(matching("backend", "10.0.0.2").await, ids(&[1, 2]));
+        assert_eq!(matching("port", "443").await, ids(&[1, 2]));
+        assert_eq!(matching("backend", "10.0.0").await, ids(&[]));
+    }
+
     #[tokio::test]
     async fn test_write_memberships_and_query() {
         let storage = setup_storage().await;
@@ -1999,6 +2483,8 @@ mod tests {
             raw_text: "test log".to_string(),
             ingestion_time: timestamp,
             config_version: 1,
+            fields: Default::default(),
+            labels: Default::default(),
         };
         storage.write_logs(&[log]).await.unwrap();
 
@@ -2011,6 +2497,9 @@ mod tests {
             first_activity: timestamp,
             last_activity: timestamp,
             closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
         };
         let fiber2 = FiberRecord {
             fiber_id: fiber_id2,
@@ -2020,6 +2509,9 @@ mod tests {
             first_activity: timestamp,
             last_activity: timestamp,
             closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
         };
         storage.write_fiber(&fiber1).await.unwrap();
         storage.write_fiber(&fiber2).await.unwrap();
@@ -2046,6 +2538,68 @@ mod tests {
         assert!(fiber_ids.contains(&fiber_id2));
     }
 
+    #[tokio::test]
+    async fn test_fiber_links() {
+        let storage = setup_storage().await;
+        let start = Utc::now();
+
+        let fiber = |fiber_type: &str, offset_secs: i64| FiberRecord {
+            fiber_id: Uuid::new_v4(),
+            fiber_type: fiber_type.to_string(),
+            config_version: 1,
+            attributes: serde_json::json!({}),
+            first_activity: start + chrono::Duration::seconds(offset_secs),
+            last_activity: start + chrono::Duration::seconds(offset_secs),
+            closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
+        };
+        let request = fiber("request_trace", 0);
+        let queries = [fiber("db_query", 2), fiber("db_query", 1)];
+        for fiber in queries.iter().chain([&request]) {
+            storage.write_fiber(fiber).await.unwrap();
+        }
+
+        let links: Vec<FiberLink> = queries
+            .iter()
+            .map(|query| FiberLink {
+                parent_fiber_id: request.fiber_id,
+                child_fiber_id: query.fiber_id,
+                linked_at: query.last_activity,
+                config_version: 1,
+            })
+            .collect();
+        storage.write_fiber_links(&links).await.unwrap();
+        // A child keeps its first parent, as when it is linked again after
+        // a restart
+        let other_request = fiber("request_trace", 3);
+        storage.write_fiber(&other_request).await.unwrap();
+        storage
+            .write_fiber_links(&[FiberLink {
+                parent_fiber_id: other_request.fiber_id,
+                ..links[0].clone()
+            }])
+            .await
+            .unwrap();
+        assert!(storage.get_child_fibers(other_request.fiber_id, 10, 0).await.unwrap().is_empty());
+
+        let children = storage.get_child_fibers(request.fiber_id, 10, 0).await.unwrap();
+        assert_eq!(
+            children.iter().map(|f| f.fiber_id).collect::<Vec<_>>(),
+            vec![queries[1].fiber_id, queries[0].fiber_id]
+        );
+
+        let parents = storage.get_parent_fibers(queries[0].fiber_id).await.unwrap();
+        assert_eq!(parents.len(), 1);
+        assert_eq!(parents[0].fiber_id, request.fiber_id);
+        assert!(storage.get_parent_fibers(request.fiber_id).await.unwrap().is_empty());
+
+        // Links go with the fibers when reprocessing
+        storage.delete_fibers(1).await.unwrap();
+        assert!(storage.get_child_fibers(request.fiber_id, 10, 0).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_get_fiber_logs() {
         let storage = setup_storage().await;
@@ -2061,6 +2615,9 @@ mod tests {
             first_activity: timestamp,
             last_activity: timestamp,
             closed: false,
+            metrics: Default::default(),
+            close_reason: None,
+            closing_pattern: None,
         };
         storage.write_fiber(&fiber).await.unwrap();
 
@@ -2076,6 +2633,8 @@ mod tests {
                 raw_text: format!("log {}", i),
                 ingestion_time: timestamp,
                 config_version: 1,
+                fields: Default::default(),
+                labels: Default::default(),
             })
             .collect();
         storage.write_logs(&logs).await.unwrap();
@@ -2115,13 +2674,15 @@ mod tests {
                 raw_text: format!("log {}", i),
                 ingestion_time: timestamp,
                 config_version: 1,
+                fields: Default::default(),
+                labels: Default::default(),
             })
             .collect();
         storage.write_logs(&logs).await.unwrap();
 
         // Test pagination
         let page1 = storage
-            .query_logs_by_time(earlier, later, 2, 0)
+            .query_logs_by_time(earlier, later, &HashMap::new(), 2, 0)
             .await
             .unwrap();
         assert_eq!(page1.len(), 2);
@@ -2129,7 +2690,7 @@ mod tests {
         assert_eq!(page1[1].raw_text, "log 1");
 
         let page2 = storage
-            .query_logs_by_time(earlier, later, 2, 2)
+            .query_logs_by_time(earlier, later, &HashMap::new(), 2, 2)
             .await
             .unwrap();
         assert_eq!(page2.len(), 2);
@@ -0,0 +1,278 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Counts kept for every fiber as logs join it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FiberMetrics {
+    pub log_count: u64,
+    /// Member logs per source
+    #[serde(default)]
+    pub source_counts: BTreeMap<String, u64>,
+    /// Member logs matched by a pattern with `mark: error`
+    #[serde(default)]
+    pub error_count: u64,
+}
+
+impl FiberMetrics {
+    pub fn record(&mut self, source_id: &str, error: bool) {
+        self.log_count += 1;
+        *self.source_counts.entry(source_id.to_string()).or_default() += 1;
+        if error {
+            self.error_count += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: FiberMetrics) {
+        self.log_count += other.log_count;
+        for (source_id, count) in other.source_counts {
+            *self.source_counts.entry(source_id).or_default() += count;
+        }
+        self.error_count += other.error_count;
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MetricQueryError {
+    #[error("expected '<metric> <op> <value>', got '{0}'")]
+    Syntax(String),
+
+    #[error("unknown metric '{0}' (expected duration, log_count, error_count or source_counts.<source>)")]
+    UnknownMetric(String),
+
+    #[error("invalid value '{value}' for {metric}: {reason}")]
+    InvalidValue {
+        metric: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("expected '<metric> [asc|desc]', got '{0}'")]
+    Sort(String),
+}
+
+/// A fiber metric that queries can filter and sort on
+#[derive(Debug, Clone, PartialEq)]
+pub enum FiberMetric {
+    /// Time from first to last activity, in microseconds
+    Duration,
+    LogCount,
+    ErrorCount,
+    /// Member logs from one source
+    SourceCount(String),
+}
+
+impl FromStr for FiberMetric {
+    type Err = MetricQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "duration" => Ok(FiberMetric::Duration),
+            "log_count" => Ok(FiberMetric::LogCount),
+            "error_count" => Ok(FiberMetric::ErrorCount),
+            _ => match s.strip_prefix("source_counts.") {
+                // A double quote would end the JSON path the count is read with
+                Some(source_id) if !source_id.is_empty() && !source_id.contains('"') => {
+                    Ok(FiberMetric::SourceCount(source_id.to_string()))
+                }
+                _ => Err(MetricQueryError::UnknownMetric(s.to_string())),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparison {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Gt => ">",
+            Comparison::Gte => ">=",
+            Comparison::Lt => "<",
+            Comparison::Lte => "<=",
+        }
+    }
+}
+
+/// A condition on a fiber metric, such as `duration > 2s` or
+/// `error_count > 0`. Durations take units and are held in microseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFilter {
+    pub metric: FiberMetric,
+    pub comparison: Comparison,
+    pub value: i64,
+}
+
+impl FromStr for MetricFilter {
+    type Err = MetricQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        static FILTER: OnceLock<Regex> = OnceLock::new();
+        let filter = FILTER
+            .get_or_init(|| Regex::new(r"^\s*(\S+?)\s*(>=|<=|!=|==|=|>|<)\s*(.+?)\s*$").unwrap());
+        let captures = filter
+            .captures(s)
+            .ok_or_else(|| MetricQueryError::Syntax(s.to_string()))?;
+
+        let metric: FiberMetric = captures[1].parse()?;
+        let comparison = match &captures[2] {
+            "=" | "==" => Comparison::Eq,
+            "!=" => Comparison::Ne,
+            ">" => Comparison::Gt,
+            ">=" => Comparison::Gte,
+            "<" => Comparison::Lt,
+            _ => Comparison::Lte,
+        };
+
+        let text = &captures[3];
+        let invalid = |reason: String| MetricQueryError::InvalidValue {
+            metric: captures[1].to_string(),
+            value: text.to_string(),
+            reason,
+        };
+        let value = match metric {
+            FiberMetric::Duration => humantime::parse_duration(text)
+                .map_err(|e| invalid(e.to_string()))?
+                .as_micros()
+                .try_into()
+                .map_err(|_| invalid("too large".to_string()))?,
+            _ => text.parse().map_err(|_| invalid("expected a whole number".to_string()))?,
+        };
+
+        Ok(Self {
+            metric,
+            comparison,
+            value,
+        })
+    }
+}
+
+/// Order for a fiber query, such as `error_count desc`. Descending unless
+/// `asc` is given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSort {
+    pub metric: FiberMetric,
+    pub descending: bool,
+}
+
+impl FromStr for MetricSort {
+    type Err = MetricQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let metric = words
+            .next()
+            .ok_or_else(|| MetricQueryError::Sort(s.to_string()))?
+            .parse()?;
+        let descending = match words.next() {
+            None | Some("desc") => true,
+            Some("asc") => false,
+            Some(_) => return Err(MetricQueryError::Sort(s.to_string())),
+        };
+        if words.next().is_some() {
+            return Err(MetricQueryError::Sort(s.to_string()));
+        }
+        Ok(Self { metric, descending })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metric_filters() {
+        assert_eq!(
+            "duration > 2s".parse(),
+            Ok(MetricFilter {
+                metric: FiberMetric::Duration,
+                comparison: Comparison::Gt,
+                value: 2_000_000,
+            })
+        );
+        assert_eq!(
+            "error_count>0".parse(),
+            Ok(MetricFilter {
+                metric: FiberMetric::ErrorCount,
+                comparison: Comparison::Gt,
+                value: 0,
+            })
+        );
+        assert_eq!(
+            "source_counts.app/api.log <= 10".parse(),
+            Ok(MetricFilter {
+                metric: FiberMetric::SourceCount("app/api.log".to_string()),
+                comparison: Comparison::Lte,
+                value: 10,
+            })
+        );
+        assert_eq!(
+            "duration >= 1m 30s".parse::<MetricFilter>().map(|f| f.value),
+            Ok(90_000_000)
+        );
+
+        assert!(matches!(
+            "log_count".parse::<MetricFilter>(),
+            Err(MetricQueryError::Syntax(_))
+        ));
+        assert!(matches!(
+            "latency > 2s".parse::<MetricFilter>(),
+            Err(MetricQueryError::UnknownMetric(_))
+        ));
+        assert!(matches!(
+            "duration > 2".parse::<MetricFilter>(),
+            Err(MetricQueryError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            "log_count > many".parse::<MetricFilter>(),
+            Err(MetricQueryError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_metric_sort() {
+        assert_eq!(
+            "error_count".parse(),
+            Ok(MetricSort {
+                metric: FiberMetric::ErrorCount,
+                descending: true,
+            })
+        );
+        assert_eq!(
+            "duration asc".parse(),
+            Ok(MetricSort {
+                metric: FiberMetric::Duration,
+                descending: false,
+            })
+        );
+        assert!("duration sideways".parse::<MetricSort>().is_err());
+    }
+
+    #[test]
+    fn test_metrics_record_and_merge() {
+        let mut a = FiberMetrics::default();
+        a.record("nginx", false);
+        a.record("app", true);
+        let mut b = FiberMetrics::default();
+        b.record("app", false);
+
+        a.merge(b);
+        assert_eq!(a.log_count, 3);
+        assert_eq!(a.error_count, 1);
+        assert_eq!(a.source_counts["app"], 2);
+        assert_eq!(a.source_counts["nginx"], 1);
+    }
+}
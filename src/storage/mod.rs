@@ -1,5 +1,7 @@
 pub mod traits;
 pub mod duckdb;
 pub mod checkpoint;
+pub mod metrics;
 
+pub use metrics::FiberMetrics;
 pub use traits::{Storage, StorageError, StoredLog, FiberRecord, FiberMembership};
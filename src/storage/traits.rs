@@ -1,9 +1,13 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use thiserror::Error;
 use uuid::Uuid;
 
 use crate::storage::checkpoint::Checkpoint;
+use crate::storage::metrics::{FiberMetrics, MetricFilter, MetricSort};
 
 /// Stored log record
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +18,12 @@ pub struct StoredLog {
     pub raw_text: String,
     pub ingestion_time: DateTime<Utc>,
     pub config_version: u64,
+    /// Structured fields parsed by the source (e.g. syslog `hostname`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Labels of the log's source (e.g. `host`, `env`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
 
 /// Fiber record for storage
@@ -26,6 +36,100 @@ pub struct FiberRecord {
     pub first_activity: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub closed: bool,
+    #[serde(default)]
+    pub metrics: FiberMetrics,
+    /// Why the fiber closed (`None` while open, and for fibers closed before
+    /// reasons were recorded)
+    #[serde(default)]
+    pub close_reason: Option<CloseReason>,
+    /// The pattern that closed the fiber, for `CloseReason::Pattern`
+    #[serde(default)]
+    pub closing_pattern: Option<String>,
+}
+
+/// How a fiber came to be closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// A pattern with `close: true` matched
+    Pattern,
+    /// No log joined within `max_gap`
+    Timeout,
+    /// Still open when processing finished
+    Flush,
+    /// Open in storage but missing from the checkpoint on restart
+    Orphaned,
+    /// Absorbed into another fiber that shared one of its keys
+    Merged,
+}
+
+impl CloseReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::Pattern => "pattern",
+            CloseReason::Timeout => "timeout",
+            CloseReason::Flush => "flush",
+            CloseReason::Orphaned => "orphaned",
+            CloseReason::Merged => "merged",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown close reason '{0}'")]
+pub struct UnknownCloseReason(pub String);
+
+impl FromStr for CloseReason {
+    type Err = UnknownCloseReason;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pattern" => Ok(CloseReason::Pattern),
+            "timeout" => Ok(CloseReason::Timeout),
+            "flush" => Ok(CloseReason::Flush),
+            "orphaned" => Ok(CloseReason::Orphaned),
+            "merged" => Ok(CloseReason::Merged),
+            _ => Err(UnknownCloseReason(s.to_string())),
+        }
+    }
+}
+
+/// A fiber closed while processing, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiberClose {
+    pub fiber_id: Uuid,
+    pub reason: CloseReason,
+    /// The pattern that closed the fiber, for `CloseReason::Pattern`
+    pub pattern: Option<String>,
+}
+
+impl FiberRecord {
+    /// Time from the fiber's first to last activity
+    pub fn duration(&self) -> chrono::Duration {
+        self.last_activity - self.first_activity
+    }
+}
+
+/// Which fibers `query_fibers_filtered` returns. Unset filters match every
+/// fiber.
+#[derive(Debug, Clone, Default)]
+pub struct FiberQuery {
+    /// Fiber types to include; every type when empty
+    pub fiber_types: Vec<String>,
+    /// Attribute values a fiber must have
+    pub attributes: HashMap<String, String>,
+    /// Source labels some member log must carry
+    pub labels: HashMap<String, String>,
+    pub metrics: Vec<MetricFilter>,
+    /// Order of the results; longest first when unset
+    pub sort: Option<MetricSort>,
+    pub closed: Option<bool>,
+    pub close_reason: Option<CloseReason>,
+    /// Only fibers active at some point in `start_time..=end_time`
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub limit: usize,
+    pub offset: usize,
 }
 
 /// Many-to-many relationship between logs and fibers
@@ -36,6 +140,16 @@ pub struct FiberMembership {
     pub config_version: u64,
 }
 
+/// A fiber nested inside a fiber of its type's `parent` type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiberLink {
+    pub parent_fiber_id: Uuid,
+    pub child_fiber_id: Uuid,
+    /// Last activity of the child when it was linked
+    pub linked_at: DateTime<Utc>,
+    pub config_version: u64,
+}
+
 /// Config version record for storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigVersion {
@@ -91,11 +205,24 @@ pub trait Storage: Send + Sync {
     /// Get multiple logs by ID
     async fn get_logs_by_ids(&self, log_ids: &[Uuid]) -> Result<Vec<StoredLog>, StorageError>;
 
-    /// Query logs within a time range with pagination
+    /// Query logs within a time range with pagination, optionally only those
+    /// carrying all of the given source labels
     async fn query_logs_by_time(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        label_filters: &std::collections::HashMap<String, String>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<StoredLog>, StorageError>;
+
+    /// Write records that arrived behind the sequencer's watermark to the
+    /// `late_logs` side table, apart from the processed logs
+    async fn write_late_logs(&self, logs: &[StoredLog]) -> Result<(), StorageError>;
+
+    /// Query late records, most recently ingested first, with pagination
+    async fn query_late_logs(
+        &self,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<StoredLog>, StorageError>;
@@ -118,16 +245,12 @@ pub trait Storage: Send + Sync {
         offset: usize,
     ) -> Result<Vec<FiberRecord>, StorageError>;
 
-    /// Query fibers with filtering by type, attributes, time overlap, and closed status
+    /// Query fibers with filtering by type, attributes, member log labels,
+    /// time overlap, closed status or close reason, and metrics. Returns a
+    /// page of the matching fibers and how many match in all.
     async fn query_fibers_filtered(
         &self,
-        fiber_types: Option<&[String]>,
-        attribute_filters: &std::collections::HashMap<String, String>,
-        closed: Option<bool>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-        max_fibers: usize,
-        offset: usize,
+        query: &FiberQuery,
     ) -> Result<(Vec<FiberRecord>, usize), StorageError>;
 
     // Memberships
@@ -157,6 +280,22 @@ pub trait Storage: Send + Sync {
     /// Get all unique source IDs
     async fn get_all_source_ids(&self) -> Result<Vec<String>, StorageError>;
 
+    // Links
+    /// Write parent/child links between fibers. A child already linked keeps
+    /// its parent.
+    async fn write_fiber_links(&self, links: &[FiberLink]) -> Result<(), StorageError>;
+
+    /// Get the fibers nested inside a fiber, by first activity
+    async fn get_child_fibers(
+        &self,
+        fiber_id: Uuid,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<FiberRecord>, StorageError>;
+
+    /// Get the fibers a fiber is nested inside
+    async fn get_parent_fibers(&self, fiber_id: Uuid) -> Result<Vec<FiberRecord>, StorageError>;
+
     // Checkpoints
     /// Load the latest checkpoint from storage
     async fn load_checkpoint(&self) -> Result<Option<Checkpoint>, StorageError>;
@@ -227,7 +366,7 @@ pub trait Storage: Send + Sync {
         end: Option<DateTime<Utc>>,
     ) -> Result<u64, StorageError>;
 
-    /// Delete fibers for a config version
+    /// Delete fibers (and the links between them) for a config version
     async fn delete_fibers(
         &self,
         config_version: u64,
@@ -6,18 +6,29 @@ use axum::{
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::config::diff::create_diff_with_context;
-use crate::config::types::{Config, FiberTypeConfig};
+use crate::config::expand_tilde;
+use crate::config::parse::{create_auto_source_fiber_config, parse_source_config};
+use crate::config::types::{Config, FiberTypeConfig, SourceConfig};
 use crate::config::version::compute_config_hash;
 use crate::fiber::processor::FiberProcessor;
+use crate::pipeline::{SourceControl, SourceControlError};
 use crate::reprocessing::{ReprocessProgress, ReprocessState, ReprocessStatus};
-use crate::storage::traits::{ConfigSource, ConfigVersion, FiberRecord, Storage, StorageError, StoredLog};
+use crate::sequencer::local::SequencerStatus;
+use crate::sequencer::merge::SharedSequencerStatus;
+use crate::source::stats::SourceStats;
+use crate::storage::checkpoint::SharedSourceStates;
+use crate::storage::metrics::{FiberMetrics, MetricFilter, MetricSort};
+use crate::storage::traits::{
+    CloseReason, ConfigSource, ConfigVersion, FiberQuery, FiberRecord, Storage, StorageError,
+    StoredLog,
+};
 
 /// Shared application state
 #[derive(Clone)]
@@ -30,6 +41,11 @@ pub struct AppState {
     pub config_path: PathBuf,
     pub config_yaml: Arc<RwLock<String>>,
     pub reprocess_state: Arc<RwLock<Option<ReprocessState>>>,
+    pub source_states: SharedSourceStates,
+    pub sequencer_status: SharedSequencerStatus,
+    /// Starts and retires local sources; None when the pipeline isn't
+    /// following local sources
+    pub source_control: Option<Arc<SourceControl>>,
 }
 
 // ============================================================================
@@ -41,6 +57,8 @@ pub struct ListLogsParams {
     pub start: Option<DateTime<Utc>>,
     pub end: Option<DateTime<Utc>>,
     pub source: Option<String>,
+    /// Comma-separated `name:value` label filters (e.g. `host:web1,env:prod`)
+    pub labels: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: usize,
     #[serde(default)]
@@ -48,6 +66,24 @@ pub struct ListLogsParams {
 }
 
 impl ListLogsParams {
+    pub fn label_filters(&self) -> Result<HashMap<String, String>, ApiError> {
+        let Some(labels) = self.labels.as_deref().filter(|labels| !labels.is_empty()) else {
+            return Ok(HashMap::new());
+        };
+        labels
+            .split(',')
+            .map(|filter| match filter.split_once(':') {
+                Some((name, value)) if !name.is_empty() => {
+                    Ok((name.to_string(), value.to_string()))
+                }
+                _ => Err(ApiError::BadRequest(format!(
+                    "Invalid label filter '{}': expected name:value",
+                    filter
+                ))),
+            })
+            .collect()
+    }
+
     pub fn limit(&self) -> usize {
         self.limit.min(1000)
     }
@@ -138,6 +174,10 @@ pub struct LogDto {
     pub source_id: String,
     pub raw_text: String,
     pub ingestion_time: DateTime<Utc>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
 
 impl From<StoredLog> for LogDto {
@@ -148,6 +188,8 @@ impl From<StoredLog> for LogDto {
             source_id: log.source_id,
             raw_text: log.raw_text,
             ingestion_time: log.ingestion_time,
+            fields: log.fields,
+            labels: log.labels,
         }
     }
 }
@@ -160,11 +202,22 @@ pub struct FiberDto {
     pub first_activity: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub closed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<CloseReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closing_pattern: Option<String>,
+    pub duration_ms: i64,
+    #[serde(flatten)]
+    pub metrics: FiberMetrics,
 }
 
 impl From<FiberRecord> for FiberDto {
     fn from(fiber: FiberRecord) -> Self {
         Self {
+            duration_ms: fiber.duration().num_milliseconds(),
+            metrics: fiber.metrics,
+            close_reason: fiber.close_reason,
+            closing_pattern: fiber.closing_pattern,
             id: fiber.fiber_id,
             fiber_type: fiber.fiber_type,
             attributes: fiber.attributes,
@@ -201,7 +254,18 @@ pub struct FilteredFibersParams {
     pub types: Vec<String>,
     #[serde(default)]
     pub attributes: HashMap<String, String>,
+    /// Only fibers with a log carrying all of these source labels
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Conditions on fiber metrics, such as `duration > 2s` or `error_count > 0`
+    #[serde(default)]
+    pub metrics: Vec<String>,
+    /// Metric to order by, such as `log_count asc` (default `duration desc`)
+    pub sort: Option<String>,
     pub closed: Option<bool>,
+    /// Only fibers closed this way (`pattern`, `timeout`, `flush`,
+    /// `orphaned` or `merged`)
+    pub close_reason: Option<CloseReason>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     #[serde(default = "default_max_fibers")]
@@ -278,11 +342,32 @@ pub struct CreateFiberTypeResponse {
     pub new_version_hash: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AddSourceRequest {
+    pub name: String,
+    pub yaml_content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceChangeResponse {
+    pub name: String,
+    pub new_version_hash: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SuccessResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ActivateConfigResponse {
+    pub message: String,
+    /// Sources that couldn't be started or stopped to match the activated
+    /// config; they need a restart
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub source_errors: Vec<String>,
+}
+
 // ============================================================================
 // Working Set Testing Types
 // ============================================================================
@@ -378,6 +463,15 @@ impl IntoResponse for ApiError {
     }
 }
 
+impl From<SourceControlError> for ApiError {
+    fn from(err: SourceControlError) -> Self {
+        match err {
+            SourceControlError::Stopped => ApiError::Conflict(err.to_string()),
+            _ => ApiError::BadRequest(err.to_string()),
+        }
+    }
+}
+
 impl From<StorageError> for ApiError {
     fn from(err: StorageError) -> Self {
         match err {
@@ -409,9 +503,32 @@ pub async fn list_logs(
     let start = params.start.unwrap_or_else(|| Utc::now() - chrono::Duration::days(1));
     let end = params.end.unwrap_or_else(|| Utc::now());
 
+    let label_filters = params.label_filters()?;
+
     let logs = state
         .storage
-        .query_logs_by_time(start, end, params.limit(), params.offset())
+        .query_logs_by_time(start, end, &label_filters, params.limit(), params.offset())
+        .await?;
+
+    let total = logs.len();
+    let logs_dto = logs.into_iter().map(LogDto::from).collect();
+
+    Ok(Json(LogsResponse {
+        logs: logs_dto,
+        total,
+        limit: params.limit(),
+        offset: params.offset(),
+    }))
+}
+
+/// GET /api/logs/late - Records stored in the `late_logs` side table
+pub async fn list_late_logs(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<LogsResponse>, ApiError> {
+    let logs = state
+        .storage
+        .query_late_logs(params.limit(), params.offset())
         .await?;
 
     let total = logs.len();
@@ -607,31 +724,81 @@ pub async fn get_fiber_logs(
     }))
 }
 
+/// GET /api/fibers/:id/children - Fibers nested inside this one
+pub async fn get_fiber_children(
+    State(state): State<AppState>,
+    Path(fiber_id): Path<Uuid>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<FibersResponse>, ApiError> {
+    state
+        .storage
+        .get_fiber(fiber_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Fiber not found: {}", fiber_id)))?;
+
+    let fibers: Vec<FiberDto> = state
+        .storage
+        .get_child_fibers(fiber_id, params.limit(), params.offset())
+        .await?
+        .into_iter()
+        .map(FiberDto::from)
+        .collect();
+
+    Ok(Json(FibersResponse {
+        total: fibers.len(),
+        fibers,
+        limit: params.limit(),
+        offset: params.offset(),
+    }))
+}
+
+/// GET /api/fibers/:id/parents - Fibers this one is nested inside
+pub async fn get_fiber_parents(
+    State(state): State<AppState>,
+    Path(fiber_id): Path<Uuid>,
+) -> Result<Json<Vec<FiberDto>>, ApiError> {
+    state
+        .storage
+        .get_fiber(fiber_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Fiber not found: {}", fiber_id)))?;
+
+    let fibers = state.storage.get_parent_fibers(fiber_id).await?;
+    Ok(Json(fibers.into_iter().map(FiberDto::from).collect()))
+}
+
 /// POST /api/fibers/query - Filtered fiber query with time overlap, attribute filters, etc.
 pub async fn query_fibers_filtered(
     State(state): State<AppState>,
     Json(params): Json<FilteredFibersParams>,
 ) -> Result<Json<FilteredFibersResponse>, ApiError> {
-    let fiber_types = if params.types.is_empty() {
-        None
-    } else {
-        Some(params.types.as_slice())
+    let metrics = params
+        .metrics
+        .iter()
+        .map(|filter| filter.parse::<MetricFilter>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let sort = params
+        .sort
+        .as_deref()
+        .map(str::parse::<MetricSort>)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let query = FiberQuery {
+        fiber_types: params.types,
+        attributes: params.attributes,
+        labels: params.labels,
+        metrics,
+        sort,
+        closed: params.closed,
+        close_reason: params.close_reason,
+        start_time: params.start_time,
+        end_time: params.end_time,
+        limit: params.max_fibers.min(1000),
+        offset: params.offset,
     };
-
-    let max_fibers = params.max_fibers.min(1000);
-
-    let (fibers, total_matching) = state
-        .storage
-        .query_fibers_filtered(
-            fiber_types,
-            &params.attributes,
-            params.closed,
-            params.start_time,
-            params.end_time,
-            max_fibers,
-            params.offset,
-        )
-        .await?;
+    let (fibers, total_matching) = state.storage.query_fibers_filtered(&query).await?;
 
     let truncated = total_matching > params.offset + fibers.len();
 
@@ -728,7 +895,7 @@ fn simplify_log_points(points: Vec<(DateTime<Utc>, String)>) -> Vec<LogPoint> {
 
 #[cfg(test)]
 mod tests {
-    use super::simplify_log_points;
+    use super::{add_entry_to_yaml_section, delete_entry_from_yaml_section, simplify_log_points};
     use chrono::{DateTime, Utc};
 
     fn ts(micros: i64) -> DateTime<Utc> {
@@ -762,6 +929,34 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn yaml_section_entries_added_and_deleted_at_top_level() {
+        let yaml = "\
+sources:
+  nginx:
+    type: file
+    path: /var/log/nginx.log
+
+fiber_types:
+  request:
+    sources:
+      nginx:
+        patterns: []";
+
+        let added =
+            add_entry_to_yaml_section(yaml, "sources", "app:\n  type: file\n  path: /var/log/app.log")
+                .unwrap();
+        assert!(added.contains("  app:\n    type: file\n    path: /var/log/app.log\n"));
+        assert!(added.find("  app:").unwrap() < added.find("fiber_types:").unwrap());
+
+        // The fiber type's own `sources:` key is left alone
+        let deleted = delete_entry_from_yaml_section(&added, "sources", "nginx").unwrap();
+        assert!(!deleted.contains("/var/log/nginx.log"));
+        assert!(deleted.contains("      nginx:\n        patterns: []"));
+
+        assert!(delete_entry_from_yaml_section(&deleted, "sources", "path").is_err());
+    }
 }
 
 /// GET /api/fiber-types
@@ -810,6 +1005,229 @@ pub async fn list_sources(
     Ok(Json(sources))
 }
 
+/// POST /api/sources - Start reading a new source without restarting
+pub async fn add_source(
+    State(state): State<AppState>,
+    Json(req): Json<AddSourceRequest>,
+) -> Result<Json<SourceChangeResponse>, ApiError> {
+    // 1. Parse and validate the incoming source YAML
+    let source = parse_source_config(&req.name, &req.yaml_content)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let control = runtime_source_control(&state)?;
+
+    // 2. Acquire write locks in the order config activation takes them
+    let mut processor_guard = state.fiber_processor.write().await;
+    let version = *state.config_version.read().await;
+    let mut config = state.config.write().await;
+    if config.sources.contains_key(&req.name) {
+        return Err(ApiError::Conflict(format!("Source '{}' already exists", req.name)));
+    }
+    let mut config_yaml_guard = state.config_yaml.write().await;
+
+    // 3. Add the source to the YAML string (preserves comments)
+    let indented_content = req.yaml_content
+        .lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("  {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let full_yaml = format!("{}:\n{}", req.name, indented_content);
+    let updated_yaml = add_entry_to_yaml_section(&config_yaml_guard, "sources", &full_yaml)?;
+    let _updated_config: Config = serde_yaml::from_str(&updated_yaml)
+        .map_err(|e| ApiError::Internal(format!("Failed to parse updated config: {}", e)))?;
+
+    // 4. Add the source's auto fiber before its first record can arrive,
+    // then let the pipeline have the processor back while the source starts.
+    // If starting fails, the unused fiber type only matches this source.
+    let auto_fiber = config.auto_source_fibers && config.fiber_types.is_some();
+    if auto_fiber {
+        processor_guard
+            .add_source_fiber_type(&req.name, version)
+            .map_err(|e| ApiError::Internal(format!("Failed to add source fiber type: {}", e)))?;
+    }
+    drop(processor_guard);
+
+    // 5. Start reading, then update in-memory state
+    control.start(&req.name, &source, None).await?;
+    if auto_fiber {
+        config
+            .fiber_types
+            .get_or_insert_with(HashMap::new)
+            .entry(req.name.clone())
+            .or_insert_with(|| create_auto_source_fiber_config(&req.name));
+    }
+    config.sources.insert(req.name.clone(), source);
+    *config_yaml_guard = updated_yaml.clone();
+
+    // 6. Record the running config as a new active version
+    let new_hash = record_active_config_version(state.storage.as_ref(), updated_yaml).await?;
+
+    Ok(Json(SourceChangeResponse {
+        name: req.name,
+        new_version_hash: new_hash,
+    }))
+}
+
+/// DELETE /api/sources/:name - Stop reading a source without restarting
+///
+/// Logs and fibers already stored for the source are kept, as is its auto
+/// source fiber type.
+pub async fn delete_source(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<SourceChangeResponse>, ApiError> {
+    let control = runtime_source_control(&state)?;
+
+    let mut config = state.config.write().await;
+    if !config.sources.contains_key(&name) {
+        return Err(ApiError::NotFound(format!("Source not found: {}", name)));
+    }
+    let mut config_yaml_guard = state.config_yaml.write().await;
+
+    // 1. Delete the source from the YAML string (preserves comments)
+    let updated_yaml = delete_entry_from_yaml_section(&config_yaml_guard, "sources", &name)?;
+    let _updated_config: Config = serde_yaml::from_str(&updated_yaml)
+        .map_err(|e| ApiError::Internal(format!("Failed to parse updated config: {}", e)))?;
+
+    // 2. Stop reading, then update in-memory state
+    control.retire(&name).await?;
+    config.sources.remove(&name);
+    *config_yaml_guard = updated_yaml.clone();
+
+    // 3. Record the running config as a new active version
+    let new_hash = record_active_config_version(state.storage.as_ref(), updated_yaml).await?;
+
+    Ok(Json(SourceChangeResponse {
+        name,
+        new_version_hash: new_hash,
+    }))
+}
+
+fn runtime_source_control(state: &AppState) -> Result<&SourceControl, ApiError> {
+    state.source_control.as_deref().ok_or_else(|| {
+        ApiError::Conflict(
+            "Sources can only be changed at runtime while following local sources (restart required)"
+                .to_string(),
+        )
+    })
+}
+
+/// Save a config changed through the UI as the active version, returning its hash
+async fn record_active_config_version(storage: &dyn Storage, yaml: String) -> Result<String, ApiError> {
+    let new_hash = compute_config_hash(&yaml);
+
+    // Check if this version already exists (same YAML hash)
+    if storage.get_config_version(&new_hash).await?.is_some() {
+        storage.touch_config_version(&new_hash).await?;
+    } else {
+        let parent_hash = storage
+            .get_active_config_version()
+            .await?
+            .map(|v| v.version_hash);
+
+        let config_version = ConfigVersion {
+            version_hash: new_hash.clone(),
+            parent_hash,
+            yaml_content: yaml,
+            created_at: Utc::now(),
+            source: ConfigSource::UI,
+            is_active: false,
+        };
+
+        storage.insert_config_version(&config_version).await?;
+    }
+    storage.mark_config_active(&new_hash).await?;
+
+    Ok(new_hash)
+}
+
+/// Start, retire or restart sources so the running pipeline matches a newly
+/// activated config.
+///
+/// A changed source that still reads the same path the same way carries on
+/// from where it was; otherwise it starts over from its configured start.
+/// Every change is attempted, and the ones that failed are returned.
+async fn apply_source_changes(
+    control: Option<&SourceControl>,
+    old: &HashMap<String, SourceConfig>,
+    new: &HashMap<String, SourceConfig>,
+) -> Vec<String> {
+    let same = |a: &SourceConfig, b: &SourceConfig| {
+        serde_yaml::to_value(a).ok() == serde_yaml::to_value(b).ok()
+    };
+    let same_position = |a: &SourceConfig, b: &SourceConfig| {
+        a.source_type == b.source_type
+            && a.path == b.path
+            && serde_yaml::to_value(&a.read).ok() == serde_yaml::to_value(&b.read).ok()
+    };
+    let mut retired: Vec<&String> = old
+        .iter()
+        .filter(|(name, source)| new.get(*name).is_none_or(|new_source| !same(source, new_source)))
+        .map(|(name, _)| name)
+        .collect();
+    let mut started: Vec<&String> = new
+        .iter()
+        .filter(|(name, source)| old.get(*name).is_none_or(|old_source| !same(source, old_source)))
+        .map(|(name, _)| name)
+        .collect();
+    if retired.is_empty() && started.is_empty() {
+        return Vec::new();
+    }
+
+    let Some(control) = control else {
+        tracing::warn!("Activated config changes sources; restart to apply them");
+        return Vec::new();
+    };
+
+    retired.sort();
+    started.sort();
+    let mut failures = Vec::new();
+    let mut positions = HashMap::new();
+    for name in retired {
+        match control.retire(name).await {
+            Ok(checkpoints) => {
+                positions.insert(name.as_str(), checkpoints);
+            }
+            Err(e) => failures.push(format!("failed to stop source '{}': {}", name, e)),
+        }
+    }
+    for name in started {
+        let checkpoints = old
+            .get(name)
+            .filter(|old_source| same_position(old_source, &new[name]))
+            .and_then(|_| positions.get(name.as_str()));
+        if let Err(e) = control.start(name, &new[name], checkpoints).await {
+            failures.push(format!("failed to start source '{}': {}", name, e));
+        }
+    }
+    for failure in &failures {
+        tracing::error!("{}", failure);
+    }
+    failures
+}
+
+/// Runtime counters of the sources read by this instance
+pub async fn get_source_stats(
+    State(state): State<AppState>,
+) -> Result<Json<BTreeMap<String, SourceStats>>, ApiError> {
+    let states = state.source_states.read().await;
+    let stats = states
+        .iter()
+        .filter_map(|(source_id, source_state)| {
+            let guard = source_state.lock().ok()?;
+            Some((source_id.clone(), guard.stats.clone()))
+        })
+        .collect();
+    Ok(Json(stats))
+}
+
+/// GET /api/sequencer/status - Which source is holding back the local sequencer
+pub async fn get_sequencer_status(
+    State(state): State<AppState>,
+) -> Result<Json<SequencerStatus>, ApiError> {
+    let status = state.sequencer_status.lock().unwrap().clone();
+    Ok(Json(status))
+}
+
 // ============================================================================
 // Config Versioning API
 // ============================================================================
@@ -998,7 +1416,7 @@ pub async fn get_config_diff(
 pub async fn activate_config_version(
     State(state): State<AppState>,
     Path(hash): Path<String>,
-) -> Result<Json<SuccessResponse>, ApiError> {
+) -> Result<Json<ActivateConfigResponse>, ApiError> {
     // 1. Check if reprocessing is running
     {
         let reprocess_guard = state.reprocess_state.read().await;
@@ -1019,43 +1437,50 @@ pub async fn activate_config_version(
         .ok_or_else(|| ApiError::NotFound(format!("Config version not found: {}", hash)))?;
 
     // 3. Parse the YAML into a Config struct to validate it
-    let new_config: Config = serde_yaml::from_str(&config_version.yaml_content)
+    let mut new_config: Config = serde_yaml::from_str(&config_version.yaml_content)
         .map_err(|e| ApiError::BadRequest(format!("Invalid config YAML: {}", e)))?;
-
-    // 4. Acquire write locks (blocks in-flight log processing)
-    let mut processor_guard = state.fiber_processor.write().await;
-    let mut version_guard = state.config_version.write().await;
-    let mut config_guard = state.config.write().await;
-
-    // 5. Flush old processor (closes all open fibers)
-    let flush_results = processor_guard.flush();
-
-    // Write flush results to storage
-    for result in flush_results {
-        for fiber in &result.new_fibers {
-            state.storage.write_fiber(fiber).await?;
-        }
-        for fiber in &result.updated_fibers {
-            state.storage.update_fiber(fiber).await?;
-        }
-        if !result.memberships.is_empty() {
-            state.storage.write_memberships(&result.memberships).await?;
-        }
+    for source in new_config.sources.values_mut() {
+        source.path = expand_tilde(&source.path);
     }
 
-    // 6. Compute new version number from hash
+    // 4. Compute new version number from hash
     let new_version = hash
         .parse::<u64>()
         .unwrap_or_else(|_| config_version.yaml_content.as_bytes().iter().map(|&b| b as u64).sum());
 
-    // 7. Create new processor with the new config
+    // 5. Create new processor with the new config; nothing has changed yet
+    // if this fails
     let new_processor = FiberProcessor::from_config(&new_config, new_version)
         .map_err(|e| ApiError::Internal(format!("Failed to create processor: {}", e)))?;
+    let new_sources = new_config.sources.clone();
 
-    // 8. Replace processor, config, and version
-    *processor_guard = new_processor;
-    *config_guard = new_config;
-    *version_guard = new_version;
+    let old_sources = {
+        // 6. Acquire write locks (blocks in-flight log processing)
+        let mut processor_guard = state.fiber_processor.write().await;
+        let mut version_guard = state.config_version.write().await;
+        let mut config_guard = state.config.write().await;
+
+        // 7. Flush old processor (closes all open fibers)
+        let flush_results = processor_guard.flush();
+
+        // Write flush results to storage
+        for result in flush_results {
+            for fiber in &result.new_fibers {
+                state.storage.write_fiber(fiber).await?;
+            }
+            for fiber in &result.updated_fibers {
+                state.storage.update_fiber(fiber).await?;
+            }
+            if !result.memberships.is_empty() {
+                state.storage.write_memberships(&result.memberships).await?;
+            }
+        }
+
+        // 8. Replace processor, config, and version
+        *processor_guard = new_processor;
+        *version_guard = new_version;
+        std::mem::replace(&mut *config_guard, new_config).sources
+    };
 
     // 9. Update in-memory YAML
     {
@@ -1066,8 +1491,22 @@ pub async fn activate_config_version(
     // 10. Mark this version as active in database
     state.storage.mark_config_active(&hash).await?;
 
-    Ok(Json(SuccessResponse {
-        message: format!("Config version {} activated successfully", &hash[..8]),
+    // 11. Start and retire sources the new version adds, removes or changes,
+    // once log processing can carry on
+    let source_errors =
+        apply_source_changes(state.source_control.as_deref(), &old_sources, &new_sources).await;
+
+    let message = if source_errors.is_empty() {
+        format!("Config version {} activated successfully", &hash[..8])
+    } else {
+        format!(
+            "Config version {} activated, but some sources could not be updated",
+            &hash[..8]
+        )
+    };
+    Ok(Json(ActivateConfigResponse {
+        message,
+        source_errors,
     }))
 }
 
@@ -1203,8 +1642,9 @@ pub async fn update_fiber_type(
             )));
         }
 
-        let yaml_after_delete = delete_fiber_type_from_yaml(&current_yaml, &original_name)?;
-        add_fiber_type_to_yaml(&yaml_after_delete, &new_name, &req.yaml_content)?
+        let yaml_after_delete =
+            delete_entry_from_yaml_section(&current_yaml, "fiber_types", &original_name)?;
+        add_entry_to_yaml_section(&yaml_after_delete, "fiber_types", &req.yaml_content)?
     } else {
         // Update: replace existing fiber type
         update_fiber_type_in_yaml(&current_yaml, &original_name, &req.yaml_content)?
@@ -1366,7 +1806,7 @@ pub async fn delete_fiber_type(
     let current_yaml = config_yaml_guard.clone();
 
     // 3. Delete the fiber type from the YAML string (preserves comments)
-    let updated_yaml = delete_fiber_type_from_yaml(&current_yaml, &name)?;
+    let updated_yaml = delete_entry_from_yaml_section(&current_yaml, "fiber_types", &name)?;
 
     // 4. Parse the updated YAML to validate it
     let _updated_config: Config = serde_yaml::from_str(&updated_yaml)
@@ -1444,7 +1884,7 @@ pub async fn create_fiber_type(
     let full_yaml = format!("{}:\n{}", req.name, indented_content);
 
     // 5. Add the fiber type to the YAML string (preserves comments)
-    let updated_yaml = add_fiber_type_to_yaml(&current_yaml, &req.name, &full_yaml)?;
+    let updated_yaml = add_entry_to_yaml_section(&current_yaml, "fiber_types", &full_yaml)?;
 
     // 5. Parse the updated YAML to validate it
     let _updated_config: Config = serde_yaml::from_str(&updated_yaml)
@@ -1728,7 +2168,7 @@ pub async fn test_working_set(
     // 7. Query all logs in time window (limit to reasonable amount, e.g., 10,000)
     let window_logs = state
         .storage
-        .query_logs_by_time(time_window_start, time_window_end, 10000, 0)
+        .query_logs_by_time(time_window_start, time_window_end, &HashMap::new(), 10000, 0)
         .await?;
 
     // 8. Convert StoredLog to LogRecord and process through temporary processor
@@ -1741,6 +2181,9 @@ pub async fn test_working_set(
             source_id: stored_log.source_id.clone(),
             raw_text: stored_log.raw_text.clone(),
             file_offset: 0, // Not needed for testing
+            fields: stored_log.fields.clone(),
+            labels: stored_log.labels.clone(),
+            late: false,
         };
 
         let results = temp_processor.process_log(&log_record);
@@ -1968,37 +2411,38 @@ fn update_fiber_type_in_yaml(yaml: &str, name: &str, new_fiber_yaml: &str) -> Re
     Ok(result.join("\n"))
 }
 
-/// Add a new fiber type to the config YAML string while preserving comments
-/// The fiber_yaml should include the fiber type name line (e.g., "request_trace:\n  description: ...")
-fn add_fiber_type_to_yaml(yaml: &str, _name: &str, fiber_yaml: &str) -> Result<String, ApiError> {
-    // Find the fiber_types section and add the new fiber type at the end
+/// Add a new entry (a fiber type or a source) to a top-level section of the
+/// config YAML string while preserving comments
+/// The entry_yaml should include the entry's name line (e.g., "request_trace:\n  description: ...")
+fn add_entry_to_yaml_section(yaml: &str, section: &str, entry_yaml: &str) -> Result<String, ApiError> {
+    // Find the section and add the new entry at the end
+    let section_line = format!("{}:", section);
     let lines: Vec<&str> = yaml.lines().collect();
     let mut result = Vec::new();
     let mut i = 0;
-    let mut found_fiber_types = false;
-    let mut fiber_types_indent;
+    let mut found_section = false;
+    let mut entry_indent;
 
     while i < lines.len() {
         let line = lines[i];
-        let trimmed = line.trim_start();
 
-        // Check if we're at fiber_types section
-        if trimmed.starts_with("fiber_types:") {
-            found_fiber_types = true;
+        // Check if we're at the section
+        if line.starts_with(&section_line) {
+            found_section = true;
             result.push(line.to_string());
             i += 1;
 
-            // Find the indent level of fiber type entries
+            // Find the indent level of the section's entries
             if i < lines.len() {
                 let next_line = lines[i];
                 let next_trimmed = next_line.trim_start();
                 if !next_trimmed.is_empty() && !next_trimmed.starts_with('#') {
-                    fiber_types_indent = next_line.len() - next_trimmed.len();
+                    entry_indent = next_line.len() - next_trimmed.len();
                 } else {
-                    fiber_types_indent = 2; // Default 2-space indent
+                    entry_indent = 2; // Default 2-space indent
                 }
             } else {
-                fiber_types_indent = 2;
+                entry_indent = 2;
             }
 
             // Keep adding lines until we hit the next top-level section
@@ -2008,11 +2452,11 @@ fn add_fiber_type_to_yaml(yaml: &str, _name: &str, fiber_yaml: &str) -> Result<S
                 let next_trimmed = next_line.trim_start();
                 let next_indent = next_line.len() - next_trimmed.len();
 
-                // If we hit a top-level section (0 indent), insert the new fiber type here
+                // If we hit a top-level section (0 indent), insert the new entry here
                 if !next_trimmed.is_empty() && !next_trimmed.starts_with('#') && next_indent == 0 {
-                    // Add the new fiber type (already includes name line)
-                    for new_line in fiber_yaml.lines() {
-                        result.push(format!("{}{}", " ".repeat(fiber_types_indent), new_line));
+                    // Add the new entry (already includes name line)
+                    for new_line in entry_yaml.lines() {
+                        result.push(format!("{}{}", " ".repeat(entry_indent), new_line));
                     }
                     result.push(String::new()); // Add blank line
                     inserted = true;
@@ -2025,9 +2469,9 @@ fn add_fiber_type_to_yaml(yaml: &str, _name: &str, fiber_yaml: &str) -> Result<S
 
             // If we reached the end of the file without inserting, add at the end
             if !inserted {
-                result.push(String::new()); // Add blank line before new fiber type
-                for new_line in fiber_yaml.lines() {
-                    result.push(format!("{}{}", " ".repeat(fiber_types_indent), new_line));
+                result.push(String::new()); // Add blank line before new entry
+                for new_line in entry_yaml.lines() {
+                    result.push(format!("{}{}", " ".repeat(entry_indent), new_line));
                 }
             }
             continue;
@@ -2037,8 +2481,8 @@ fn add_fiber_type_to_yaml(yaml: &str, _name: &str, fiber_yaml: &str) -> Result<S
         i += 1;
     }
 
-    if !found_fiber_types {
-        return Err(ApiError::Internal("fiber_types section not found in config".to_string()));
+    if !found_section {
+        return Err(ApiError::Internal(format!("{} section not found in config", section)));
     }
 
     Ok(result.join("\n"))
@@ -2145,41 +2589,44 @@ fn extract_fiber_type_yaml_with_name(yaml: &str, name: &str) -> Result<String, A
     Ok(result.join("\n"))
 }
 
-/// Remove a fiber type from the config YAML string while preserving comments
-fn delete_fiber_type_from_yaml(yaml: &str, name: &str) -> Result<String, ApiError> {
+/// Remove an entry (a fiber type or a source) from a top-level section of the
+/// config YAML string while preserving comments
+fn delete_entry_from_yaml_section(yaml: &str, section: &str, name: &str) -> Result<String, ApiError> {
+    let section_line = format!("{}:", section);
     let lines: Vec<&str> = yaml.lines().collect();
     let mut result = Vec::new();
     let mut i = 0;
-    let mut in_fiber_types = false;
+    let mut in_section = false;
+    // Indent of the section's entries, taken from the first one
+    let mut entry_indent = None;
     let mut found_target = false;
 
     while i < lines.len() {
         let line = lines[i];
         let trimmed = line.trim_start();
 
-        // Check if we're entering fiber_types section
-        if trimmed.starts_with("fiber_types:") {
-            in_fiber_types = true;
+        // Check if we're entering the section
+        if line.starts_with(&section_line) {
+            in_section = true;
             result.push(line.to_string());
             i += 1;
             continue;
         }
 
-        // If we're in fiber_types, look for our target fiber
-        if in_fiber_types {
+        // If we're in the section, look for our target entry
+        if in_section && !trimmed.is_empty() && !trimmed.starts_with('#') {
             let current_indent = line.len() - trimmed.len();
 
             // If we hit another top-level section, we're done
-            if !trimmed.is_empty() && !trimmed.starts_with('#') && current_indent == 0 {
-                in_fiber_types = false;
+            if current_indent == 0 {
+                in_section = false;
             }
+            let is_entry = in_section && *entry_indent.get_or_insert(current_indent) == current_indent;
 
-            // Check if this is our target fiber type
-            if trimmed.starts_with(&format!("{}:", name)) && !trimmed.starts_with(&format!("{}::", name)) {
+            // Check if this is our target entry
+            if is_entry && trimmed.starts_with(&format!("{}:", name)) && !trimmed.starts_with(&format!("{}::", name)) {
                 found_target = true;
-                let fiber_indent = current_indent;
-
-                // Skip this fiber type and all its children
+                // Skip this entry and all its children
                 i += 1;
                 while i < lines.len() {
                     let next_line = lines[i];
@@ -2187,7 +2634,7 @@ fn delete_fiber_type_from_yaml(yaml: &str, name: &str) -> Result<String, ApiErro
                     let next_indent = next_line.len() - next_trimmed.len();
 
                     // Stop if we hit another same-level or higher-level key
-                    if !next_trimmed.is_empty() && !next_trimmed.starts_with('#') && next_indent <= fiber_indent {
+                    if !next_trimmed.is_empty() && !next_trimmed.starts_with('#') && next_indent <= current_indent {
                         break;
                     }
                     i += 1;
@@ -2201,7 +2648,7 @@ fn delete_fiber_type_from_yaml(yaml: &str, name: &str) -> Result<String, ApiErro
     }
 
     if !found_target {
-        return Err(ApiError::NotFound(format!("Fiber type '{}' not found in config", name)));
+        return Err(ApiError::NotFound(format!("'{}' not found in {} section of config", name, section)));
     }
 
     Ok(result.join("\n"))
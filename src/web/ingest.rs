@@ -0,0 +1,304 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::source::ingest::{decode_bulk, decode_loki, decode_ndjson, IngestError, IngestHub};
+
+use super::api::{ErrorDetail, ErrorResponse};
+
+/// Seconds a shipper is asked to wait before retrying a rejected push
+const RETRY_AFTER_SECS: &str = "1";
+
+/// Largest body a gzip request may decode to, so a small compressed body
+/// can't expand without bound
+const MAX_DECODED_BYTES: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct IngestResponse {
+    pub accepted: usize,
+}
+
+/// A rejected push, rendered in the API's error format
+#[derive(Debug)]
+pub struct IngestFailure {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl IngestFailure {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<IngestError> for IngestFailure {
+    fn from(err: IngestError) -> Self {
+        let (status, code) = match &err {
+            IngestError::UnknownSource(_) => (StatusCode::NOT_FOUND, "NOT_FOUND"),
+            IngestError::TooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE"),
+            IngestError::Full(_) => (StatusCode::TOO_MANY_REQUESTS, "BUFFER_FULL"),
+            IngestError::Busy(..) | IngestError::Closed(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "UNAVAILABLE")
+            }
+            _ => (StatusCode::BAD_REQUEST, "BAD_REQUEST"),
+        };
+        Self::new(status, code, err.to_string())
+    }
+}
+
+impl IntoResponse for IngestFailure {
+    fn into_response(self) -> Response {
+        let body = Json(ErrorResponse {
+            error: ErrorDetail {
+                code: self.code.to_string(),
+                message: self.message,
+            },
+        });
+
+        // Shippers retry 429/503 responses; tell them how long to back off
+        if matches!(
+            self.status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        ) {
+            (self.status, [(header::RETRY_AFTER, RETRY_AFTER_SECS)], body).into_response()
+        } else {
+            (self.status, body).into_response()
+        }
+    }
+}
+
+/// POST /ingest and /ingest/:source
+///
+/// Newline-delimited JSON. Without a source in the path, records go to the
+/// only configured http source.
+pub async fn ingest_ndjson(
+    State(hub): State<Arc<IngestHub>>,
+    source: Option<Path<String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<IngestResponse>, IngestFailure> {
+    let body = decode_body(&headers, body)?;
+    let records = decode_ndjson(as_utf8(&body)?, Utc::now())?;
+    let accepted = hub.submit(source.as_deref().map(String::as_str), records).await?;
+    Ok(Json(IngestResponse { accepted }))
+}
+
+/// POST /ingest/loki/api/v1/push and /ingest/:source/loki/api/v1/push
+///
+/// Loki push API, JSON encoding only.
+pub async fn ingest_loki(
+    State(hub): State<Arc<IngestHub>>,
+    source: Option<Path<String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, IngestFailure> {
+    let is_protobuf = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-protobuf"));
+    if is_protobuf {
+        return Err(IngestFailure::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+            "only JSON-encoded Loki pushes are supported",
+        ));
+    }
+
+    let body = decode_body(&headers, body)?;
+    let records = decode_loki(&body)?;
+    hub.submit(source.as_deref().map(String::as_str), records).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /ingest/_bulk and /ingest/:source/_bulk
+///
+/// Elasticsearch bulk API (`index` and `create` actions). The response has
+/// the shape shippers expect from Elasticsearch.
+pub async fn ingest_bulk(
+    State(hub): State<Arc<IngestHub>>,
+    source: Option<Path<String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<serde_json::Value>, IngestFailure> {
+    let started = std::time::Instant::now();
+    let body = decode_body(&headers, body)?;
+    let items = decode_bulk(as_utf8(&body)?, Utc::now())?;
+
+    let actions: Vec<String> = items.iter().map(|item| item.action.clone()).collect();
+    let records = items.into_iter().map(|item| item.record).collect();
+    hub.submit(source.as_deref().map(String::as_str), records).await?;
+
+    let items: Vec<_> = actions
+        .into_iter()
+        .map(|action| json!({ action: { "status": 201, "result": "created" } }))
+        .collect();
+    Ok(Json(json!({
+        "took": started.elapsed().as_millis() as u64,
+        "errors": false,
+        "items": items,
+    })))
+}
+
+/// Undo a gzip `Content-Encoding`, which Fluent Bit and Vector can apply
+fn decode_body(headers: &HeaderMap, body: Bytes) -> Result<Vec<u8>, IngestFailure> {
+    let encoding = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("identity");
+
+    match encoding {
+        "identity" => Ok(body.to_vec()),
+        "gzip" => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(&body[..])
+                .take(MAX_DECODED_BYTES + 1)
+                .read_to_end(&mut decoded)
+                .map_err(|e| {
+                    IngestFailure::new(
+                        StatusCode::BAD_REQUEST,
+                        "BAD_REQUEST",
+                        format!("invalid gzip body: {}", e),
+                    )
+                })?;
+            if decoded.len() as u64 > MAX_DECODED_BYTES {
+                return Err(IngestFailure::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "PAYLOAD_TOO_LARGE",
+                    format!("gzip body decodes to more than {} bytes", MAX_DECODED_BYTES),
+                ));
+            }
+            Ok(decoded)
+        }
+        other => Err(IngestFailure::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+            format!("unsupported content encoding '{}'", other),
+        )),
+    }
+}
+
+fn as_utf8(body: &[u8]) -> Result<&str, IngestFailure> {
+    std::str::from_utf8(body).map_err(|e| {
+        IngestFailure::new(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            format!("body is not valid UTF-8: {}", e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::BackpressureStrategy;
+    use std::io::Write;
+
+    async fn ndjson(hub: &Arc<IngestHub>, source: Option<&str>, body: &'static str) -> Response {
+        ingest_ndjson(
+            State(Arc::clone(hub)),
+            source.map(|s| Path(s.to_string())),
+            HeaderMap::new(),
+            Bytes::from(body),
+        )
+        .await
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_ingest_formats() {
+        let mut hub = IngestHub::new(BackpressureStrategy::Drop, 10);
+        let mut rx = hub.register("push");
+        let hub = Arc::new(hub);
+
+        let response = ndjson(&hub, None, "{\"message\":\"one\"}\n\"two\"\n").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().text, "one");
+        assert_eq!(rx.recv().await.unwrap().text, "two");
+
+        let loki = r#"{"streams":[{"stream":{"job":"api"},"values":[["1741608000000000000","three"]]}]}"#;
+        let status = ingest_loki(
+            State(Arc::clone(&hub)),
+            Some(Path("push".to_string())),
+            HeaderMap::new(),
+            Bytes::from(loki),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.text, "three");
+        assert_eq!(record.fields["job"], "api");
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(b"{\"create\":{}}\n{\"log\":\"four\"}\n").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_ENCODING, "gzip".parse().unwrap());
+        let Json(body) = ingest_bulk(
+            State(Arc::clone(&hub)),
+            None,
+            headers,
+            Bytes::from(gzip.finish().unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["errors"], false);
+        assert_eq!(body["items"][0]["create"]["status"], 201);
+        assert_eq!(rx.recv().await.unwrap().text, "four");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_rejections() {
+        let mut hub = IngestHub::new(BackpressureStrategy::Drop, 2);
+        let _rx = hub.register("push");
+        let hub = Arc::new(hub);
+
+        assert_eq!(ndjson(&hub, None, "{bad").await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            ndjson(&hub, Some("other"), "\"x\"").await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            ndjson(&hub, None, "\"a\"\n\"b\"\n\"c\"\n").await.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // Fill the buffer; the drop strategy rejects the next push outright
+        assert_eq!(ndjson(&hub, None, "\"a\"\n\"b\"\n").await.status(), StatusCode::OK);
+        let response = ndjson(&hub, None, "\"c\"").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], RETRY_AFTER_SECS);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/x-protobuf".parse().unwrap());
+        let err = ingest_loki(State(Arc::clone(&hub)), None, headers, Bytes::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // A gzip body that expands past the limit is refused
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let zeros = vec![0u8; 1024 * 1024];
+        for _ in 0..MAX_DECODED_BYTES / zeros.len() as u64 {
+            gzip.write_all(&zeros).unwrap();
+        }
+        gzip.write_all(b"\n").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_ENCODING, "gzip".parse().unwrap());
+        let err = decode_body(&headers, Bytes::from(gzip.finish().unwrap())).unwrap_err();
+        assert_eq!(err.status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
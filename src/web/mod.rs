@@ -1,4 +1,5 @@
 pub mod api;
+pub mod ingest;
 pub mod server;
 
 pub use server::run_server;
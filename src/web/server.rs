@@ -1,26 +1,25 @@
-use axum::{routing::{get, post, put}, Router, response::{Html, IntoResponse}, http::StatusCode};
-use std::path::PathBuf;
+use axum::{routing::{delete, get, post, put}, Router, response::{Html, IntoResponse}, http::StatusCode};
 use std::sync::Arc;
-use tokio::sync::{watch, RwLock};
+use tokio::sync::watch;
 use tower_http::services::ServeDir;
 
 use crate::collector::api::{
     acknowledge, get_batches, get_checkpoint, get_status, rewind, CollectorState,
 };
-use crate::config::{types::Config, WebConfig};
-use crate::fiber::processor::FiberProcessor;
-use crate::reprocessing::ReprocessState;
-use crate::storage::Storage;
+use crate::config::WebConfig;
+use crate::source::ingest::IngestHub;
 
 use super::api::{
-    activate_config_version, cancel_reprocessing, create_fiber_type, delete_fiber_type,
+    activate_config_version, add_source, cancel_reprocessing, create_fiber_type, delete_fiber_type,
+    delete_source,
     get_config_diff, get_config_history, get_config_version, get_current_config, get_fiber,
-    get_fiber_logs, get_fiber_membership_summaries, get_fiber_type, get_fiber_type_from_version,
-    get_log, get_log_fibers, get_logs_batch, get_reprocess_status, health_check,
-    hot_reload_fiber_type, list_fiber_types, list_fibers, list_logs, list_sources,
+    get_fiber_children, get_fiber_logs, get_fiber_membership_summaries, get_fiber_parents, get_fiber_type, get_fiber_type_from_version,
+    get_log, get_log_fibers, get_logs_batch, get_reprocess_status, get_sequencer_status, get_source_stats, health_check,
+    hot_reload_fiber_type, list_fiber_types, list_fibers, list_late_logs, list_logs, list_sources,
     query_fibers_filtered, start_reprocessing, test_working_set, update_config, update_fiber_type,
     AppState,
 };
+use super::ingest::{ingest_bulk, ingest_loki, ingest_ndjson};
 
 /// Handler to serve index.html for frontend routes (enables client-side routing)
 async fn serve_index_html() -> impl IntoResponse {
@@ -33,43 +32,24 @@ async fn serve_index_html() -> impl IntoResponse {
     }
 }
 
-/// Start the web server with the given storage backend and configuration.
+/// Start the web server, serving the API from `app_state`.
 ///
 /// When `collector_state` is `Some`, the `/collector/*` routes are mounted for
-/// serving log batches to parent instances.
+/// serving log batches to parent instances. When `ingest_hub` is `Some`, the
+/// `/ingest/*` push routes are mounted for http sources. When `source_control`
+/// is `Some`, sources can be added and removed through `/api/sources`.
 pub async fn run_server(
-    storage: Arc<dyn Storage>,
-    fiber_processor: Arc<RwLock<FiberProcessor>>,
-    config: Arc<RwLock<Config>>,
-    config_version: Arc<RwLock<u64>>,
-    reprocess_state: Arc<RwLock<Option<ReprocessState>>>,
-    config_path: PathBuf,
-    config_yaml: Arc<RwLock<String>>,
+    app_state: AppState,
     web_config: WebConfig,
     mut shutdown_rx: watch::Receiver<bool>,
     collector_state: Option<Arc<CollectorState>>,
+    ingest_hub: Option<Arc<IngestHub>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Extract fiber_types from config for backwards compatibility
-    let fiber_types = {
-        let config_guard = config.read().await;
-        Arc::new(config_guard.fiber_types_or_empty().clone())
-    };
-
-    let app_state = AppState {
-        storage,
-        fiber_types,
-        fiber_processor,
-        config,
-        config_version,
-        config_path,
-        config_yaml,
-        reprocess_state,
-    };
-
     // API routes
     let api_routes = Router::new()
         .route("/health", get(health_check))
         .route("/api/logs", get(list_logs))
+        .route("/api/logs/late", get(list_late_logs))
         .route("/api/logs/:id", get(get_log))
         .route("/api/logs/batch", post(get_logs_batch))
         .route("/api/logs/:id/fibers", get(get_log_fibers))
@@ -78,11 +58,16 @@ pub async fn run_server(
         .route("/api/fibers/membership-summaries", post(get_fiber_membership_summaries))
         .route("/api/fibers/:id", get(get_fiber))
         .route("/api/fibers/:id/logs", get(get_fiber_logs))
+        .route("/api/fibers/:id/children", get(get_fiber_children))
+        .route("/api/fibers/:id/parents", get(get_fiber_parents))
         .route("/api/fiber-types", get(list_fiber_types).post(create_fiber_type))
         .route("/api/fiber-types/:name", get(get_fiber_type).put(update_fiber_type).delete(delete_fiber_type))
         .route("/api/fiber-types/:name/hot-reload", post(hot_reload_fiber_type))
         .route("/api/fiber-types/:name/test-working-set", post(test_working_set))
-        .route("/api/sources", get(list_sources))
+        .route("/api/sources", get(list_sources).post(add_source))
+        .route("/api/sources/stats", get(get_source_stats))
+        .route("/api/sources/:name", delete(delete_source))
+        .route("/api/sequencer/status", get(get_sequencer_status))
         .route("/api/config/current", get(get_current_config))
         .route("/api/config/history", get(get_config_history))
         .route("/api/config/versions/:hash", get(get_config_version))
@@ -111,6 +96,19 @@ pub async fn run_server(
         None
     };
 
+    // Push ingestion routes for http sources (conditionally mounted)
+    let ingest_routes = ingest_hub.map(|hub| {
+        tracing::info!("Mounting ingest routes at /ingest/*");
+        Router::new()
+            .route("/ingest", post(ingest_ndjson))
+            .route("/ingest/loki/api/v1/push", post(ingest_loki))
+            .route("/ingest/_bulk", post(ingest_bulk))
+            .route("/ingest/:source", post(ingest_ndjson))
+            .route("/ingest/:source/loki/api/v1/push", post(ingest_loki))
+            .route("/ingest/:source/_bulk", post(ingest_bulk))
+            .with_state(hub)
+    });
+
     // Serve static frontend files
     let frontend_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("frontend");
     let serve_dir = ServeDir::new(frontend_dir.clone());
@@ -121,11 +119,14 @@ pub async fn run_server(
         .route("/viewer", get(serve_index_html))
         .route("/fiber-rules", get(serve_index_html));
 
-    // Combine routes: API first, then collector (if any), then frontend routes, then static files
+    // Combine routes: API first, then collector and ingest (if any), then frontend routes, then static files
     let mut app = api_routes.merge(frontend_routes);
     if let Some(routes) = collector_routes {
         app = app.merge(routes);
     }
+    if let Some(routes) = ingest_routes {
+        app = app.merge(routes);
+    }
     let app = app.fallback_service(serve_dir);
 
     let listener = tokio::net::TcpListener::bind(&web_config.listen).await?;
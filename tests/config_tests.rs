@@ -1,4 +1,6 @@
 use noil::config::{generate::generate_starter_config, load_config};
+use noil::config::types::{AttributeMode, LateRecordPolicy};
+use noil::config::parse::parse_source_config;
 use std::fs;
 use tempfile::TempDir;
 
@@ -11,7 +13,7 @@ fn test_generated_config_is_valid() {
 
     // Generated config must not contain any mode-based references
     assert!(!config_content.contains("\nmode:"), "Generated config should not have a mode field");
-    assert!(!config_content.contains("parent:"), "Generated config should not have a parent section");
+    assert!(!config_content.contains("\nparent:"), "Generated config should not have a parent section");
 
     fs::write(&config_path, &config_content).unwrap();
 
@@ -1638,3 +1640,1462 @@ web:
     assert!(config.has_collector_serving());
     assert!(!config.stores_logs(), "No fiber_types section means no log storage");
 }
+
+#[test]
+fn test_glob_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  app:
+    type: glob
+    path: /var/log/app/*.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+    read:
+      start: beginning
+      follow: true
+      discover_interval: 10s
+  pods:
+    type: directory
+    path: /var/log/pods
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+    read:
+      start: end
+      follow: true
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let config = load_config(&config_path).expect("Glob config should be valid");
+
+    let app = &config.sources["app"];
+    assert_eq!(app.source_type, noil::config::types::SourceType::Glob);
+    assert_eq!(
+        app.read.discover_interval,
+        Some(std::time::Duration::from_secs(10))
+    );
+    assert_eq!(
+        config.sources["pods"].source_type,
+        noil::config::types::SourceType::Directory
+    );
+    assert_eq!(config.sources["pods"].read.discover_interval, None);
+
+    // Auto source fibers are generated for the glob source as a whole
+    assert!(config.fiber_types_or_empty().contains_key("app"));
+}
+
+#[test]
+fn test_invalid_glob_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  app:
+    type: glob
+    path: /var/log/app/[*.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+    read:
+      start: beginning
+      follow: true
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'app': invalid glob pattern"));
+}
+
+#[test]
+fn test_stdin_and_command_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  piped:
+    type: stdin
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+  journal:
+    type: command
+    command: [journalctl, -o, short-iso, -f]
+    restart:
+      policy: always
+      backoff: 2s
+      max_backoff: 30s
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+  kube:
+    type: command
+    command: [kubectl, logs, -f, deploy/api]
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let config = load_config(&config_path).expect("Stream source config should be valid");
+
+    use noil::config::types::{RestartPolicy, SourceType};
+    assert_eq!(config.sources["piped"].source_type, SourceType::Stdin);
+
+    let journal = &config.sources["journal"];
+    assert_eq!(journal.source_type, SourceType::Command);
+    assert_eq!(journal.command, vec!["journalctl", "-o", "short-iso", "-f"]);
+    let restart = journal.restart.as_ref().unwrap();
+    assert_eq!(restart.policy, RestartPolicy::Always);
+    assert_eq!(restart.backoff, std::time::Duration::from_secs(2));
+    assert_eq!(restart.max_backoff, std::time::Duration::from_secs(30));
+
+    assert!(config.sources["kube"].restart.is_none());
+}
+
+#[test]
+fn test_invalid_stream_sources() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  first:
+    type: stdin
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+  second:
+    type: stdin
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+  no_command:
+    type: command
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+  no_path:
+    type: file
+    command: [cat]
+    timestamp:
+      pattern: '^(?P<ts>\d{4})'
+      format: '%Y'
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("only one stdin source is allowed, found: first, second"));
+    assert!(err_msg.contains("source 'no_command': command sources require a non-empty command"));
+    assert!(err_msg.contains("source 'no_path': path is required"));
+    assert!(err_msg.contains(
+        "source 'no_path': command and restart are only supported for command sources"
+    ));
+}
+
+#[test]
+fn test_syslog_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  network:
+    type: syslog
+    syslog:
+      listen: 0.0.0.0:5514
+      protocol: tcp
+      lateness: 10s
+
+fiber_types:
+  ssh_sessions:
+    temporal:
+      max_gap: 30s
+    attributes:
+      - name: host
+        type: string
+        key: true
+    sources:
+      network:
+        patterns:
+          - fields:
+              app_name: '^sshd$'
+              hostname: '(?P<host>.+)'
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let config = load_config(&config_path).expect("Syslog config should be valid");
+
+    use noil::config::types::{SourceType, SyslogProtocol};
+    let network = &config.sources["network"];
+    assert_eq!(network.source_type, SourceType::Syslog);
+    let syslog = network.syslog.as_ref().unwrap();
+    assert_eq!(syslog.listen, "0.0.0.0:5514");
+    assert_eq!(syslog.protocol, SyslogProtocol::Tcp);
+    assert_eq!(syslog.lateness, std::time::Duration::from_secs(10));
+
+    let pattern = &config.fiber_types_or_empty()["ssh_sessions"].sources["network"].patterns[0];
+    assert_eq!(pattern.regex, "");
+    assert_eq!(pattern.fields["app_name"], "^sshd$");
+}
+
+#[test]
+fn test_invalid_syslog_sources() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  no_listener:
+    type: syslog
+  file_with_syslog:
+    type: file
+    path: /var/log/app.log
+    syslog:
+      listen: 0.0.0.0:514
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains(
+        "source 'no_listener': syslog sources require a 'syslog' section with a listen address"
+    ));
+    assert!(err_msg.contains("source 'file_with_syslog': syslog is only supported for syslog sources"));
+    assert!(err_msg.contains("source 'file_with_syslog': timestamp is required"));
+}
+
+#[test]
+fn test_http_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  shipped:
+    type: http
+    http:
+      lateness: 30s
+  defaults:
+    type: http
+  not_http:
+    type: file
+    path: /var/log/app.log
+    timestamp:
+      pattern: '^(?P<ts>\S+)'
+      format: iso8601
+    http:
+      lateness: 1s
+
+fiber_types:
+
+pipeline:
+  backpressure:
+    strategy: drop
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'not_http': http is only supported for http sources"));
+    assert!(!err_msg.contains("source 'shipped'"));
+    assert!(!err_msg.contains("source 'defaults'"));
+
+    // Without the misconfigured source the http sources are valid
+    let config_yaml = config_yaml.replace("  not_http:", "  unused:").replace(
+        "    http:\n      lateness: 1s\n",
+        "",
+    );
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("http sources should be valid");
+
+    use noil::config::types::SourceType;
+    let shipped = &config.sources["shipped"];
+    assert_eq!(shipped.source_type, SourceType::Http);
+    assert_eq!(
+        shipped.http.as_ref().unwrap().lateness,
+        std::time::Duration::from_secs(30)
+    );
+    assert!(config.sources["defaults"].http.is_none());
+}
+
+#[test]
+fn test_json_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  api:
+    type: file
+    path: /var/log/api.json
+    format: json
+    timestamp:
+      pointer: /time
+      format: iso8601
+    read:
+      start: beginning
+      follow: false
+
+fiber_types:
+  requests:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - name: request_id
+        type: string
+        key: true
+    sources:
+      api:
+        patterns:
+          - json:
+              request_id: /req/id
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("JSON source config should be valid");
+
+    use noil::config::types::SourceFormat;
+    let api = &config.sources["api"];
+    assert_eq!(api.format, SourceFormat::Json);
+    assert_eq!(api.timestamp.pointer.as_deref(), Some("/time"));
+    let pattern = &config.fiber_types_or_empty()["requests"].sources["api"].patterns[0];
+    assert_eq!(pattern.json["request_id"], "/req/id");
+
+    // A regex timestamp, a relative pointer, and an unknown attribute are rejected
+    let invalid = config_yaml
+        .replace("      pointer: /time\n", "      pattern: '^(?P<ts>\\S+)'\n")
+        .replace("request_id: /req/id", "request_id: req/id\n              user: /user");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'api': json sources require timestamp.pointer"));
+    assert!(err_msg.contains("source 'api': timestamp.pattern is not used by json sources"));
+    assert!(err_msg.contains("invalid JSON pointer 'req/id' for attribute 'request_id'"));
+    assert!(err_msg.contains("json extracts 'user' which is not a defined attribute"));
+}
+
+#[test]
+fn test_container_source_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  pods:
+    type: glob
+    path: /var/log/pods/*/*/*.log
+    format: cri
+    read:
+      start: beginning
+      follow: true
+
+fiber_types:
+  by_pod:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - name: pod
+        type: string
+        key: true
+    sources:
+      pods:
+        patterns:
+          - fields:
+              pod: (?P<pod>.+)
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("CRI source config should be valid");
+
+    use noil::config::types::SourceFormat;
+    let pods = &config.sources["pods"];
+    assert_eq!(pods.format, SourceFormat::Cri);
+    assert!(pods.timestamp.pattern.is_empty());
+
+    // Timestamps come from the runtime, so a JSON pointer is rejected
+    let invalid = config_yaml.replace(
+        "    format: cri\n",
+        "    format: docker\n    timestamp:\n      pointer: /time\n      format: iso8601\n",
+    );
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'pods': timestamp.pointer is only supported for json sources"));
+}
+
+#[test]
+fn test_timestamp_timezone_and_year_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  legacy:
+    type: file
+    path: /var/log/legacy.log
+    timestamp:
+      pattern: '^(?P<ts>\w{3} [ \d]\d \d{2}:\d{2}:\d{2})'
+      format: '%b %e %H:%M:%S'
+      timezone: UTC
+      clock_offset: -1s 500ms
+      assume_year: 2024
+    read:
+      start: beginning
+      follow: false
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("timestamp options should be valid");
+
+    let timestamp = &config.sources["legacy"].timestamp;
+    assert_eq!(timestamp.timezone.as_deref(), Some("UTC"));
+    assert_eq!(timestamp.clock_offset, Some(chrono::Duration::milliseconds(-1500)));
+    assert_eq!(timestamp.assume_year, Some(2024));
+
+    // Zones must exist, and neither option means anything for ISO 8601 timestamps
+    let invalid = config_yaml.replace("timezone: UTC", "timezone: Mars/Olympus_Mons");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'legacy': timestamp.timezone: unknown time zone 'Mars/Olympus_Mons'"));
+
+    let invalid = config_yaml.replace("format: '%b %e %H:%M:%S'", "format: iso8601");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("timestamp.timezone only applies to strptime timestamp formats"));
+    assert!(err_msg.contains("timestamp.assume_year only applies to strptime formats without a year"));
+
+    let invalid = config_yaml.replace("      format: '%b %e %H:%M:%S'\n", "");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'legacy': timestamp.format is required"));
+}
+
+#[test]
+fn test_timestamp_fallbacks_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  mixed:
+    type: file
+    path: /var/log/mixed.log
+    timestamp:
+      pattern: '^(?P<ts>\S+Z) '
+      format: iso8601
+      fallbacks:
+        - pattern: '^\[(?P<ts>[^\]]+)\]'
+          format: '%d/%b/%Y:%H:%M:%S %z'
+        - pattern: ''
+          format: auto
+    read:
+      start: beginning
+      follow: false
+  detected:
+    type: file
+    path: /var/log/detected.log
+    timestamp:
+      format: auto
+    read:
+      start: beginning
+      follow: false
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("timestamp fallbacks should be valid");
+
+    let fallbacks = &config.sources["mixed"].timestamp.fallbacks;
+    assert_eq!(fallbacks.len(), 2);
+    assert_eq!(fallbacks[0].format, "%d/%b/%Y:%H:%M:%S %z");
+    assert_eq!(fallbacks[1].format, "auto");
+    assert!(config.sources["detected"].timestamp.pattern.is_empty());
+
+    // Fallback patterns need a 'ts' group and a format
+    let invalid = config_yaml.replace("'^\\[(?P<ts>[^\\]]+)\\]'", "'^\\[([^\\]]+)\\]'");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(
+        err_msg.contains("source 'mixed': timestamp.fallbacks[0]: timestamp pattern must contain named capture group 'ts'"),
+        "{}",
+        err_msg
+    );
+
+    let invalid = config_yaml.replace("- pattern: ''\n          format: auto", "- pattern: ''\n          format: ''");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'mixed': timestamp.fallbacks[1]: format is required"));
+}
+
+#[test]
+fn test_multiline_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  java:
+    type: file
+    path: /var/log/java.log
+    timestamp:
+      pattern: '(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+      format: iso8601
+    read:
+      start: beginning
+      follow: true
+      multiline:
+        continue: '^\s+at '
+        max_lines: 500
+        max_bytes: 65536
+        flush_after: 2s
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("multiline options should be valid");
+
+    let multiline = config.sources["java"].read.multiline.as_ref().unwrap();
+    assert_eq!(multiline.continue_pattern.as_deref(), Some(r"^\s+at "));
+    assert_eq!(multiline.max_lines, Some(500));
+    assert_eq!(multiline.max_bytes, Some(65536));
+    assert_eq!(multiline.flush_after, Some(std::time::Duration::from_secs(2)));
+
+    let invalid = config_yaml.replace("        max_lines: 500\n", "        start: '^['\n        max_lines: 0\n");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("read.multiline.start and read.multiline.continue are mutually exclusive"));
+    assert!(err_msg.contains("source 'java': invalid read.multiline.start regex"));
+    assert!(err_msg.contains("source 'java': read.multiline.max_lines must be greater than 0"));
+}
+
+#[test]
+fn test_drop_and_redact_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  checkout:
+    type: file
+    path: /var/log/checkout.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+      format: iso8601
+    read:
+      start: beginning
+      follow: false
+    drop_if:
+      - 'GET /health'
+    redact:
+      - pattern: '[\w.+-]+@[\w-]+\.\w+'
+        replace: '<email>'
+      - pattern: 'token=(?P<value>\w+)'
+        hash_key_env: NOIL_REDACT_KEY
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("drop and redact rules should be valid");
+
+    let source = &config.sources["checkout"];
+    assert_eq!(source.drop_if, vec!["GET /health".to_string()]);
+    assert_eq!(source.redact[0].replace.as_deref(), Some("<email>"));
+    assert_eq!(source.redact[1].hash_key_env.as_deref(), Some("NOIL_REDACT_KEY"));
+
+    let invalid = config_yaml
+        .replace("'GET /health'", "'GET /(health'")
+        .replace("        hash_key_env: NOIL_REDACT_KEY\n", "");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'checkout': invalid drop_if[0] regex"));
+    assert!(err_msg.contains(
+        "source 'checkout': redact[1]: exactly one of replace, hash_key_env or hash_key_file is required"
+    ));
+}
+
+
+#[test]
+fn test_source_labels_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  nginx:
+    type: file
+    path: /var/log/nginx/access.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+      format: iso8601
+    read:
+      start: beginning
+      follow: false
+    labels:
+      host: web1
+      env: prod
+
+fiber_types:
+  request_trace:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - name: request_id
+        type: string
+        key: true
+      - name: host
+        type: string
+        key: true
+    sources:
+      nginx:
+        patterns:
+          - regex: 'request_id=(?P<request_id>\w+)'
+            labels:
+              host: '(?P<host>.+)'
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("source labels should be valid");
+
+    let source = &config.sources["nginx"];
+    assert_eq!(source.labels["host"], "web1");
+    assert_eq!(source.labels["env"], "prod");
+    let pattern = &config.fiber_types_or_empty()["request_trace"].sources["nginx"].patterns[0];
+    assert_eq!(pattern.labels["host"], "(?P<host>.+)");
+
+    let invalid = config_yaml
+        .replace("      env: prod", "      deploy-env: prod")
+        .replace("'(?P<host>.+)'", "'(?P<host>.+'");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("source 'nginx': invalid label name 'deploy-env'"));
+    assert!(err_msg.contains("for label 'host'"));
+}
+
+#[test]
+fn test_idle_timeout_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  quiet:
+    type: file
+    path: /var/log/quiet.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+      format: iso8601
+    read:
+      start: end
+      follow: true
+      idle_timeout: 2m
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+  idle_timeout: 30s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("idle timeouts should be valid");
+    assert_eq!(config.sequencer.idle_timeout, Some(std::time::Duration::from_secs(30)));
+    assert_eq!(
+        config.sources["quiet"].read.idle_timeout,
+        Some(std::time::Duration::from_secs(120))
+    );
+
+    let invalid = config_yaml
+        .replace("idle_timeout: 2m", "idle_timeout: 0s")
+        .replace("idle_timeout: 30s", "idle_timeout: 0s");
+    fs::write(&config_path, invalid).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("sequencer.idle_timeout must be greater than 0"));
+    assert!(err_msg.contains("source 'quiet': read.idle_timeout must be greater than 0"));
+}
+
+#[test]
+fn test_late_records_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = r#"
+sources:
+  app:
+    type: file
+    path: /var/log/app.log
+    timestamp:
+      pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+      format: iso8601
+    read:
+      start: beginning
+      follow: true
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+  late_records: side_table
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#;
+
+    fs::write(&config_path, config_yaml).unwrap();
+    let config = load_config(&config_path).expect("late_records policy should be valid");
+    assert_eq!(config.sequencer.late_records, LateRecordPolicy::SideTable);
+
+    // Emitting late records out of order is the default
+    fs::write(&config_path, config_yaml.replace("  late_records: side_table\n", "")).unwrap();
+    let config = load_config(&config_path).unwrap();
+    assert_eq!(config.sequencer.late_records, LateRecordPolicy::EmitAnyway);
+
+    fs::write(&config_path, config_yaml.replace("side_table", "reorder")).unwrap();
+    assert!(load_config(&config_path).is_err());
+}
+
+#[test]
+fn test_parse_runtime_source_config() {
+    let source_yaml = r#"
+type: file
+path: ~/logs/app.log
+labels:
+  env: prod
+timestamp:
+  pattern: '^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)'
+  format: iso8601
+read:
+  start: end
+  follow: true
+"#;
+
+    let source = parse_source_config("app", source_yaml).expect("source should be valid");
+    assert!(!source.path.starts_with("~"));
+    assert_eq!(source.labels["env"], "prod");
+    assert!(source.read.follow);
+
+    let invalid = source_yaml.replace("env: prod", "bad-name: prod");
+    let err = parse_source_config("app", &invalid).unwrap_err().to_string();
+    assert!(err.contains("invalid label name 'bad-name'"), "unexpected error: {}", err);
+}
+
+#[test]
+fn test_pattern_guards_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = |guards: &str| {
+        format!(
+            r#"
+sources:
+  nginx:
+    type: file
+    path: /tmp/test.log
+    timestamp:
+      pattern: '^(?P<ts>\d{{4}})'
+      format: '%Y'
+
+fiber_types:
+  requests:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - name: client_ip
+        type: ip
+        key: true
+      - name: status
+        type: int
+    sources:
+      nginx:
+        patterns:
+          - regex: '(?P<client_ip>\S+) .* (?P<status>\d{{3}})'
+            close: true
+            when:
+{guards}
+          - regex: '(?P<client_ip>\S+)'
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#
+        )
+    };
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "              - attribute: status\n                gte: 500\n              - attribute: client_ip\n                in: [10.0.0.0/8]",
+        ),
+    )
+    .unwrap();
+    let config = load_config(&config_path).unwrap();
+    let patterns = &config.fiber_types.unwrap()["requests"].sources["nginx"].patterns;
+    assert_eq!(patterns[0].when.len(), 2);
+    assert!(patterns[1].when.is_empty());
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "              - attribute: client_ip\n                gt: 5\n              - attribute: status\n                eq: ok\n              - attribute: missing\n                eq: 1",
+        ),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("numeric comparison on 'client_ip'"), "{}", err_msg);
+    assert!(err_msg.contains("'ok' is not a valid Int value for 'status'"), "{}", err_msg);
+    assert!(err_msg.contains("'missing' is not a defined attribute"), "{}", err_msg);
+
+    // A range is two guards; one guard with both bounds is rejected
+    fs::write(
+        &config_path,
+        config_yaml("              - attribute: status\n                gte: 500\n                lt: 600"),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(
+        err_msg.contains("guard on 'status' has several conditions (gte, lt)"),
+        "{}",
+        err_msg
+    );
+
+    fs::write(&config_path, config_yaml("              - attribute: status")).unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("guard on 'status' needs one of"), "{}", err_msg);
+
+    fs::write(
+        &config_path,
+        config_yaml("              - attribute: status\n                above: 500"),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("unknown field `above`"), "{}", err_msg);
+}
+
+#[test]
+fn test_attribute_modes_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = |attributes: &str| {
+        format!(
+            r#"
+sources:
+  app:
+    type: file
+    path: /tmp/test.log
+    timestamp:
+      pattern: '^(?P<ts>\d{{4}})'
+      format: '%Y'
+
+fiber_types:
+  requests:
+    temporal:
+      max_gap: 5s
+    attributes:
+{attributes}
+    sources:
+      app:
+        patterns:
+          - regex: 'req=(?P<request_id>\S+) backend=(?P<backend>\S+)'
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#
+        )
+    };
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "      - { name: request_id, type: string, key: true, mode: first }\n      - { name: backend, type: ip, mode: set }",
+        ),
+    )
+    .unwrap();
+    let config = load_config(&config_path).unwrap();
+    let attributes = &config.fiber_types.unwrap()["requests"].attributes;
+    assert_eq!(attributes[0].mode, AttributeMode::First);
+    assert_eq!(attributes[1].mode, AttributeMode::Set);
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "      - { name: request_id, type: string, key: true, mode: set }\n      - { name: backend, type: ip, mode: sum }",
+        ),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("key attribute 'request_id' must use mode 'first' or 'last'"), "{}", err_msg);
+    assert!(err_msg.contains("attribute 'backend' uses mode 'sum'"), "{}", err_msg);
+}
+
+#[test]
+fn test_parent_fiber_type_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = |parent: &str| {
+        format!(
+            r#"
+sources:
+  app:
+    type: file
+    path: /tmp/test.log
+    timestamp:
+      pattern: '^(?P<ts>\d{{4}})'
+      format: '%Y'
+
+fiber_types:
+  request_trace:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - {{ name: thread, type: string, key: true }}
+    sources:
+      app:
+        patterns:
+          - regex: 'thread-(?P<thread>\d+)'
+
+  db_query:
+    temporal:
+      max_gap: 5s
+    attributes:
+      - {{ name: query_id, type: string, key: true }}
+      - {{ name: thread, type: string }}
+    sources:
+      app:
+        patterns:
+          - regex: 'thread-(?P<thread>\d+) query=(?P<query_id>\S+)'
+{parent}
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#
+        )
+    };
+
+    fs::write(
+        &config_path,
+        config_yaml("    parent:\n      fiber_type: request_trace\n      on: [thread]"),
+    )
+    .unwrap();
+    let config = load_config(&config_path).unwrap();
+    let parent = config.fiber_types.unwrap()["db_query"].parent.clone().unwrap();
+    assert_eq!(parent.fiber_type, "request_trace");
+    assert_eq!(parent.on, vec!["thread".to_string()]);
+
+    fs::write(
+        &config_path,
+        config_yaml("    parent:\n      fiber_type: request_trace\n      on: [query_id]"),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(
+        err_msg.contains("parent attribute 'query_id' is not defined on fiber type 'request_trace'"),
+        "{}",
+        err_msg
+    );
+
+    fs::write(
+        &config_path,
+        config_yaml("    parent:\n      fiber_type: db_query\n      on: [thread]"),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("parent fiber types form a cycle"), "{}", err_msg);
+
+    fs::write(
+        &config_path,
+        config_yaml("    parent:\n      fiber_type: sessions\n      on: [thread]"),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("parent references non-existent fiber type 'sessions'"), "{}", err_msg);
+}
+
+#[test]
+fn test_sequence_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.yml");
+
+    let config_yaml = |sequence: &str, last_step: &str| {
+        format!(
+            r#"
+sources:
+  app:
+    type: file
+    path: /tmp/test.log
+    timestamp:
+      pattern: '^(?P<ts>\d{{4}})'
+      format: '%Y'
+
+fiber_types:
+  order:
+    temporal:
+      max_gap: 5m
+    attributes:
+      - {{ name: order_id, type: string, key: true }}
+    sources:
+      app:
+        patterns:
+          - regex: 'order (?P<order_id>\d+) received'
+            step: received
+          - regex: 'order (?P<order_id>\d+) shipped'
+            step: {last_step}
+            close: true
+{sequence}
+
+pipeline:
+  backpressure:
+    strategy: block
+  errors:
+    on_parse_error: drop
+  checkpoint:
+    enabled: true
+    interval_seconds: 30
+
+sequencer:
+  batch_epoch_duration: 10s
+  watermark_safety_margin: 1s
+
+storage:
+  path: /tmp/test.duckdb
+  batch_size: 1000
+  flush_interval_seconds: 5
+
+web:
+  listen: 127.0.0.1:7104
+"#
+        )
+    };
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "    sequence:\n      - step: received\n      - step: shipped\n        within: 1h",
+            "shipped",
+        ),
+    )
+    .unwrap();
+    let config = load_config(&config_path).unwrap();
+    let sequence = config.fiber_types.unwrap()["order"].sequence.clone();
+    assert_eq!(sequence.len(), 2);
+    assert_eq!(sequence[0].step, "received");
+    assert_eq!(sequence[1].within, Some(std::time::Duration::from_secs(3600)));
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "    sequence:\n      - step: received\n      - step: shipped",
+            "delivered",
+        ),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(err_msg.contains("pattern step 'delivered' is not in the sequence"), "{}", err_msg);
+    assert!(
+        err_msg.contains("sequence step 'shipped' is not the step of any pattern"),
+        "{}",
+        err_msg
+    );
+
+    fs::write(
+        &config_path,
+        config_yaml(
+            "    sequence:\n      - step: received\n        within: 1s\n      - step: shipped",
+            "shipped",
+        ),
+    )
+    .unwrap();
+    let err_msg = load_config(&config_path).unwrap_err().to_string();
+    assert!(
+        err_msg.contains("first sequence step 'received' has no previous step for 'within'"),
+        "{}",
+        err_msg
+    );
+}
@@ -28,6 +28,9 @@ mod tests {
             source_id: source.to_string(),
             raw_text: text.to_string(),
             file_offset: 0,
+            fields: Default::default(),
+            labels: Default::default(),
+            late: false,
         }
     }
 